@@ -24,7 +24,7 @@ args @ {
   ignoreLockHash,
   cargoConfig ? {},
 }: let
-  nixifiedLockHash = "f44765aabb1ee95fec52811d115ada843e6f13fe726f9d4731c78c1910ba10f8";
+  nixifiedLockHash = "9b3fa9bc6ef0f240524a86e7508e9eb03047b2425a54c5e370f7e30ff1638894";
   workspaceSrc =
     if args.workspaceSrc == null
     then ./.
@@ -87,6 +87,25 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".aead."0.5.2" = overridableMkRustCrate (profileName: rec {
+      name = "aead";
+      version = "0.5.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "d122413f284cf2d62fb1b7db97e02edb8cda96d769b16e443a4f6195e35662b0";
+      };
+      features = builtins.concatLists [
+        ["alloc"]
+        ["getrandom"]
+        ["rand_core"]
+      ];
+      dependencies = {
+        crypto_common = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".crypto-common."0.1.7" {inherit profileName;}).out;
+        generic_array = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".generic-array."0.14.7" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".ahash."0.8.12" = overridableMkRustCrate (profileName: rec {
       name = "ahash";
       version = "0.8.12";
@@ -128,6 +147,29 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".alloc-no-stdlib."3.0.0" = overridableMkRustCrate (profileName: rec {
+      name = "alloc-no-stdlib";
+      version = "3.0.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "c2fb6cfd47bf496ff64095c20eaba0c201404ee38714d4142fcfa1dc334fcc7a";
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".alloc-stdlib."0.3.0" = overridableMkRustCrate (profileName: rec {
+      name = "alloc-stdlib";
+      version = "0.3.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "0b5c1865780388bfa186411ab5f247819487fc4864c6e9c3106611fa347586e1";
+      };
+      dependencies = {
+        alloc_no_stdlib = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".alloc-no-stdlib."3.0.0" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".allocator-api2."0.2.21" = overridableMkRustCrate (profileName: rec {
       name = "allocator-api2";
       version = "0.2.21";
@@ -141,6 +183,22 @@ in
       ];
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".ammonia."4.2.3" = overridableMkRustCrate (profileName: rec {
+      name = "ammonia";
+      version = "4.2.3";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "061e83b03c2681c18a6787d956e355c74e0b98ba7ba3d69b0822ade1e6f1d716";
+      };
+      dependencies = {
+        cssparser = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cssparser."0.38.0" {inherit profileName;}).out;
+        html5ever = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".html5ever."0.40.1" {inherit profileName;}).out;
+        maplit = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".maplit."1.0.2" {inherit profileName;}).out;
+        url = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".url."2.5.8" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".android_system_properties."0.1.5" = overridableMkRustCrate (profileName: rec {
       name = "android_system_properties";
       version = "0.1.5";
@@ -257,6 +315,40 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".arc-swap."1.9.2" = overridableMkRustCrate (profileName: rec {
+      name = "arc-swap";
+      version = "1.9.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "c049c0be4daef0b145cb3555416b3b8ef5b7888a38aea1a3a155801fe7b0810b";
+      };
+      dependencies = {
+        rustversion = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".rustversion."1.0.22" {profileName = "__noProfile";}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".async-compression."0.4.50" = overridableMkRustCrate (profileName: rec {
+      name = "async-compression";
+      version = "0.4.50";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "ee19bd99b43e3691acbad4e840420a4881cea6c0b66a208125a824f8fd53f5a1";
+      };
+      features = builtins.concatLists [
+        ["brotli"]
+        ["gzip"]
+        ["tokio"]
+      ];
+      dependencies = {
+        compression_codecs = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".compression-codecs."0.4.45" {inherit profileName;}).out;
+        compression_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".compression-core."0.4.33" {inherit profileName;}).out;
+        pin_project_lite = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project-lite."0.2.17" {inherit profileName;}).out;
+        tokio = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".async-trait."0.1.89" = overridableMkRustCrate (profileName: rec {
       name = "async-trait";
       version = "0.1.89";
@@ -407,6 +499,40 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".axum-extra."0.9.6" = overridableMkRustCrate (profileName: rec {
+      name = "axum-extra";
+      version = "0.9.6";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "c794b30c904f0a1c2fb7740f7df7f7972dfaa14ef6f57cb6178dc63e5dca2f04";
+      };
+      features = builtins.concatLists [
+        ["cookie"]
+        ["default"]
+        ["multipart"]
+        ["tracing"]
+      ];
+      dependencies = {
+        axum = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".axum."0.7.9" {inherit profileName;}).out;
+        axum_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".axum-core."0.4.5" {inherit profileName;}).out;
+        bytes = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.11.1" {inherit profileName;}).out;
+        cookie = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cookie."0.18.2" {inherit profileName;}).out;
+        fastrand = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".fastrand."2.5.0" {inherit profileName;}).out;
+        futures_util = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-util."0.3.32" {inherit profileName;}).out;
+        http = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http."1.4.0" {inherit profileName;}).out;
+        http_body = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http-body."1.0.1" {inherit profileName;}).out;
+        http_body_util = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http-body-util."0.1.3" {inherit profileName;}).out;
+        mime = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".mime."0.3.17" {inherit profileName;}).out;
+        multer = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".multer."3.1.0" {inherit profileName;}).out;
+        pin_project_lite = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project-lite."0.2.17" {inherit profileName;}).out;
+        serde = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.228" {inherit profileName;}).out;
+        tower = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower."0.5.3" {inherit profileName;}).out;
+        tower_layer = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-layer."0.3.3" {inherit profileName;}).out;
+        tower_service = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-service."0.3.3" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".axum-tracing-opentelemetry."0.16.0" = overridableMkRustCrate (profileName: rec {
       name = "axum-tracing-opentelemetry";
       version = "0.16.0";
@@ -512,6 +638,57 @@ in
       ];
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".block-buffer."0.10.4" = overridableMkRustCrate (profileName: rec {
+      name = "block-buffer";
+      version = "0.10.4";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "3078c7629b62d3f0439517fa394996acacc5cbc91c5a20d8c658e77abd503a71";
+      };
+      dependencies = {
+        generic_array = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".generic-array."0.14.7" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".brotli."9.0.0" = overridableMkRustCrate (profileName: rec {
+      name = "brotli";
+      version = "9.0.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "f8b851b75c23ca7873623d612fe49bd1989aeb03d08fb9432187eb253d3d4c6b";
+      };
+      features = builtins.concatLists [
+        ["alloc-stdlib"]
+        ["default"]
+        ["std"]
+      ];
+      dependencies = {
+        alloc_no_stdlib = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".alloc-no-stdlib."3.0.0" {inherit profileName;}).out;
+        alloc_stdlib = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".alloc-stdlib."0.3.0" {inherit profileName;}).out;
+        brotli_decompressor = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".brotli-decompressor."6.0.1" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".brotli-decompressor."6.0.1" = overridableMkRustCrate (profileName: rec {
+      name = "brotli-decompressor";
+      version = "6.0.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "941cd9bd4ddab83cb46fa5a2d428f1c857b24ac78cb876cf7beb710840934bd7";
+      };
+      features = builtins.concatLists [
+        ["alloc-stdlib"]
+        ["std"]
+      ];
+      dependencies = {
+        alloc_no_stdlib = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".alloc-no-stdlib."3.0.0" {inherit profileName;}).out;
+        alloc_stdlib = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".alloc-stdlib."0.3.0" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".bstr."1.12.1" = overridableMkRustCrate (profileName: rec {
       name = "bstr";
       version = "1.12.1";
@@ -581,6 +758,12 @@ in
         inherit name version;
         sha256 = "e629a66d692cb9ff1a1c664e41771b3dcaf961985a9774c0eb0bd1b51cf60a48";
       };
+      features = builtins.concatLists [
+        ["serde1"]
+      ];
+      dependencies = {
+        serde_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_core."1.0.228" {inherit profileName;}).out;
+      };
     });
 
     "registry+https://github.com/rust-lang/crates.io-index".cc."1.2.62" = overridableMkRustCrate (profileName: rec {
@@ -617,6 +800,85 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".cfg_aliases."0.2.2" = overridableMkRustCrate (profileName: rec {
+      name = "cfg_aliases";
+      version = "0.2.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "f079e83a288787bcd14a6aea84cee5c87a67c5a3e660c30f557a3d24761b3527";
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".chacha20."0.9.1" = overridableMkRustCrate (profileName: rec {
+      name = "chacha20";
+      version = "0.9.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "c3613f74bd2eac03dad61bd53dbe620703d4371614fe0bc3b9f04dd36fe4e818";
+      };
+      features = builtins.concatLists [
+        ["zeroize"]
+      ];
+      dependencies = {
+        cfg_if = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.4" {inherit profileName;}).out;
+        cipher = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cipher."0.4.4" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "x86_64" || hostPlatform.parsed.cpu.name == "i686"
+          then "cpufeatures"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cpufeatures."0.2.17" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".chacha20."0.10.2" = overridableMkRustCrate (profileName: rec {
+      name = "chacha20";
+      version = "0.10.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "65c35e4b699c7e15ccbe7ee35c005e4fc0a278d22238a2857e6ce2dadeda1b06";
+      };
+      features = builtins.concatLists [
+        ["rng"]
+      ];
+      dependencies = {
+        cfg_if = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.4" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "x86_64" || hostPlatform.parsed.cpu.name == "i686"
+          then "cpufeatures"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cpufeatures."0.3.1" {inherit profileName;}).out;
+        rand_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rand_core."0.10.1" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".chacha20poly1305."0.10.1" = overridableMkRustCrate (profileName: rec {
+      name = "chacha20poly1305";
+      version = "0.10.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "10cd79432192d1c0f4e1a0fef9527696cc039165d729fb41b3f4f4f354c2dc35";
+      };
+      features = builtins.concatLists [
+        ["alloc"]
+        ["default"]
+        ["getrandom"]
+        ["rand_core"]
+      ];
+      dependencies = {
+        aead = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".aead."0.5.2" {inherit profileName;}).out;
+        chacha20 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".chacha20."0.9.1" {inherit profileName;}).out;
+        cipher = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cipher."0.4.4" {inherit profileName;}).out;
+        poly1305 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".poly1305."0.8.0" {inherit profileName;}).out;
+        zeroize = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".zeroize."1.8.2" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".chrono."0.4.44" = overridableMkRustCrate (profileName: rec {
       name = "chrono";
       version = "0.4.44";
@@ -670,6 +932,24 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".cipher."0.4.4" = overridableMkRustCrate (profileName: rec {
+      name = "cipher";
+      version = "0.4.4";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "773f3b9af64447d2ce9850330c473515014aa235e6a783b02db81ff39e4a3dad";
+      };
+      features = builtins.concatLists [
+        ["zeroize"]
+      ];
+      dependencies = {
+        crypto_common = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".crypto-common."0.1.7" {inherit profileName;}).out;
+        inout = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".inout."0.1.4" {inherit profileName;}).out;
+        zeroize = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".zeroize."1.8.2" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".clap."4.6.1" = overridableMkRustCrate (profileName: rec {
       name = "clap";
       version = "4.6.1";
@@ -787,6 +1067,38 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".compression-codecs."0.4.45" = overridableMkRustCrate (profileName: rec {
+      name = "compression-codecs";
+      version = "0.4.45";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "98fc98460ba0ad5317075d3632b8dfc45d0be8c4a49347c2a38272019717614a";
+      };
+      features = builtins.concatLists [
+        ["brotli"]
+        ["flate2"]
+        ["gzip"]
+        ["memchr"]
+      ];
+      dependencies = {
+        brotli = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".brotli."9.0.0" {inherit profileName;}).out;
+        compression_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".compression-core."0.4.33" {inherit profileName;}).out;
+        flate2 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".flate2."1.1.9" {inherit profileName;}).out;
+        memchr = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".memchr."2.8.0" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".compression-core."0.4.33" = overridableMkRustCrate (profileName: rec {
+      name = "compression-core";
+      version = "0.4.33";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "6e8ccc4ea9f6acc32d102c0f6d471d11d913ad15f20c04de743374861fa1d414";
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".comrak."0.21.0" = overridableMkRustCrate (profileName: rec {
       name = "comrak";
       version = "0.21.0";
@@ -834,6 +1146,27 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".cookie."0.18.2" = overridableMkRustCrate (profileName: rec {
+      name = "cookie";
+      version = "0.18.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "1a373e3602691c3cdea496d2f0ee5935151e6168fe87739483c463db1b2f2f87";
+      };
+      features = builtins.concatLists [
+        ["percent-encode"]
+        ["percent-encoding"]
+      ];
+      dependencies = {
+        percent_encoding = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".percent-encoding."2.3.2" {inherit profileName;}).out;
+        time = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".time."0.3.47" {inherit profileName;}).out;
+      };
+      buildDependencies = {
+        version_check = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".version_check."0.9.5" {profileName = "__noProfile";}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".core-foundation."0.10.1" = overridableMkRustCrate (profileName: rec {
       name = "core-foundation";
       version = "0.10.1";
@@ -866,6 +1199,55 @@ in
       ];
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".core_detect."1.0.0" = overridableMkRustCrate (profileName: rec {
+      name = "core_detect";
+      version = "1.0.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "7f8f80099a98041a3d1622845c271458a2d73e688351bf3cb999266764b81d48";
+      };
+      features = builtins.concatLists [
+        ["default"]
+      ];
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".cpufeatures."0.2.17" = overridableMkRustCrate (profileName: rec {
+      name = "cpufeatures";
+      version = "0.2.17";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "59ed5838eebb26a2bb2e58f6d5b5316989ae9d08bab10e0e6d103e656d1b0280";
+      };
+      dependencies = {
+        ${
+          if hostPlatform.config == "aarch64-linux-android" || hostPlatform.parsed.cpu.name == "aarch64" && hostPlatform.parsed.kernel.name == "linux" || hostPlatform.parsed.cpu.name == "aarch64" && hostPlatform.parsed.vendor.name == "apple" || hostPlatform.parsed.cpu.name == "loongarch64" && hostPlatform.parsed.kernel.name == "linux"
+          then "libc"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".libc."0.2.186" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".cpufeatures."0.3.1" = overridableMkRustCrate (profileName: rec {
+      name = "cpufeatures";
+      version = "0.3.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "5ca28b0ae3115b884660db4118d803791fd6756b6e88f39c0f3f7859060d7566";
+      };
+      dependencies = {
+        ${
+          if hostPlatform.parsed.cpu.name == "aarch64" && hostPlatform.parsed.kernel.name == "android" || hostPlatform.parsed.cpu.name == "aarch64" && hostPlatform.parsed.kernel.name == "linux" || hostPlatform.parsed.cpu.name == "aarch64" && hostPlatform.parsed.vendor.name == "apple" || hostPlatform.parsed.cpu.name == "loongarch64" && hostPlatform.parsed.kernel.name == "linux"
+          then "libc"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".libc."0.2.186" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".crc32fast."1.5.0" = overridableMkRustCrate (profileName: rec {
       name = "crc32fast";
       version = "1.5.0";
@@ -949,6 +1331,41 @@ in
       ];
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".crypto-common."0.1.7" = overridableMkRustCrate (profileName: rec {
+      name = "crypto-common";
+      version = "0.1.7";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "78c8292055d1c1df0cce5d180393dc8cce0abec0a7102adb6c7b1eef6016d60a";
+      };
+      features = builtins.concatLists [
+        ["getrandom"]
+        ["rand_core"]
+        ["std"]
+      ];
+      dependencies = {
+        generic_array = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".generic-array."0.14.7" {inherit profileName;}).out;
+        rand_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rand_core."0.6.4" {inherit profileName;}).out;
+        typenum = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".typenum."1.20.1" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".cssparser."0.38.0" = overridableMkRustCrate (profileName: rec {
+      name = "cssparser";
+      version = "0.38.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "11119743ad110e8c1bdccd930d7f5c30c99e5fc76a7b63ec9807e84eef0c5f59";
+      };
+      dependencies = {
+        dtoa_short = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".dtoa-short."0.3.5" {inherit profileName;}).out;
+        itoa = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".itoa."1.0.18" {inherit profileName;}).out;
+        smallvec = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".smallvec."1.15.1" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".darling."0.14.4" = overridableMkRustCrate (profileName: rec {
       name = "darling";
       version = "0.14.4";
@@ -1082,6 +1499,30 @@ in
       ];
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".digest."0.10.7" = overridableMkRustCrate (profileName: rec {
+      name = "digest";
+      version = "0.10.7";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "9ed9a281f7bc9b7576e61468ba615a66a5c8cfdff42420a70aa82701a3b1e292";
+      };
+      features = builtins.concatLists [
+        ["alloc"]
+        ["block-buffer"]
+        ["core-api"]
+        ["default"]
+        ["mac"]
+        ["std"]
+        ["subtle"]
+      ];
+      dependencies = {
+        block_buffer = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".block-buffer."0.10.4" {inherit profileName;}).out;
+        crypto_common = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".crypto-common."0.1.7" {inherit profileName;}).out;
+        subtle = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".subtle."2.6.1" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".displaydoc."0.2.5" = overridableMkRustCrate (profileName: rec {
       name = "displaydoc";
       version = "0.2.5";
@@ -1107,6 +1548,29 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".dtoa."1.0.11" = overridableMkRustCrate (profileName: rec {
+      name = "dtoa";
+      version = "1.0.11";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "4c3cf4824e2d5f025c7b531afcb2325364084a16806f6d47fbc1f5fbd9960590";
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".dtoa-short."0.3.5" = overridableMkRustCrate (profileName: rec {
+      name = "dtoa-short";
+      version = "0.3.5";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "cd1511a7b6a56299bd043a9c167a6d2bfb37bf84a6dfceaba651168adfb43c87";
+      };
+      dependencies = {
+        dtoa = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".dtoa."1.0.11" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".dunce."1.0.5" = overridableMkRustCrate (profileName: rec {
       name = "dunce";
       version = "1.0.5";
@@ -1123,12 +1587,46 @@ in
       registry = "registry+https://github.com/rust-lang/crates.io-index";
       src = fetchCratesIo {
         inherit name version;
-        sha256 = "48c757948c5ede0e46177b7add2e67155f70e33c07fea8284df6576da70b3719";
+        sha256 = "48c757948c5ede0e46177b7add2e67155f70e33c07fea8284df6576da70b3719";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["std"]
+      ];
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".encoding_rs."0.8.42" = overridableMkRustCrate (profileName: rec {
+      name = "encoding_rs";
+      version = "0.8.42";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "8e985e0451871ad22fb8d2b6b076e2028a502a0d3950998c2c5c0a4f9b5d9679";
       };
       features = builtins.concatLists [
+        ["alloc"]
         ["default"]
-        ["std"]
       ];
+      dependencies = {
+        cfg_if = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.4" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "x86_64" || hostPlatform.parsed.cpu.name == "i686"
+          then "core_detect"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".core_detect."1.0.0" {inherit profileName;}).out;
+        multiversion_no_op = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".multiversion_no_op."1.0.0" {profileName = "__noProfile";}).out;
+        scopeguard = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".scopeguard."1.2.0" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "x86_64" || hostPlatform.parsed.cpu.name == "i686" || hostPlatform.parsed.cpu.name == "aarch64"
+          then "simdutf8"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".simdutf8."0.1.5" {inherit profileName;}).out;
+      };
+      buildDependencies = {
+        rustversion = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".rustversion."1.0.22" {profileName = "__noProfile";}).out;
+      };
     });
 
     "registry+https://github.com/rust-lang/crates.io-index".entities."1.0.1" = overridableMkRustCrate (profileName: rec {
@@ -1200,6 +1698,21 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".fastrand."2.5.0" = overridableMkRustCrate (profileName: rec {
+      name = "fastrand";
+      version = "2.5.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "da7c62ceae207dd37ea5b845da6a0696c799f85e97da1ab5b7910be3c1c80223";
+      };
+      features = builtins.concatLists [
+        ["alloc"]
+        ["default"]
+        ["std"]
+      ];
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".filetime."0.2.29" = overridableMkRustCrate (profileName: rec {
       name = "filetime";
       version = "0.2.29";
@@ -1438,6 +1951,25 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".generic-array."0.14.7" = overridableMkRustCrate (profileName: rec {
+      name = "generic-array";
+      version = "0.14.7";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "85649ca51fd72272d7821adaf274ad91c288277713d9c18820d8499a7ff69e9a";
+      };
+      features = builtins.concatLists [
+        ["more_lengths"]
+      ];
+      dependencies = {
+        typenum = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".typenum."1.20.1" {inherit profileName;}).out;
+      };
+      buildDependencies = {
+        version_check = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".version_check."0.9.5" {profileName = "__noProfile";}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".getrandom."0.2.17" = overridableMkRustCrate (profileName: rec {
       name = "getrandom";
       version = "0.2.17";
@@ -1515,6 +2047,49 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".getrandom."0.4.3" = overridableMkRustCrate (profileName: rec {
+      name = "getrandom";
+      version = "0.4.3";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "300e883d756b2e4ec94e02791f39b04b522276138852cfc41d9fb7e904106099";
+      };
+      features = builtins.concatLists [
+        ["std"]
+        ["sys_rng"]
+        ["wasm_js"]
+      ];
+      dependencies = {
+        cfg_if = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.4" {inherit profileName;}).out;
+        ${
+          if false
+          then "js_sys"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".js-sys."0.3.98" {inherit profileName;}).out;
+        ${
+          if (hostPlatform.parsed.kernel.name == "linux" || hostPlatform.parsed.kernel.name == "android") && !(hostPlatform.parsed.kernel.name == "linux" && hostPlatform.parsed.abi.name == "") || hostPlatform.parsed.kernel.name == "dragonfly" || hostPlatform.parsed.kernel.name == "freebsd" || hostPlatform.parsed.kernel.name == "hurd" || hostPlatform.parsed.kernel.name == "illumos" || hostPlatform.parsed.kernel.name == "cygwin" || hostPlatform.parsed.kernel.name == "horizon" && (hostPlatform.parsed.cpu.name == "armv6l" || hostPlatform.parsed.cpu.name == "armv7l") || hostPlatform.parsed.kernel.name == "haiku" || hostPlatform.parsed.kernel.name == "redox" || hostPlatform.parsed.kernel.name == "nto" || hostPlatform.parsed.kernel.name == "aix" || hostPlatform.parsed.kernel.name == "ios" || hostPlatform.parsed.kernel.name == "visionos" || hostPlatform.parsed.kernel.name == "watchos" || hostPlatform.parsed.kernel.name == "tvos" || hostPlatform.parsed.kernel.name == "darwin" || hostPlatform.parsed.kernel.name == "openbsd" || hostPlatform.parsed.kernel.name == "vita" || hostPlatform.parsed.kernel.name == "emscripten" || hostPlatform.parsed.kernel.name == "netbsd" || hostPlatform.parsed.kernel.name == "solaris" || hostPlatform.parsed.kernel.name == "vxworks"
+          then "libc"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".libc."0.2.186" {inherit profileName;}).out;
+        ${
+          if false
+          then "r_efi"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".r-efi."6.0.0" {inherit profileName;}).out;
+        rand_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rand_core."0.10.1" {inherit profileName;}).out;
+        ${
+          if false
+          then "wasm_bindgen"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".wasm-bindgen."0.2.121" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".git2."0.20.4" = overridableMkRustCrate (profileName: rec {
       name = "git2";
       version = "0.20.4";
@@ -1631,6 +2206,24 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".hashbrown."0.13.2" = overridableMkRustCrate (profileName: rec {
+      name = "hashbrown";
+      version = "0.13.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "43a3c133739dddd0d2990f9a4bdf8eb4b21ef50e4851ca85ab661199821d510e";
+      };
+      features = builtins.concatLists [
+        ["ahash"]
+        ["default"]
+        ["inline-more"]
+      ];
+      dependencies = {
+        ahash = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".ahash."0.8.12" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".hashbrown."0.14.5" = overridableMkRustCrate (profileName: rec {
       name = "hashbrown";
       version = "0.14.5";
@@ -1689,6 +2282,34 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".hmac."0.12.1" = overridableMkRustCrate (profileName: rec {
+      name = "hmac";
+      version = "0.12.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "6c49c37c09c17a53d937dfbb742eb3a961d65a994e6bcdcf37e7399d0cc8ab5e";
+      };
+      dependencies = {
+        digest = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".digest."0.10.7" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".html5ever."0.40.1" = overridableMkRustCrate (profileName: rec {
+      name = "html5ever";
+      version = "0.40.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "456a1a377e608e555d22ddab27ac0114bc7a7b4199078108e34c2aeae6c9b130";
+      };
+      dependencies = {
+        log = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".log."0.4.29" {inherit profileName;}).out;
+        markup5ever = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".markup5ever."0.40.0" {inherit profileName;}).out;
+        memchr = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".memchr."2.8.0" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".http."1.4.0" = overridableMkRustCrate (profileName: rec {
       name = "http";
       version = "1.4.0";
@@ -1819,7 +2440,11 @@ in
       features = builtins.concatLists [
         ["aws-lc-rs"]
         ["http1"]
+        ["ring"]
         ["rustls-native-certs"]
+        ["tls12"]
+        ["webpki-roots"]
+        ["webpki-tokio"]
       ];
       dependencies = {
         http = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http."1.4.0" {inherit profileName;}).out;
@@ -1830,6 +2455,7 @@ in
         tokio = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
         tokio_rustls = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio-rustls."0.26.4" {inherit profileName;}).out;
         tower_service = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-service."0.3.3" {inherit profileName;}).out;
+        webpki_roots = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".webpki-roots."1.0.9" {inherit profileName;}).out;
       };
     });
 
@@ -1844,22 +2470,27 @@ in
       features = builtins.concatLists [
         ["client"]
         ["client-legacy"]
+        ["client-proxy"]
         ["default"]
         ["http1"]
         ["http2"]
         ["server"]
         ["server-auto"]
+        ["server-graceful"]
         ["service"]
         ["tokio"]
       ];
       dependencies = {
+        base64 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".base64."0.22.1" {inherit profileName;}).out;
         bytes = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.11.1" {inherit profileName;}).out;
         futures_channel = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-channel."0.3.32" {inherit profileName;}).out;
         futures_util = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-util."0.3.32" {inherit profileName;}).out;
         http = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http."1.4.0" {inherit profileName;}).out;
         http_body = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http-body."1.0.1" {inherit profileName;}).out;
         hyper = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".hyper."1.9.0" {inherit profileName;}).out;
+        ipnet = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".ipnet."2.12.0" {inherit profileName;}).out;
         libc = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".libc."0.2.186" {inherit profileName;}).out;
+        percent_encoding = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".percent-encoding."2.3.2" {inherit profileName;}).out;
         pin_project_lite = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project-lite."0.2.17" {inherit profileName;}).out;
         socket2 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".socket2."0.6.3" {inherit profileName;}).out;
         tokio = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
@@ -2178,6 +2809,19 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".inout."0.1.4" = overridableMkRustCrate (profileName: rec {
+      name = "inout";
+      version = "0.1.4";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "879f10e63c20629ecabbb64a8010319738c66a5cd0c29b02d63d272b03751d01";
+      };
+      dependencies = {
+        generic_array = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".generic-array."0.14.7" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".ipnet."2.12.0" = overridableMkRustCrate (profileName: rec {
       name = "ipnet";
       version = "2.12.0";
@@ -2187,6 +2831,7 @@ in
         sha256 = "d98f6fed1fde3f8c21bc40a1abb88dd75e67924f9cffc3ef95607bad8017f8e2";
       };
       features = builtins.concatLists [
+        ["default"]
         ["std"]
       ];
     });
@@ -2434,31 +3079,73 @@ in
       ];
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".lru."0.9.0" = overridableMkRustCrate (profileName: rec {
+      name = "lru";
+      version = "0.9.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "71e7d46de488603ffdd5f30afbc64fbba2378214a2c3a2fb83abf3d33126df17";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["hashbrown"]
+      ];
+      dependencies = {
+        hashbrown = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".hashbrown."0.13.2" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".lru-slab."0.1.3" = overridableMkRustCrate (profileName: rec {
+      name = "lru-slab";
+      version = "0.1.3";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "4050469837a6ff301cd14c1f8f24f88549e6d548f24f64e2148eb0f72cebc51f";
+      };
+    });
+
     "unknown".maddie-wtf."0.2.0" = overridableMkRustCrate (profileName: rec {
       name = "maddie-wtf";
       version = "0.2.0";
       registry = "unknown";
       src = fetchCrateLocal workspaceSrc;
       dependencies = {
+        ammonia = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".ammonia."4.2.3" {inherit profileName;}).out;
+        arc_swap = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".arc-swap."1.9.2" {inherit profileName;}).out;
         axum = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".axum."0.7.9" {inherit profileName;}).out;
+        axum_extra = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".axum-extra."0.9.6" {inherit profileName;}).out;
         axum_tracing_opentelemetry = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".axum-tracing-opentelemetry."0.16.0" {inherit profileName;}).out;
         camino = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".camino."1.2.2" {inherit profileName;}).out;
+        chacha20poly1305 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".chacha20poly1305."0.10.1" {inherit profileName;}).out;
         chrono = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".chrono."0.4.44" {inherit profileName;}).out;
         clap = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".clap."4.6.1" {inherit profileName;}).out;
         comrak = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".comrak."0.21.0" {inherit profileName;}).out;
         dotenv = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".dotenv."0.15.0" {inherit profileName;}).out;
         either = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".either."1.15.0" {inherit profileName;}).out;
+        flate2 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".flate2."1.1.9" {inherit profileName;}).out;
+        grass = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".grass."0.13.4" {inherit profileName;}).out;
+        hmac = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".hmac."0.12.1" {inherit profileName;}).out;
+        hyper_util = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".hyper-util."0.1.20" {inherit profileName;}).out;
         ignore = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".ignore."0.4.25" {inherit profileName;}).out;
         lazy_static = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".lazy_static."1.5.0" {inherit profileName;}).out;
+        lru = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".lru."0.9.0" {inherit profileName;}).out;
         maud = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".maud."0.26.0" {inherit profileName;}).out;
         metrics = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".metrics."0.24.6" {inherit profileName;}).out;
         notify = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".notify."6.1.1" {inherit profileName;}).out;
         notify_debouncer_mini = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".notify-debouncer-mini."0.4.1" {inherit profileName;}).out;
+        reqwest = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".reqwest."0.12.28" {inherit profileName;}).out;
         serde = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.228" {inherit profileName;}).out;
+        serde_json = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_json."1.0.149" {inherit profileName;}).out;
+        sha2 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".sha2."0.10.9" {inherit profileName;}).out;
         syntect = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".syntect."5.3.0" {inherit profileName;}).out;
         tap = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tap."1.0.1" {inherit profileName;}).out;
+        tar = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tar."0.4.46" {inherit profileName;}).out;
+        tempfile = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tempfile."3.27.0" {inherit profileName;}).out;
         thiserror = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".thiserror."1.0.69" {inherit profileName;}).out;
         tokio = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
+        tokio_stream = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio-stream."0.1.19" {inherit profileName;}).out;
         toml = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".toml."0.8.23" {inherit profileName;}).out;
         tower_http = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-http."0.5.2" {inherit profileName;}).out;
         tower_livereload = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-livereload."0.9.6" {inherit profileName;}).out;
@@ -2474,6 +3161,31 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".maplit."1.0.2" = overridableMkRustCrate (profileName: rec {
+      name = "maplit";
+      version = "1.0.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "3e2e65a1a2e43cfcb47a895c4c8b10d1f4a61097f9f254f183aee60cad9c651d";
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".markup5ever."0.40.0" = overridableMkRustCrate (profileName: rec {
+      name = "markup5ever";
+      version = "0.40.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "0ab3dc68ac4a0f5719e560136778c1ee716e296030d75dbd4484e37e39e3a842";
+      };
+      dependencies = {
+        log = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".log."0.4.29" {inherit profileName;}).out;
+        tendril = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tendril."0.5.1" {inherit profileName;}).out;
+        web_atoms = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".web_atoms."0.3.0" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".matchers."0.2.0" = overridableMkRustCrate (profileName: rec {
       name = "matchers";
       version = "0.2.0";
@@ -2763,6 +3475,52 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".multer."3.1.0" = overridableMkRustCrate (profileName: rec {
+      name = "multer";
+      version = "3.1.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "83e87776546dc87511aa5ee218730c92b666d7264ab6ed41f9d215af9cd5224b";
+      };
+      features = builtins.concatLists [
+        ["default"]
+      ];
+      dependencies = {
+        bytes = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.11.1" {inherit profileName;}).out;
+        encoding_rs = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".encoding_rs."0.8.42" {inherit profileName;}).out;
+        futures_util = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-util."0.3.32" {inherit profileName;}).out;
+        http = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http."1.4.0" {inherit profileName;}).out;
+        httparse = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".httparse."1.10.1" {inherit profileName;}).out;
+        memchr = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".memchr."2.8.0" {inherit profileName;}).out;
+        mime = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".mime."0.3.17" {inherit profileName;}).out;
+        spin = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".spin."0.9.9" {inherit profileName;}).out;
+      };
+      buildDependencies = {
+        version_check = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".version_check."0.9.5" {profileName = "__noProfile";}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".multiversion_no_op."1.0.0" = overridableMkRustCrate (profileName: rec {
+      name = "multiversion_no_op";
+      version = "1.0.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "743fb55ba31b18fb1ecef6bdc9aa2743314978ac084044301a7eee33fb99a20d";
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".new_debug_unreachable."1.0.6" = overridableMkRustCrate (profileName: rec {
+      name = "new_debug_unreachable";
+      version = "1.0.6";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "650eef8c711430f1a879fdd01d4745a7deea475becfb90269c06775983bbf086";
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".notify."6.1.1" = overridableMkRustCrate (profileName: rec {
       name = "notify";
       version = "6.1.1";
@@ -2954,6 +3712,16 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".opaque-debug."0.3.1" = overridableMkRustCrate (profileName: rec {
+      name = "opaque-debug";
+      version = "0.3.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "c08d65885ee38876c4f86fa503fb49d7b507c2b62552df7c70b2fce627e06381";
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".openssl-probe."0.2.1" = overridableMkRustCrate (profileName: rec {
       name = "openssl-probe";
       version = "0.2.1";
@@ -3129,6 +3897,38 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".phf."0.14.0" = overridableMkRustCrate (profileName: rec {
+      name = "phf";
+      version = "0.14.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "010378780309880b08997fae13be7834dba947d36393bd372f2b1556deb2a2f6";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["std"]
+      ];
+      dependencies = {
+        phf_shared = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".phf_shared."0.14.0" {inherit profileName;}).out;
+        serde = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.228" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".phf_codegen."0.14.0" = overridableMkRustCrate (profileName: rec {
+      name = "phf_codegen";
+      version = "0.14.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "41b585a510fb76fdebead6897982ef2a03a21d8e6cbcca904999742a4afc6ffe";
+      };
+      dependencies = {
+        phf_generator = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".phf_generator."0.14.0" {inherit profileName;}).out;
+        phf_shared = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".phf_shared."0.14.0" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".phf_generator."0.11.3" = overridableMkRustCrate (profileName: rec {
       name = "phf_generator";
       version = "0.11.3";
@@ -3143,6 +3943,20 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".phf_generator."0.14.0" = overridableMkRustCrate (profileName: rec {
+      name = "phf_generator";
+      version = "0.14.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "aeb62e0959d5a1bebc965f4d15d9e2b7cea002b6b0f5ba8cde6cc26738467100";
+      };
+      dependencies = {
+        fastrand = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".fastrand."2.5.0" {inherit profileName;}).out;
+        phf_shared = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".phf_shared."0.14.0" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".phf_macros."0.11.3" = overridableMkRustCrate (profileName: rec {
       name = "phf_macros";
       version = "0.11.3";
@@ -3176,6 +3990,23 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".phf_shared."0.14.0" = overridableMkRustCrate (profileName: rec {
+      name = "phf_shared";
+      version = "0.14.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "c6fd9027e2d9319be6349febd1db4e8d02aa544921200c9b777720ac34a3aa89";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["std"]
+      ];
+      dependencies = {
+        siphasher = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".siphasher."1.0.3" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".pin-project-lite."0.2.17" = overridableMkRustCrate (profileName: rec {
       name = "pin-project-lite";
       version = "0.2.17";
@@ -3217,6 +4048,26 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".poly1305."0.8.0" = overridableMkRustCrate (profileName: rec {
+      name = "poly1305";
+      version = "0.8.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "8159bd90725d2df49889a078b54f4f79e87f1f8a8444194cdca81d38f5393abf";
+      };
+      dependencies = {
+        ${
+          if hostPlatform.parsed.cpu.name == "x86_64" || hostPlatform.parsed.cpu.name == "i686"
+          then "cpufeatures"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cpufeatures."0.2.17" {inherit profileName;}).out;
+        opaque_debug = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".opaque-debug."0.3.1" {inherit profileName;}).out;
+        universal_hash = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".universal-hash."0.5.1" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".portable-atomic."1.13.1" = overridableMkRustCrate (profileName: rec {
       name = "portable-atomic";
       version = "1.13.1";
@@ -3273,6 +4124,16 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".precomputed-hash."0.1.1" = overridableMkRustCrate (profileName: rec {
+      name = "precomputed-hash";
+      version = "0.1.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "925383efa346730478fb4838dbe9137d2a47675ad789c546d150a6e1dd4ab31c";
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".proc-macro-error."1.0.4" = overridableMkRustCrate (profileName: rec {
       name = "proc-macro-error";
       version = "1.0.4";
@@ -3385,6 +4246,121 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".quinn."0.11.12" = overridableMkRustCrate (profileName: rec {
+      name = "quinn";
+      version = "0.11.12";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "4051e23e9185c255a7e33ef59cdbca87a22d359052eecd22fc6b901fb37d9d11";
+      };
+      features = builtins.concatLists [
+        ["ring"]
+        ["runtime-tokio"]
+        ["rustls"]
+        ["rustls-ring"]
+      ];
+      dependencies = {
+        bytes = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.11.1" {inherit profileName;}).out;
+        pin_project_lite = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project-lite."0.2.17" {inherit profileName;}).out;
+        proto = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".quinn-proto."0.11.19" {inherit profileName;}).out;
+        udp = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".quinn-udp."0.5.16" {inherit profileName;}).out;
+        rustc_hash = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustc-hash."2.1.3" {inherit profileName;}).out;
+        rustls = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls."0.23.40" {inherit profileName;}).out;
+        socket2 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".socket2."0.6.3" {inherit profileName;}).out;
+        thiserror = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".thiserror."2.0.18" {inherit profileName;}).out;
+        tokio = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
+        tracing = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tracing."0.1.44" {inherit profileName;}).out;
+        ${
+          if false
+          then "web_time"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".web-time."1.1.0" {inherit profileName;}).out;
+      };
+      buildDependencies = {
+        cfg_aliases = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg_aliases."0.2.2" {profileName = "__noProfile";}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".quinn-proto."0.11.19" = overridableMkRustCrate (profileName: rec {
+      name = "quinn-proto";
+      version = "0.11.19";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "0e750cca55fe4f0439a15d0bb529da9651e79993e8e72c61a899a36d462befbe";
+      };
+      features = builtins.concatLists [
+        ["ring"]
+        ["rustls-ring"]
+      ];
+      dependencies = {
+        bytes = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.11.1" {inherit profileName;}).out;
+        ${
+          if false
+          then "getrandom"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".getrandom."0.4.3" {inherit profileName;}).out;
+        lru_slab = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".lru-slab."0.1.3" {inherit profileName;}).out;
+        rand = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rand."0.10.3" {inherit profileName;}).out;
+        rand_pcg = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rand_pcg."0.10.2" {inherit profileName;}).out;
+        ring = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".ring."0.17.14" {inherit profileName;}).out;
+        rustc_hash = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustc-hash."2.1.3" {inherit profileName;}).out;
+        rustls = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls."0.23.40" {inherit profileName;}).out;
+        ${
+          if false
+          then "rustls_pki_types"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls-pki-types."1.14.1" {inherit profileName;}).out;
+        slab = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".slab."0.4.12" {inherit profileName;}).out;
+        thiserror = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".thiserror."2.0.18" {inherit profileName;}).out;
+        tinyvec = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tinyvec."1.13.3" {inherit profileName;}).out;
+        tracing = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tracing."0.1.44" {inherit profileName;}).out;
+        ${
+          if false
+          then "web_time"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".web-time."1.1.0" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".quinn-udp."0.5.16" = overridableMkRustCrate (profileName: rec {
+      name = "quinn-udp";
+      version = "0.5.16";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "af66907df18639dcf4db56ca65490cabc4b27a97dbadd96f2926cca73298f016";
+      };
+      features = builtins.concatLists [
+        ["tracing"]
+      ];
+      dependencies = {
+        libc = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".libc."0.2.186" {inherit profileName;}).out;
+        ${
+          if hostPlatform.isWindows
+          then "once_cell"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".once_cell."1.21.4" {inherit profileName;}).out;
+        socket2 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".socket2."0.6.3" {inherit profileName;}).out;
+        tracing = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tracing."0.1.44" {inherit profileName;}).out;
+        ${
+          if hostPlatform.isWindows
+          then "windows_sys"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".windows-sys."0.61.2" {inherit profileName;}).out;
+      };
+      buildDependencies = {
+        cfg_aliases = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg_aliases."0.2.2" {profileName = "__noProfile";}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".quote."1.0.45" = overridableMkRustCrate (profileName: rec {
       name = "quote";
       version = "1.0.45";
@@ -3412,6 +4388,16 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".r-efi."6.0.0" = overridableMkRustCrate (profileName: rec {
+      name = "r-efi";
+      version = "6.0.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "f8dcc9c7d52a811697d2151c701e0d08956f92b0e24136cf4cf27b57a6a0d9bf";
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".rand."0.8.6" = overridableMkRustCrate (profileName: rec {
       name = "rand";
       version = "0.8.6";
@@ -3463,6 +4449,29 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".rand."0.10.3" = overridableMkRustCrate (profileName: rec {
+      name = "rand";
+      version = "0.10.3";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "65c9fb96cbc91e3478eaae79a69fcd3f1ae4ad052e471fe6732fff548984b4af";
+      };
+      features = builtins.concatLists [
+        ["alloc"]
+        ["default"]
+        ["std"]
+        ["std_rng"]
+        ["sys_rng"]
+        ["thread_rng"]
+      ];
+      dependencies = {
+        chacha20 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".chacha20."0.10.2" {inherit profileName;}).out;
+        getrandom = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".getrandom."0.4.3" {inherit profileName;}).out;
+        rand_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rand_core."0.10.1" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".rand_chacha."0.3.1" = overridableMkRustCrate (profileName: rec {
       name = "rand_chacha";
       version = "0.3.1";
@@ -3532,6 +4541,29 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".rand_core."0.10.1" = overridableMkRustCrate (profileName: rec {
+      name = "rand_core";
+      version = "0.10.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "63b8176103e19a2643978565ca18b50549f6101881c443590420e4dc998a3c69";
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".rand_pcg."0.10.2" = overridableMkRustCrate (profileName: rec {
+      name = "rand_pcg";
+      version = "0.10.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "caa0f4137e1c0a72f4c651489402276c8e8e1cf081f3b0ba156d2cbeef09e86a";
+      };
+      dependencies = {
+        rand_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rand_core."0.10.1" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".rand_xoshiro."0.7.0" = overridableMkRustCrate (profileName: rec {
       name = "rand_xoshiro";
       version = "0.7.0";
@@ -3689,6 +4721,162 @@ in
       ];
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".reqwest."0.12.28" = overridableMkRustCrate (profileName: rec {
+      name = "reqwest";
+      version = "0.12.28";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "eddd3ca559203180a307f12d114c268abf583f59b03cb906fd0b3ff8646c1147";
+      };
+      features = builtins.concatLists [
+        ["__rustls"]
+        ["__rustls-ring"]
+        ["__tls"]
+        ["json"]
+        ["rustls-tls"]
+        ["rustls-tls-webpki-roots"]
+        ["rustls-tls-webpki-roots-no-provider"]
+      ];
+      dependencies = {
+        base64 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".base64."0.22.1" {inherit profileName;}).out;
+        bytes = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.11.1" {inherit profileName;}).out;
+        futures_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-core."0.3.32" {inherit profileName;}).out;
+        http = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http."1.4.0" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "http_body"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http-body."1.0.1" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "http_body_util"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http-body-util."0.1.3" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "hyper"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".hyper."1.9.0" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "hyper_rustls"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".hyper-rustls."0.27.9" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "hyper_util"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".hyper-util."0.1.20" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "wasm32"
+          then "js_sys"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".js-sys."0.3.98" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "log"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".log."0.4.29" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "percent_encoding"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".percent-encoding."2.3.2" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "pin_project_lite"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project-lite."0.2.17" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "quinn"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".quinn."0.11.12" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "rustls"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls."0.23.40" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "rustls_pki_types"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls-pki-types."1.14.1" {inherit profileName;}).out;
+        serde = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde."1.0.228" {inherit profileName;}).out;
+        serde_json = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_json."1.0.149" {inherit profileName;}).out;
+        serde_urlencoded = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".serde_urlencoded."0.7.1" {inherit profileName;}).out;
+        sync_wrapper = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".sync_wrapper."1.0.2" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "tokio"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "tokio_rustls"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio-rustls."0.26.4" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "tower"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower."0.5.3" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "tower_http"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-http."0.6.11" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "tower_service"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-service."0.3.3" {inherit profileName;}).out;
+        url = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".url."2.5.8" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "wasm32"
+          then "wasm_bindgen"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".wasm-bindgen."0.2.121" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "wasm32"
+          then "wasm_bindgen_futures"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".wasm-bindgen-futures."0.4.71" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "wasm32"
+          then "web_sys"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".web-sys."0.3.98" {inherit profileName;}).out;
+        ${
+          if !(hostPlatform.parsed.cpu.name == "wasm32")
+          then "webpki_roots"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".webpki-roots."1.0.9" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".ring."0.17.14" = overridableMkRustCrate (profileName: rec {
       name = "ring";
       version = "0.17.14";
@@ -3699,6 +4887,9 @@ in
       };
       features = builtins.concatLists [
         ["alloc"]
+        ["default"]
+        ["dev_urandom_fallback"]
+        ["wasm32_unknown_unknown_js"]
       ];
       dependencies = {
         cfg_if = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.4" {inherit profileName;}).out;
@@ -3722,6 +4913,20 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".rustc-hash."2.1.3" = overridableMkRustCrate (profileName: rec {
+      name = "rustc-hash";
+      version = "2.1.3";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "6b1e7f9a428571be2dc5bc0505c13fb6bf936822b894ec87abf8a08a4e51742d";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["std"]
+      ];
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".rustix."1.1.4" = overridableMkRustCrate (profileName: rec {
       name = "rustix";
       version = "1.1.4";
@@ -3733,6 +4938,7 @@ in
       features = builtins.concatLists [
         ["alloc"]
         ["default"]
+        ["fs"]
         ["std"]
         ["termios"]
       ];
@@ -3776,11 +4982,14 @@ in
       features = builtins.concatLists [
         ["aws-lc-rs"]
         ["aws_lc_rs"]
+        ["ring"]
         ["std"]
+        ["tls12"]
       ];
       dependencies = {
         aws_lc_rs = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".aws-lc-rs."1.17.0" {inherit profileName;}).out;
         once_cell = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".once_cell."1.21.4" {inherit profileName;}).out;
+        ring = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".ring."0.17.14" {inherit profileName;}).out;
         pki_types = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls-pki-types."1.14.1" {inherit profileName;}).out;
         webpki = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls-webpki."0.103.13" {inherit profileName;}).out;
         subtle = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".subtle."2.6.1" {inherit profileName;}).out;
@@ -3831,8 +5040,16 @@ in
         ["alloc"]
         ["default"]
         ["std"]
+        ["web"]
+        ["web-time"]
       ];
       dependencies = {
+        ${
+          if false
+          then "web_time"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".web-time."1.1.0" {inherit profileName;}).out;
         zeroize = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".zeroize."1.8.2" {inherit profileName;}).out;
       };
     });
@@ -3848,6 +5065,7 @@ in
       features = builtins.concatLists [
         ["alloc"]
         ["aws-lc-rs"]
+        ["ring"]
         ["std"]
       ];
       dependencies = {
@@ -3987,6 +5205,7 @@ in
       };
       features = builtins.concatLists [
         ["alloc"]
+        ["default"]
         ["result"]
         ["std"]
       ];
@@ -4097,6 +5316,30 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".sha2."0.10.9" = overridableMkRustCrate (profileName: rec {
+      name = "sha2";
+      version = "0.10.9";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "a7507d819769d01a365ab707794a4084392c824f54a7a6a7862f8c3d0892b283";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["std"]
+      ];
+      dependencies = {
+        cfg_if = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.4" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.cpu.name == "aarch64" || hostPlatform.parsed.cpu.name == "x86_64" || hostPlatform.parsed.cpu.name == "i686"
+          then "cpufeatures"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cpufeatures."0.2.17" {inherit profileName;}).out;
+        digest = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".digest."0.10.7" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".sharded-slab."0.1.7" = overridableMkRustCrate (profileName: rec {
       name = "sharded-slab";
       version = "0.1.7";
@@ -4162,6 +5405,20 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".simdutf8."0.1.5" = overridableMkRustCrate (profileName: rec {
+      name = "simdutf8";
+      version = "0.1.5";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "e3a9fe34e3e7a50316060351f37187a3f546bce95496156754b601a5fa71b76e";
+      };
+      features = builtins.concatLists [
+        ["aarch64_neon"]
+        ["public_imp"]
+      ];
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".siphasher."1.0.3" = overridableMkRustCrate (profileName: rec {
       name = "siphasher";
       version = "1.0.3";
@@ -4260,6 +5517,20 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".spin."0.9.9" = overridableMkRustCrate (profileName: rec {
+      name = "spin";
+      version = "0.9.9";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "3763264f6b73151db08c50ff20d7d8a0b8796e021cdea7ceedad07b80155fa0e";
+      };
+      features = builtins.concatLists [
+        ["mutex"]
+        ["spin_mutex"]
+      ];
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".stable_deref_trait."1.2.1" = overridableMkRustCrate (profileName: rec {
       name = "stable_deref_trait";
       version = "1.2.1";
@@ -4270,6 +5541,38 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".string_cache."0.11.0" = overridableMkRustCrate (profileName: rec {
+      name = "string_cache";
+      version = "0.11.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "ffa8a5dbe8b3f0bbe29d4c3225daafaeead63afdc1b65fc4c01a1384166038e6";
+      };
+      dependencies = {
+        debug_unreachable = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".new_debug_unreachable."1.0.6" {inherit profileName;}).out;
+        parking_lot = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".parking_lot."0.12.5" {inherit profileName;}).out;
+        phf_shared = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".phf_shared."0.14.0" {inherit profileName;}).out;
+        precomputed_hash = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".precomputed-hash."0.1.1" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".string_cache_codegen."0.11.2" = overridableMkRustCrate (profileName: rec {
+      name = "string_cache_codegen";
+      version = "0.11.2";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "928dcdf75e47626b3617a976ec205d9f057584c371c1f23b782129268d0e6edc";
+      };
+      dependencies = {
+        phf_generator = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".phf_generator."0.14.0" {inherit profileName;}).out;
+        phf_shared = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".phf_shared."0.14.0" {inherit profileName;}).out;
+        proc_macro2 = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".proc-macro2."1.0.106" {inherit profileName;}).out;
+        quote = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".quote."1.0.45" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".strsim."0.10.0" = overridableMkRustCrate (profileName: rec {
       name = "strsim";
       version = "0.10.0";
@@ -4362,6 +5665,13 @@ in
         inherit name version;
         sha256 = "0bf256ce5efdfa370213c1dabab5935a12e49f2c58d15e9eac2870d3b4f27263";
       };
+      features = builtins.concatLists [
+        ["futures"]
+        ["futures-core"]
+      ];
+      dependencies = {
+        futures_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-core."0.3.32" {inherit profileName;}).out;
+      };
     });
 
     "registry+https://github.com/rust-lang/crates.io-index".synstructure."0.13.2" = overridableMkRustCrate (profileName: rec {
@@ -4442,6 +5752,84 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".tar."0.4.46" = overridableMkRustCrate (profileName: rec {
+      name = "tar";
+      version = "0.4.46";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "3f6221d9a6003c78398e3b239969f352578258df48c8eb051caadae0015bc840";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["xattr"]
+      ];
+      dependencies = {
+        filetime = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".filetime."0.2.29" {inherit profileName;}).out;
+        ${
+          if hostPlatform.isUnix
+          then "libc"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".libc."0.2.186" {inherit profileName;}).out;
+        ${
+          if hostPlatform.isUnix
+          then "xattr"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".xattr."1.6.1" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".tempfile."3.27.0" = overridableMkRustCrate (profileName: rec {
+      name = "tempfile";
+      version = "3.27.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "32497e9a4c7b38532efcdebeef879707aa9f794296a4f0244f6f69e9bc8574bd";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["getrandom"]
+      ];
+      dependencies = {
+        fastrand = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".fastrand."2.5.0" {inherit profileName;}).out;
+        ${
+          if hostPlatform.isUnix || hostPlatform.isWindows || hostPlatform.parsed.kernel.name == "wasi"
+          then "getrandom"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".getrandom."0.3.4" {inherit profileName;}).out;
+        once_cell = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".once_cell."1.21.4" {inherit profileName;}).out;
+        ${
+          if hostPlatform.isUnix || hostPlatform.parsed.kernel.name == "wasi"
+          then "rustix"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustix."1.1.4" {inherit profileName;}).out;
+        ${
+          if hostPlatform.isWindows
+          then "windows_sys"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".windows-sys."0.61.2" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".tendril."0.5.1" = overridableMkRustCrate (profileName: rec {
+      name = "tendril";
+      version = "0.5.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "5fed54709c5b3a53d09bb1c113ea4f5ceafd1e772ddcb0030a82e1d56c087b08";
+      };
+      dependencies = {
+        debug_unreachable = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".new_debug_unreachable."1.0.6" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".terminal_size."0.4.4" = overridableMkRustCrate (profileName: rec {
       name = "terminal_size";
       version = "0.4.4";
@@ -4551,6 +5939,7 @@ in
         ["alloc"]
         ["default"]
         ["formatting"]
+        ["macros"]
         ["parsing"]
         ["std"]
       ];
@@ -4610,6 +5999,20 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".tinyvec."1.13.3" = overridableMkRustCrate (profileName: rec {
+      name = "tinyvec";
+      version = "1.13.3";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "fd3ca314f692efd6c868f8408f53fe444634a845f96c028b97d35f6a1f79f0ee";
+      };
+      features = builtins.concatLists [
+        ["alloc"]
+        ["default"]
+      ];
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" = overridableMkRustCrate (profileName: rec {
       name = "tokio";
       version = "1.52.3";
@@ -4692,12 +6095,38 @@ in
         inherit name version;
         sha256 = "1729aa945f29d91ba541258c8df89027d5792d85a8841fb65e8bf0f4ede4ef61";
       };
+      features = builtins.concatLists [
+        ["ring"]
+        ["tls12"]
+      ];
       dependencies = {
         rustls = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls."0.23.40" {inherit profileName;}).out;
         tokio = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".tokio-stream."0.1.19" = overridableMkRustCrate (profileName: rec {
+      name = "tokio-stream";
+      version = "0.1.19";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "a3d06f0b082ba57c26b79407372e57cf2a1e28124f78e9479fe80322cf53420b";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["sync"]
+        ["time"]
+        ["tokio-util"]
+      ];
+      dependencies = {
+        futures_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-core."0.3.32" {inherit profileName;}).out;
+        pin_project_lite = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project-lite."0.2.17" {inherit profileName;}).out;
+        tokio = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
+        tokio_util = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio-util."0.7.18" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".tokio-util."0.7.18" = overridableMkRustCrate (profileName: rec {
       name = "tokio-util";
       version = "0.7.18";
@@ -4829,7 +6258,9 @@ in
         ["log"]
         ["make"]
         ["pin-project-lite"]
+        ["retry"]
         ["sync_wrapper"]
+        ["timeout"]
         ["tokio"]
         ["tracing"]
         ["util"]
@@ -4855,8 +6286,12 @@ in
         sha256 = "1e9cd434a998747dd2c4276bc96ee2e0c7a2eadf3cae88e52be55a05fa9053f5";
       };
       features = builtins.concatLists [
+        ["async-compression"]
+        ["compression-br"]
+        ["compression-gzip"]
         ["default"]
         ["fs"]
+        ["futures-core"]
         ["futures-util"]
         ["httpdate"]
         ["mime"]
@@ -4868,8 +6303,10 @@ in
         ["tracing"]
       ];
       dependencies = {
+        async_compression = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".async-compression."0.4.50" {inherit profileName;}).out;
         bitflags = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bitflags."2.11.1" {inherit profileName;}).out;
         bytes = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.11.1" {inherit profileName;}).out;
+        futures_core = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-core."0.3.32" {inherit profileName;}).out;
         futures_util = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-util."0.3.32" {inherit profileName;}).out;
         http = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http."1.4.0" {inherit profileName;}).out;
         http_body = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http-body."1.0.1" {inherit profileName;}).out;
@@ -4888,6 +6325,33 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".tower-http."0.6.11" = overridableMkRustCrate (profileName: rec {
+      name = "tower-http";
+      version = "0.6.11";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "4cfcf7e2740e6fc6d4d688b4ef00650406bb94adf4731e43c096c3a19fe40840";
+      };
+      features = builtins.concatLists [
+        ["follow-redirect"]
+        ["futures-util"]
+        ["tower"]
+      ];
+      dependencies = {
+        bitflags = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bitflags."2.11.1" {inherit profileName;}).out;
+        bytes = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".bytes."1.11.1" {inherit profileName;}).out;
+        futures_util = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".futures-util."0.3.32" {inherit profileName;}).out;
+        http = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http."1.4.0" {inherit profileName;}).out;
+        http_body = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".http-body."1.0.1" {inherit profileName;}).out;
+        pin_project_lite = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".pin-project-lite."0.2.17" {inherit profileName;}).out;
+        tower = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower."0.5.3" {inherit profileName;}).out;
+        tower_layer = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-layer."0.3.3" {inherit profileName;}).out;
+        tower_service = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tower-service."0.3.3" {inherit profileName;}).out;
+        url = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".url."2.5.8" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".tower-layer."0.3.3" = overridableMkRustCrate (profileName: rec {
       name = "tower-layer";
       version = "0.3.3";
@@ -5128,6 +6592,16 @@ in
       ];
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".typenum."1.20.1" = overridableMkRustCrate (profileName: rec {
+      name = "typenum";
+      version = "1.20.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "b6f5e870be6c3b371b77fe0ee0bafb859fa4964b4404c27de1d380043c4dda20";
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".unicase."2.9.0" = overridableMkRustCrate (profileName: rec {
       name = "unicase";
       version = "2.9.0";
@@ -5158,6 +6632,20 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".universal-hash."0.5.1" = overridableMkRustCrate (profileName: rec {
+      name = "universal-hash";
+      version = "0.5.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "fc1de2c688dc15305988b563c3854064043356019f97a4b46276fe734c4f07ea";
+      };
+      dependencies = {
+        crypto_common = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".crypto-common."0.1.7" {inherit profileName;}).out;
+        subtle = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".subtle."2.6.1" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".untrusted."0.9.0" = overridableMkRustCrate (profileName: rec {
       name = "untrusted";
       version = "0.9.0";
@@ -5339,6 +6827,24 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".wasm-bindgen-futures."0.4.71" = overridableMkRustCrate (profileName: rec {
+      name = "wasm-bindgen-futures";
+      version = "0.4.71";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "96492d0d3ffba25305a7dc88720d250b1401d7edca02cc3bcd50633b424673b8";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["std"]
+      ];
+      dependencies = {
+        js_sys = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".js-sys."0.3.98" {inherit profileName;}).out;
+        wasm_bindgen = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".wasm-bindgen."0.2.121" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".wasm-bindgen-macro."0.2.121" = overridableMkRustCrate (profileName: rec {
       name = "wasm-bindgen-macro";
       version = "0.2.121";
@@ -5392,9 +6898,25 @@ in
         sha256 = "4b572dff8bcf38bad0fa19729c89bb5748b2b9b1d8be70cf90df697e3a8f32aa";
       };
       features = builtins.concatLists [
+        ["AbortController"]
+        ["AbortSignal"]
+        ["Blob"]
+        ["BlobPropertyBag"]
         ["EventTarget"]
+        ["File"]
+        ["FormData"]
+        ["Headers"]
         ["Performance"]
+        ["ReadableStream"]
+        ["Request"]
+        ["RequestCache"]
+        ["RequestCredentials"]
+        ["RequestInit"]
+        ["RequestMode"]
+        ["Response"]
+        ["ServiceWorkerGlobalScope"]
         ["Window"]
+        ["WorkerGlobalScope"]
         ["default"]
         ["std"]
       ];
@@ -5428,6 +6950,61 @@ in
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".web-time."1.1.0" = overridableMkRustCrate (profileName: rec {
+      name = "web-time";
+      version = "1.1.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "5a6580f308b1fad9207618087a65c04e7a10bc77e02c8e84e9b00dd4b12fa0bb";
+      };
+      dependencies = {
+        ${
+          if false
+          then "js_sys"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".js-sys."0.3.98" {inherit profileName;}).out;
+        ${
+          if false
+          then "wasm_bindgen"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".wasm-bindgen."0.2.121" {inherit profileName;}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".web_atoms."0.3.0" = overridableMkRustCrate (profileName: rec {
+      name = "web_atoms";
+      version = "0.3.0";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "7572660c8890448ba236b7376f27e389c6a7e1c70195622faced601f855c0ada";
+      };
+      dependencies = {
+        phf = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".phf."0.14.0" {inherit profileName;}).out;
+        string_cache = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".string_cache."0.11.0" {inherit profileName;}).out;
+      };
+      buildDependencies = {
+        phf_codegen = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".phf_codegen."0.14.0" {profileName = "__noProfile";}).out;
+        string_cache_codegen = (buildRustPackages."registry+https://github.com/rust-lang/crates.io-index".string_cache_codegen."0.11.2" {profileName = "__noProfile";}).out;
+      };
+    });
+
+    "registry+https://github.com/rust-lang/crates.io-index".webpki-roots."1.0.9" = overridableMkRustCrate (profileName: rec {
+      name = "webpki-roots";
+      version = "1.0.9";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "7dcd9d09a39985f5344844e66b0c530a33843579125f23e21e9f0f220850f22a";
+      };
+      dependencies = {
+        pki_types = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustls-pki-types."1.14.1" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".winapi."0.3.9" = overridableMkRustCrate (profileName: rec {
       name = "winapi";
       version = "0.3.9";
@@ -5990,12 +7567,41 @@ in
         cfg_if = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".cfg-if."1.0.4" {inherit profileName;}).out;
         clap = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".clap."4.6.1" {inherit profileName;}).out;
         metrics_exporter_prometheus = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".metrics-exporter-prometheus."0.17.2" {inherit profileName;}).out;
+        thiserror = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".thiserror."1.0.69" {inherit profileName;}).out;
         tokio = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tokio."1.52.3" {inherit profileName;}).out;
         tracing = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tracing."0.1.44" {inherit profileName;}).out;
         tracing_subscriber = (rustPackages."registry+https://github.com/rust-lang/crates.io-index".tracing-subscriber."0.3.23" {inherit profileName;}).out;
       };
     });
 
+    "registry+https://github.com/rust-lang/crates.io-index".xattr."1.6.1" = overridableMkRustCrate (profileName: rec {
+      name = "xattr";
+      version = "1.6.1";
+      registry = "registry+https://github.com/rust-lang/crates.io-index";
+      src = fetchCratesIo {
+        inherit name version;
+        sha256 = "32e45ad4206f6d2479085147f02bc2ef834ac85886624a23575ae137c8aa8156";
+      };
+      features = builtins.concatLists [
+        ["default"]
+        ["unsupported"]
+      ];
+      dependencies = {
+        ${
+          if hostPlatform.parsed.kernel.name == "freebsd" || hostPlatform.parsed.kernel.name == "netbsd"
+          then "libc"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".libc."0.2.186" {inherit profileName;}).out;
+        ${
+          if hostPlatform.parsed.kernel.name == "android" || hostPlatform.parsed.kernel.name == "linux" || hostPlatform.parsed.kernel.name == "darwin" || hostPlatform.parsed.kernel.name == "hurd"
+          then "rustix"
+          else null
+        } =
+          (rustPackages."registry+https://github.com/rust-lang/crates.io-index".rustix."1.1.4" {inherit profileName;}).out;
+      };
+    });
+
     "registry+https://github.com/rust-lang/crates.io-index".xdg."2.5.2" = overridableMkRustCrate (profileName: rec {
       name = "xdg";
       version = "2.5.2";
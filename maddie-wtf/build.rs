@@ -1,8 +1,9 @@
 use std::{
     env::{self, VarError},
-    fs::File,
+    fs::{self, File},
     io::{self, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use grass::{Options, OutputStyle};
@@ -14,9 +15,18 @@ fn main() {
         panic!("failed to acquire build-time information: {}", error);
     }
 
+    record_build_time();
+
     if let Err(error) = compile_stylesheet() {
         panic!("{error}");
     }
+
+    // The example content is only embedded when it's going to be served.
+    if env::var_os("CARGO_FEATURE_DEMO").is_some() {
+        if let Err(error) = embed_demo_files() {
+            panic!("{error}");
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -34,6 +44,33 @@ enum CompileStylesheetError {
     WriteCss(PathBuf, #[source] io::Error),
 }
 
+#[derive(Debug, Error)]
+enum EmbedDemoFilesError {
+    #[error("could not read value of env var {0}: {1}")]
+    Var(&'static str, #[source] VarError),
+
+    #[error("could not list files in {0}: {1}")]
+    ReadDir(PathBuf, #[source] io::Error),
+
+    #[error("could not write demo file list to {0}: {1}")]
+    WriteList(PathBuf, #[source] io::Error),
+}
+
+/// Record when the binary was built, in seconds since the epoch. Builds that are meant to be
+/// reproducible can set `SOURCE_DATE_EPOCH` to use instead.
+fn record_build_time() {
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let built_at = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs())
+        });
+    println!("cargo:rustc-env=BUILT_AT={built_at}");
+}
+
 fn compile_stylesheet() -> Result<(), CompileStylesheetError> {
     println!("cargo:rerun-if-changed=scss/");
     let compiled_css = grass::from_path(
@@ -52,3 +89,60 @@ fn compile_stylesheet() -> Result<(), CompileStylesheetError> {
     write!(stylesheet, "{}", compiled_css)
         .map_err(|err| CompileStylesheetError::WriteCss(out_path, err))
 }
+
+/// Generate a list of every file in the example content and static directories, embedded with
+/// `include_bytes!`, for the binary to serve in demo mode.
+fn embed_demo_files() -> Result<(), EmbedDemoFilesError> {
+    println!("cargo:rerun-if-changed=example-content/");
+    println!("cargo:rerun-if-changed=static/");
+
+    let manifest_dir: PathBuf = env::var("CARGO_MANIFEST_DIR")
+        .map_err(|err| EmbedDemoFilesError::Var("CARGO_MANIFEST_DIR", err))?
+        .into();
+
+    let mut files = Vec::new();
+    for (source, target) in [("example-content", "content"), ("static", "static")] {
+        collect_files(&manifest_dir.join(source), Path::new(target), &mut files)?;
+    }
+    files.sort();
+
+    let mut list = String::from("&[\n");
+    for (target, source) in files {
+        list.push_str(&format!(
+            "    ({:?}, include_bytes!({:?}).as_slice()),\n",
+            target.to_string_lossy(),
+            source.to_string_lossy(),
+        ));
+    }
+    list.push(']');
+
+    let mut out_path: PathBuf = env::var("OUT_DIR")
+        .map_err(|err| EmbedDemoFilesError::Var("OUT_DIR", err))?
+        .into();
+    out_path.push("demo_files.rs");
+
+    fs::write(&out_path, list).map_err(|err| EmbedDemoFilesError::WriteList(out_path, err))
+}
+
+fn collect_files(
+    dir: &Path,
+    target: &Path,
+    files: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<(), EmbedDemoFilesError> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| EmbedDemoFilesError::ReadDir(dir.to_owned(), err))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|err| EmbedDemoFilesError::ReadDir(dir.to_owned(), err))?;
+        let path = entry.path();
+        let target = target.join(entry.file_name());
+
+        if path.is_dir() {
+            collect_files(&path, &target, files)?;
+        } else {
+            files.push((target, path));
+        }
+    }
+
+    Ok(())
+}
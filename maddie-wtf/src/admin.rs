@@ -0,0 +1,232 @@
+use std::{collections::BTreeMap, convert::Infallible, sync::Arc};
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, Request, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive},
+        IntoResponse, Response, Sse,
+    },
+    routing::get,
+    Json, Router,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
+use tracing::{debug, info, warn};
+use www::observability::{LogFilter, SetLogFilterError};
+
+use crate::{
+    crypto::constant_time_eq,
+    state::{
+        self,
+        embargo::EmbargoedPost,
+        manifest::{Manifest, ManifestDiff},
+        Content, RequestLog, RequestSummary, Settings, Site, StaleDraft, StaleNode,
+    },
+    templates::pages,
+};
+
+/// The routes nested under `/admin`, all of which require the given token to be presented as a
+/// bearer token.
+pub fn routes(token: String) -> Router<state::State> {
+    Router::new()
+        .route("/requests", get(request_log))
+        .route("/drafts", get(stale_drafts))
+        .route("/stale", get(stale_nodes))
+        .route("/manifest", get(manifest))
+        .route("/diff", get(diff).post(diff_from))
+        .route("/embargoed", get(embargoed))
+        .route("/log-level", get(log_level).post(set_log_level))
+        .route("/flags", get(flags).post(sign_flags))
+        .route("/feed/:post", get(feed_preview))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(token),
+            require_token,
+        ))
+}
+
+async fn require_token(
+    State(token): State<Arc<str>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|val| val.to_str().ok())
+        .and_then(|val| val.strip_prefix("Bearer "))
+        .is_some_and(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes()));
+
+    if authorized {
+        next.run(request).await
+    } else {
+        warn!(route = %request.uri(), "rejecting unauthorized admin request");
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+/// Streams a summary of every request handled by the server as server-sent events, starting with
+/// the most recent ones still in the buffer.
+async fn request_log(
+    State(request_log): State<RequestLog>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (recent, receiver) = request_log.subscribe();
+    debug!(replayed = %recent.len(), "subscribed to request log");
+
+    let live = BroadcastStream::new(receiver).filter_map(|result| match result {
+        Ok(summary) => Some(summary),
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+            warn!(%skipped, "request log subscriber lagged behind");
+            None
+        }
+    });
+
+    let events = tokio_stream::iter(recent).chain(live).filter_map(to_event);
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Lists the drafts that have been left unfinished for longer than the configured age, oldest
+/// first.
+async fn stale_drafts(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+) -> Json<Vec<StaleDraft>> {
+    let today = Utc::now().date_naive();
+    Json(content.stale_drafts(today, settings.max_draft_age()))
+}
+
+/// Lists the files that failed to load the last time they were loaded, along with whether their
+/// previous versions are still being served.
+async fn stale_nodes(State(content): State<Content>) -> Json<Vec<StaleNode>> {
+    Json(content.errors())
+}
+
+/// Lists the posts that are embargoed, soonest to be published first, along with where each can be
+/// previewed if previews are enabled.
+async fn embargoed(State(content): State<Content>) -> Json<Vec<EmbargoedPost>> {
+    Json(content.embargoed())
+}
+
+/// The directives currently deciding which logs are recorded, in the same form as `RUST_LOG`.
+async fn log_level(State(log_filter): State<LogFilter>) -> Result<String, StatusCode> {
+    log_filter.current().map_err(|error| {
+        warn!(%error, "failed to read log filter");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Replaces the directives deciding which logs are recorded with the ones in the body, e.g.
+/// `info,maddie_wtf::state=debug`, until they're changed again or the server restarts.
+async fn set_log_level(
+    State(log_filter): State<LogFilter>,
+    directives: String,
+) -> Result<String, (StatusCode, String)> {
+    match log_filter.set(directives.trim()) {
+        Ok(()) => {
+            info!(directives = %directives.trim(), "changed log filter");
+            Ok(directives.trim().to_owned())
+        }
+        Err(error @ SetLogFilterError::Parse(_)) => {
+            Err((StatusCode::BAD_REQUEST, error.to_string()))
+        }
+        Err(error) => {
+            warn!(%error, "failed to change log filter");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, error.to_string()))
+        }
+    }
+}
+
+/// Every feature flag, and whether it's on for everyone.
+async fn flags(State(settings): State<Settings>) -> Json<BTreeMap<String, bool>> {
+    Json(settings.flags().defaults().clone())
+}
+
+/// Signs the overrides to the feature flags in the body, e.g. `new-layout,!old-thing`, returning
+/// the value to set as the `flags` cookie (or `X-Feature-Flags` header) to see the site with them.
+async fn sign_flags(
+    State(settings): State<Settings>,
+    overrides: String,
+) -> Result<String, (StatusCode, String)> {
+    settings
+        .flags()
+        .sign(&overrides)
+        .map_err(|error| (StatusCode::BAD_REQUEST, error.to_string()))
+}
+
+/// The RSS items for a post, even if it's still a draft, exactly as they'll appear in the feed, to
+/// check for markup or links that won't survive the trip to a feed reader.
+async fn feed_preview(
+    State(content): State<Content>,
+    State(site): State<Site>,
+    Path(post): Path<String>,
+) -> Result<Response, StatusCode> {
+    let preview = content
+        .nodes(true)
+        .into_rss_preview(&post, site.base_url())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let feed = pages::rss_preview(preview, &site).await;
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/rss+xml")],
+        feed.into_string(),
+    )
+        .into_response())
+}
+
+/// The URL of every post, entry and page currently served, along with a hash of the whole lot to
+/// compare against with `/admin/diff` later.
+async fn manifest(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+) -> Json<Manifest> {
+    Json(content.manifest(settings.show_drafts()))
+}
+
+#[derive(Deserialize)]
+struct DiffQuery {
+    since: String,
+}
+
+/// Lists the URLs that have been added, changed or removed since the content had the manifest with
+/// the hash `since`. Only recent manifests are remembered, and none survive a restart, so
+/// [`diff_from`] takes the whole of an older manifest instead.
+async fn diff(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+    Query(query): Query<DiffQuery>,
+) -> Result<Json<ManifestDiff>, StatusCode> {
+    match content.diff_since(&query.since, settings.show_drafts()) {
+        Some(diff) => Ok(Json(diff)),
+        None => {
+            debug!(since = %query.since, "no remembered manifest with that hash");
+            Err(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+/// Lists the URLs that have been added, changed or removed since the manifest in the request body,
+/// as previously fetched from `/admin/manifest`, however long ago that was.
+async fn diff_from(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+    Json(previous): Json<Manifest>,
+) -> Json<ManifestDiff> {
+    Json(content.manifest(settings.show_drafts()).diff(&previous))
+}
+
+fn to_event(summary: RequestSummary) -> Option<Result<Event, Infallible>> {
+    match Event::default().json_data(&summary) {
+        Ok(event) => Some(Ok(event)),
+        Err(error) => {
+            warn!(%error, "failed to serialize request summary");
+            None
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use std::{collections::HashSet, fmt};
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::{errors::HandlerError, templates::partials};
+
+/// An accessibility problem found in a rendered page.
+#[derive(Debug)]
+pub enum Issue {
+    MissingAlt { src: String },
+    DuplicateId(String),
+    EmptyLink { href: String },
+    SkippedHeading { from: usize, to: usize },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MissingAlt { src } => write!(f, "image {src} has no alt text"),
+            Issue::DuplicateId(id) => write!(f, "id \"{id}\" is used more than once"),
+            Issue::EmptyLink { href } => write!(f, "link to {href} has no text"),
+            Issue::SkippedHeading { from, to } => {
+                write!(f, "heading skips from h{from} to h{to}")
+            }
+        }
+    }
+}
+
+/// Look for common accessibility problems in a page: images without alt text, IDs used more than
+/// once, links with nothing to read out, and headings that skip a level.
+///
+/// This only understands as much HTML as the templates and comrak produce, which is plenty to catch
+/// them introducing a problem.
+pub fn audit(html: &str) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut ids = HashSet::new();
+    let mut reported_ids = HashSet::new();
+    let mut heading = None;
+    // The target of the link currently open, and whether anything inside it can be read out.
+    let mut link: Option<(String, bool)> = None;
+
+    for token in Tokens::new(html) {
+        match token {
+            Token::Start { name, attrs } => {
+                let attr = |wanted: &str| {
+                    attrs
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+                        .map(|(_, value)| value.trim())
+                };
+                let labelled = ["aria-label", "title"]
+                    .into_iter()
+                    .any(|name| attr(name).is_some_and(|value| !value.is_empty()));
+
+                if let Some(id) = attr("id") {
+                    if !ids.insert(id.to_owned()) && reported_ids.insert(id.to_owned()) {
+                        issues.push(Issue::DuplicateId(id.to_owned()));
+                    }
+                }
+
+                match name.as_str() {
+                    "img" => {
+                        let alt = attr("alt").filter(|alt| !alt.is_empty());
+                        let decorative = attr("role") == Some("presentation")
+                            || attr("aria-hidden") == Some("true");
+                        if alt.is_none() && !decorative {
+                            issues.push(Issue::MissingAlt {
+                                src: attr("src").unwrap_or_default().to_owned(),
+                            });
+                        }
+                        if let Some((_, readable)) = &mut link {
+                            *readable |= alt.is_some() || labelled;
+                        }
+                    }
+                    "a" => {
+                        link = Some((attr("href").unwrap_or_default().to_owned(), labelled));
+                    }
+                    "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                        let level = usize::from(name.as_bytes()[1] - b'0');
+                        if let Some(from) = heading {
+                            if level > from + 1 {
+                                issues.push(Issue::SkippedHeading { from, to: level });
+                            }
+                        }
+                        heading = Some(level);
+                    }
+                    _ => {}
+                }
+            }
+            Token::End { name } => {
+                if name == "a" {
+                    if let Some((href, false)) = link.take() {
+                        issues.push(Issue::EmptyLink { href });
+                    }
+                }
+            }
+            Token::Text(text) => {
+                if let Some((_, readable)) = &mut link {
+                    *readable |= !text.trim().is_empty();
+                }
+            }
+        }
+    }
+
+    issues
+}
+
+/// Every ID given to an element in a page, i.e. everything a `#fragment` can link to.
+pub fn ids(html: &str) -> HashSet<String> {
+    Tokens::new(html)
+        .filter_map(|token| match token {
+            Token::Start { attrs, .. } => attrs
+                .into_iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("id"))
+                .map(|(_, id)| id.to_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+enum Token<'a> {
+    Start {
+        name: String,
+        attrs: Vec<(&'a str, &'a str)>,
+    },
+    End {
+        name: String,
+    },
+    Text(&'a str),
+}
+
+/// Splits HTML into start tags, end tags and the text between them, skipping comments, doctypes
+/// and the contents of scripts and styles.
+struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(html: &'a str) -> Self {
+        Self { rest: html }
+    }
+
+    /// Move past `end`, or to the end of the input if it never appears.
+    fn skip_past(&mut self, end: &str) {
+        self.rest = self
+            .rest
+            .find(end)
+            .map_or("", |index| &self.rest[index + end.len()..]);
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+
+            let Some(rest) = self.rest.strip_prefix('<') else {
+                let end = self.rest.find('<').unwrap_or(self.rest.len());
+                let text = &self.rest[..end];
+                self.rest = &self.rest[end..];
+                return Some(Token::Text(text));
+            };
+
+            if rest.starts_with("!--") {
+                self.skip_past("-->");
+                continue;
+            }
+
+            if rest.starts_with('!') || rest.starts_with('?') {
+                self.skip_past(">");
+                continue;
+            }
+
+            if let Some(rest) = rest.strip_prefix('/') {
+                let name = tag_name(rest);
+                self.skip_past(">");
+                return Some(Token::End { name });
+            }
+
+            let name = tag_name(rest);
+            let (attrs, after) = parse_attrs(&rest[name.len()..]);
+            self.rest = after;
+
+            if name == "script" || name == "style" {
+                self.skip_past(&format!("</{name}"));
+                self.skip_past(">");
+            }
+
+            return Some(Token::Start { name, attrs });
+        }
+    }
+}
+
+fn tag_name(raw: &str) -> String {
+    raw.chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Parse the attributes of a start tag, up to and including the `>` that closes it, returning them
+/// along with whatever follows the tag.
+fn parse_attrs(mut raw: &str) -> (Vec<(&str, &str)>, &str) {
+    let mut attrs = Vec::new();
+
+    loop {
+        raw = raw.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if raw.is_empty() {
+            return (attrs, raw);
+        }
+        if let Some(after) = raw.strip_prefix('>') {
+            return (attrs, after);
+        }
+
+        let name_end = raw
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(raw.len())
+            .max(1);
+        let name = &raw[..name_end];
+        raw = raw[name_end..].trim_start();
+
+        let Some(after) = raw.strip_prefix('=') else {
+            attrs.push((name, ""));
+            continue;
+        };
+        raw = after.trim_start();
+
+        let value = match raw.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = raw[1..].find(quote).map_or(raw.len(), |end| end + 1);
+                let value = &raw[1..end];
+                raw = raw.get(end + 1..).unwrap_or_default();
+                value
+            }
+            _ => {
+                let end = raw
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(raw.len());
+                let value = &raw[..end];
+                raw = &raw[end..];
+                value
+            }
+        };
+        attrs.push((name, value));
+    }
+}
+
+/// Middleware that audits every page served, logging anything it finds and listing it in an
+/// overlay at the bottom of the page. This is only used in development, since it has to buffer
+/// and rewrite every page.
+pub async fn overlay(request: Request, next: Next) -> Response {
+    let route = request.uri().to_string();
+    let response = next.run(request).await;
+
+    let is_html = response.status() == StatusCode::OK
+        && response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|val| val.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("text/html"));
+
+    if !is_html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            warn!(%error, "failed to buffer response body");
+            return HandlerError::InternalError.into_response();
+        }
+    };
+    let html = String::from_utf8_lossy(&bytes);
+
+    let issues = audit(&html);
+    if issues.is_empty() {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    for issue in &issues {
+        warn!(%route, %issue, "accessibility audit found a problem");
+    }
+
+    let overlay = partials::audit_overlay(&issues).into_string();
+    let html = match html.rfind("</body>") {
+        Some(index) => format!("{}{overlay}{}", &html[..index], &html[index..]),
+        None => format!("{html}{overlay}"),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(html))
+}
@@ -1 +1,15 @@
+use chrono::{DateTime, Utc};
+
 include!(concat!(env!("OUT_DIR"), "/built.rs"));
+
+/// The commit the binary was built from, which `built` finds when it's built in a git checkout,
+/// and which can otherwise be passed in as `COMMIT_HASH` (like in Nix builds).
+pub fn commit_hash() -> Option<&'static str> {
+    GIT_COMMIT_HASH.or(option_env!("COMMIT_HASH"))
+}
+
+/// When the binary was built.
+pub fn built_at() -> Option<DateTime<Utc>> {
+    let secs = env!("BUILT_AT").parse().ok()?;
+    DateTime::from_timestamp(secs, 0)
+}
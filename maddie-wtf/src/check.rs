@@ -0,0 +1,686 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, io,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::NaiveDate;
+use comrak::{
+    nodes::{AstNode, NodeValue},
+    parse_document, Arena, ComrakOptions,
+};
+use ignore::Walk;
+use maud::Render;
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    audit,
+    config::{ExternalLint, LintConfig, Severity},
+    state::{find_delimiter, node_key, Config},
+};
+
+/// Lint every markdown file in the content path, printing a line for each problem found.
+///
+/// Fails if any lint configured as an error fires, or if any lint fires at all when warnings are
+/// denied.
+pub async fn check(config: Config, deny_warnings: bool) -> Result<(), CheckError> {
+    let lints = &config.lints;
+    let deny_warnings = deny_warnings || lints.strict;
+
+    let mut findings = Vec::new();
+    let mut tags: HashMap<String, Vec<(Utf8PathBuf, usize)>> = HashMap::new();
+    let mut see_also = Vec::new();
+    let mut fragments = Vec::new();
+    let mut nodes = HashSet::new();
+    let mut files = Vec::new();
+
+    for result in Walk::new(&config.content_path) {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(error) => {
+                error!(%error, "directory walker encountered error");
+                continue;
+            }
+        };
+
+        let Ok(path) = Utf8PathBuf::from_path_buf(entry.path().to_path_buf()) else {
+            warn!(path = ?entry.path(), "skipping entry with path that contains invalid UTF-8");
+            continue;
+        };
+
+        if !path.is_file() || path.extension() != Some("md") {
+            continue;
+        }
+
+        let relative_path = path
+            .strip_prefix(&config.content_path)
+            .unwrap_or(&path)
+            .to_owned();
+        nodes.insert(node_key(&relative_path));
+        files.push(relative_path.clone());
+
+        let raw = match fs::read_to_string(&path).await {
+            Ok(raw) => raw,
+            Err(error) => {
+                findings.push(Finding {
+                    path: relative_path,
+                    line: 1,
+                    severity: Severity::Error,
+                    lint: "read".to_owned(),
+                    message: format!("failed to read file: {error}"),
+                });
+                continue;
+            }
+        };
+
+        let mut file = FileLinter {
+            lints,
+            path: &relative_path,
+            findings: &mut findings,
+        };
+        file.lint(&raw, &mut tags, &mut see_also, &mut fragments);
+    }
+
+    for link in see_also {
+        if link_resolves(&link.target, &nodes, &tags) {
+            if link.target.contains('#') {
+                fragments.push(link);
+            }
+        } else {
+            findings.push(Finding {
+                path: link.path,
+                line: link.line,
+                severity: lints.see_also,
+                lint: "see_also".to_owned(),
+                message: format!(
+                    "\"{}\" is neither an http(s) URL nor the path of any content",
+                    link.target
+                ),
+            });
+        }
+    }
+
+    for (tag, uses) in tags {
+        if let [(path, line)] = uses.as_slice() {
+            findings.push(Finding {
+                path: path.clone(),
+                line: *line,
+                severity: lints.single_use_tags,
+                lint: "single_use_tags".to_owned(),
+                message: format!("tag \"{tag}\" isn't used by any other post"),
+            });
+        }
+    }
+
+    // Files are passed to the tools in a consistent order, so their output is too.
+    files.sort();
+
+    let rendered = if lints.rendered_html != Severity::Off || lints.fragments != Severity::Off {
+        render_all(&config, &files).await
+    } else {
+        BTreeMap::new()
+    };
+
+    if lints.rendered_html != Severity::Off {
+        findings.extend(audit_rendered(&rendered, lints.rendered_html));
+    }
+
+    if lints.fragments != Severity::Off {
+        findings.extend(check_fragments(fragments, &rendered, lints.fragments));
+    }
+
+    for external in &lints.external {
+        if external.severity != Severity::Off {
+            findings.extend(run_external(external, &config.content_path, &files).await?);
+        }
+    }
+
+    findings.retain(|finding| finding.severity != Severity::Off);
+    findings.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+
+    for finding in &findings {
+        println!("{finding}");
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    let warnings = findings.len() - errors;
+
+    info!(%errors, %warnings, %deny_warnings, "finished checking content");
+
+    if errors > 0 || (deny_warnings && warnings > 0) {
+        Err(CheckError::Failed { errors, warnings })
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CheckError {
+    #[error("content check failed with {errors} errors and {warnings} warnings")]
+    Failed { errors: usize, warnings: usize },
+
+    #[error("external lint {0} has an empty command")]
+    EmptyCommand(String),
+
+    #[error("failed to run external lint {name}: {source}")]
+    RunExternal {
+        name: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// Render every post and page the way the site would, keyed by the file it came from.
+///
+/// Files that failed to load are reported by the other lints, and embargoed posts aren't part of
+/// the content yet, so neither of them are included.
+async fn render_all(config: &Config, files: &[Utf8PathBuf]) -> BTreeMap<Utf8PathBuf, String> {
+    let content = config.load_content().await;
+
+    files
+        .iter()
+        .filter_map(|file| {
+            let node = node_key(file);
+            let html = if let Some(post) = content.post(&node, true) {
+                post.render()
+            } else {
+                content.page(&node)?.render()
+            };
+            Some((file.clone(), html.into_string()))
+        })
+        .collect()
+}
+
+/// Audit the HTML every post and page renders to for accessibility problems. These are reported
+/// against the first line of the file, since there's no telling which line of the markdown (if
+/// any) they came from.
+fn audit_rendered(
+    rendered: &BTreeMap<Utf8PathBuf, String>,
+    severity: Severity,
+) -> impl Iterator<Item = Finding> + '_ {
+    rendered.iter().flat_map(move |(file, html)| {
+        audit::audit(html).into_iter().map(move |issue| Finding {
+            path: file.clone(),
+            line: 1,
+            severity,
+            lint: "rendered_html".to_owned(),
+            message: issue.to_string(),
+        })
+    })
+}
+
+/// Check that every link to a `#fragment` leads to something with that ID, in the HTML the page it
+/// leads to renders to.
+fn check_fragments(
+    links: Vec<Link>,
+    rendered: &BTreeMap<Utf8PathBuf, String>,
+    severity: Severity,
+) -> Vec<Finding> {
+    let mut ids = HashMap::new();
+    let mut findings = Vec::new();
+
+    // Links lead to nodes, which are rendered from files that aren't always named after them.
+    let files = rendered
+        .keys()
+        .map(|file| (node_key(file), file))
+        .collect::<HashMap<_, _>>();
+
+    for link in links {
+        let Some((target, fragment)) = link.target.split_once('#') else {
+            continue;
+        };
+        let file = if target.is_empty() {
+            link.path.clone()
+        } else {
+            let Some(node) = fragment_target(target) else {
+                continue;
+            };
+            match files.get(&node) {
+                Some(file) => (*file).clone(),
+                None => continue,
+            }
+        };
+
+        // Anything that isn't rendered has either been reported already, or isn't content whose
+        // IDs can be known here.
+        let Some(html) = rendered.get(&file) else {
+            continue;
+        };
+        let ids = ids.entry(file).or_insert_with(|| audit::ids(html));
+
+        if !ids.contains(fragment) {
+            findings.push(Finding {
+                path: link.path,
+                line: link.line,
+                severity,
+                lint: "fragments".to_owned(),
+                message: format!(
+                    "\"{}\" links to #{fragment}, which isn't on that page",
+                    link.target
+                ),
+            });
+        }
+    }
+
+    findings
+}
+
+/// The content (relative to the content path, without an extension) that a path on the site
+/// serves, if it serves a single post or page.
+fn fragment_target(target: &str) -> Option<Utf8PathBuf> {
+    let path = target.strip_prefix('/')?;
+    let path = path
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        [""] => Some("_index".into()),
+        ["posts", post] | ["posts", post, "entry", _] => Some((*post).into()),
+        [page] if !matches!(*page, "posts" | "chrono" | "tags" | "rss.xml") => Some((*page).into()),
+        _ => None,
+    }
+}
+
+/// Run an external tool over every file, turning each line of its output that names a file and a
+/// line into a finding.
+///
+/// Tools tend to exit unsuccessfully when they find anything, so the exit status is ignored, and
+/// only failing to run the tool at all is an error.
+async fn run_external(
+    external: &ExternalLint,
+    content_path: &Utf8Path,
+    files: &[Utf8PathBuf],
+) -> Result<Vec<Finding>, CheckError> {
+    let Some((program, args)) = external.command.split_first() else {
+        return Err(CheckError::EmptyCommand(external.name.clone()));
+    };
+
+    let output = Command::new(program)
+        .args(args)
+        .args(files)
+        .current_dir(content_path)
+        .output()
+        .await
+        .map_err(|source| CheckError::RunExternal {
+            name: external.name.clone(),
+            source,
+        })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let findings = stdout
+        .lines()
+        .filter_map(|line| {
+            let finding = parse_external_finding(line, external);
+            if finding.is_none() && !line.trim().is_empty() {
+                debug!(lint = %external.name, %line, "ignoring line of output without a location");
+            }
+            finding
+        })
+        .collect::<Vec<_>>();
+
+    info!(lint = %external.name, findings = %findings.len(), "ran external lint");
+    Ok(findings)
+}
+
+/// Parse a line of output in the form `path:line: message`, optionally with a column number after
+/// the line number.
+fn parse_external_finding(output: &str, external: &ExternalLint) -> Option<Finding> {
+    let (path, rest) = output.split_once(':')?;
+    let (line, rest) = rest.split_once(':')?;
+    let line = line.trim().parse::<usize>().ok()?;
+
+    let rest = match rest.split_once(':') {
+        Some((column, rest)) if column.trim().parse::<usize>().is_ok() => rest,
+        _ => rest,
+    };
+
+    Some(Finding {
+        path: Utf8PathBuf::from(path.trim().trim_start_matches("./")),
+        line,
+        severity: external.severity,
+        lint: external.name.clone(),
+        message: rest.trim().to_owned(),
+    })
+}
+
+/// A link to be checked once every file has been seen, e.g. one given in the `see_also` list of a
+/// post.
+struct Link {
+    path: Utf8PathBuf,
+    line: usize,
+    target: String,
+}
+
+struct Finding {
+    path: Utf8PathBuf,
+    line: usize,
+    severity: Severity,
+    lint: String,
+    message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}[{}]: {}",
+            self.path, self.line, self.severity, self.lint, self.message
+        )
+    }
+}
+
+/// The parts of the frontmatter of posts, thread entries and pages that are needed for linting.
+/// Anything else is ignored here, since it's validated when the content is loaded.
+#[derive(Deserialize, Default)]
+struct Frontmatter {
+    title: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    see_also: Vec<String>,
+}
+
+/// A frontmatter block and the markdown that follows it, along with the line each starts on.
+pub struct Section<'a> {
+    pub frontmatter: &'a str,
+    pub frontmatter_line: usize,
+    pub body: &'a str,
+    pub body_line: usize,
+}
+
+struct FileLinter<'a> {
+    lints: &'a LintConfig,
+    path: &'a Utf8Path,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl FileLinter<'_> {
+    fn push(&mut self, line: usize, severity: Severity, lint: &'static str, message: String) {
+        self.findings.push(Finding {
+            path: self.path.to_owned(),
+            line,
+            severity,
+            lint: lint.to_owned(),
+            message,
+        });
+    }
+
+    fn lint(
+        &mut self,
+        raw: &str,
+        tags: &mut HashMap<String, Vec<(Utf8PathBuf, usize)>>,
+        see_also: &mut Vec<Link>,
+        fragments: &mut Vec<Link>,
+    ) {
+        // Only posts (whose file names, or bundle names, start with a date) can be split into
+        // thread entries.
+        let is_post = node_key(self.path)
+            .file_name()
+            .is_some_and(|name| NaiveDate::parse_and_remainder(name, "%Y-%m-%d").is_ok());
+
+        let Some(sections) = split_sections(raw, is_post) else {
+            self.push(
+                1,
+                Severity::Error,
+                "frontmatter",
+                "missing or malformed frontmatter".to_owned(),
+            );
+            return;
+        };
+
+        for (i, section) in sections.iter().enumerate() {
+            let frontmatter = match toml::from_str::<Frontmatter>(section.frontmatter.trim()) {
+                Ok(frontmatter) => frontmatter,
+                Err(error) => {
+                    self.push(
+                        section.frontmatter_line,
+                        Severity::Error,
+                        "frontmatter",
+                        format!("failed to parse frontmatter: {}", error.message()),
+                    );
+                    Frontmatter::default()
+                }
+            };
+
+            // Only the first frontmatter block of a post carries its tags and related links.
+            if is_post && i == 0 {
+                let line_of = |key: &str| {
+                    section
+                        .frontmatter
+                        .lines()
+                        .position(|line| line.trim_start().starts_with(key))
+                        .map_or(section.frontmatter_line, |offset| {
+                            section.frontmatter_line + offset
+                        })
+                };
+
+                let tags_line = line_of("tags");
+                for tag in frontmatter.tags {
+                    tags.entry(tag)
+                        .or_default()
+                        .push((self.path.to_owned(), tags_line));
+                }
+
+                let see_also_line = line_of("see_also");
+                see_also.extend(frontmatter.see_also.into_iter().map(|target| Link {
+                    path: self.path.to_owned(),
+                    line: see_also_line,
+                    target,
+                }));
+            }
+
+            // Pages don't render their title as a heading, so they're expected to start with one.
+            let title = frontmatter.title.as_deref().filter(|_| is_post);
+            self.lint_body(section, title, fragments);
+        }
+    }
+
+    fn lint_body(&mut self, section: &Section<'_>, title: Option<&str>, fragments: &mut Vec<Link>) {
+        let arena = Arena::new();
+        let root = parse_document(&arena, section.body, &ComrakOptions::default());
+
+        // Source positions are relative to the start of the body.
+        let line_of =
+            |node: &AstNode<'_>| node.data.borrow().sourcepos.start.line + section.body_line - 1;
+
+        let mut last_heading_level = None;
+        let mut first_heading = true;
+
+        for node in root.descendants() {
+            let line = line_of(node);
+
+            match &node.data.borrow().value {
+                NodeValue::Heading(heading) => {
+                    let level = heading.level;
+
+                    if let Some(last) = last_heading_level {
+                        if level > last + 1 {
+                            self.push(
+                                line,
+                                self.lints.heading_levels,
+                                "heading_levels",
+                                format!("heading skips from level {last} to level {level}"),
+                            );
+                        }
+                    }
+                    last_heading_level = Some(level);
+
+                    if first_heading {
+                        first_heading = false;
+
+                        if let Some(title) = title {
+                            if normalize(&collect_text(node)) == normalize(title) {
+                                self.push(
+                                    line,
+                                    self.lints.duplicate_title,
+                                    "duplicate_title",
+                                    "first heading duplicates the title".to_owned(),
+                                );
+                            }
+                        }
+                    }
+                }
+                NodeValue::Link(link) if link.url.contains('#') => {
+                    fragments.push(Link {
+                        path: self.path.to_owned(),
+                        line,
+                        target: link.url.clone(),
+                    });
+                }
+                NodeValue::Image(image) => {
+                    let alt = collect_text(node);
+                    if alt.trim().is_empty() {
+                        self.push(
+                            line,
+                            self.lints.image_alt,
+                            "image_alt",
+                            format!("image {} has no alt text", image.url),
+                        );
+                    } else if normalize(&alt) == normalize(&image.title) {
+                        self.push(
+                            line,
+                            self.lints.figure_alt,
+                            "figure_alt",
+                            format!("image {} has the same alt text as its caption", image.url),
+                        );
+                    }
+                }
+                NodeValue::Paragraph => {
+                    let words = collect_text(node).split_whitespace().count();
+                    if words > self.lints.max_paragraph_words {
+                        self.push(
+                            line,
+                            self.lints.long_paragraphs,
+                            "long_paragraphs",
+                            format!(
+                                "paragraph is {words} words long (the limit is {})",
+                                self.lints.max_paragraph_words
+                            ),
+                        );
+                    }
+                }
+                NodeValue::Text(text) if text.contains("http://") || text.contains("https://") => {
+                    let in_link = node.ancestors().skip(1).any(|ancestor| {
+                        matches!(
+                            ancestor.data.borrow().value,
+                            NodeValue::Link(_) | NodeValue::Image(_)
+                        )
+                    });
+
+                    if !in_link {
+                        self.push(
+                            line,
+                            self.lints.bare_urls,
+                            "bare_urls",
+                            "bare URL should be written as a link".to_owned(),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Split a file into its frontmatter blocks and the markdown following each of them, the same way
+/// the content loader does.
+pub fn split_sections(raw: &str, allow_thread: bool) -> Option<Vec<Section<'_>>> {
+    let line_at = |offset: usize| raw[..offset].matches('\n').count() + 1;
+
+    let mut offset = raw.strip_prefix("---").map(|_| 3)?;
+    let mut sections = Vec::new();
+
+    loop {
+        let frontmatter_end = offset + find_delimiter(&raw[offset..])?;
+        let body_start = frontmatter_end + 3;
+
+        // Another frontmatter block starts wherever the next pair of `---` delimiters is.
+        let next = find_delimiter(&raw[body_start..]).and_then(|next| {
+            let next = body_start + next;
+            find_delimiter(&raw[next + 3..]).map(|_| next)
+        });
+        let body_end = next.filter(|_| allow_thread).unwrap_or(raw.len());
+
+        sections.push(Section {
+            frontmatter: &raw[offset..frontmatter_end],
+            frontmatter_line: line_at(offset),
+            body: &raw[body_start..body_end],
+            body_line: line_at(body_start),
+        });
+
+        if body_end == raw.len() {
+            return Some(sections);
+        }
+        offset = body_end + 3;
+    }
+}
+
+/// Whether a link is either an http(s) URL or a path that the site serves, given the paths of all
+/// the content (relative to the content path, without extensions) and every tag in use.
+fn link_resolves(
+    target: &str,
+    nodes: &HashSet<Utf8PathBuf>,
+    tags: &HashMap<String, Vec<(Utf8PathBuf, usize)>>,
+) -> bool {
+    if target.starts_with("https://") || target.starts_with("http://") {
+        return true;
+    }
+
+    let Some(path) = target.strip_prefix('/') else {
+        return false;
+    };
+    let path = path
+        .split(['#', '?'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+
+    let is_post = |post: &str| {
+        nodes.contains(Utf8Path::new(post))
+            && NaiveDate::parse_and_remainder(post, "%Y-%m-%d").is_ok()
+    };
+
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        [""] | ["posts"] | ["chrono"] | ["tags"] | ["rss.xml"] => true,
+        ["posts", post] => is_post(post),
+        ["posts", post, "entry", index] => is_post(post) && index.parse::<usize>().is_ok(),
+        ["tagged", tag] => tags.contains_key(*tag),
+        [page] => *page != "_index" && nodes.contains(Utf8Path::new(page)),
+        _ => false,
+    }
+}
+
+/// Gather the plain text content of a node, with line breaks replaced by spaces.
+fn collect_text<'a>(node: &'a AstNode<'a>) -> String {
+    let mut text = String::new();
+    for descendant in node.descendants() {
+        match &descendant.data.borrow().value {
+            NodeValue::Text(literal) => text.push_str(literal),
+            NodeValue::Code(code) => text.push_str(&code.literal),
+            NodeValue::SoftBreak | NodeValue::LineBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Lowercase a title, dropping markdown formatting and punctuation, so that headings and
+/// frontmatter titles can be compared.
+fn normalize(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
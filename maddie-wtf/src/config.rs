@@ -0,0 +1,966 @@
+use std::{collections::BTreeMap, env, fmt, io, num::NonZeroUsize};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+use www::config::Environment;
+
+use crate::git::Forge;
+
+/// The structure of the optional TOML config file passed with `--config`.
+///
+/// Every field has a default, so an empty file is valid. Paths and toggles given on the command
+/// line or through the environment take precedence over the values in this file. Any `${VAR}` (or
+/// `${VAR:-default}`) in a string value is replaced with the value of that environment variable.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    /// A directory containing `content`, `static` and `themes` subdirectories, used for any of
+    /// those paths that aren't given explicitly.
+    pub site_root: Option<Utf8PathBuf>,
+    pub content_path: Option<Utf8PathBuf>,
+    pub static_path: Option<Utf8PathBuf>,
+    pub themes_path: Option<Utf8PathBuf>,
+    /// A directory of `.sublime-syntax` files, which falls back to a `syntaxes` subdirectory of
+    /// the site root if there is one, but isn't required.
+    pub syntaxes_path: Option<Utf8PathBuf>,
+    pub drafts: bool,
+    /// Whether any file in the content path failing to load aborts startup, which defaults to on
+    /// in production.
+    pub strict: Option<bool>,
+    pub site: SiteConfig,
+    pub feeds: FeedsConfig,
+    pub theme: ThemeConfig,
+    pub nav: Option<Vec<Link>>,
+    pub footer: FooterConfig,
+    pub redirects: BTreeMap<String, String>,
+    pub admin: AdminConfig,
+    pub git: GitConfig,
+    pub lints: LintConfig,
+    pub linkcheck: LinkcheckConfig,
+    pub limits: LimitsConfig,
+    pub warmup: WarmupConfig,
+    pub stale_drafts: StaleDraftsConfig,
+    pub watcher: WatcherConfig,
+    pub compression: CompressionConfig,
+    pub cache_control: CacheControlConfig,
+    pub embeds: EmbedsConfig,
+    pub embargo: EmbargoConfig,
+    pub events: EventsConfig,
+    pub flags: BTreeMap<String, bool>,
+    pub markdown: MarkdownConfig,
+    pub tags: BTreeMap<String, TagConfig>,
+}
+
+impl ConfigFile {
+    pub fn read(path: &Utf8Path) -> Result<Self, ReadConfigError> {
+        let raw = std::fs::read_to_string(path).map_err(ReadConfigError::Read)?;
+        let mut value = toml::Value::Table(raw.parse()?);
+        interpolate_values(&mut value)?;
+        Ok(value.try_into()?)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ReadConfigError {
+    #[error("failed to read config file: {0}")]
+    Read(#[source] io::Error),
+
+    #[error("config file refers to environment variable {0}, which isn't set")]
+    MissingVar(String),
+
+    #[error("config file contains an unterminated ${{...}}")]
+    UnterminatedVar,
+
+    #[error("failed to parse config file: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// Interpolate the environment into every string in `value`, however deeply it's nested. Only the
+/// parsed strings are touched, so a value can't change the structure of the file, and comments are
+/// left alone.
+fn interpolate_values(value: &mut toml::Value) -> Result<(), ReadConfigError> {
+    match value {
+        toml::Value::String(string) => *string = interpolate_env(string)?,
+        toml::Value::Array(array) => {
+            for value in array {
+                interpolate_values(value)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                interpolate_values(value)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Replace every `${VAR}` in `raw` with the value of the environment variable `VAR`, or with
+/// `default` for `${VAR:-default}` if it isn't set. `$$` is a literal `$`.
+fn interpolate_env(raw: &str) -> Result<String, ReadConfigError> {
+    use ReadConfigError::*;
+
+    let mut output = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(index) = rest.find('$') {
+        output.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            output.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let (expr, after) = after.split_once('}').ok_or(UnterminatedVar)?;
+            let (name, default) = match expr.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (expr, None),
+            };
+
+            match (env::var(name), default) {
+                (Ok(value), _) => output.push_str(&value),
+                (Err(_), Some(default)) => output.push_str(default),
+                (Err(_), None) => return Err(MissingVar(name.to_owned())),
+            }
+            rest = after;
+        } else {
+            output.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// The identity of the site, used in page titles, the header and the RSS feed.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteConfig {
+    pub title: String,
+    pub author: String,
+    pub description: String,
+    pub base_url: Url,
+    pub language: String,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self {
+            title: "maddie, wtf?!".to_owned(),
+            author: "Madeleine Mortensen".to_owned(),
+            description: "Madeleine Mortensen".to_owned(),
+            base_url: "https://maddie.wtf"
+                .parse()
+                .expect("default base URL is valid"),
+            language: "en-GB".to_owned(),
+        }
+    }
+}
+
+impl SiteConfig {
+    /// The base URL as a string without a trailing slash, ready to have an absolute path appended.
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str().trim_end_matches('/')
+    }
+}
+
+/// How much goes in the RSS feed and the sitemap before they're split across several documents,
+/// so that nothing is left out of them however much content there is.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeedsConfig {
+    /// The most entries in the RSS feed. Older entries go in archive feeds of this many entries
+    /// each, linked to from the feed as described in RFC 5005.
+    pub feed_entries: usize,
+
+    /// The most URLs in a sitemap. Once there are more, `/sitemap.xml` becomes a sitemap index
+    /// linking to several sitemaps. The protocol allows at most 50,000.
+    pub sitemap_urls: usize,
+}
+
+impl Default for FeedsConfig {
+    fn default() -> Self {
+        Self {
+            feed_entries: 100,
+            sitemap_urls: 50_000,
+        }
+    }
+}
+
+/// The names of the syntect themes used for syntax highlighting by the default theme, along with
+/// any other named themes that readers can pick between. Themes are loaded from the themes path, on
+/// top of the ones built into syntect.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub light: String,
+    pub dark: String,
+    pub named: BTreeMap<String, NamedThemeConfig>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            light: "OneHalfLight".to_owned(),
+            dark: "OneHalfDark".to_owned(),
+            named: BTreeMap::new(),
+        }
+    }
+}
+
+/// A theme that readers can pick instead of the default one, made up of a pair of syntect themes
+/// and the colours to use for the rest of the page alongside each of them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamedThemeConfig {
+    pub light: String,
+    pub dark: String,
+    #[serde(default)]
+    pub light_colors: ThemeColors,
+    #[serde(default)]
+    pub dark_colors: ThemeColors,
+}
+
+/// Overrides for the colours set by the stylesheet. Any that are missing keep the default.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeColors {
+    pub text: Option<String>,
+    pub rule: Option<String>,
+    pub bg: Option<String>,
+    pub accent: Option<String>,
+    pub code_bg: Option<String>,
+}
+
+impl ThemeColors {
+    /// The CSS custom property declarations for each colour that's set.
+    pub fn to_css(&self) -> String {
+        [
+            ("text", &self.text),
+            ("rule", &self.rule),
+            ("bg", &self.bg),
+            ("accent", &self.accent),
+            ("code-bg", &self.code_bg),
+        ]
+        .into_iter()
+        .filter_map(|(name, color)| color.as_ref().map(|color| format!("--{name}: {color};")))
+        .collect()
+    }
+}
+
+/// Settings for a single tag, given under `[tags.<name>]`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagConfig {
+    /// A colour to use in place of the theme's accent wherever the tag is shown: on its own page,
+    /// in the frontmatter of the posts tagged with it, and in the list of tags.
+    pub accent: Option<String>,
+}
+
+/// A single link in the navigation list in the site header, or in the footer.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Link {
+    pub name: String,
+    pub href: String,
+}
+
+impl Link {
+    fn new(name: &str, href: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            href: href.to_owned(),
+        }
+    }
+}
+
+pub fn default_nav() -> Vec<Link> {
+    vec![
+        Link::new("projects", "/projects"),
+        Link::new("posts", "/posts"),
+        Link::new("chrono", "/chrono"),
+        Link::new("tags", "/tags"),
+    ]
+}
+
+/// The contents of the site footer. The build info line is always shown, between the configured
+/// links and the link to the source repository.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FooterConfig {
+    pub links: Vec<Link>,
+
+    /// An optional blurb, written in markdown, shown underneath the links.
+    pub text: Option<String>,
+
+    /// The repository the source code of the site is hosted in. If this is a GitHub repository,
+    /// the "source" link will point at the exact commit being served.
+    pub repository: Option<Url>,
+}
+
+impl Default for FooterConfig {
+    fn default() -> Self {
+        Self {
+            links: vec![Link::new("feed", "/rss.xml")],
+            text: None,
+            repository: Some(
+                "https://github.com/maddiemort/maddie-wtf"
+                    .parse()
+                    .expect("default repository URL is valid"),
+            ),
+        }
+    }
+}
+
+/// Settings for the authenticated `/admin` routes, which are only served when a token is set.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    /// The bearer token that requests to the admin routes must present.
+    pub token: Option<String>,
+}
+
+/// Settings for keeping the content up to date with the git repository it's kept in.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitConfig {
+    /// A repository to clone the content from at startup, instead of reading it from the content
+    /// path. It's cloned into the checkout path, and brought up to date there if it's already
+    /// been cloned.
+    pub url: Option<String>,
+    /// The branch to check out, instead of the repository's default branch.
+    pub branch: Option<String>,
+    /// Where the repository is cloned to, which defaults to a directory in the system's temporary
+    /// directory.
+    pub checkout_path: Option<Utf8PathBuf>,
+    /// The directory inside the repository that the content is in, if it isn't the root.
+    pub subdirectory: Option<Utf8PathBuf>,
+    /// The repository's page on GitHub, like `https://github.com/maddiemort/content`. When it's
+    /// set, each post links to where its file can be edited there, on the branch that's checked
+    /// out (or `main`), and to its history, which links to each commit that changed it.
+    pub web_url: Option<Url>,
+    /// Whether a post that doesn't say when it was updated is taken to have been updated when the
+    /// last commit that changed its file was made, if that was after the day it was posted. In a
+    /// thread, that's the newest entry, and an entry without a `date` is taken to have been posted
+    /// with the first commit since the entry before it.
+    pub dates: bool,
+    /// The secret GitHub signs webhook deliveries with. When it's set, a push delivered to
+    /// `/hooks/github` pulls the content repository and reloads everything in it.
+    pub webhook_secret: Option<String>,
+}
+
+impl GitConfig {
+    pub fn checkout_path(&self) -> Utf8PathBuf {
+        self.checkout_path.clone().unwrap_or_else(|| {
+            Utf8PathBuf::from_path_buf(env::temp_dir())
+                .unwrap_or_else(|_| Utf8PathBuf::from("/tmp"))
+                .join("maddie-wtf-content")
+        })
+    }
+
+    /// Where the repository can be browsed on GitHub, if it's been configured.
+    pub fn forge(&self) -> Option<Forge> {
+        let web_url = self.web_url.as_ref()?;
+        let branch = self.branch.as_deref().unwrap_or("main");
+        Some(Forge::new(web_url, branch, self.subdirectory.as_deref()))
+    }
+}
+
+/// Limits on how long the server spends on a single request, and on how large the content it
+/// serves can get.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// How long cached pages get to render, in milliseconds, before the last copy rendered is
+    /// served in their place.
+    pub render_budget_ms: u64,
+
+    /// How long any page can take, in milliseconds, before an error page is served instead.
+    pub request_timeout_ms: u64,
+
+    /// How many posts and entries are kept rendered to HTML at once. The rest are rendered again
+    /// from their markdown when they're next read.
+    pub rendered_bodies: NonZeroUsize,
+
+    /// The largest file, in bytes, that's loaded as a post or page. Anything larger isn't even
+    /// read, so that a log file pasted in by accident can't fill up memory.
+    pub max_file_bytes: u64,
+
+    /// The most HTML, in bytes, that a page or the body of a post or entry can render to. Bodies
+    /// that render to more are replaced with a note saying so.
+    pub max_rendered_bytes: usize,
+
+    /// The most entries a thread can have.
+    pub max_thread_entries: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            render_budget_ms: 2_000,
+            request_timeout_ms: 10_000,
+            rendered_bodies: NonZeroUsize::new(256).expect("256 is not zero"),
+            max_file_bytes: 4 * 1024 * 1024,
+            max_rendered_bytes: 16 * 1024 * 1024,
+            max_thread_entries: 1_000,
+        }
+    }
+}
+
+/// Rendering the most visited pages ahead of time, at startup and whenever the content changes, so
+/// that the first reader to visit them afterwards doesn't have to wait for them to render.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WarmupConfig {
+    pub enabled: bool,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Previews of media from elsewhere, like YouTube videos, that are fetched by the server so that
+/// readers' browsers don't talk to the provider until they choose to play something.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbedsConfig {
+    /// How many requests can be made to providers each minute, across all previews. Previews that
+    /// can't be fetched because of this fall back to a plain link, and are tried again later.
+    pub fetches_per_minute: u32,
+
+    /// How many previews are kept cached.
+    pub cached_previews: NonZeroUsize,
+}
+
+impl Default for EmbedsConfig {
+    fn default() -> Self {
+        Self {
+            fetches_per_minute: 30,
+            cached_previews: NonZeroUsize::new(256).expect("256 is not zero"),
+        }
+    }
+}
+
+/// Extensions to commonmark that are turned on when rendering markdown. Apart from footnotes,
+/// they're all off unless they're asked for, so that turning one on never changes how existing
+/// content renders by surprise.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkdownConfig {
+    /// Footnotes written as `[^name]`, with their definitions (`[^name]: ...`) collected into a
+    /// list at the end of the post, page or entry they're in.
+    pub footnotes: bool,
+
+    /// GitHub-style tables, with a row of `---` separating the header from the body.
+    pub tables: bool,
+
+    /// `~~Struck through~~` text.
+    pub strikethrough: bool,
+
+    /// List items starting with `[ ]` or `[x]`, shown with a checkbox.
+    pub tasklist: bool,
+
+    /// `^Superscript^` text.
+    pub superscript: bool,
+
+    /// Bare URLs and email addresses turned into links, without needing `<...>` around them.
+    pub autolink: bool,
+
+    /// Shortcodes like `:tada:` replaced with the emoji they stand for, everywhere but in code.
+    pub emoji: bool,
+
+    /// Images given `loading="lazy"` and `decoding="async"`, so that they don't hold up the rest
+    /// of the page. The first image in a post or page is still loaded straight away, since
+    /// it's likely to be on screen from the start.
+    pub lazy_images: bool,
+
+    /// Line numbers beside every code block, rather than only beside those with `linenos` in their
+    /// info string (like ```` ```rust,linenos ````). Those with `nolinenos` never get them. The
+    /// numbers can't be selected, so they're left out when the code is copied.
+    pub line_numbers: bool,
+
+    /// HTML written into the markdown cleaned of anything that could run a script, like `<script>`
+    /// tags and `onclick` attributes, for content from sources that aren't fully trusted. Embeds
+    /// from shortcodes (and the HTML the site adds itself, like highlighted code) are kept.
+    pub sanitize: bool,
+
+    pub figures: FiguresConfig,
+
+    pub images: ImagesConfig,
+
+    pub external_links: ExternalLinksConfig,
+
+    pub diagrams: DiagramsConfig,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            footnotes: true,
+            tables: false,
+            strikethrough: false,
+            tasklist: false,
+            superscript: false,
+            autolink: false,
+            emoji: false,
+            lazy_images: false,
+            line_numbers: false,
+            sanitize: false,
+            figures: FiguresConfig::default(),
+            images: ImagesConfig::default(),
+            external_links: ExternalLinksConfig::default(),
+            diagrams: DiagramsConfig::default(),
+        }
+    }
+}
+
+/// Images that stand alone in a paragraph and have a title, like `![alt](image.png "Caption")`,
+/// rendered as a `<figure>` with the title as its caption.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FiguresConfig {
+    pub enabled: bool,
+
+    /// The class given to figures, for styling them. Left off if empty.
+    pub class: String,
+}
+
+impl Default for FiguresConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            class: "image".to_owned(),
+        }
+    }
+}
+
+/// Smaller copies of the images in posts and pages, offered to browsers with `srcset` so that
+/// phones don't download full-size screenshots. Only images in the static path or in a post's
+/// bundle are resized, and only in builds with the `responsive-images` feature.
+///
+/// Those images are served from URLs named after a hash of their content too, so the same image in
+/// several posts is only stored and downloaded once, and can be cached forever.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImagesConfig {
+    pub enabled: bool,
+
+    /// The widths to make copies at, in pixels. Images are never scaled up, so an image only gets
+    /// copies at the widths smaller than it is.
+    pub widths: Vec<u32>,
+
+    /// The `sizes` attribute given to resized images, telling browsers how wide they'll be shown.
+    pub sizes: String,
+
+    /// Other formats to offer copies in as well, in order of preference, which browsers that
+    /// support them will pick over the original's. AVIF is much slower to encode, and needs a
+    /// build with the `avif-images` feature.
+    pub formats: Vec<ImageEncoding>,
+
+    /// Where copies are kept, so that they're only made once. Defaults to a directory in the
+    /// system's temporary directory.
+    pub cache_path: Option<Utf8PathBuf>,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            widths: vec![480, 960, 1440],
+            sizes: "(max-width: 48rem) 100vw, 48rem".to_owned(),
+            formats: vec![ImageEncoding::Webp],
+            cache_path: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageEncoding {
+    Avif,
+
+    /// Lossless WebP, which only PNGs are converted to, since it would make photos bigger.
+    Webp,
+}
+
+impl ImagesConfig {
+    pub fn cache_path(&self) -> Utf8PathBuf {
+        self.cache_path.clone().unwrap_or_else(|| {
+            Utf8PathBuf::from_path_buf(env::temp_dir())
+                .unwrap_or_else(|_| Utf8PathBuf::from("/tmp"))
+                .join("maddie-wtf-images")
+        })
+    }
+}
+
+/// Attributes added to links that lead away from the site, meaning any absolute link to a host
+/// other than the one in the base URL.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExternalLinksConfig {
+    /// Mark external links with `rel="noopener noreferrer"` and the class below.
+    pub enabled: bool,
+
+    /// Open external links in a new tab.
+    pub new_tab: bool,
+
+    /// The class given to external links, for styling them differently. Left off if empty.
+    pub class: String,
+}
+
+impl Default for ExternalLinksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            new_tab: false,
+            class: "external".to_owned(),
+        }
+    }
+}
+
+/// Code blocks fenced as a diagram language, like `mermaid` or `dot`, shown as the diagram they
+/// describe rather than as code.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiagramsConfig {
+    pub render: DiagramRendering,
+
+    /// For rendering on the server, the command that renders each language to SVG. It's given the
+    /// source of the diagram on stdin, and should print the SVG to stdout.
+    pub commands: BTreeMap<String, Vec<String>>,
+
+    /// For rendering in the browser, the module script that renders each language, which is loaded
+    /// by any page with a diagram in that language. It's given a `pre` with the source of the
+    /// diagram, with the language as one of its classes, which is what Mermaid looks for.
+    pub scripts: BTreeMap<String, String>,
+}
+
+impl Default for DiagramsConfig {
+    fn default() -> Self {
+        let command = |args: &[&str]| args.iter().map(|&arg| arg.to_owned()).collect();
+
+        Self {
+            render: DiagramRendering::Off,
+            commands: BTreeMap::from([
+                ("dot".to_owned(), command(&["dot", "-Tsvg"])),
+                (
+                    "mermaid".to_owned(),
+                    command(&[
+                        "mmdc",
+                        "--input",
+                        "-",
+                        "--output",
+                        "-",
+                        "--outputFormat",
+                        "svg",
+                    ]),
+                ),
+            ]),
+            scripts: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagramRendering {
+    /// Leave diagrams as code blocks.
+    Off,
+
+    /// Render diagrams to SVG with the configured commands, the first time each one is rendered.
+    /// Any that fail to render are left as code blocks.
+    Server,
+
+    /// Leave diagrams to the configured scripts to render in the browser.
+    Client,
+}
+
+/// Posts with an `embargo_until` in their frontmatter, which are kept encrypted in memory until
+/// then, and published automatically once it passes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbargoConfig {
+    /// A secret that preview links for embargoed posts are derived from, so that they can be
+    /// shared before they're published. Without one, embargoed posts can't be previewed at
+    /// all.
+    pub preview_secret: Option<String>,
+}
+
+/// A webhook that's told whenever a post or page is added, updated or removed, for automations
+/// like search indexers or CDN purgers to react to.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventsConfig {
+    /// Where each event is posted, as JSON. Nothing is sent without one.
+    pub webhook: Option<Url>,
+
+    /// A secret sent along with each event as a bearer token, so the webhook can tell the events
+    /// are really from this site.
+    pub secret: Option<String>,
+}
+
+/// Reminders about drafts that have been sitting around unfinished, listed at `/admin/drafts`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StaleDraftsConfig {
+    /// How many days after its date a draft counts as stale.
+    pub max_age_days: u32,
+
+    /// Whether to log a warning for each stale draft at startup, as well as listing them.
+    pub warn: bool,
+}
+
+impl Default for StaleDraftsConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: 60,
+            warn: true,
+        }
+    }
+}
+
+/// How changes to the content are picked up while the server is running.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatcherConfig {
+    /// Whether a post or page that's renamed redirects from its old URL to its new one, until the
+    /// server restarts.
+    pub redirect_renames: bool,
+    /// How long a file has to stop changing for before it's loaded, in milliseconds.
+    pub debounce_ms: u64,
+    /// Whether to look for changes by scanning the watched paths every so often, instead of being
+    /// told about them. It's slower, but it works on file systems that never send any events, like
+    /// NFS and some Docker volumes.
+    pub poll: bool,
+    /// How often the watched paths are scanned when polling, in milliseconds.
+    pub poll_interval_ms: u64,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            redirect_renames: false,
+            debounce_ms: 25,
+            poll: false,
+            poll_interval_ms: 2000,
+        }
+    }
+}
+
+/// Whether responses are compressed, with gzip or brotli depending on what the client accepts, in
+/// each environment.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionConfig {
+    pub development: bool,
+    pub production: bool,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            development: false,
+            production: true,
+        }
+    }
+}
+
+impl CompressionConfig {
+    pub fn enabled_in(&self, environment: Environment) -> bool {
+        match environment {
+            Environment::Development => self.development,
+            Environment::Production => self.production,
+        }
+    }
+}
+
+/// The `Cache-Control` headers attached to responses that don't already have one. Responses under
+/// `/admin` and `/hooks`, and from `/readyz` and `/version`, are never cached, and HTML pages are
+/// only cached privately when they were requested with theme or feature flag cookies.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheControlConfig {
+    /// Values for responses to paths starting with each prefix. The longest matching prefix wins.
+    pub routes: BTreeMap<String, String>,
+
+    /// The value for responses to paths that don't match any of the prefixes in `routes`.
+    pub default: Option<String>,
+
+    /// The value for every response in development, so that changes always show up.
+    pub development: Option<String>,
+}
+
+impl Default for CacheControlConfig {
+    fn default() -> Self {
+        Self {
+            routes: BTreeMap::from([
+                ("/static/".to_owned(), "public, max-age=604800".to_owned()),
+                ("/rss.xml".to_owned(), "public, max-age=900".to_owned()),
+                // Images under here are named after a hash of their content, so they never change.
+                (
+                    "/images/".to_owned(),
+                    "public, max-age=31536000, immutable".to_owned(),
+                ),
+            ]),
+            default: Some("public, max-age=300".to_owned()),
+            development: Some("no-store".to_owned()),
+        }
+    }
+}
+
+impl CacheControlConfig {
+    /// The `Cache-Control` value for responses to the given path, if there is one.
+    pub fn value_for(&self, path: &str, environment: Environment) -> Option<&str> {
+        match environment {
+            Environment::Development => self.development.as_deref(),
+            Environment::Production => self
+                .routes
+                .iter()
+                .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, value)| value.as_str())
+                .or(self.default.as_deref()),
+        }
+    }
+}
+
+/// How seriously to take each of the lints run by the `check` subcommand.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    /// Headings that skip a level, e.g. an `h4` directly under an `h2`.
+    pub heading_levels: Severity,
+
+    /// Images without any alt text.
+    pub image_alt: Severity,
+
+    /// Images whose alt text is the same as their title, which is read out twice when it's shown
+    /// as a caption. The alt text should describe the image, and the caption say why it's there.
+    pub figure_alt: Severity,
+
+    /// Paragraphs longer than `max_paragraph_words`.
+    pub long_paragraphs: Severity,
+    pub max_paragraph_words: usize,
+
+    /// URLs written out as plain text rather than as links.
+    pub bare_urls: Severity,
+
+    /// A first heading with the same text as the title in the frontmatter.
+    pub duplicate_title: Severity,
+
+    /// Tags that only a single post uses.
+    pub single_use_tags: Severity,
+
+    /// Links in a post's `see_also` list that are neither http(s) URLs nor paths to content.
+    pub see_also: Severity,
+
+    /// Links to a `#fragment` that isn't the ID of anything on the page they lead to, like a
+    /// heading that's since been reworded. Both links within a page and links to other posts and
+    /// pages are checked.
+    pub fragments: Severity,
+
+    /// Accessibility problems in the HTML that posts and pages render to, like IDs used more than
+    /// once or links without any text, which can come from the templates as well as the markdown.
+    pub rendered_html: Severity,
+
+    /// Treat warnings as failures, as if `check --deny-warnings` had been passed.
+    pub strict: bool,
+
+    /// Other tools to run over the content, like `vale` or `codespell`.
+    pub external: Vec<ExternalLint>,
+}
+
+/// Checking that links to other sites still lead somewhere, with the `linkcheck` subcommand.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinkcheckConfig {
+    /// How long to wait for each site to respond, in seconds, before counting the link as dead.
+    pub timeout_secs: u64,
+
+    /// How many links are checked at once.
+    pub concurrency: usize,
+
+    /// Hosts whose links aren't checked, e.g. because they turn away anything that isn't a
+    /// browser. Links to their subdomains are skipped too.
+    pub allow: Vec<String>,
+}
+
+impl Default for LinkcheckConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 10,
+            concurrency: 8,
+            allow: Vec::new(),
+        }
+    }
+}
+
+/// A tool that's run over every markdown file, whose findings are included alongside the built-in
+/// ones.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExternalLint {
+    /// The name the tool's findings are reported under.
+    pub name: String,
+
+    /// The program to run, followed by its arguments. The path of every file is appended to these,
+    /// relative to the content path, which it's run from.
+    ///
+    /// It should print each finding on its own line, as `path:line: message` or
+    /// `path:line:column: message`, like `vale --output=line` and `codespell` do.
+    pub command: Vec<String>,
+
+    #[serde(default = "ExternalLint::default_severity")]
+    pub severity: Severity,
+}
+
+impl ExternalLint {
+    fn default_severity() -> Severity {
+        Severity::Warning
+    }
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            heading_levels: Severity::Warning,
+            image_alt: Severity::Error,
+            figure_alt: Severity::Warning,
+            long_paragraphs: Severity::Warning,
+            max_paragraph_words: 200,
+            bare_urls: Severity::Warning,
+            duplicate_title: Severity::Warning,
+            single_use_tags: Severity::Warning,
+            see_also: Severity::Error,
+            fragments: Severity::Warning,
+            rendered_html: Severity::Warning,
+            strict: false,
+            external: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Off => write!(f, "off"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
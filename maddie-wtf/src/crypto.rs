@@ -0,0 +1,20 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Whether two byte strings are the same, taking as long to find out wherever they differ, so that
+/// comparing a secret that was presented against the real one doesn't reveal how much of it
+/// matched.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The HMAC-SHA256 of `message` under `key`, as lowercase hex.
+pub fn hmac_sha256_hex(key: &[u8], message: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take a key of any length");
+    mac.update(message);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
@@ -0,0 +1,59 @@
+#[cfg(feature = "demo")]
+use std::{fs, io};
+
+#[cfg(feature = "demo")]
+use camino::{Utf8Path, Utf8PathBuf};
+#[cfg(feature = "demo")]
+use tempfile::TempDir;
+#[cfg(feature = "demo")]
+use tracing::info;
+
+/// Every file in the example content and static directories, paired with the path (relative to the
+/// demo root) that it's extracted to. The list is generated by the build script.
+#[cfg(feature = "demo")]
+const DEMO_FILES: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/demo_files.rs"));
+
+/// The syntect themes used in demo mode, which are built into syntect rather than loaded from the
+/// themes path.
+pub const LIGHT_THEME: &str = "InspiredGitHub";
+pub const DARK_THEME: &str = "base16-ocean.dark";
+
+/// The demo files once they've been extracted, which are deleted again when this is dropped.
+#[cfg(feature = "demo")]
+#[derive(Debug)]
+pub struct DemoFiles {
+    root: Utf8PathBuf,
+    _dir: TempDir,
+}
+
+#[cfg(feature = "demo")]
+impl DemoFiles {
+    /// The directory the files were extracted to.
+    pub fn root(&self) -> &Utf8Path {
+        &self.root
+    }
+}
+
+/// Write the embedded demo files out to a new directory under the system temporary directory, with
+/// `content`, `static` and (empty) `themes` subdirectories.
+#[cfg(feature = "demo")]
+pub fn extract() -> io::Result<DemoFiles> {
+    let dir = tempfile::Builder::new()
+        .prefix("maddie-wtf-demo-")
+        .tempdir()?;
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned())
+        .map_err(|_| io::Error::other("temporary directory path contains invalid UTF-8"))?;
+
+    fs::create_dir_all(root.join("themes"))?;
+
+    for (relative_path, contents) in DEMO_FILES {
+        let path = root.join(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+    }
+
+    info!(%root, files = %DEMO_FILES.len(), "extracted demo files");
+    Ok(DemoFiles { root, _dir: dir })
+}
@@ -0,0 +1,32 @@
+use axum::{extract::State, routing::get, Router};
+use maud::Markup;
+
+use crate::{
+    state::{self, Content, Site, Theme},
+    templates::pages,
+};
+
+/// The routes nested under `/_dev`, which are only there in development, for finding out why
+/// something isn't showing up without reading through the logs.
+pub fn routes() -> Router<state::State> {
+    Router::new()
+        .route("/errors", get(errors))
+        .route("/content", get(content))
+}
+
+/// Lists the files that are failing to load, along with why.
+async fn errors(State(content): State<Content>, theme: Theme, State(site): State<Site>) -> Markup {
+    pages::dev_errors(content.errors(), content.last_reload(), theme, site).await
+}
+
+/// Lists every post and page that's loaded, drafts included, with the keys they're loaded under.
+async fn content(State(content): State<Content>, theme: Theme, State(site): State<Site>) -> Markup {
+    pages::dev_content(
+        content.inventory(),
+        content.errors().len(),
+        content.last_reload(),
+        theme,
+        site,
+    )
+    .await
+}
@@ -0,0 +1,426 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Bytes,
+    extract::{FromRef, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use lru::LruCache;
+use maud::{html, Markup};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{debug, warn};
+use url::{form_urlencoded, Url};
+
+use crate::{
+    build_info,
+    config::EmbedsConfig,
+    errors::HandlerError,
+    state::{self, Site, Theme},
+    templates::pages,
+};
+
+/// How long to wait for a provider before giving up and falling back to a plain link.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The largest thumbnail that will be fetched and cached.
+const MAX_THUMBNAIL_BYTES: usize = 1024 * 1024;
+
+/// Every URL that a preview card has been made for while rendering content. The card, thumbnail
+/// and player are only served for these, so the routes can't be used to fetch arbitrary media
+/// through the site, or to spend its budget for asking providers.
+static EMBEDDED: LazyLock<Mutex<HashSet<Url>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// A site whose media can be embedded, along with where to ask it about a piece of its media.
+#[derive(Debug)]
+pub struct Provider {
+    pub name: &'static str,
+    hosts: &'static [&'static str],
+    oembed_endpoint: &'static str,
+}
+
+const PROVIDERS: &[Provider] = &[
+    Provider {
+        name: "YouTube",
+        hosts: &[
+            "youtube.com",
+            "www.youtube.com",
+            "m.youtube.com",
+            "youtu.be",
+        ],
+        oembed_endpoint: "https://www.youtube.com/oembed",
+    },
+    Provider {
+        name: "Vimeo",
+        hosts: &["vimeo.com", "www.vimeo.com", "player.vimeo.com"],
+        oembed_endpoint: "https://vimeo.com/api/oembed.json",
+    },
+];
+
+/// The provider of the media at `url`, if it's from a site whose media can be embedded.
+pub fn provider(url: &Url) -> Option<&'static Provider> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    let host = url.host_str()?;
+    PROVIDERS
+        .iter()
+        .find(|provider| provider.hosts.contains(&host))
+}
+
+/// A frame showing the preview card for the media at `url`, if it's from a site whose media can be
+/// embedded. Nothing is loaded from the site itself until the reader asks to play it.
+pub fn card_frame(url: &Url) -> Option<Markup> {
+    let provider = provider(url)?;
+    EMBEDDED
+        .lock()
+        .expect("embedded URLs mutex is not poisoned")
+        .insert(url.clone());
+    Some(html! {
+        figure class="embed" {
+            iframe
+                src=(card_path(url))
+                title=(format!("Media from {}", provider.name))
+                loading="lazy" {}
+        }
+    })
+}
+
+/// Where the preview card for the media at `url` is served.
+pub fn card_path(url: &Url) -> String {
+    format!("/embeds/card?url={}", encode(url))
+}
+
+/// Where the thumbnail for the media at `url` is served, after being fetched from the provider.
+pub fn thumbnail_path(url: &Url) -> String {
+    format!("/embeds/thumbnail?url={}", encode(url))
+}
+
+/// Where the provider's own player for the media at `url` is served, once the reader asks for it.
+pub fn player_path(url: &Url) -> String {
+    format!("/embeds/player?url={}", encode(url))
+}
+
+/// Whether a preview card has been made for the media at `url` while rendering content.
+fn is_embedded(url: &Url) -> bool {
+    EMBEDDED
+        .lock()
+        .expect("embedded URLs mutex is not poisoned")
+        .contains(url)
+}
+
+fn encode(url: &Url) -> String {
+    form_urlencoded::byte_serialize(url.as_str().as_bytes()).collect()
+}
+
+/// What a provider says about a piece of media, in response to an oEmbed request.
+#[derive(Clone, Debug, Deserialize)]
+struct OEmbed {
+    title: Option<String>,
+    author_name: Option<String>,
+    thumbnail_url: Option<Url>,
+    html: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Preview {
+    pub provider: &'static str,
+    pub title: Option<String>,
+    pub author_name: Option<String>,
+    /// The provider's own markup for playing the media, which is only served once the reader asks
+    /// for it.
+    pub player_html: Option<String>,
+    pub thumbnail: Option<Thumbnail>,
+}
+
+#[derive(Debug)]
+pub struct Thumbnail {
+    pub content_type: String,
+    pub bytes: Bytes,
+}
+
+/// Fetches previews of media from providers, keeping the most recently used ones cached and
+/// limiting how often the providers are asked for anything.
+#[derive(Clone, Debug)]
+pub struct Embeds {
+    client: reqwest::Client,
+    previews: Arc<Mutex<LruCache<Url, Arc<Preview>>>>,
+    budget: Arc<Mutex<FetchBudget>>,
+}
+
+impl Embeds {
+    pub fn new(config: &EmbedsConfig) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .user_agent(format!("maddie-wtf/{}", build_info::PKG_VERSION))
+            .build()?;
+
+        Ok(Self {
+            client,
+            previews: Arc::new(Mutex::new(LruCache::new(config.cached_previews))),
+            budget: Arc::new(Mutex::new(FetchBudget::new(config.fetches_per_minute))),
+        })
+    }
+
+    /// The preview of the media at `url`, fetched from its provider unless it's already cached.
+    pub async fn preview(&self, url: &Url) -> Result<Arc<Preview>, FetchPreviewError> {
+        use FetchPreviewError::*;
+
+        let provider = provider(url).ok_or_else(|| UnknownProvider(url.clone()))?;
+
+        if let Some(preview) = self
+            .previews
+            .lock()
+            .expect("embed previews mutex is not poisoned")
+            .get(url)
+        {
+            return Ok(Arc::clone(preview));
+        }
+
+        debug!(%url, provider = %provider.name, "fetching embed preview");
+
+        self.spend()?;
+        let oembed = self
+            .client
+            .get(provider.oembed_endpoint)
+            .query(&[("url", url.as_str()), ("format", "json")])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<OEmbed>()
+            .await?;
+
+        let thumbnail = match &oembed.thumbnail_url {
+            Some(thumbnail_url) => match self.fetch_thumbnail(thumbnail_url).await {
+                Ok(thumbnail) => Some(thumbnail),
+                // Caching the preview without its thumbnail would keep it that way until it's
+                // evicted, so it's better to try again later.
+                Err(RateLimited) => return Err(RateLimited),
+                Err(error) => {
+                    warn!(%url, %error, "failed to fetch embed thumbnail");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let preview = Arc::new(Preview {
+            provider: provider.name,
+            title: oembed.title,
+            author_name: oembed.author_name,
+            player_html: oembed.html,
+            thumbnail,
+        });
+
+        self.previews
+            .lock()
+            .expect("embed previews mutex is not poisoned")
+            .put(url.clone(), Arc::clone(&preview));
+
+        Ok(preview)
+    }
+
+    async fn fetch_thumbnail(&self, url: &Url) -> Result<Thumbnail, FetchPreviewError> {
+        use FetchPreviewError::*;
+
+        if url.scheme() != "https" {
+            return Err(InsecureThumbnail(url.clone()));
+        }
+
+        self.spend()?;
+        let response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_THUMBNAIL_BYTES as u64)
+        {
+            return Err(ThumbnailTooLarge);
+        }
+
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|val| val.to_str().ok())
+            .filter(|content_type| content_type.starts_with("image/"))
+            .ok_or(NotAnImage)?
+            .to_owned();
+
+        let bytes = response.bytes().await?;
+        if bytes.len() > MAX_THUMBNAIL_BYTES {
+            return Err(ThumbnailTooLarge);
+        }
+
+        Ok(Thumbnail {
+            content_type,
+            bytes,
+        })
+    }
+
+    fn spend(&self) -> Result<(), FetchPreviewError> {
+        let mut budget = self
+            .budget
+            .lock()
+            .expect("embed fetch budget mutex is not poisoned");
+        if budget.try_spend() {
+            Ok(())
+        } else {
+            Err(FetchPreviewError::RateLimited)
+        }
+    }
+}
+
+impl FromRef<state::State> for Embeds {
+    fn from_ref(input: &state::State) -> Self {
+        input.embeds.clone()
+    }
+}
+
+/// A fixed number of requests to providers that can be made each minute.
+#[derive(Debug)]
+struct FetchBudget {
+    per_minute: u32,
+    window_start: Instant,
+    spent: u32,
+}
+
+impl FetchBudget {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            window_start: Instant::now(),
+            spent: 0,
+        }
+    }
+
+    fn try_spend(&mut self) -> bool {
+        if self.window_start.elapsed() >= Duration::from_secs(60) {
+            self.window_start = Instant::now();
+            self.spent = 0;
+        }
+
+        if self.spent < self.per_minute {
+            self.spent += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum FetchPreviewError {
+    #[error("{0} isn't from a provider whose media can be embedded")]
+    UnknownProvider(Url),
+
+    #[error("too many requests have been made to providers in the last minute")]
+    RateLimited,
+
+    #[error("request to provider failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("thumbnail at {0} isn't served over HTTPS")]
+    InsecureThumbnail(Url),
+
+    #[error("thumbnail is larger than {MAX_THUMBNAIL_BYTES} bytes")]
+    ThumbnailTooLarge,
+
+    #[error("thumbnail isn't an image")]
+    NotAnImage,
+}
+
+/// The routes nested under `/embeds`, which serve preview cards in place of embedded media.
+pub fn routes() -> Router<state::State> {
+    Router::new()
+        .route("/card", get(card))
+        .route("/thumbnail", get(thumbnail))
+        .route("/player", get(player))
+}
+
+#[derive(Deserialize)]
+struct EmbedQuery {
+    url: Url,
+}
+
+impl EmbedQuery {
+    /// The URL being asked about, as long as it's one that content on the site embeds.
+    fn embedded_url(&self) -> Result<&Url, HandlerError> {
+        if is_embedded(&self.url) {
+            Ok(&self.url)
+        } else {
+            Err(HandlerError::NotFound)
+        }
+    }
+}
+
+/// A card previewing the media at a URL, which is shown in an iframe where the media would
+/// otherwise be embedded. If the preview can't be fetched, the card is just a link to the media.
+async fn card(
+    State(embeds): State<Embeds>,
+    theme: Theme,
+    State(site): State<Site>,
+    Query(query): Query<EmbedQuery>,
+) -> Result<Markup, HandlerError> {
+    let url = query.embedded_url()?;
+    let provider = provider(url).ok_or(HandlerError::NotFound)?;
+
+    let preview = match embeds.preview(url).await {
+        Ok(preview) => Some(preview),
+        Err(error) => {
+            warn!(%url, %error, "failed to fetch embed preview");
+            None
+        }
+    };
+
+    Ok(pages::embed_card(url, provider, preview.as_deref(), theme, site).await)
+}
+
+async fn thumbnail(
+    State(embeds): State<Embeds>,
+    Query(query): Query<EmbedQuery>,
+) -> Result<Response, HandlerError> {
+    let preview = embeds
+        .preview(query.embedded_url()?)
+        .await
+        .map_err(|_| HandlerError::NotFound)?;
+    let thumbnail = preview.thumbnail.as_ref().ok_or(HandlerError::NotFound)?;
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, thumbnail.content_type.clone())],
+        thumbnail.bytes.clone(),
+    )
+        .into_response())
+}
+
+/// The provider's own player for the media at a URL, which replaces the card in its iframe when the
+/// reader asks to play the media.
+async fn player(
+    State(embeds): State<Embeds>,
+    theme: Theme,
+    State(site): State<Site>,
+    Query(query): Query<EmbedQuery>,
+) -> Result<Markup, HandlerError> {
+    let preview = embeds
+        .preview(query.embedded_url()?)
+        .await
+        .map_err(|_| HandlerError::NotFound)?;
+    let player_html = preview
+        .player_html
+        .as_deref()
+        .ok_or(HandlerError::NotFound)?;
+
+    Ok(pages::embed_player(preview.provider, player_html, theme, site).await)
+}
@@ -1,14 +1,17 @@
 use axum::{
     body::Body,
     extract::State,
-    http::{Request, StatusCode},
+    http::{header, HeaderValue, Request, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use thiserror::Error;
 use tracing::debug;
 
-use crate::{state::Theme, templates::pages};
+use crate::{
+    state::{RenderError, Site, Theme},
+    templates::pages,
+};
 
 /// Errors that can be returned by request handlers.
 #[derive(Error, Clone, Debug)]
@@ -20,6 +23,20 @@ pub enum HandlerError {
     /// An internal server error occurred while trying to handle the request.
     #[error("internal server error")]
     InternalError,
+
+    /// Handling the request took too long, and there was nothing else to serve instead.
+    #[error("request timed out")]
+    Timeout,
+}
+
+impl From<RenderError> for HandlerError {
+    fn from(error: RenderError) -> Self {
+        match error {
+            RenderError::NotFound => HandlerError::NotFound,
+            RenderError::OverBudget => HandlerError::Timeout,
+            RenderError::Failed => HandlerError::InternalError,
+        }
+    }
 }
 
 /// `HandlerError` does implement [`IntoResponse`], so it can be returned from handlers as the error
@@ -42,7 +59,8 @@ impl IntoResponse for HandlerError {
 ///
 /// This is done so that state can be accessed when rendering errors.
 pub async fn render_error(
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     request: Request<Body>,
     next: Next,
 ) -> Response {
@@ -52,15 +70,23 @@ pub async fn render_error(
         debug!(error = %handler_error, "rendering error");
         match handler_error {
             HandlerError::NotFound => {
-                let mut response = pages::not_found(theme).await.into_response();
+                let mut response = pages::not_found(theme, site).await.into_response();
                 *response.status_mut() = StatusCode::NOT_FOUND;
                 response
             }
             HandlerError::InternalError => {
-                let mut response = pages::internal_error(theme).await.into_response();
+                let mut response = pages::internal_error(theme, site).await.into_response();
                 *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
                 response
             }
+            HandlerError::Timeout => {
+                let mut response = pages::timeout(theme, site).await.into_response();
+                *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from_static("5"));
+                response
+            }
         }
     } else {
         response
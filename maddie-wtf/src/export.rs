@@ -0,0 +1,321 @@
+use std::{io, iter};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use flate2::{write::GzEncoder, Compression};
+use ignore::WalkBuilder;
+use maud::Markup;
+use tap::TryConv;
+use thiserror::Error;
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::{
+    handlers::{self, ROOT_ICONS, SITEMAP_KEY},
+    state::{
+        assets::{Assets, CompileStylesheetError, STYLESHEET_NAME},
+        manifest::Manifest,
+        names::TagName,
+        render::FeedPage,
+        Config, Content, LoadThemeError, Settings, Site, Theme, Themes,
+    },
+    templates::pages,
+};
+
+/// Render every route of the site into `output` as a static site, which can be served by anything
+/// that serves files, like object storage.
+///
+/// Pages are written as `index.html` in a directory named after their path, so that they keep the
+/// same URLs, and everything is rendered with the default theme. Anything that needs the server,
+/// like choosing a theme or the players for embedded media, won't work. Files already in `output`
+/// are overwritten, but never removed.
+pub async fn build(config: Config, output: &Utf8Path) -> Result<(), ExportError> {
+    render_site(config, output).await.map(|_| ())
+}
+
+/// Render the site like [`build`], and pack it into a gzipped tarball at `output`, as a snapshot of
+/// the site at one point in time to upload to an archive or keep.
+///
+/// The site is under `site/` in the tarball, and the content manifest it was rendered from is
+/// alongside it as `manifest.json`, so that the snapshot can later be compared with the content.
+pub async fn archive(config: Config, output: &Utf8Path) -> Result<(), ExportError> {
+    use ExportError::*;
+
+    let dir = tempfile::tempdir().map_err(CreateTempDir)?;
+    let root = Utf8PathBuf::from_path_buf(dir.path().to_owned()).map_err(|_| {
+        CreateTempDir(io::Error::other(
+            "temporary directory path contains invalid UTF-8",
+        ))
+    })?;
+
+    let manifest = render_site(config, &root.join("site")).await?;
+    let manifest_path = root.join("manifest.json");
+    let manifest = serde_json::to_string_pretty(&manifest).map_err(SerializeManifest)?;
+    fs::write(&manifest_path, manifest)
+        .await
+        .map_err(|error| WriteFile(manifest_path, error))?;
+
+    // Compressing the whole site takes a while, and none of it is async.
+    let packed = output.to_owned();
+    tokio::task::spawn_blocking(move || pack(&root, &packed))
+        .await
+        .map_err(io::Error::other)
+        .and_then(|packed| packed)
+        .map_err(|error| WriteFile(output.to_owned(), error))?;
+
+    info!(%output, "archived static site");
+    Ok(())
+}
+
+/// Write the site and manifest in `root` to a gzipped tarball at `output`.
+fn pack(root: &Utf8Path, output: &Utf8Path) -> io::Result<()> {
+    let file = std::fs::File::create(output)?;
+    let mut tarball = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    tarball.append_dir_all("site", root.join("site"))?;
+    tarball.append_path_with_name(root.join("manifest.json"), "manifest.json")?;
+    tarball.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Render the site into `output`, returning the manifest of the content it was rendered from.
+async fn render_site(config: Config, output: &Utf8Path) -> Result<Manifest, ExportError> {
+    use ExportError::*;
+
+    let (content, failures) = config.load_content_counting_failures().await;
+    if config.strict && failures > 0 {
+        return Err(InvalidContent(failures));
+    }
+
+    let settings = config.settings();
+    let themes = Themes::load(&config.themes_path, &config.theme)?;
+    let theme = themes
+        .get(None, None)
+        .await
+        .with_flags(settings.flags().resolve(None));
+
+    let images = config
+        .markdown
+        .images
+        .enabled
+        .then(|| config.markdown.images.cache_path());
+    let assets = Assets::load(&config.static_path).await;
+    let site = Site::new(
+        config.site,
+        config.feeds,
+        config.nav,
+        config.footer,
+        &config.tags,
+        assets.clone(),
+    );
+
+    let mut export = Export {
+        root: output.to_owned(),
+        written: 0,
+    };
+
+    let paths = content.nodes(settings.show_drafts()).into_paths();
+    for path in &paths {
+        let page = render_page(
+            path,
+            content.clone(),
+            theme.clone(),
+            site.clone(),
+            settings.clone(),
+        );
+        match page.await {
+            Some(html) => export.write(&html_file(path), html.into_string()).await?,
+            None => warn!(%path, "skipping page that couldn't be rendered"),
+        }
+    }
+
+    let not_found = pages::not_found(theme, site.clone()).await;
+    export.write("404.html", not_found.into_string()).await?;
+
+    // Archives are numbered from 1, and stop at the first one that doesn't exist.
+    for page in iter::once(FeedPage::Current).chain((1..).map(FeedPage::Archive)) {
+        let feed =
+            handlers::render_rss_feed(content.clone(), site.clone(), settings.clone(), page).await;
+        let Some(feed) = feed else {
+            break;
+        };
+        export.write(&page.path(), feed.into_string()).await?;
+    }
+
+    // The same goes for the sitemaps listed in the sitemap index, if there is one.
+    for number in iter::once(None).chain((1..).map(Some)) {
+        let sitemap =
+            handlers::render_sitemap(content.clone(), site.clone(), settings.clone(), number).await;
+        let Some(sitemap) = sitemap else {
+            break;
+        };
+        let path = match number {
+            Some(number) => format!("/sitemaps/{number}.xml"),
+            None => SITEMAP_KEY.to_owned(),
+        };
+        export.write(&path, sitemap.into_string()).await?;
+    }
+
+    let stylesheet = assets.stylesheet().await?;
+    if let Some(hash) = assets.hash(STYLESHEET_NAME) {
+        let path = format!("assets/{hash}/{STYLESHEET_NAME}");
+        export.write(&path, stylesheet.clone()).await?;
+    }
+    export.write(STYLESHEET_NAME, stylesheet).await?;
+
+    // Static files are linked to by their fingerprinted URLs, but can still be found at their
+    // plain ones too.
+    for file in assets.files() {
+        let from = config.static_path.join(&file);
+        if let Some(hash) = assets.hash(file.as_str()) {
+            export.copy(&from, &format!("assets/{hash}/{file}")).await?;
+        }
+        export.copy(&from, &format!("static/{file}")).await?;
+        if ROOT_ICONS.contains(&file.as_str()) {
+            export.copy(&from, file.as_str()).await?;
+        }
+    }
+
+    // Only bundled posts are directories, with files alongside them.
+    let posts = paths
+        .iter()
+        .filter_map(|path| path.strip_prefix("/posts/"))
+        .filter(|post| !post.contains('/'));
+    for post in posts {
+        let Ok(mut entries) = fs::read_dir(config.content_path.join(post)).await else {
+            continue;
+        };
+        while let Some(entry) = entries.next_entry().await.map_err(ReadFile)? {
+            let Some(name) = entry.file_name().to_str().map(ToOwned::to_owned) else {
+                continue;
+            };
+            if let Some(file) = content.bundled_file(post, &name) {
+                export.copy(&file, &format!("posts/{post}/{name}")).await?;
+            }
+        }
+    }
+
+    // Smaller copies of images are made while the pages they're in are rendered, so they're all in
+    // the cache by now.
+    if let Some(cache_path) = images.filter(|path| path.is_dir()) {
+        for entry in WalkBuilder::new(&cache_path)
+            .standard_filters(false)
+            .build()
+        {
+            let entry = entry.map_err(|error| ReadFile(io::Error::other(error)))?;
+            let Ok(path) = Utf8PathBuf::from_path_buf(entry.into_path()) else {
+                continue;
+            };
+            let Ok(relative) = path.strip_prefix(&cache_path) else {
+                continue;
+            };
+            if path.is_file() {
+                export.copy(&path, &format!("images/{relative}")).await?;
+            }
+        }
+    }
+
+    info!(%output, files = %export.written, "built static site");
+    Ok(content.manifest(settings.show_drafts()))
+}
+
+/// Render the HTML page at `path`, the same way the route it would be served by does.
+async fn render_page(
+    path: &str,
+    content: Content,
+    theme: Theme,
+    site: Site,
+    settings: Settings,
+) -> Option<Markup> {
+    let show_drafts = settings.show_drafts();
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+    match segments.as_slice() {
+        [""] => handlers::render_index(content, theme, site, settings).await,
+        ["posts"] => handlers::render_posts(content, theme, site, settings).await,
+        ["chrono"] => handlers::render_chrono(content, theme, site, settings).await,
+        ["tags"] => Some(pages::tags(content.nodes(show_drafts).into_tags(), theme, site).await),
+        ["tagged", tag] => {
+            let tag = tag.to_string().try_conv::<TagName>().ok()?;
+            let posts = content.nodes(show_drafts).into_tagged(tag);
+            Some(pages::tagged(posts, theme, site).await)
+        }
+        ["posts", post] => {
+            handlers::render_post(content, theme, site, settings, post.to_string()).await
+        }
+        ["posts", post, "entry", index] => {
+            let entry = content
+                .post(post, show_drafts)?
+                .into_entry(index.parse().ok()?, show_drafts)?;
+            Some(pages::entry(entry, theme, site).await)
+        }
+        [page] => Some(pages::page(content.page(page)?, theme, site).await),
+        _ => None,
+    }
+}
+
+/// The file the page at `path` is written to, so that it's served at the same URL.
+fn html_file(path: &str) -> String {
+    match path.trim_matches('/') {
+        "" => "index.html".to_owned(),
+        path => format!("{path}/index.html"),
+    }
+}
+
+struct Export {
+    root: Utf8PathBuf,
+    written: usize,
+}
+
+impl Export {
+    async fn write(&mut self, path: &str, contents: String) -> Result<(), ExportError> {
+        let path = self.prepare(path).await?;
+        fs::write(&path, contents)
+            .await
+            .map_err(|error| ExportError::WriteFile(path, error))?;
+        self.written += 1;
+        Ok(())
+    }
+
+    async fn copy(&mut self, from: &Utf8Path, path: &str) -> Result<(), ExportError> {
+        let path = self.prepare(path).await?;
+        fs::copy(from, &path)
+            .await
+            .map_err(|error| ExportError::WriteFile(path, error))?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// The path in the output directory to write a file to, making sure its parent exists.
+    async fn prepare(&self, path: &str) -> Result<Utf8PathBuf, ExportError> {
+        let path = self.root.join(path.trim_start_matches('/'));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|error| ExportError::WriteFile(parent.to_owned(), error))?;
+        }
+        Ok(path)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("{0} file(s) in the content path failed to load, which isn't allowed in strict mode")]
+    InvalidContent(usize),
+
+    #[error(transparent)]
+    LoadTheme(#[from] LoadThemeError),
+
+    #[error(transparent)]
+    CompileStylesheet(#[from] CompileStylesheetError),
+
+    #[error("failed to read file to copy: {0}")]
+    ReadFile(#[source] io::Error),
+
+    #[error("failed to write {0}: {1}")]
+    WriteFile(Utf8PathBuf, #[source] io::Error),
+
+    #[error("failed to create a temporary directory to render the site into: {0}")]
+    CreateTempDir(#[source] io::Error),
+
+    #[error("failed to serialize content manifest: {0}")]
+    SerializeManifest(#[source] serde_json::Error),
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use thiserror::Error;
+
+use crate::crypto::{constant_time_eq, hmac_sha256_hex};
+
+/// The name of the cookie that holds a signed set of overrides to the feature flags.
+pub const FLAGS_COOKIE: &str = "flags";
+
+/// The header that can hold a signed set of overrides instead of the cookie, which is handier for
+/// trying something out with `curl`.
+pub const FLAGS_HEADER: &str = "x-feature-flags";
+
+/// The feature flags defined in the config, each on or off for everyone, along with the key that
+/// overrides to them are signed with.
+///
+/// An override is a comma-separated list of flags to turn on, with `!` in front of any to turn off
+/// instead, followed by a `.` and its signature. Only overrides signed with the admin token are
+/// honoured, so readers can't turn on anything that isn't ready for them.
+#[derive(Clone, Debug)]
+pub struct FeatureFlags {
+    defaults: Arc<BTreeMap<String, bool>>,
+    key: Option<Arc<str>>,
+}
+
+impl FeatureFlags {
+    pub fn new(defaults: BTreeMap<String, bool>, key: Option<String>) -> Self {
+        Self {
+            defaults: Arc::new(defaults),
+            key: key.map(Arc::from),
+        }
+    }
+
+    pub fn defaults(&self) -> &BTreeMap<String, bool> {
+        &self.defaults
+    }
+
+    /// The flags that are on for a request presenting the given override, or for everyone if it
+    /// presents none (or one with the wrong signature).
+    pub fn resolve(&self, signed: Option<&str>) -> Flags {
+        let mut enabled = self
+            .defaults
+            .iter()
+            .filter(|(_, on)| **on)
+            .map(|(name, _)| name.clone())
+            .collect::<BTreeSet<_>>();
+
+        let overrides = signed
+            .and_then(|signed| signed.rsplit_once('.'))
+            .filter(|(overrides, signature)| {
+                self.signature(overrides).is_some_and(|expected| {
+                    constant_time_eq(expected.as_bytes(), signature.as_bytes())
+                })
+            })
+            .map(|(overrides, _)| overrides);
+
+        for item in overrides
+            .into_iter()
+            .flat_map(|overrides| overrides.split(','))
+        {
+            let (name, on) = match item.strip_prefix('!') {
+                Some(name) => (name, false),
+                None => (item, true),
+            };
+            if !self.defaults.contains_key(name) {
+                continue;
+            }
+            if on {
+                enabled.insert(name.to_owned());
+            } else {
+                enabled.remove(name);
+            }
+        }
+
+        Flags {
+            enabled: Arc::new(enabled),
+        }
+    }
+
+    /// Sign a set of overrides, so that it can be set as the cookie or header.
+    pub fn sign(&self, overrides: &str) -> Result<String, SignFlagsError> {
+        use SignFlagsError::*;
+
+        let overrides = overrides.trim();
+        for item in overrides.split(',') {
+            let name = item.strip_prefix('!').unwrap_or(item);
+            if !self.defaults.contains_key(name) {
+                return Err(UnknownFlag(name.to_owned()));
+            }
+        }
+
+        let signature = self.signature(overrides).ok_or(NoKey)?;
+        Ok(format!("{overrides}.{signature}"))
+    }
+
+    /// An HMAC of the overrides, keyed with the admin token. The message is prefixed so that it
+    /// can't be mistaken for anything else signed with the same key.
+    fn signature(&self, overrides: &str) -> Option<String> {
+        let key = self.key.as_deref()?;
+        Some(hmac_sha256_hex(
+            key.as_bytes(),
+            format!("flags\0{overrides}").as_bytes(),
+        ))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SignFlagsError {
+    #[error("there's no flag called \"{0}\"")]
+    UnknownFlag(String),
+
+    #[error("overrides can't be signed without an admin token")]
+    NoKey,
+}
+
+/// The feature flags that are on for a single request, for templates to check before rendering
+/// anything that's still being tried out.
+#[derive(Clone, Debug, Default)]
+pub struct Flags {
+    enabled: Arc<BTreeSet<String>>,
+}
+
+impl Flags {
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.contains(name)
+    }
+
+    /// The names of every flag that's on, in order.
+    pub fn enabled(&self) -> impl Iterator<Item = &str> {
+        self.enabled.iter().map(String::as_str)
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    collections::HashMap,
+    io,
+    process::{self, ExitStatus, Output},
+    sync::Arc,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::NaiveDate;
+use thiserror::Error;
+use tokio::{process::Command, sync::Mutex};
+use tracing::info;
+use url::Url;
+
+/// The git repository the content is kept in, which can be brought up to date with its remote.
+///
+/// The content path only has to be somewhere inside the repository's working tree, not its root.
+#[derive(Clone, Debug)]
+pub struct ContentRepo {
+    path: Arc<Utf8PathBuf>,
+    /// Held while git is running, so that two updates never run in the same working tree at once.
+    lock: Arc<Mutex<()>>,
+}
+
+impl ContentRepo {
+    pub fn new(path: Utf8PathBuf) -> Self {
+        Self {
+            path: Arc::new(path),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Fetch from the remote, and reset to whatever the current branch tracks there, throwing
+    /// away anything that's changed in the working tree since.
+    pub async fn pull(&self) -> Result<(), GitError> {
+        let _guard = self.lock.lock().await;
+
+        git(&self.path, &["fetch", "--quiet"]).await?;
+        git(&self.path, &["reset", "--hard", "--quiet", "@{upstream}"]).await?;
+
+        let head = git(&self.path, &["rev-parse", "--short", "HEAD"]).await?;
+        info!(path = %self.path, %head, "updated content repository");
+        Ok(())
+    }
+}
+
+/// The days on which every file under `dir` (keyed relative to it) was changed by a commit, newest
+/// first, from a single pass over the log. Files that have never been committed aren't included.
+pub async fn commit_dates(
+    dir: &Utf8Path,
+) -> Result<HashMap<Utf8PathBuf, Vec<NaiveDate>>, GitError> {
+    let log = git(
+        dir,
+        &[
+            "-c",
+            "core.quotePath=false",
+            "log",
+            "--format=%x00%as",
+            "--name-only",
+            "--relative",
+            "--",
+            ".",
+        ],
+    )
+    .await?;
+
+    let mut dates = HashMap::<Utf8PathBuf, Vec<NaiveDate>>::new();
+    for commit in log.split('\0').filter(|commit| !commit.is_empty()) {
+        let mut lines = commit.lines();
+        let date = lines.next().unwrap_or_default();
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| GitError::UnexpectedOutput(date.to_owned()))?;
+        for file in lines.filter(|line| !line.is_empty()) {
+            dates.entry(Utf8PathBuf::from(file)).or_default().push(date);
+        }
+    }
+
+    Ok(dates)
+}
+
+/// A commit that changed a file.
+#[derive(Clone, Debug)]
+pub struct Commit {
+    pub hash: String,
+    /// The day it was authored.
+    pub date: NaiveDate,
+    pub subject: String,
+}
+
+/// Every commit that changed `file` (relative to `dir`), newest first, following it back through
+/// any renames.
+pub async fn history(dir: &Utf8Path, file: &Utf8Path) -> Result<Vec<Commit>, GitError> {
+    let log = git(
+        dir,
+        &[
+            "log",
+            "--follow",
+            "--format=%H%x09%as%x09%s",
+            "--",
+            file.as_str(),
+        ],
+    )
+    .await?;
+
+    log.lines()
+        .map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(hash), Some(date), Some(subject)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(GitError::UnexpectedOutput(line.to_owned()));
+            };
+            let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| GitError::UnexpectedOutput(line.to_owned()))?;
+            Ok(Commit {
+                hash: hash.to_owned(),
+                date,
+                subject: subject.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Where the content repository can be browsed on GitHub, to link to its files and commits.
+#[derive(Clone, Debug)]
+pub struct Forge {
+    /// The repository's page, without a trailing slash.
+    url: String,
+    branch: String,
+    /// The directory in the repository that the content is in, with a trailing slash, or nothing
+    /// if it's the root.
+    subdirectory: String,
+}
+
+impl Forge {
+    pub fn new(url: &Url, branch: &str, subdirectory: Option<&Utf8Path>) -> Self {
+        Self {
+            url: url.as_str().trim_end_matches('/').to_owned(),
+            branch: branch.to_owned(),
+            subdirectory: subdirectory
+                .map(|dir| format!("{}/", dir.as_str().trim_matches('/')))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Where `file` (relative to the content root) can be edited.
+    pub fn edit_link(&self, file: &Utf8Path) -> String {
+        format!(
+            "{}/edit/{}/{}{file}",
+            self.url, self.branch, self.subdirectory
+        )
+    }
+
+    /// Where the commit `hash` can be seen.
+    pub fn commit_link(&self, hash: &str) -> String {
+        format!("{}/commit/{hash}", self.url)
+    }
+}
+
+/// Clone the repository at `url` into `path`, or bring the clone that's already there up to date
+/// with it, and check out `branch` (or the remote's default branch).
+///
+/// This only happens at startup, before anything else is running, so it blocks until git is done.
+pub fn checkout(url: &str, branch: Option<&str>, path: &Utf8Path) -> Result<(), GitError> {
+    let redacted = redact_url(url);
+    // The URL is part of some of the commands, and git can repeat it when it fails.
+    let git_blocking = |dir: &Utf8Path, args: &[&str]| {
+        git_blocking(dir, args).map_err(|error| error.redact(url, &redacted))
+    };
+
+    if path.join(".git").exists() {
+        git_blocking(path, &["remote", "set-url", "origin", url])?;
+        git_blocking(path, &["fetch", "--quiet", "origin"])?;
+        match branch {
+            Some(branch) => {
+                let upstream = format!("origin/{branch}");
+                git_blocking(path, &["checkout", "--quiet", "-B", branch, &upstream])?;
+            }
+            None => {
+                git_blocking(path, &["reset", "--hard", "--quiet", "@{upstream}"])?;
+            }
+        }
+    } else {
+        let mut args = vec!["clone", "--quiet"];
+        if let Some(branch) = branch {
+            args.extend(["--branch", branch]);
+        }
+        args.extend(["--", url, path.as_str()]);
+        git_blocking(Utf8Path::new("."), &args)?;
+    }
+
+    let head = git_blocking(path, &["rev-parse", "--short", "HEAD"])?;
+    info!(url = %redacted, %path, %head, "checked out content repository");
+    Ok(())
+}
+
+/// `url` with any username or password in it replaced, since a clone URL often has a token in it,
+/// so that it can be logged or printed.
+pub fn redact_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut url) if !url.username().is_empty() || url.password().is_some() => {
+            // Neither can fail, because a URL that already has a username can have one.
+            let _ = url.set_username("redacted");
+            let _ = url.set_password(None);
+            url.into()
+        }
+        _ => url.to_owned(),
+    }
+}
+
+/// Run git in `dir`, returning what it printed.
+async fn git(dir: &Utf8Path, args: &[&str]) -> Result<String, GitError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await;
+    check_output(args, output)
+}
+
+fn git_blocking(dir: &Utf8Path, args: &[&str]) -> Result<String, GitError> {
+    let output = process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output();
+    check_output(args, output)
+}
+
+fn check_output(args: &[&str], output: io::Result<Output>) -> Result<String, GitError> {
+    use GitError::*;
+
+    let output = output.map_err(Run)?;
+    if !output.status.success() {
+        return Err(Failed {
+            command: args.join(" "),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+#[derive(Error, Debug)]
+pub enum GitError {
+    #[error("failed to run git: {0}")]
+    Run(#[source] io::Error),
+
+    #[error("git {command} exited with {status}: {stderr}")]
+    Failed {
+        command: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+
+    #[error("git printed something unexpected: {0}")]
+    UnexpectedOutput(String),
+}
+
+impl GitError {
+    /// Replace `url` wherever it appears in the command or what git printed with `redacted`.
+    fn redact(self, url: &str, redacted: &str) -> Self {
+        use GitError::*;
+
+        match self {
+            Failed {
+                command,
+                status,
+                stderr,
+            } => Failed {
+                command: command.replace(url, redacted),
+                status,
+                stderr: stderr.replace(url, redacted),
+            },
+            error => error,
+        }
+    }
+}
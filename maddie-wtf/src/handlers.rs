@@ -1,45 +1,72 @@
+use std::{borrow::Cow, iter, sync::Arc, time::Duration};
+
 use axum::{
     body::Body,
-    extract::{Path, State},
-    http::{header, Request, Response},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, Method, Request, Response, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Redirect},
+    Form, Json,
+};
+use axum_extra::extract::{
+    cookie::{Cookie, SameSite},
+    CookieJar,
 };
+use chrono::{DateTime, Utc};
 use maud::Markup;
+use serde::{Deserialize, Serialize};
 use tap::TryConv;
-use tracing::warn;
+use tokio::time;
+use tower_http::services::ServeFile;
+use tracing::{debug, warn};
+use url::{form_urlencoded, Url};
+use www::config::Environment;
 
 use crate::{
+    build_info,
+    config::CacheControlConfig,
     errors::HandlerError,
-    state::{names::TagName, Content, Settings, Theme},
-    templates::pages,
+    flags::{FLAGS_COOKIE, FLAGS_HEADER},
+    state::{
+        assets::{fingerprint, STYLESHEET_NAME},
+        names::TagName,
+        render::FeedPage,
+        watcher::{WatcherHealth, WatcherStatus},
+        ColorScheme, Content, Redirects, RenderCache, Settings, Site, StaleNode, Theme,
+        COLOR_SCHEME_COOKIE, DEFAULT_THEME, THEME_COOKIE,
+    },
+    templates::{pages, partials},
 };
 
-const STYLESHEET: &str = include_str!(concat!(env!("OUT_DIR"), "/style.css"));
+/// The key the RSS feed is kept in the render cache under.
+pub const RSS_FEED_KEY: &str = "/rss.xml";
+
+/// The key the sitemap (or sitemap index) is kept in the render cache under.
+pub const SITEMAP_KEY: &str = "/sitemap.xml";
 
 pub async fn index(
     State(content): State<Content>,
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     State(settings): State<Settings>,
-    request: Request<Body>,
+    State(renders): State<RenderCache>,
 ) -> Result<Markup, HandlerError> {
-    let recent_posts = content
-        .nodes(settings.show_drafts())
-        .await
-        .into_recent_pubs();
-    if let Some(index) = content.page("_index").await {
-        Ok(pages::index(index, recent_posts, theme).await)
-    } else {
-        Err(not_found(request).await)
-    }
+    let key = render_key("/", &theme);
+    let markup = renders
+        .render(key, render_index(content, theme, site, settings))
+        .await?;
+    Ok(markup)
 }
 
 pub async fn page(
     State(content): State<Content>,
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     Path(page): Path<String>,
     request: Request<Body>,
 ) -> Result<Markup, HandlerError> {
-    if let Some(page) = content.page(page).await {
-        Ok(pages::page(page, theme).await)
+    if let Some(page) = content.page(page) {
+        Ok(pages::page(page, theme, site).await)
     } else {
         Err(not_found(request).await)
     }
@@ -47,78 +74,135 @@ pub async fn page(
 
 pub async fn posts(
     State(content): State<Content>,
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     State(settings): State<Settings>,
-    _request: Request<Body>,
+    State(renders): State<RenderCache>,
 ) -> Result<Markup, HandlerError> {
-    let posts = content.nodes(settings.show_drafts()).await.into_posts();
-    Ok(pages::posts(posts, theme).await)
+    let key = render_key("/posts", &theme);
+    let markup = renders
+        .render(key, render_posts(content, theme, site, settings))
+        .await?;
+    Ok(markup)
 }
 
 pub async fn post(
     State(content): State<Content>,
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     State(settings): State<Settings>,
+    State(renders): State<RenderCache>,
     Path(post): Path<String>,
-    request: Request<Body>,
+    Query(query): Query<PreviewQuery>,
 ) -> Result<Markup, HandlerError> {
-    if let Some(post) = content.post(post, settings.show_drafts()).await {
-        Ok(pages::post(post, theme).await)
-    } else {
-        Err(not_found(request).await)
+    // Previews of embargoed posts are never cached, since anyone without the token mustn't see
+    // them.
+    if let Some(token) = query.preview {
+        let post = content
+            .preview_embargoed(&post, &token)
+            .await
+            .ok_or(HandlerError::NotFound)?;
+        return Ok(pages::post(post, theme, site).await);
     }
+
+    let key = render_key(&format!("/posts/{post}"), &theme);
+    let markup = renders
+        .render(key, render_post(content, theme, site, settings, post))
+        .await?;
+    Ok(markup)
+}
+
+#[derive(Deserialize)]
+pub struct PreviewQuery {
+    preview: Option<String>,
 }
 
 pub async fn entry(
     State(content): State<Content>,
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     State(settings): State<Settings>,
     Path((post, index)): Path<(String, usize)>,
     request: Request<Body>,
 ) -> Result<Markup, HandlerError> {
     if let Some(entry) = content
         .post(post, settings.show_drafts())
-        .await
         .and_then(|p| p.into_entry(index, settings.show_drafts()))
     {
-        Ok(pages::entry(entry, theme).await)
+        Ok(pages::entry(entry, theme, site).await)
     } else {
         Err(not_found(request).await)
     }
 }
 
+pub async fn history(
+    State(content): State<Content>,
+    theme: Theme,
+    State(site): State<Site>,
+    State(settings): State<Settings>,
+    State(renders): State<RenderCache>,
+    Path(post): Path<String>,
+) -> Result<Markup, HandlerError> {
+    let key = render_key(&format!("/posts/{post}/history"), &theme);
+    let markup = renders
+        .render(key, render_history(content, theme, site, settings, post))
+        .await?;
+    Ok(markup)
+}
+
 pub async fn chrono(
     State(content): State<Content>,
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     State(settings): State<Settings>,
-    _request: Request<Body>,
+    State(renders): State<RenderCache>,
 ) -> Result<Markup, HandlerError> {
-    let posts = content.nodes(settings.show_drafts()).await.into_chrono();
-    Ok(pages::chrono(posts, theme).await)
+    let key = render_key("/chrono", &theme);
+    let markup = renders
+        .render(key, render_chrono(content, theme, site, settings))
+        .await?;
+    Ok(markup)
 }
 
 pub async fn tags(
     State(content): State<Content>,
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     State(settings): State<Settings>,
-    _request: Request<Body>,
+    State(renders): State<RenderCache>,
+    request: Request<Body>,
 ) -> Result<Markup, HandlerError> {
-    let posts = content.nodes(settings.show_drafts()).await.into_tags();
-    Ok(pages::tags(posts, theme).await)
+    let key = render_key(request.uri().path(), &theme);
+    let markup = renders
+        .render(key, async move {
+            let posts = content.nodes(settings.show_drafts()).into_tags();
+            Some(pages::tags(posts, theme, site).await)
+        })
+        .await?;
+    Ok(markup)
 }
 
 pub async fn tagged(
     State(content): State<Content>,
-    State(theme): State<Theme>,
+    theme: Theme,
+    State(site): State<Site>,
     State(settings): State<Settings>,
+    State(renders): State<RenderCache>,
     Path(tag): Path<String>,
-    _request: Request<Body>,
+    request: Request<Body>,
 ) -> Result<Markup, HandlerError> {
-    match tag.try_conv::<TagName>() {
+    // Tags are always lowercase, but links to them might not be.
+    match tag.to_lowercase().try_conv::<TagName>() {
         Ok(tag) => {
-            if content.tag_exists(&tag).await {
-                let posts = content.nodes(settings.show_drafts()).await.into_tagged(tag);
-                Ok(pages::tagged(posts, theme).await)
+            if content.tag_exists(&tag) {
+                let key = render_key(request.uri().path(), &theme);
+                let markup = renders
+                    .render(key, async move {
+                        let posts = content.nodes(settings.show_drafts()).into_tagged(tag);
+                        Some(pages::tagged(posts, theme, site).await)
+                    })
+                    .await?;
+                Ok(markup)
             } else {
                 warn!(%tag, "requested tag doesn't exist");
                 Err(HandlerError::NotFound)
@@ -131,27 +215,694 @@ pub async fn tagged(
     }
 }
 
-pub async fn stylesheet(_request: Request<Body>) -> Result<Response<String>, HandlerError> {
+/// The key a page is kept in the render cache under: its path, and everything else that changes
+/// what it looks like.
+pub fn render_key(path: &str, theme: &Theme) -> String {
+    let scheme = theme
+        .color_scheme()
+        .map_or("system", |scheme| scheme.as_str());
+    let flags = theme.flags().enabled().collect::<Vec<_>>().join(",");
+    format!("{} {} {} {}", path, theme.name(), scheme, flags)
+}
+
+pub async fn render_index(
+    content: Content,
+    theme: Theme,
+    site: Site,
+    settings: Settings,
+) -> Option<Markup> {
+    let recent_posts = content.nodes(settings.show_drafts()).into_recent_pubs();
+    let index = content.page("_index")?;
+    Some(pages::index(index, recent_posts, theme, site).await)
+}
+
+pub async fn render_posts(
+    content: Content,
+    theme: Theme,
+    site: Site,
+    settings: Settings,
+) -> Option<Markup> {
+    let posts = content.nodes(settings.show_drafts()).into_posts();
+    Some(pages::posts(posts, theme, site).await)
+}
+
+pub async fn render_post(
+    content: Content,
+    theme: Theme,
+    site: Site,
+    settings: Settings,
+    post: String,
+) -> Option<Markup> {
+    let post = content.post(post, settings.show_drafts())?;
+    Some(pages::post(post, theme, site).await)
+}
+
+pub async fn render_history(
+    content: Content,
+    theme: Theme,
+    site: Site,
+    settings: Settings,
+    post: String,
+) -> Option<Markup> {
+    let history = content.history(&post, settings.show_drafts()).await?;
+    Some(pages::post_history(history, theme, site).await)
+}
+
+pub async fn render_chrono(
+    content: Content,
+    theme: Theme,
+    site: Site,
+    settings: Settings,
+) -> Option<Markup> {
+    let posts = content.nodes(settings.show_drafts()).into_chrono();
+    Some(pages::chrono(posts, theme, site).await)
+}
+
+pub async fn render_rss_feed(
+    content: Content,
+    site: Site,
+    settings: Settings,
+    page: FeedPage,
+) -> Option<Markup> {
+    let feed = content.nodes(settings.show_drafts()).into_rss_feed(
+        site.base_url(),
+        site.feeds().feed_entries,
+        page,
+    )?;
+    Some(pages::rss_feed(feed, &site).await)
+}
+
+pub async fn render_sitemap(
+    content: Content,
+    site: Site,
+    settings: Settings,
+    number: Option<usize>,
+) -> Option<Markup> {
+    let sitemap = content.nodes(settings.show_drafts()).into_sitemap(
+        site.base_url(),
+        site.feeds().sitemap_urls,
+        number,
+    )?;
+    Some(pages::sitemap(sitemap).await)
+}
+
+pub async fn stylesheet(
+    State(site): State<Site>,
+    _request: Request<Body>,
+) -> Result<Response<String>, HandlerError> {
+    let stylesheet = site.assets().stylesheet().await.map_err(|error| {
+        warn!(%error, "failed to compile stylesheet");
+        HandlerError::InternalError
+    })?;
+
     Response::builder()
         .header(header::CONTENT_TYPE, "text/css")
-        .body(STYLESHEET.to_owned())
+        .body(stylesheet)
         .map_err(|_| HandlerError::InternalError)
 }
 
+/// Serves a fingerprinted file from the static path (or the stylesheet). If the hash in the URL is
+/// the file's current one, it's marked as immutable so browsers never ask for it again; an outdated
+/// hash still gets the current file, just without the long-lived caching.
+pub async fn asset(
+    State(site): State<Site>,
+    Path((hash, file)): Path<(String, String)>,
+    request: Request<Body>,
+) -> Result<axum::response::Response, HandlerError> {
+    let assets = site.assets();
+    let file = file.trim_start_matches('/');
+
+    // Only files that have been fingerprinted are served, which also rules out any paths that
+    // would escape the static path.
+    let Some(current) = assets.hash(file) else {
+        debug!(%file, "requested asset doesn't exist");
+        return Err(HandlerError::NotFound);
+    };
+
+    let mut response = if cfg!(not(debug_assertions)) && file == STYLESHEET_NAME {
+        stylesheet(State(site.clone()), request)
+            .await?
+            .into_response()
+    } else {
+        ServeFile::new(assets.static_path().join(file))
+            .try_call(request)
+            .await
+            .map_err(|error| {
+                warn!(%file, %error, "failed to serve asset");
+                HandlerError::InternalError
+            })?
+            .map(Body::new)
+    };
+
+    let cache_control = if hash == current {
+        "public, max-age=31536000, immutable"
+    } else {
+        debug!(%file, requested = %hash, %current, "asset requested with outdated hash");
+        "no-cache"
+    };
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+
+    Ok(response)
+}
+
+/// Icons that browsers and other clients ask for at the root of the site, whether or not any page
+/// links to them.
+pub const ROOT_ICONS: &[&str] = &[
+    "favicon.ico",
+    "favicon.svg",
+    "apple-touch-icon.png",
+    "apple-touch-icon-precomposed.png",
+];
+
+/// Serves one of the icons clients look for at the root from the static path. If the site doesn't
+/// have it, the response is empty rather than a 404, since nothing is actually broken.
+pub async fn root_icon(
+    State(site): State<Site>,
+    request: Request<Body>,
+) -> Result<axum::response::Response, HandlerError> {
+    let assets = site.assets();
+    let file = request.uri().path().trim_start_matches('/').to_owned();
+
+    if assets.hash(&file).is_none() {
+        debug!(%file, "requested icon doesn't exist");
+        return Ok(StatusCode::NO_CONTENT.into_response());
+    }
+
+    let response = ServeFile::new(assets.static_path().join(&file))
+        .try_call(request)
+        .await
+        .map_err(|error| {
+            warn!(%file, %error, "failed to serve icon");
+            HandlerError::InternalError
+        })?
+        .map(Body::new);
+
+    Ok(response)
+}
+
+/// Serves one of the files bundled alongside a post, like an image it shows. Nothing is served for
+/// posts that can't be read yet, so a draft's images stay as hidden as the draft.
+pub async fn bundled_file(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+    Path((post, file)): Path<(String, String)>,
+    request: Request<Body>,
+) -> Result<axum::response::Response, HandlerError> {
+    if content.post(&post, settings.show_drafts()).is_none() {
+        return Err(HandlerError::NotFound);
+    }
+    let path = content
+        .bundled_file(&post, &file)
+        .ok_or(HandlerError::NotFound)?;
+
+    let response = ServeFile::new(&path)
+        .try_call(request)
+        .await
+        .map_err(|error| {
+            warn!(%path, %error, "failed to serve bundled file");
+            HandlerError::InternalError
+        })?
+        .map(Body::new);
+
+    Ok(response)
+}
+
+#[derive(Serialize)]
+pub struct Version {
+    name: &'static str,
+    version: &'static str,
+    /// The commit it was built from, with `-dirty` on the end if there were uncommitted changes.
+    commit: Option<&'static str>,
+    built_at: Option<DateTime<Utc>>,
+    rustc: &'static str,
+}
+
+/// Reports which build of the server is running, the same one the footer shows.
+pub async fn version() -> Json<Version> {
+    Json(Version {
+        name: build_info::PKG_NAME,
+        version: build_info::PKG_VERSION,
+        commit: build_info::commit_hash(),
+        built_at: build_info::built_at(),
+        rustc: build_info::RUSTC_VERSION,
+    })
+}
+
+#[derive(Serialize)]
+pub struct Liveness {
+    status: &'static str,
+}
+
+/// Reports that the process is alive, which it is if it can answer at all. It's served outside of
+/// everything else, so that being probed every few seconds doesn't show up in the request metrics,
+/// the request log or traces.
+pub async fn healthz() -> Json<Liveness> {
+    Json(Liveness { status: "ok" })
+}
+
+#[derive(Serialize)]
+pub struct Readiness {
+    /// Whether the content has been loaded since the server started, with every file loading.
+    loaded: bool,
+    /// How many posts and pages are being served, drafts included.
+    nodes: usize,
+    /// How many files failed to load the last time they were loaded.
+    error_count: usize,
+    /// Those files, some of whose previous versions are still being served.
+    errors: Vec<StaleNode>,
+    /// Whether changes to the content are still being picked up.
+    watcher: WatcherStatus,
+}
+
+/// Reports whether the server is ready, which it is once the content has been loaded, as long as
+/// there's something in it to serve.
+///
+/// Content that fails to load doesn't make it any less ready, since the previous version of it (if
+/// any) can still be served, and neither does a file watcher that's stopped working (which is
+/// restarted in the background), but both are listed so that they don't go unnoticed. In strict
+/// mode, either is reported as a failure, so that a deploy with broken or outdated content doesn't
+/// look healthy.
+pub async fn readyz(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+    State(watcher): State<WatcherHealth>,
+) -> (StatusCode, Json<Readiness>) {
+    let initial_failures = content.initial_failures();
+    let nodes = content.len();
+    let errors = content.errors();
+    let watcher = watcher.status();
+
+    let serveable = initial_failures.is_some() && nodes > 0;
+    let strict_failure = settings.strict() && (!errors.is_empty() || !watcher.healthy);
+    let status = if serveable && !strict_failure {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let readiness = Readiness {
+        loaded: initial_failures == Some(0),
+        nodes,
+        error_count: errors.len(),
+        errors,
+        watcher,
+    };
+    (status, Json(readiness))
+}
+
+/// Pins a list of the files that are failing to load to the bottom of every page, in development,
+/// so that broken content is noticed while it's being written rather than days later.
+pub async fn load_errors_banner(
+    State(content): State<Content>,
+    request: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let response = next.run(request).await;
+
+    let errors = content.errors();
+    let is_html = response.status() == StatusCode::OK
+        && response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|val| val.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("text/html"));
+
+    if errors.is_empty() || !is_html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            warn!(%error, "failed to buffer response body");
+            return HandlerError::InternalError.into_response();
+        }
+    };
+    let html = String::from_utf8_lossy(&bytes);
+
+    let banner = partials::load_errors_banner(&errors).into_string();
+    let html = match html.rfind("</body>") {
+        Some(index) => format!("{}{banner}{}", &html[..index], &html[index..]),
+        None => format!("{html}{banner}"),
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(html))
+}
+
 pub async fn rss_feed(
     State(content): State<Content>,
     State(settings): State<Settings>,
-    _request: Request<Body>,
+    State(site): State<Site>,
+    State(renders): State<RenderCache>,
 ) -> Result<Response<String>, HandlerError> {
-    let feed = content.nodes(settings.show_drafts()).await.into_rss_feed();
-    let feed_output = pages::rss_feed(feed).await;
+    // The feed looks the same whatever the theme, so there's only ever one copy of it.
+    let feed_output = renders
+        .render(
+            RSS_FEED_KEY.to_owned(),
+            render_rss_feed(content, site, settings, FeedPage::Current),
+        )
+        .await?;
 
+    xml_response("application/rss+xml", feed_output)
+}
+
+/// One of the archives of entries too old to be in the RSS feed itself, at `/rss/archive/N.xml`.
+pub async fn rss_archive(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+    State(site): State<Site>,
+    State(renders): State<RenderCache>,
+    Path(file): Path<String>,
+) -> Result<Response<String>, HandlerError> {
+    let number = numbered_xml_file(&file).ok_or(HandlerError::NotFound)?;
+    let page = FeedPage::Archive(number);
+    let feed_output = renders
+        .render(page.path(), render_rss_feed(content, site, settings, page))
+        .await?;
+
+    xml_response("application/rss+xml", feed_output)
+}
+
+pub async fn sitemap(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+    State(site): State<Site>,
+    State(renders): State<RenderCache>,
+) -> Result<Response<String>, HandlerError> {
+    let sitemap_output = renders
+        .render(
+            SITEMAP_KEY.to_owned(),
+            render_sitemap(content, site, settings, None),
+        )
+        .await?;
+
+    xml_response("application/xml", sitemap_output)
+}
+
+/// One of the sitemaps listed in the sitemap index, at `/sitemaps/N.xml`, which only exist when
+/// there are too many URLs for a single sitemap.
+pub async fn sitemap_file(
+    State(content): State<Content>,
+    State(settings): State<Settings>,
+    State(site): State<Site>,
+    State(renders): State<RenderCache>,
+    Path(file): Path<String>,
+) -> Result<Response<String>, HandlerError> {
+    let number = numbered_xml_file(&file).ok_or(HandlerError::NotFound)?;
+    let sitemap_output = renders
+        .render(
+            format!("/sitemaps/{number}.xml"),
+            render_sitemap(content, site, settings, Some(number)),
+        )
+        .await?;
+
+    xml_response("application/xml", sitemap_output)
+}
+
+/// The number in a file name like `3.xml`.
+fn numbered_xml_file(file: &str) -> Option<usize> {
+    file.strip_suffix(".xml")?.parse().ok()
+}
+
+fn xml_response(content_type: &str, markup: Markup) -> Result<Response<String>, HandlerError> {
     Response::builder()
-        .header(header::CONTENT_TYPE, "application/rss+xml")
-        .body(feed_output.into_string())
+        .header(header::CONTENT_TYPE, content_type)
+        .body(markup.into_string())
         .map_err(|_| HandlerError::InternalError)
 }
 
+/// Middleware that answers requests for any path in the configured redirect map with a permanent
+/// redirect, before they reach the router.
+pub async fn redirect(
+    State(redirects): State<Redirects>,
+    request: Request<Body>,
+    next: Next,
+) -> axum::response::Response {
+    if let Some(target) = redirects.get(request.uri().path()) {
+        debug!(from = %request.uri().path(), to = %target, "redirecting request");
+        Redirect::permanent(&target).into_response()
+    } else {
+        next.run(request).await
+    }
+}
+
+/// Middleware that gives up on any request that takes longer than the given timeout, so that the
+/// reader gets an error page rather than waiting forever.
+pub async fn timeout(
+    State(timeout): State<Duration>,
+    request: Request<Body>,
+    next: Next,
+) -> axum::response::Response {
+    let route = request.uri().to_string();
+    match time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!(%route, ?timeout, "request timed out");
+            HandlerError::Timeout.into_response()
+        }
+    }
+}
+
+/// Middleware that tags successful responses for pages, feeds and the stylesheet with a hash of
+/// their body, and answers requests that already have a response with the same hash with a `304 Not
+/// Modified` and no body.
+pub async fn conditional_get(request: Request<Body>, next: Next) -> axum::response::Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+    let response = next.run(request).await;
+
+    let taggable = response.status() == StatusCode::OK
+        && response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|val| val.to_str().ok())
+            .is_some_and(|content_type| {
+                [
+                    "text/html",
+                    "text/css",
+                    "application/rss+xml",
+                    "application/xml",
+                ]
+                .iter()
+                .any(|taggable| content_type.starts_with(taggable))
+            });
+
+    if !taggable {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            warn!(%error, "failed to buffer response body");
+            return HandlerError::InternalError.into_response();
+        }
+    };
+
+    let etag = HeaderValue::from_str(&format!("\"{}\"", fingerprint(&bytes)))
+        .expect("a quoted hex string is a valid header value");
+
+    let matches = if_none_match
+        .as_ref()
+        .and_then(|val| val.to_str().ok())
+        .is_some_and(|val| {
+            val.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag)
+        });
+
+    parts.headers.insert(header::ETAG, etag);
+
+    if matches {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_TYPE);
+        parts.headers.remove(header::CONTENT_LENGTH);
+        axum::response::Response::from_parts(parts, Body::empty())
+    } else {
+        axum::response::Response::from_parts(parts, Body::from(bytes))
+    }
+}
+
+/// Paths that are only for the operator or about the server itself, whose responses are never
+/// cached, whatever's configured.
+const UNCACHEABLE_PATHS: &[&str] = &["/admin", "/hooks", "/readyz", "/version"];
+
+/// Middleware that attaches the configured `Cache-Control` header to successful responses that
+/// don't already have one.
+///
+/// HTML pages depend on the reader's theme and feature flag cookies (and the feature flags
+/// header), so they vary on those, and shared caches are told not to keep a copy when any of them
+/// were sent.
+pub async fn cache_control(
+    State((config, environment)): State<(Arc<CacheControlConfig>, Environment)>,
+    jar: CookieJar,
+    request: Request<Body>,
+    next: Next,
+) -> axum::response::Response {
+    let path = request.uri().path().to_owned();
+    let personalized = request.headers().contains_key(FLAGS_HEADER)
+        || [COLOR_SCHEME_COOKIE, THEME_COOKIE, FLAGS_COOKIE]
+            .into_iter()
+            .any(|name| jar.get(name).is_some());
+    let mut response = next.run(request).await;
+
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    if is_html {
+        response.headers_mut().append(
+            header::VARY,
+            HeaderValue::from_static("Cookie, X-Feature-Flags"),
+        );
+    }
+
+    let cacheable = response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED;
+    if !cacheable || response.headers().contains_key(header::CACHE_CONTROL) {
+        return response;
+    }
+
+    let uncacheable = UNCACHEABLE_PATHS.iter().any(|prefix| {
+        path.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    });
+    let value = match config.value_for(&path, environment) {
+        _ if uncacheable => Cow::Borrowed("no-store"),
+        Some(value) if is_html && personalized => Cow::Owned(private(value)),
+        Some(value) => Cow::Borrowed(value),
+        None => return response,
+    };
+
+    match HeaderValue::from_str(&value) {
+        Ok(value) => {
+            response.headers_mut().insert(header::CACHE_CONTROL, value);
+        }
+        Err(error) => warn!(%error, %value, "configured Cache-Control value is invalid"),
+    }
+
+    response
+}
+
+/// A `Cache-Control` value with the same directives, except that only the reader's browser can
+/// keep a copy of the response.
+fn private(value: &str) -> String {
+    let directives = value.split(',').map(str::trim).filter(|directive| {
+        !directive.eq_ignore_ascii_case("public") && !directive.eq_ignore_ascii_case("private")
+    });
+
+    iter::once("private")
+        .chain(directives)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ThemeForm {
+    scheme: Option<String>,
+    theme: Option<String>,
+}
+
+/// Stores the reader's choice of colour scheme and/or named theme in cookies, then sends them back
+/// to the page they came from.
+///
+/// Any colour scheme other than a known one (e.g. "system") clears that choice, so that their OS
+/// preference applies again. Likewise, picking the default theme or one that doesn't exist clears
+/// the choice of theme.
+pub async fn set_theme(
+    State(site): State<Site>,
+    theme: Theme,
+    jar: CookieJar,
+    headers: HeaderMap,
+    Form(form): Form<ThemeForm>,
+) -> (CookieJar, Redirect) {
+    let build_cookie = |name: &'static str, value: String| {
+        Cookie::build((name, value))
+            .path("/")
+            .same_site(SameSite::Lax)
+            .http_only(true)
+            .permanent()
+    };
+
+    let jar = match form.scheme.map(|scheme| scheme.parse::<ColorScheme>()) {
+        Some(Ok(scheme)) => jar.add(build_cookie(
+            COLOR_SCHEME_COOKIE,
+            scheme.as_str().to_owned(),
+        )),
+        Some(Err(_)) => jar.remove(Cookie::build(COLOR_SCHEME_COOKIE).path("/")),
+        None => jar,
+    };
+
+    let jar = match form.theme {
+        Some(name) if name != DEFAULT_THEME && theme.has(&name) => {
+            jar.add(build_cookie(THEME_COOKIE, name))
+        }
+        Some(_) => jar.remove(Cookie::build(THEME_COOKIE).path("/")),
+        None => jar,
+    };
+
+    // Only a referer on this site is followed, and only its path and query are used, so that this
+    // can't redirect anywhere else. A path starting with `//` would be taken by the browser as
+    // another host, so that falls back to the home page too. A theme in the query would take
+    // precedence over the new cookie, so it's dropped.
+    let back = headers
+        .get(header::REFERER)
+        .and_then(|val| val.to_str().ok())
+        .and_then(|str| str.parse::<Url>().ok())
+        .filter(|url| is_same_origin(url, &headers, site.base_url()))
+        .filter(|url| !url.path().starts_with("//") && !url.path().starts_with("/\\"))
+        .map_or_else(
+            || "/".to_owned(),
+            |url| {
+                let query = url
+                    .query_pairs()
+                    .filter(|(key, _)| key != THEME_COOKIE)
+                    .collect::<Vec<_>>();
+
+                if query.is_empty() {
+                    url.path().to_owned()
+                } else {
+                    let query = form_urlencoded::Serializer::new(String::new())
+                        .extend_pairs(query)
+                        .finish();
+                    format!("{}?{}", url.path(), query)
+                }
+            },
+        );
+
+    (jar, Redirect::to(&back))
+}
+
+/// Whether a URL points at this site, either as the request was addressed (its `Host` header) or
+/// at the configured base URL.
+fn is_same_origin(url: &Url, headers: &HeaderMap, base_url: &str) -> bool {
+    let Some(authority) = url.host_str().map(|host| match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_owned(),
+    }) else {
+        return false;
+    };
+
+    let requested = headers
+        .get(header::HOST)
+        .and_then(|val| val.to_str().ok())
+        .is_some_and(|host| host.eq_ignore_ascii_case(&authority));
+
+    requested
+        || base_url
+            .parse::<Url>()
+            .is_ok_and(|base| base.origin() == url.origin())
+}
+
 pub async fn not_found(_request: Request<Body>) -> HandlerError {
     HandlerError::NotFound
 }
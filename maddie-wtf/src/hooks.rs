@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::post,
+    Router,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::{error, info, warn};
+
+use crate::{
+    git::ContentRepo,
+    state::{self, watcher::ReloadTrigger},
+};
+
+/// The largest delivery that's read to check its signature, which is as large as GitHub ever sends.
+const MAX_DELIVERY_BYTES: usize = 25 * 1024 * 1024;
+
+/// The routes nested under `/hooks`, which are only served when a webhook secret is set. Every
+/// delivery has to be signed with it.
+pub fn routes(secret: String) -> Router<state::State> {
+    Router::new()
+        .route("/github", post(github))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(secret),
+            require_signature,
+        ))
+}
+
+/// Rejects any delivery that isn't signed with the webhook secret, the way GitHub signs them: an
+/// HMAC-SHA256 of the body, in the `X-Hub-Signature-256` header.
+async fn require_signature(
+    State(secret): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_DELIVERY_BYTES).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+
+    let signature = parts
+        .headers
+        .get("x-hub-signature-256")
+        .and_then(|val| val.to_str().ok())
+        .and_then(|val| val.strip_prefix("sha256="))
+        .and_then(decode_hex);
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take a key of any length");
+    mac.update(&bytes);
+
+    // Verifying the tag compares it in constant time, so nothing leaks about how much of it
+    // matched.
+    if signature.is_some_and(|signature| mac.verify_slice(&signature).is_ok()) {
+        next.run(Request::from_parts(parts, Body::from(bytes)))
+            .await
+    } else {
+        warn!(route = %parts.uri, "rejecting webhook delivery with a missing or wrong signature");
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+}
+
+/// Brings the content repository up to date when anything's pushed to it, and reloads all of the
+/// content from it.
+async fn github(
+    State(repo): State<ContentRepo>,
+    State(reloads): State<ReloadTrigger>,
+    headers: HeaderMap,
+) -> Response {
+    let event = headers
+        .get("x-github-event")
+        .and_then(|val| val.to_str().ok())
+        .unwrap_or_default();
+
+    match event {
+        "ping" => StatusCode::NO_CONTENT.into_response(),
+        "push" => match repo.pull().await {
+            Ok(()) => {
+                info!("pulled content after a push, reloading");
+                reloads.reload();
+                StatusCode::NO_CONTENT.into_response()
+            }
+            Err(error) => {
+                error!(%error, "failed to pull content after a push");
+                (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response()
+            }
+        },
+        _ => {
+            info!(%event, "ignoring webhook event");
+            StatusCode::ACCEPTED.into_response()
+        }
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair)
+                .ok()
+                .filter(|pair| pair.len() == 2)?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
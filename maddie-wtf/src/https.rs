@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use camino::Utf8PathBuf;
+use tokio::fs;
+use tracing::debug;
+use url::Url;
+
+/// The routes of the plain HTTP listener, which sends every request on to the same path under the
+/// site's HTTPS base URL, apart from the challenges an ACME certificate authority makes over plain
+/// HTTP, which are answered from the files in `acme_challenges` if it's given.
+pub fn routes(base_url: Url, acme_challenges: Option<Utf8PathBuf>) -> Router {
+    let router = Router::new()
+        .fallback(redirect)
+        .with_state(Arc::new(base_url));
+
+    match acme_challenges {
+        Some(dir) => router.route(
+            "/.well-known/acme-challenge/:token",
+            get(acme_challenge).with_state(Arc::new(dir)),
+        ),
+        None => router,
+    }
+}
+
+async fn redirect(State(base_url): State<Arc<Url>>, uri: Uri) -> Response {
+    let mut target = (*base_url).clone();
+    // The site might be served under a path of its own, which the request's path is relative to.
+    let path = format!("{}{}", base_url.path().trim_end_matches('/'), uri.path());
+    target.set_path(&path);
+    target.set_query(uri.query());
+
+    debug!(from = %uri, to = %target, "redirecting to HTTPS");
+    (
+        StatusCode::MOVED_PERMANENTLY,
+        [(header::LOCATION, target.to_string())],
+    )
+        .into_response()
+}
+
+/// The key authorization for the challenge `token`, which the ACME client writes to a file named
+/// after it.
+async fn acme_challenge(
+    State(dir): State<Arc<Utf8PathBuf>>,
+    Path(token): Path<String>,
+) -> Response {
+    // Tokens are always base64url, so anything else can't be one, and might not stay in the
+    // directory.
+    let valid = !token.is_empty()
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    match fs::read(dir.join(&token)).await {
+        Ok(key_authorization) => {
+            debug!(%token, "answering ACME challenge");
+            (
+                [(header::CONTENT_TYPE, "application/octet-stream")],
+                key_authorization,
+            )
+                .into_response()
+        }
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
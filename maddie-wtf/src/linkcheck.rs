@@ -0,0 +1,256 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use camino::Utf8PathBuf;
+use comrak::{nodes::NodeValue, parse_document, Arena, ComrakOptions};
+use ignore::Walk;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::{fs, sync::Semaphore, task::JoinSet};
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use crate::{build_info, check::split_sections, state::Config};
+
+/// Find every link to another site in the content, and check that each one still leads somewhere,
+/// printing a line for each that doesn't.
+///
+/// Every URL is only requested once, however many places link to it, and links to allowed hosts
+/// (or to the site itself) aren't requested at all. Fails if any link is dead.
+pub async fn linkcheck(config: Config) -> Result<(), LinkcheckError> {
+    use LinkcheckError::*;
+
+    let settings = &config.linkcheck;
+    let links = collect_links(&config).await;
+    let (allowed, to_check): (Vec<_>, Vec<_>) = links.into_iter().partition(|(url, _)| {
+        // Links to the site itself are `check`'s job, and would be slow to check while it's down.
+        url.host_str() == config.site.base_url.host_str() || is_allowed(url, &settings.allow)
+    });
+
+    info!(
+        urls = %to_check.len(),
+        allowed = %allowed.len(),
+        "checking external links",
+    );
+
+    let client = Client::builder()
+        .timeout(Duration::from_secs(settings.timeout_secs))
+        .user_agent(format!(
+            "maddie-wtf/{} (link checker)",
+            build_info::PKG_VERSION
+        ))
+        .build()
+        .map_err(CreateHttpClient)?;
+    let permits = Arc::new(Semaphore::new(settings.concurrency.max(1)));
+
+    let mut checks = JoinSet::new();
+    for (url, uses) in to_check {
+        let client = client.clone();
+        let permits = Arc::clone(&permits);
+        checks.spawn(async move {
+            let _permit = permits
+                .acquire_owned()
+                .await
+                .expect("link check semaphore is never closed");
+            let result = check_url(&client, &url).await;
+            (url, uses, result)
+        });
+    }
+
+    let mut dead = Vec::new();
+    while let Some(joined) = checks.join_next().await {
+        let (url, uses, result) = match joined {
+            Ok(checked) => checked,
+            Err(error) => {
+                error!(%error, "link check task failed");
+                continue;
+            }
+        };
+
+        match result {
+            Ok(()) => debug!(%url, "link is alive"),
+            Err(reason) => {
+                warn!(%url, %reason, "link is dead");
+                dead.extend(uses.into_iter().map(|(path, line)| DeadLink {
+                    path,
+                    line,
+                    url: url.clone(),
+                    reason: reason.clone(),
+                }));
+            }
+        }
+    }
+
+    dead.sort_by(|a, b| (&a.path, a.line, &a.url).cmp(&(&b.path, b.line, &b.url)));
+    for link in &dead {
+        println!("{}:{}: {}: {}", link.path, link.line, link.url, link.reason);
+    }
+
+    info!(dead = %dead.len(), "finished checking external links");
+
+    if dead.is_empty() {
+        Ok(())
+    } else {
+        Err(Dead(dead.len()))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LinkcheckError {
+    #[error("failed to create HTTP client: {0}")]
+    CreateHttpClient(#[source] reqwest::Error),
+
+    #[error("found {0} dead links")]
+    Dead(usize),
+}
+
+struct DeadLink {
+    path: Utf8PathBuf,
+    line: usize,
+    url: Url,
+    reason: String,
+}
+
+/// The parts of a post's frontmatter that can link to other sites.
+#[derive(Deserialize, Default)]
+struct Frontmatter {
+    #[serde(default)]
+    see_also: Vec<String>,
+    lobsters: Option<String>,
+    hacker_news: Option<String>,
+}
+
+/// Every http(s) URL linked to from the content, along with each file and line that links to it.
+/// Fragments are dropped, since they're never sent to the server anyway.
+async fn collect_links(config: &Config) -> BTreeMap<Url, Vec<(Utf8PathBuf, usize)>> {
+    let mut links: BTreeMap<Url, Vec<(Utf8PathBuf, usize)>> = BTreeMap::new();
+
+    for result in Walk::new(&config.content_path) {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(error) => {
+                error!(%error, "directory walker encountered error");
+                continue;
+            }
+        };
+
+        let Ok(path) = Utf8PathBuf::from_path_buf(entry.path().to_path_buf()) else {
+            warn!(path = ?entry.path(), "skipping entry with path that contains invalid UTF-8");
+            continue;
+        };
+
+        if !path.is_file() || path.extension() != Some("md") {
+            continue;
+        }
+
+        let relative_path = path
+            .strip_prefix(&config.content_path)
+            .unwrap_or(&path)
+            .to_owned();
+
+        let raw = match fs::read_to_string(&path).await {
+            Ok(raw) => raw,
+            Err(error) => {
+                warn!(%path, %error, "skipping file that couldn't be read");
+                continue;
+            }
+        };
+
+        let Some(sections) = split_sections(&raw, true) else {
+            warn!(%path, "skipping file with missing or malformed frontmatter");
+            continue;
+        };
+
+        let mut add = |target: &str, line: usize| {
+            let Ok(mut url) = Url::parse(target) else {
+                return;
+            };
+            if matches!(url.scheme(), "http" | "https") {
+                url.set_fragment(None);
+                links
+                    .entry(url)
+                    .or_default()
+                    .push((relative_path.clone(), line));
+            }
+        };
+
+        for section in sections {
+            let frontmatter =
+                toml::from_str::<Frontmatter>(section.frontmatter.trim()).unwrap_or_default();
+            let targets = frontmatter
+                .see_also
+                .iter()
+                .chain(&frontmatter.lobsters)
+                .chain(&frontmatter.hacker_news);
+            for target in targets {
+                add(target, section.frontmatter_line);
+            }
+
+            let arena = Arena::new();
+            let root = parse_document(&arena, section.body, &ComrakOptions::default());
+            for node in root.descendants() {
+                let ast = node.data.borrow();
+                let (NodeValue::Link(link) | NodeValue::Image(link)) = &ast.value else {
+                    continue;
+                };
+                // Source positions are relative to the start of the body.
+                add(&link.url, ast.sourcepos.start.line + section.body_line - 1);
+            }
+        }
+    }
+
+    links
+}
+
+/// Whether a link is to one of the allowed hosts (or a subdomain of one), which aren't checked.
+fn is_allowed(url: &Url, allow: &[String]) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    allow.iter().any(|allowed| {
+        host == allowed
+            || host
+                .strip_suffix(allowed.as_str())
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+/// Request a URL, returning why it counts as dead if it does.
+///
+/// A `HEAD` request is tried first, falling back to `GET` for servers that don't handle `HEAD`
+/// properly. Being rate limited doesn't count as dead, since it says nothing about the link.
+async fn check_url(client: &Client, url: &Url) -> Result<(), String> {
+    let mut response = client.head(url.clone()).send().await;
+
+    let head_unsupported = response.as_ref().is_ok_and(|response| {
+        matches!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::FORBIDDEN | StatusCode::NOT_IMPLEMENTED
+        )
+    });
+    if head_unsupported {
+        response = client.get(url.clone()).send().await;
+    }
+
+    match response {
+        Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+            warn!(%url, "rate limited while checking link, assuming it's alive");
+            Ok(())
+        }
+        Ok(response)
+            if response.status().is_client_error() || response.status().is_server_error() =>
+        {
+            Err(response.status().to_string())
+        }
+        Ok(_) => Ok(()),
+        Err(error) if error.is_timeout() => Err("timed out".to_owned()),
+        Err(error) => {
+            // reqwest's own message doesn't say what went wrong, only its innermost source does.
+            let mut source: &dyn std::error::Error = &error;
+            while let Some(inner) = source.source() {
+                source = inner;
+            }
+            Err(source.to_string())
+        }
+    }
+}
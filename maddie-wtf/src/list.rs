@@ -0,0 +1,75 @@
+use chrono::{NaiveDate, Utc};
+use thiserror::Error;
+
+use crate::state::{Config, InventoryItem};
+
+/// Print every post and page, with their dates, tags and paths, oldest first.
+///
+/// With `only_drafts`, only posts that are drafts (or threads with draft entries) are listed, so
+/// the first ones are those that have been left unfinished the longest. With `json`, the list is
+/// printed as a JSON array instead, for scripts.
+pub async fn list(config: Config, only_drafts: bool, json: bool) -> Result<(), ListError> {
+    let items = config
+        .load_content()
+        .await
+        .inventory()
+        .into_iter()
+        .filter(|item| !only_drafts || item.draft || item.draft_entries > 0)
+        .collect::<Vec<_>>();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&items)?);
+        return Ok(());
+    }
+
+    let today = Utc::now().date_naive();
+    let path_width = items
+        .iter()
+        .map(|item| item.path.as_str().len())
+        .max()
+        .unwrap_or_default();
+
+    for item in &items {
+        let date = item
+            .date
+            .map_or_else(|| "-".to_owned(), |date| date.to_string());
+        let kind = item.kind.name();
+        let title = item.title.as_deref().unwrap_or("(untitled)");
+        let tags = item
+            .tags
+            .iter()
+            .map(|tag| format!(" #{tag}"))
+            .collect::<String>();
+
+        println!(
+            "{date:<10}  {kind:<6}  {status:<20}  {path:<path_width$}  {title}{tags}",
+            status = status(item, today),
+            path = item.path,
+        );
+    }
+
+    Ok(())
+}
+
+/// Whether a post has been published, or how long it's been a draft for.
+fn status(item: &InventoryItem, today: NaiveDate) -> String {
+    let Some(date) = item.date else {
+        return String::new();
+    };
+
+    if item.draft {
+        return format!("draft for {}d", (today - date).num_days());
+    }
+
+    match item.draft_entries {
+        0 => "published".to_owned(),
+        1 => "1 draft entry".to_owned(),
+        entries => format!("{entries} draft entries"),
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ListError {
+    #[error("failed to serialize content list: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
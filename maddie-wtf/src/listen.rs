@@ -0,0 +1,252 @@
+use std::{env, fmt, future::Future, io, net::SocketAddr, process, str::FromStr, time::Duration};
+
+use axum::Router;
+use camino::Utf8PathBuf;
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::{conn::auto::Builder, graceful::GracefulShutdown},
+    service::TowerToHyperService,
+};
+use thiserror::Error;
+use tokio::{
+    net::{TcpListener, UnixListener},
+    time,
+};
+use tracing::{debug, error, info, warn};
+
+/// The first file descriptor systemd passes sockets in, after stdin, stdout and stderr.
+const LISTEN_FDS_START: i32 = 3;
+
+/// Where to listen for connections: a TCP address, or the path of a Unix socket, written as
+/// `unix:/run/maddie-wtf.sock`.
+#[derive(Clone, Debug)]
+pub enum Listen {
+    Tcp(SocketAddr),
+    Unix(Utf8PathBuf),
+}
+
+impl FromStr for Listen {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("unix:") {
+            Some("") => {
+                Err("a Unix socket needs a path, like unix:/run/maddie-wtf.sock".to_owned())
+            }
+            Some(path) => Ok(Listen::Unix(path.into())),
+            None => s
+                .strip_prefix("tcp:")
+                .unwrap_or(s)
+                .parse()
+                .map(Listen::Tcp)
+                .map_err(|error| format!("{error}")),
+        }
+    }
+}
+
+impl fmt::Display for Listen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Listen::Tcp(address) => write!(f, "{address}"),
+            Listen::Unix(path) => write!(f, "unix:{path}"),
+        }
+    }
+}
+
+/// The socket the site is served on.
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix {
+        listener: UnixListener,
+        /// The socket's file, if it was created here rather than inherited, so that it's removed
+        /// again once the server stops.
+        path: Option<Utf8PathBuf>,
+    },
+}
+
+impl Listener {
+    /// The socket to serve the site on.
+    ///
+    /// If one was handed over by whatever started the server, either as `inherit_fd` or with
+    /// systemd's socket activation, it's used as it is, so that connections keep queueing up on it
+    /// while the server restarts instead of being refused. Otherwise, a new one is bound to
+    /// `listen`, and if that's a Unix socket, it's given the permissions in `socket_mode`.
+    pub async fn new(
+        listen: &Listen,
+        inherit_fd: Option<i32>,
+        socket_mode: Option<u32>,
+    ) -> Result<Self, ListenError> {
+        use ListenError::*;
+
+        if let Some(fd) = inherit_fd.or_else(systemd_fd) {
+            let listener = inherit(fd).map_err(|error| Inherit(fd, error))?;
+            info!(%fd, "inherited listener");
+            return Ok(listener);
+        }
+
+        Self::bind(listen, socket_mode)
+            .await
+            .map_err(|error| Bind(listen.clone(), error))
+    }
+
+    /// A new socket bound to `listen`, never an inherited one.
+    pub async fn bind(listen: &Listen, socket_mode: Option<u32>) -> io::Result<Self> {
+        match listen {
+            Listen::Tcp(address) => {
+                let listener = TcpListener::bind(address).await?;
+                info!(addr = %address, "bound TCP listener");
+                Ok(Listener::Tcp(listener))
+            }
+            Listen::Unix(path) => {
+                let listener = bind_unix(path, socket_mode)?;
+                info!(%path, "bound Unix socket listener");
+                Ok(Listener::Unix {
+                    listener,
+                    path: Some(path.clone()),
+                })
+            }
+        }
+    }
+
+    /// Serve `app` until `shutdown` completes, then wait for the connections that are still open
+    /// to finish.
+    pub async fn serve<F>(self, app: Router, shutdown: F) -> io::Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        match self {
+            Listener::Tcp(listener) => {
+                axum::serve(listener, app.into_make_service())
+                    .with_graceful_shutdown(shutdown)
+                    .await
+            }
+            Listener::Unix { listener, path } => {
+                serve_unix(listener, app, shutdown).await;
+                if let Some(path) = path {
+                    if let Err(error) = std::fs::remove_file(&path) {
+                        warn!(%path, %error, "failed to remove Unix socket");
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Bind a Unix socket at `path`, replacing one that was left behind by a server that didn't stop
+/// cleanly, but never anything else.
+fn bind_unix(path: &Utf8PathBuf, mode: Option<u32>) -> io::Result<UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            debug!(%path, "removing stale Unix socket");
+            std::fs::remove_file(path)?;
+        }
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "something that isn't a socket is already there",
+            ));
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+        Err(error) => return Err(error),
+    }
+
+    let listener = UnixListener::bind(path)?;
+    if let Some(mode) = mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    Ok(listener)
+}
+
+/// Serve `app` on a Unix socket, which `axum::serve` only does for TCP.
+async fn serve_unix<F>(listener: UnixListener, app: Router, shutdown: F)
+where
+    F: Future<Output = ()>,
+{
+    let builder = Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    tokio::pin!(shutdown);
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(error) => {
+                    // This is usually running out of file descriptors, which waiting a moment
+                    // might help with.
+                    error!(%error, "failed to accept connection");
+                    time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            },
+            () = &mut shutdown => break,
+        };
+
+        let service = TowerToHyperService::new(app.clone());
+        let connection = builder
+            .serve_connection_with_upgrades(TokioIo::new(stream), service)
+            .into_owned();
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(error) = connection.await {
+                debug!(%error, "failed to serve connection");
+            }
+        });
+    }
+
+    drop(listener);
+    graceful.shutdown().await;
+}
+
+/// The first socket systemd passed with socket activation, if it passed any to this process.
+fn systemd_fd() -> Option<i32> {
+    let pid = env::var("LISTEN_PID").ok()?.parse::<u32>().ok()?;
+    let fds = env::var("LISTEN_FDS").ok()?.parse::<i32>().ok()?;
+    if pid != process::id() || fds < 1 {
+        return None;
+    }
+
+    if fds > 1 {
+        warn!(%fds, "systemd passed more than one socket, only serving on the first");
+    }
+    Some(LISTEN_FDS_START)
+}
+
+/// Take on the listening socket `fd`, whether it's TCP or Unix.
+fn inherit(fd: i32) -> io::Result<Listener> {
+    use std::os::{fd::BorrowedFd, unix::net};
+
+    // The descriptor is duplicated rather than taken over, so that if it turns out not to be a
+    // socket (or it's one the runtime owns), dropping it doesn't close anything else's.
+    //
+    // SAFETY: The descriptor is only borrowed for as long as it takes to duplicate it, which fails
+    // cleanly if it isn't open.
+    let fd = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned()?;
+
+    let tcp = std::net::TcpListener::from(fd.try_clone()?);
+    if let Ok(addr) = tcp.local_addr() {
+        debug!(%addr, "inherited socket is TCP");
+        tcp.set_nonblocking(true)?;
+        return TcpListener::from_std(tcp).map(Listener::Tcp);
+    }
+
+    let unix = net::UnixListener::from(fd);
+    let addr = unix.local_addr()?;
+    debug!(?addr, "inherited socket is Unix");
+    unix.set_nonblocking(true)?;
+    Ok(Listener::Unix {
+        listener: UnixListener::from_std(unix)?,
+        path: None,
+    })
+}
+
+#[derive(Error, Debug)]
+pub enum ListenError {
+    #[error("failed to listen on {0}: {1}")]
+    Bind(Listen, #[source] io::Error),
+
+    #[error("failed to inherit listener from file descriptor {0}: {1}")]
+    Inherit(i32, #[source] io::Error),
+}
@@ -1,99 +1,489 @@
-use std::net::SocketAddr;
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     middleware::{self, Next},
     response::Response,
-    routing::get,
+    routing::{get, post},
     Router,
 };
 use axum_tracing_opentelemetry::middleware::OtelAxumLayer;
 use camino::Utf8PathBuf;
-use clap::Parser;
-use tokio::net::TcpListener;
-use tower_http::services::ServeDir;
+use chrono::Utc;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use thiserror::Error;
+use tower_http::{compression::CompressionLayer, services::ServeDir};
 use tower_livereload::LiveReloadLayer;
-use tracing::{error, error_span, field, info, Instrument, Span};
+use tracing::{error, error_span, field, info, warn, Instrument, Span};
 use url::Url;
-use www::config::Environment;
-
-use crate::state::Config;
+use www::{config::Environment, observability::LogFilter};
+
+use crate::{
+    check::CheckError,
+    export::ExportError,
+    linkcheck::LinkcheckError,
+    list::ListError,
+    listen::{Listen, Listener},
+    migrate::MigrateUrlsError,
+    preview::PreviewError,
+    scaffold::ScaffoldError,
+    state::{Config, RequestLog, RequestSummary, Site},
+};
 
+mod admin;
+mod audit;
 mod build_info;
+mod check;
+mod config;
+mod crypto;
+mod demo;
+mod dev;
+mod embeds;
 mod errors;
+mod export;
+mod flags;
+mod git;
 mod handlers;
+mod hooks;
+mod https;
+mod linkcheck;
+mod list;
+mod listen;
 mod metric;
+mod migrate;
+mod preview;
+mod scaffold;
 mod state;
 mod templates;
+mod warmup;
 
+// Serving the site is the default when no subcommand is given. Every option can be given before or
+// after the subcommand, since clap would otherwise only take them before it.
 #[derive(Parser, Clone, Debug)]
 pub struct Args {
-    #[arg(long, short, env = "ADDRESS", default_value = "0.0.0.0:6942")]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(flatten)]
+    serve: ServeArgs,
+}
+
+// The options every subcommand shares, which are combined with the config file to make up the
+// config. These aren't doc comments, since clap would use them to describe the whole program.
+#[derive(clap::Args, Clone, Debug)]
+pub struct ConfigArgs {
+    #[arg(long, short, env = "CONFIG_PATH", global = true)]
+    config: Option<Utf8PathBuf>,
+
+    /// Serve the example content embedded in the binary, ignoring any configured paths. Only
+    /// available in builds with the `demo` feature.
+    #[arg(long, env = "DEMO", global = true)]
+    demo: bool,
+
+    /// Show drafts alongside everything that's published. Takes precedence over the config file
+    /// either way, so pass `--drafts=false` to hide them when it turns them on.
+    #[arg(
+        long,
+        short,
+        env = "DRAFTS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        global = true
+    )]
+    drafts: Option<bool>,
+
+    /// Abort startup if any file in the content path fails to load, instead of leaving it out of
+    /// the site. On by default in production; pass `--strict=false` to turn it off.
+    #[arg(
+        long,
+        env = "STRICT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        global = true
+    )]
+    strict: Option<bool>,
+
+    /// A directory with `content`, `static` and `themes` subdirectories, used for any of those
+    /// paths that aren't given explicitly.
+    #[arg(long, env = "SITE_ROOT", global = true)]
+    site_root: Option<Utf8PathBuf>,
+
+    #[arg(long, env = "CONTENT_PATH", global = true)]
+    content_path: Option<Utf8PathBuf>,
+
+    /// A git repository to clone the content from at startup (or bring an earlier clone of up to
+    /// date), instead of reading it from the content path.
+    #[arg(long, env = "CONTENT_GIT_URL", global = true)]
+    content_git_url: Option<String>,
+
+    #[arg(long, env = "STATIC_PATH", global = true)]
+    static_path: Option<Utf8PathBuf>,
+
+    #[arg(long, env = "THEMES_PATH", global = true)]
+    themes_path: Option<Utf8PathBuf>,
+
+    /// A directory of `.sublime-syntax` files for languages to highlight on top of the ones built
+    /// into syntect. They're only loaded at startup.
+    #[arg(long, env = "SYNTAXES_PATH", global = true)]
+    syntaxes_path: Option<Utf8PathBuf>,
+
+    /// Whether the site is running in development or production. Required.
+    // clap can't require global options, so this is checked once they've been parsed.
+    #[arg(long, env = "ENVIRONMENT", global = true)]
+    environment: Option<Environment>,
+
+    #[arg(long, env = "ADMIN_TOKEN", hide_env_values = true, global = true)]
+    admin_token: Option<String>,
+}
+
+// The options that only matter when serving the site, which every other subcommand ignores.
+#[derive(clap::Args, Clone, Debug)]
+#[command(next_help_heading = "Serving options")]
+struct ServeArgs {
+    #[arg(
+        long,
+        short,
+        env = "ADDRESS",
+        default_value = "0.0.0.0:6942",
+        global = true
+    )]
     address: SocketAddr,
 
-    #[arg(long, short, env = "DRAFTS")]
-    drafts: bool,
+    /// Where to listen instead of the address: either another address, or `unix:` followed by the
+    /// path of a Unix socket to create, like `unix:/run/maddie-wtf.sock`.
+    #[arg(long, env = "LISTEN", global = true)]
+    listen: Option<Listen>,
+
+    /// The permissions to give the Unix socket, in octal, like `660`. Without this, they're
+    /// whatever the umask leaves them as.
+    #[arg(long, env = "SOCKET_MODE", value_parser = parse_mode, global = true)]
+    socket_mode: Option<u32>,
+
+    /// Serve on the listening socket with this file descriptor, handed over by whatever started
+    /// the server, instead of binding to the address. A socket passed with systemd's socket
+    /// activation is used without this.
+    #[arg(long, env = "INHERIT_FD", global = true)]
+    inherit_fd: Option<i32>,
+
+    /// Also listen for plain HTTP on this address, redirecting everything to the same path under
+    /// the base URL, if that's HTTPS.
+    #[arg(long, env = "REDIRECT_ADDRESS", global = true)]
+    redirect_address: Option<SocketAddr>,
+
+    /// A directory of responses to ACME HTTP-01 challenges, named after their tokens, which are
+    /// served over plain HTTP instead of being redirected.
+    #[arg(long, env = "ACME_CHALLENGE_PATH", global = true)]
+    acme_challenge_path: Option<Utf8PathBuf>,
+
+    #[arg(long, env = "METRICS_PORT", global = true)]
+    metrics_port: Option<u16>,
 
-    #[arg(long, env = "CONTENT_PATH")]
-    content_path: Utf8PathBuf,
+    /// Audit every page served for common accessibility problems, listing them in an overlay on
+    /// the page. Only has an effect in development.
+    #[arg(long, env = "AUDIT", global = true)]
+    audit: bool,
+}
 
-    #[arg(long, env = "STATIC_PATH")]
-    static_path: Utf8PathBuf,
+fn parse_mode(mode: &str) -> Result<u32, String> {
+    u32::from_str_radix(mode, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| format!("{mode} isn't a file mode in octal"))
+}
 
-    #[arg(long, env = "THEMES_PATH")]
-    themes_path: Utf8PathBuf,
+#[derive(Subcommand, Clone, Debug)]
+enum Command {
+    /// Serve the site, watching the content for changes.
+    Serve,
+
+    /// Render every route to a directory as a static site, to host a snapshot of it anywhere that
+    /// serves files.
+    Build {
+        /// The directory to write the site to, which is created if it doesn't exist.
+        output: Utf8PathBuf,
+    },
+
+    /// Render the site like `build`, and pack it into a gzipped tarball along with the content
+    /// manifest, as a snapshot to upload to an archive or keep.
+    Archive {
+        /// The file to write the tarball to, like `snapshot.tar.gz`.
+        output: Utf8PathBuf,
+    },
+
+    /// Lint all of the content, reporting problems with their file and line.
+    Check {
+        /// Fail on warnings as well as errors.
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+
+    /// Check that every link to another site still leads somewhere, reporting the dead ones with
+    /// their file and line.
+    Linkcheck,
+
+    /// List every post and page with its date, tags and path, oldest first.
+    List {
+        /// Only list drafts, and threads with draft entries.
+        #[arg(long)]
+        only_drafts: bool,
+
+        /// Print the list as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that a list of old URLs still resolves, and print the redirects needed for the rest.
+    MigrateUrls {
+        /// An XML sitemap, or a file with one `old [new]` pair of paths per line.
+        input: Utf8PathBuf,
+
+        /// Write the `[redirects]` table here instead of to stdout.
+        #[arg(long, short)]
+        output: Option<Utf8PathBuf>,
+    },
+
+    /// Start a new draft post, or a page, with its frontmatter filled in, and print its path.
+    New {
+        title: String,
+
+        /// Make the post a bundle: a directory with its markdown in `index.md`, so that images and
+        /// other files can go alongside it.
+        #[arg(long, conflicts_with = "page")]
+        bundle: bool,
+
+        /// Make a page instead of a post.
+        #[arg(long)]
+        page: bool,
+    },
+
+    /// Print the effective configuration, after combining the config file, environment and command
+    /// line, as TOML.
+    PrintConfig,
+
+    /// Render a single post or page to HTML exactly as the site would, to preview it without
+    /// running the server.
+    Render {
+        /// The markdown file to render, which doesn't need to be in the content path.
+        file: Utf8PathBuf,
+
+        /// Wrap the content in the site's layout, with the stylesheet and theme embedded.
+        #[arg(long)]
+        standalone: bool,
+
+        /// Write the HTML here instead of to stdout.
+        #[arg(long, short)]
+        output: Option<Utf8PathBuf>,
+
+        /// Open the HTML in the browser, writing it to a temporary file if there's no output file.
+        #[arg(long)]
+        open: bool,
+    },
+}
 
-    #[arg(long, env = "ENVIRONMENT")]
-    environment: Environment,
+impl Command {
+    /// Whether the command reads (or writes) the content, rather than only the config or a file
+    /// it's given.
+    fn reads_content(&self) -> bool {
+        !matches!(self, Command::PrintConfig | Command::Render { .. })
+    }
+}
 
-    #[arg(long, env = "METRICS_PORT")]
-    metrics_port: Option<u16>,
+#[derive(Error, Debug)]
+enum CommandError {
+    #[error(transparent)]
+    Build(#[from] ExportError),
+
+    #[error(transparent)]
+    Check(#[from] CheckError),
+
+    #[error(transparent)]
+    Linkcheck(#[from] LinkcheckError),
+
+    #[error(transparent)]
+    List(#[from] ListError),
+
+    #[error(transparent)]
+    MigrateUrls(#[from] MigrateUrlsError),
+
+    #[error(transparent)]
+    New(#[from] ScaffoldError),
+
+    #[error("failed to serialize config: {0}")]
+    PrintConfig(#[from] toml::ser::Error),
+
+    #[error(transparent)]
+    Render(#[from] PreviewError),
 }
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
-    www::observability::init_tracing(cfg!(debug_assertions))
+    let log_filter = www::observability::init_tracing(cfg!(debug_assertions))
         .expect("failed to set global default subscriber");
 
-    let args = Args::parse();
+    let Args {
+        command,
+        config: config_args,
+        serve: serve_args,
+    } = Args::parse();
+    let Some(environment) = config_args.environment else {
+        Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "the environment must be given with --environment or ENVIRONMENT",
+            )
+            .exit();
+    };
 
-    info!(addr = %args.address, "starting TCP server");
+    // The config file is read again whenever the server is asked to reload.
+    let config_path = config_args.config.clone();
+    let config = match Config::try_from(config_args) {
+        Ok(config) => config,
+        Err(error) => {
+            error!(%error, "failed to load config, aborting");
+            std::process::exit(1);
+        }
+    };
 
-    let listener = match TcpListener::bind(&args.address).await {
-        Ok(listener) => {
-            info!(addr = %args.address, "bound TCP listener");
-            listener
+    // Whatever the command does with the config, the demo files have to stay around until it's
+    // done.
+    #[cfg(feature = "demo")]
+    let _demo_files = config.demo_files.clone();
+
+    // Content in git is only checked out for the commands that read it, since that can mean going
+    // over the network.
+    let command = command.unwrap_or(Command::Serve);
+    if command.reads_content() {
+        if let Err(error) = config.check_out_content() {
+            error!(%error, "failed to check out content repository, aborting");
+            std::process::exit(1);
+        }
+    }
+
+    let result: Result<(), CommandError> = match command {
+        Command::Serve => {
+            serve(config, config_path, serve_args, environment, log_filter).await;
+            return;
         }
+        Command::Build { output } => export::build(config, &output).await.map_err(Into::into),
+        Command::Archive { output } => export::archive(config, &output).await.map_err(Into::into),
+        Command::Check { deny_warnings } => check::check(config, deny_warnings)
+            .await
+            .map_err(Into::into),
+        Command::Linkcheck => linkcheck::linkcheck(config).await.map_err(Into::into),
+        Command::List { only_drafts, json } => list::list(config, only_drafts, json)
+            .await
+            .map_err(Into::into),
+        Command::MigrateUrls { input, output } => {
+            migrate::migrate_urls(config, &input, output.as_deref())
+                .await
+                .map_err(Into::into)
+        }
+        Command::New {
+            title,
+            bundle,
+            page,
+        } => scaffold::new(config, &title, bundle, page)
+            .await
+            .map_err(Into::into),
+        Command::PrintConfig => toml::to_string(&config.to_file())
+            .map(|toml| print!("{toml}"))
+            .map_err(Into::into),
+        Command::Render {
+            file,
+            standalone,
+            output,
+            open,
+        } => preview::render(config, &file, standalone, output.as_deref(), open)
+            .await
+            .map_err(Into::into),
+    };
+
+    if let Err(error) = result {
+        error!(%error, "command failed");
+        std::process::exit(1);
+    }
+}
+
+async fn serve(
+    config: Config,
+    config_path: Option<Utf8PathBuf>,
+    args: ServeArgs,
+    environment: Environment,
+    log_filter: LogFilter,
+) {
+    let listen = args.listen.unwrap_or(Listen::Tcp(args.address));
+    info!(%listen, "starting server");
+
+    let listener = match Listener::new(&listen, args.inherit_fd, args.socket_mode).await {
+        Ok(listener) => listener,
         Err(error) => {
-            error!(addr = %args.address, %error, "failed to bind TCP listener, aborting");
+            error!(%error, "failed to listen, aborting");
             return;
         }
     };
 
     if let Some(port) = args.metrics_port {
-        www::observability::init_metrics(port, args.environment)
+        www::observability::init_metrics(port, environment)
             .expect("should be able to install Prometheus metrics recorder and exporter");
 
         info!(
             %port,
-            environment = %args.environment,
+            %environment,
             "installed Prometheus metrics recorder and exporter",
         );
+
+        metric::spawn_process_sampler();
     }
 
     metrics::counter!(*metric::REQUESTS_RECEIVED).absolute(0);
+    metrics::gauge!(*metric::STALE_CONTENT).set(0);
+    metrics::counter!(*metric::WATCHER_RESTARTS).absolute(0);
 
-    let config = Config::from(args);
+    let audit = args.audit && matches!(environment, Environment::Development);
+    if args.audit && !audit {
+        warn!("the accessibility audit only runs in development, ignoring --audit");
+    }
 
     info!(
         %config.drafts,
+        %config.strict,
         %config.content_path,
         %config.static_path,
         %config.themes_path,
+        syntaxes_path = ?config.syntaxes_path,
+        %config.site.base_url,
+        redirects = %config.redirects.len(),
+        admin = %config.admin_token.is_some(),
         "loaded config",
     );
 
+    // Plain HTTP can only be redirected when there's HTTPS to send it to, or it'd go round in
+    // circles.
+    let redirect = match args.redirect_address {
+        Some(address) if config.site.base_url.scheme() == "https" => {
+            let routes = https::routes(
+                config.site.base_url.clone(),
+                args.acme_challenge_path.clone(),
+            );
+            Some((address, routes))
+        }
+        Some(_) => {
+            warn!("the base URL isn't HTTPS, so plain HTTP isn't being redirected");
+            None
+        }
+        None => None,
+    };
+
     let live_reload = LiveReloadLayer::new();
     let reloader = live_reload.reloader();
 
@@ -102,28 +492,134 @@ async fn main() {
         .route("/posts", get(handlers::posts))
         .route("/posts/:post", get(handlers::post))
         .route("/posts/:post/entry/:index", get(handlers::entry))
+        .route("/posts/:post/history", get(handlers::history))
+        .route("/posts/:post/:file", get(handlers::bundled_file))
         .route("/chrono", get(handlers::chrono))
         .route("/tags", get(handlers::tags))
         .route("/tagged/:tag", get(handlers::tagged))
         .route("/style.css", get(handlers::stylesheet))
-        .route("/rss.xml", get(handlers::rss_feed));
+        .route("/assets/:hash/*file", get(handlers::asset))
+        .route("/rss.xml", get(handlers::rss_feed))
+        .route("/rss/archive/:file", get(handlers::rss_archive))
+        .route("/sitemap.xml", get(handlers::sitemap))
+        .route("/sitemaps/:file", get(handlers::sitemap_file))
+        .route("/readyz", get(handlers::readyz))
+        .route("/version", get(handlers::version))
+        .route("/theme", post(handlers::set_theme));
 
     let app = app.nest_service("/static", ServeDir::new(&config.static_path));
 
+    // Smaller copies of images are only made when resizing is turned on.
+    let app = if config.markdown.images.enabled {
+        app.nest_service(
+            "/images",
+            ServeDir::new(config.markdown.images.cache_path()),
+        )
+    } else {
+        app
+    };
+
     #[cfg(debug_assertions)]
     let app = app.route("/break", get(handlers::internal_error));
 
-    let app = app.route("/:page", get(handlers::page));
+    let app = handlers::ROOT_ICONS.iter().fold(app, |app, icon| {
+        app.route(&format!("/{icon}"), get(handlers::root_icon))
+    });
 
-    let state = match config.load_state(reloader).await {
+    let app = app
+        .nest("/embeds", embeds::routes())
+        .route("/:page", get(handlers::page));
+
+    // The audit goes inside everything else, so that the overlay is part of the page that gets
+    // tagged.
+    let app = if audit {
+        app.route_layer(middleware::from_fn(audit::overlay))
+    } else {
+        app
+    };
+
+    // The admin routes include a long-lived event stream, so only the site itself is subject to the
+    // request timeout, or has its responses buffered to tag them.
+    let app = app
+        .route_layer(middleware::from_fn_with_state(
+            Duration::from_millis(config.limits.request_timeout_ms),
+            handlers::timeout,
+        ))
+        .route_layer(middleware::from_fn(handlers::conditional_get));
+
+    let app = match config.admin_token.clone() {
+        Some(token) => app.nest("/admin", admin::routes(token)),
+        None => app,
+    };
+
+    let app = match config.git.webhook_secret.clone() {
+        Some(secret) => app.nest("/hooks", hooks::routes(secret)),
+        None => app,
+    };
+
+    let app = if matches!(environment, Environment::Development) {
+        app.nest("/_dev", dev::routes())
+    } else {
+        app
+    };
+
+    let compress = config.compression.enabled_in(environment);
+    let cache_control = Arc::new(config.cache_control.clone());
+
+    let state = match config.load_state(reloader, log_filter).await {
         Ok(state) => state,
         Err(error) => {
             error!(%error, "failed to load state, aborting");
-            return;
+            std::process::exit(1);
         }
     };
 
-    let app = app.fallback(handlers::not_found);
+    if let Some((address, routes)) = redirect {
+        let listener = match Listener::bind(&Listen::Tcp(address), None).await {
+            Ok(listener) => listener,
+            Err(error) => {
+                error!(addr = %address, %error, "failed to listen for plain HTTP, aborting");
+                return;
+            }
+        };
+        info!(addr = %address, "redirecting plain HTTP to HTTPS");
+        // Redirects are over in an instant, so they're just cut off when the site shuts down.
+        tokio::spawn(async move {
+            if let Err(error) = listener.serve(routes, std::future::pending()).await {
+                error!(%error, "plain HTTP service exited with error");
+            }
+        });
+    }
+
+    // A hangup reloads whatever can be reloaded without restarting, instead of shutting down.
+    tokio::spawn(www::lifecycle::reload_on_hangup({
+        let state = state.clone();
+        move || {
+            let state = state.clone();
+            let config_path = config_path.clone();
+            async move { state.reload(config_path.as_deref()).await }
+        }
+    }));
+
+    let app = app
+        .fallback(handlers::not_found)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            handlers::redirect,
+        ))
+        .layer(middleware::from_fn_with_state(
+            (cache_control, environment),
+            handlers::cache_control,
+        ));
+
+    let app = if matches!(environment, Environment::Development) {
+        app.layer(middleware::from_fn_with_state(
+            state.clone(),
+            handlers::load_errors_banner,
+        ))
+    } else {
+        app
+    };
 
     #[cfg(debug_assertions)]
     let app = app.layer(live_reload);
@@ -134,23 +630,28 @@ async fn main() {
             state.clone(),
             errors::render_error,
         ))
-        .layer(middleware::from_fn(
-            async |request: Request, next: Next| -> Response {
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            async |State(site): State<Site>,
+                   State(request_log): State<RequestLog>,
+                   request: Request,
+                   next: Next|
+                   -> Response {
                 async {
+                    let start = Instant::now();
                     let route = request.uri().to_string();
                     Span::current().record("route", route.clone());
 
-                    if let Some(referer) = request
+                    let referer = request
                         .headers()
                         .get("Referer")
                         .and_then(|val| val.to_str().ok())
                         .and_then(|str| str.parse::<Url>().ok())
-                    {
-                        if let Some(referer) = referer.host_str() {
-                            if referer != "maddie.wtf" {
-                                Span::current().record("referer", referer);
-                            }
-                        }
+                        .and_then(|referer| referer.host_str().map(ToOwned::to_owned))
+                        .filter(|referer| Some(referer.as_str()) != site.base_url.host_str());
+
+                    if let Some(referer) = &referer {
+                        Span::current().record("referer", referer);
                     }
 
                     info!("handling request");
@@ -160,11 +661,19 @@ async fn main() {
 
                     metrics::counter!(
                         *metric::REQUESTS_RECEIVED,
-                        "route" => route,
+                        "route" => route.clone(),
                         "status_code" => status_code.as_str().to_owned(),
                     )
                     .increment(1);
 
+                    request_log.record(RequestSummary {
+                        time: Utc::now(),
+                        route,
+                        status: status_code.as_u16(),
+                        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+                        referer,
+                    });
+
                     response
                 }
                 .instrument(error_span!(
@@ -177,8 +686,18 @@ async fn main() {
         ))
         .with_state(state);
 
-    match axum::serve(listener, app.into_make_service())
-        .with_graceful_shutdown(www::lifecycle::graceful_shutdown())
+    // Liveness probes skip every layer above, so they aren't counted, logged or traced.
+    let app = app.route("/healthz", get(handlers::healthz));
+
+    // Compression goes outside everything else, so that error pages are compressed too.
+    let app = if compress {
+        app.layer(CompressionLayer::new())
+    } else {
+        app
+    };
+
+    match listener
+        .serve(app, www::lifecycle::graceful_shutdown())
         .await
     {
         Ok(_) => {
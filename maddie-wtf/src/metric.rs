@@ -1,9 +1,179 @@
-use std::sync::LazyLock;
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
 
 use metrics::Unit;
+use tokio::{runtime::Handle, time};
 
 pub static REQUESTS_RECEIVED: LazyLock<&'static str> = LazyLock::new(|| {
     let key = "maddie_wtf.requests_received_count";
     metrics::describe_counter!(key, Unit::Count, "Number of HTTP requests received");
     key
 });
+
+pub static CACHE_WARMUP_DURATION: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.cache_warmup_duration_seconds";
+    metrics::describe_histogram!(
+        key,
+        Unit::Seconds,
+        "Time taken to render pages into the cache ahead of time"
+    );
+    key
+});
+
+pub static STALE_CONTENT: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.stale_content_count";
+    metrics::describe_gauge!(
+        key,
+        Unit::Count,
+        "Number of files that failed to reload, whose previous versions are still being served"
+    );
+    key
+});
+
+pub static WATCHER_HEALTHY: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.watcher_healthy";
+    metrics::describe_gauge!(
+        key,
+        Unit::Count,
+        "Whether the file watcher and content loader are working, as 1 or 0"
+    );
+    key
+});
+
+pub static WATCHER_RESTARTS: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.watcher_restarts_count";
+    metrics::describe_counter!(
+        key,
+        Unit::Count,
+        "Number of times the file watcher has been restarted after it stopped working"
+    );
+    key
+});
+
+pub static MARKDOWN_PASS_DURATION: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.markdown_pass_duration_seconds";
+    metrics::describe_histogram!(
+        key,
+        Unit::Seconds,
+        "Time taken by each pass over a piece of markdown while rendering it"
+    );
+    key
+});
+
+pub static UPTIME: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.process_uptime_seconds";
+    metrics::describe_gauge!(key, Unit::Seconds, "Time since the server started");
+    key
+});
+
+pub static RESIDENT_MEMORY: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.process_resident_memory_bytes";
+    metrics::describe_gauge!(
+        key,
+        Unit::Bytes,
+        "Memory held by the server process that's actually in RAM"
+    );
+    key
+});
+
+pub static OPEN_FDS: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.process_open_fds_count";
+    metrics::describe_gauge!(
+        key,
+        Unit::Count,
+        "Number of file descriptors the server process has open"
+    );
+    key
+});
+
+pub static RUNTIME_WORKERS: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.runtime_workers_count";
+    metrics::describe_gauge!(key, Unit::Count, "Number of tokio worker threads");
+    key
+});
+
+pub static RUNTIME_ALIVE_TASKS: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.runtime_alive_tasks_count";
+    metrics::describe_gauge!(
+        key,
+        Unit::Count,
+        "Number of tasks currently alive in the tokio runtime"
+    );
+    key
+});
+
+pub static RUNTIME_GLOBAL_QUEUE_DEPTH: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.runtime_global_queue_depth_count";
+    metrics::describe_gauge!(
+        key,
+        Unit::Count,
+        "Number of tasks waiting in the tokio runtime's global queue"
+    );
+    key
+});
+
+pub static RUNTIME_BUSY: LazyLock<&'static str> = LazyLock::new(|| {
+    let key = "maddie_wtf.runtime_busy_seconds";
+    metrics::describe_counter!(
+        key,
+        Unit::Seconds,
+        "Time tokio worker threads have spent busy, summed across all of them"
+    );
+    key
+});
+
+/// How often the process and runtime metrics are sampled.
+const PROCESS_SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Keep the process and runtime metrics up to date for as long as the server runs, sampling them
+/// every so often rather than on every scrape.
+///
+/// Memory and file descriptors are read from `/proc`, so they're only recorded on Linux.
+pub fn spawn_process_sampler() {
+    let started = Instant::now();
+    let runtime = Handle::current();
+
+    tokio::spawn(async move {
+        let mut interval = time::interval(PROCESS_SAMPLE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            metrics::gauge!(*UPTIME).set(started.elapsed().as_secs_f64());
+
+            if let Some(bytes) = resident_memory() {
+                metrics::gauge!(*RESIDENT_MEMORY).set(bytes as f64);
+            }
+            if let Ok(fds) = std::fs::read_dir("/proc/self/fd") {
+                metrics::gauge!(*OPEN_FDS).set(fds.count() as f64);
+            }
+
+            let runtime_metrics = runtime.metrics();
+            let workers = runtime_metrics.num_workers();
+            metrics::gauge!(*RUNTIME_WORKERS).set(workers as f64);
+            metrics::gauge!(*RUNTIME_ALIVE_TASKS).set(runtime_metrics.num_alive_tasks() as f64);
+            metrics::gauge!(*RUNTIME_GLOBAL_QUEUE_DEPTH)
+                .set(runtime_metrics.global_queue_depth() as f64);
+
+            let busy = (0..workers)
+                .map(|worker| runtime_metrics.worker_total_busy_duration(worker))
+                .sum::<Duration>();
+            metrics::counter!(*RUNTIME_BUSY).absolute(busy.as_secs());
+        }
+    });
+}
+
+/// The resident set size of this process, from the `VmRSS` line of `/proc/self/status`.
+fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
@@ -0,0 +1,223 @@
+use std::{collections::BTreeMap, io};
+
+use camino::Utf8Path;
+use chrono::NaiveDate;
+use serde::Serialize;
+use tap::TryConv;
+use thiserror::Error;
+use tokio::fs;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::state::{names::TagName, Config, Content};
+
+/// Check that every old URL listed in `input` still resolves against the current content, and write
+/// out the redirect entries (as a `[redirects]` table for the config file) needed for those that
+/// don't.
+///
+/// The input can either be an XML sitemap, in which case every `<loc>` is checked, or a plain text
+/// mapping file with one `old [new]` pair of paths per line. When no new path is given, an attempt
+/// is made to guess it from the slug of the old one.
+pub async fn migrate_urls(
+    config: Config,
+    input: &Utf8Path,
+    output: Option<&Utf8Path>,
+) -> Result<(), MigrateUrlsError> {
+    use MigrateUrlsError::*;
+
+    let raw_input = fs::read_to_string(input).await.map_err(ReadInput)?;
+    let mappings = if raw_input.contains("<urlset") {
+        parse_sitemap(&raw_input)
+    } else {
+        parse_mapping(&raw_input)
+    };
+
+    let content = config.load_content().await;
+    let resolver = Resolver {
+        config: &config,
+        content: &content,
+    };
+
+    let mut redirects = BTreeMap::new();
+    let mut unresolved = 0;
+
+    for (old, new) in mappings {
+        if resolver.resolves(&old) {
+            info!(%old, "resolves directly");
+            continue;
+        }
+
+        if let Some(target) = config.redirects.get(&old) {
+            if resolver.resolves(target) {
+                info!(%old, %target, "resolves via existing redirect");
+            } else {
+                warn!(%old, %target, "existing redirect points at a path that doesn't resolve");
+                unresolved += 1;
+            }
+            continue;
+        }
+
+        let new = match new {
+            Some(new) => Some(new),
+            None => resolver.guess(&old),
+        };
+
+        match new {
+            Some(new) if resolver.resolves(&new) => {
+                info!(%old, %new, "adding redirect");
+                redirects.insert(old, new);
+            }
+            Some(new) => {
+                warn!(%old, %new, "new path doesn't resolve either, skipping");
+                unresolved += 1;
+            }
+            None => {
+                warn!(%old, "couldn't find a new path, skipping");
+                unresolved += 1;
+            }
+        }
+    }
+
+    info!(
+        redirects = %redirects.len(),
+        %unresolved,
+        "finished checking old URLs",
+    );
+
+    let toml = toml::to_string(&RedirectsTable { redirects })?;
+    match output {
+        Some(output) => fs::write(output, toml).await.map_err(WriteOutput)?,
+        None => print!("{toml}"),
+    }
+
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+pub enum MigrateUrlsError {
+    #[error("failed to read input file: {0}")]
+    ReadInput(#[source] io::Error),
+
+    #[error("failed to serialize redirects: {0}")]
+    Serialize(#[from] toml::ser::Error),
+
+    #[error("failed to write output file: {0}")]
+    WriteOutput(#[source] io::Error),
+}
+
+#[derive(Serialize)]
+struct RedirectsTable {
+    redirects: BTreeMap<String, String>,
+}
+
+/// Extract the path of every `<loc>` in a sitemap.
+fn parse_sitemap(raw: &str) -> Vec<(String, Option<String>)> {
+    raw.split("<loc>")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("</loc>"))
+        .map(|(loc, _)| (to_path(loc.trim()), None))
+        .collect()
+}
+
+/// Parse a mapping file, which has one old path per line, optionally followed by whitespace and
+/// the new path it should redirect to. Blank lines and lines starting with `#` are ignored.
+fn parse_mapping(raw: &str) -> Vec<(String, Option<String>)> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let old = parts.next().map(to_path).unwrap_or_default();
+            (old, parts.next().map(to_path))
+        })
+        .collect()
+}
+
+/// Turn an absolute URL into just its path, leaving anything that's already a path alone.
+fn to_path(raw: &str) -> String {
+    match raw.parse::<Url>() {
+        Ok(url) => url.path().to_owned(),
+        Err(_) => raw.to_owned(),
+    }
+}
+
+/// Works out whether a path would be served successfully, mirroring the routes in `main`.
+struct Resolver<'a> {
+    config: &'a Config,
+    content: &'a Content,
+}
+
+impl Resolver<'_> {
+    fn resolves(&self, path: &str) -> bool {
+        let show_drafts = self.config.drafts;
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let segments = path
+            .strip_prefix('/')
+            .unwrap_or(path)
+            .split('/')
+            .collect::<Vec<_>>();
+
+        match segments.as_slice() {
+            [""] => self.content.page("_index").is_some(),
+            ["posts" | "chrono" | "tags" | "style.css" | "rss.xml"] => true,
+            ["posts", post] => self.content.post(post, show_drafts).is_some(),
+            ["posts", post, "entry", index] => {
+                let Ok(index) = index.parse::<usize>() else {
+                    return false;
+                };
+                self.content
+                    .post(post, show_drafts)
+                    .and_then(|post| post.into_entry(index, show_drafts))
+                    .is_some()
+            }
+            ["tagged", tag] => match (*tag).try_conv::<TagName>() {
+                Ok(tag) => self.content.tag_exists(&tag),
+                Err(_) => false,
+            },
+            ["static", rest @ ..] => self.config.static_path.join(rest.join("/")).is_file(),
+            [page] => self.content.page(page).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Try to find the new location of an old path by matching its last segment against the names
+    /// of posts and pages, ignoring any file extension and date prefix.
+    fn guess(&self, old: &str) -> Option<String> {
+        let slug = old
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .map(|segment| segment.split_once('.').map_or(segment, |(stem, _)| stem))
+            .filter(|slug| !slug.is_empty())?;
+        let slug = strip_date(slug);
+
+        let nodes = self.content.nodes(self.config.drafts);
+
+        let mut candidates = nodes
+            .posts()
+            .filter(|(path, _)| strip_date(path.as_str()) == slug)
+            .map(|(path, _)| format!("/posts/{path}"))
+            .chain(
+                nodes
+                    .pages()
+                    .filter(|(path, _)| path.as_str() == slug)
+                    .map(|(path, _)| format!("/{path}")),
+            );
+
+        match (candidates.next(), candidates.next()) {
+            (Some(only), None) => Some(only),
+            (Some(_), Some(_)) => {
+                warn!(%old, "found more than one candidate for the new path");
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+fn strip_date(slug: &str) -> &str {
+    match NaiveDate::parse_and_remainder(slug, "%Y-%m-%d") {
+        Ok((_, rest)) => rest.trim_start_matches('-'),
+        Err(_) => slug,
+    }
+}
@@ -0,0 +1,138 @@
+use std::{env, io, process};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use maud::Render;
+use thiserror::Error;
+use tokio::fs;
+use tracing::info;
+
+use crate::{
+    flags::FeatureFlags,
+    state::{
+        assets::{Assets, CompileStylesheetError},
+        Config, Content, LoadContentError, LoadThemeError, Site, Themes,
+    },
+    templates::pages,
+};
+
+/// Render a single post or page the same way the server would, printing the HTML or writing it to
+/// a file.
+///
+/// Drafts are always rendered. With `standalone`, the result is a whole page in the site's layout,
+/// with the stylesheet and default theme embedded so that it can be opened straight from disk;
+/// otherwise it's just the content. With `open`, the result is written to a temporary file (unless
+/// an output file is given) and opened in the browser.
+pub async fn render(
+    config: Config,
+    file: &Utf8Path,
+    standalone: bool,
+    output: Option<&Utf8Path>,
+    open: bool,
+) -> Result<(), PreviewError> {
+    use PreviewError::*;
+
+    let file = file.canonicalize_utf8().map_err(ReadFile)?;
+    let metadata = fs::metadata(&file).await.map_err(ReadFile)?;
+    let (Some(root), Some(name)) = (file.parent(), file.file_stem()) else {
+        return Err(NotContent(file));
+    };
+
+    config.configure_markdown();
+    let content = Content::empty_in(root.to_owned(), &config.limits);
+    content.load(&file, metadata).await?;
+
+    let html = if standalone {
+        let themes = Themes::load(&config.themes_path, &config.theme)?;
+        let flags = FeatureFlags::new(config.flags, None);
+        let theme = themes.get(None, None).await.with_flags(flags.resolve(None));
+
+        let assets = Assets::load(&config.static_path).await;
+        let stylesheet = assets.stylesheet().await?;
+        let assets = assets.with_inline_stylesheet(stylesheet);
+        let site = Site::new(
+            config.site,
+            config.feeds,
+            config.nav,
+            config.footer,
+            &config.tags,
+            assets,
+        );
+
+        if let Some(post) = content.post(name, true) {
+            pages::post(post, theme, site).await
+        } else if let Some(page) = content.page(name) {
+            pages::page(page, theme, site).await
+        } else {
+            return Err(NotContent(file));
+        }
+    } else if let Some(post) = content.post(name, true) {
+        post.render()
+    } else if let Some(page) = content.page(name) {
+        page.render()
+    } else {
+        return Err(NotContent(file));
+    };
+
+    let output = match output {
+        Some(output) => Some(output.to_owned()),
+        None if open => {
+            let temp = Utf8PathBuf::from_path_buf(env::temp_dir())
+                .map_err(|_| TempDir)?
+                .join(format!("maddie-wtf-preview-{name}.html"));
+            Some(temp)
+        }
+        None => None,
+    };
+
+    match &output {
+        Some(output) => {
+            fs::write(output, html.into_string())
+                .await
+                .map_err(WriteOutput)?;
+            info!(%output, "wrote rendered HTML");
+        }
+        None => print!("{}", html.into_string()),
+    }
+
+    if let Some(output) = output.filter(|_| open) {
+        let opener = if cfg!(target_os = "macos") {
+            "open"
+        } else {
+            "xdg-open"
+        };
+
+        process::Command::new(opener)
+            .arg(&output)
+            .status()
+            .map_err(Open)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+pub enum PreviewError {
+    #[error("failed to read file: {0}")]
+    ReadFile(#[source] io::Error),
+
+    #[error("{0} is not a markdown post or page")]
+    NotContent(Utf8PathBuf),
+
+    #[error(transparent)]
+    LoadContent(#[from] LoadContentError),
+
+    #[error(transparent)]
+    LoadTheme(#[from] LoadThemeError),
+
+    #[error(transparent)]
+    CompileStylesheet(#[from] CompileStylesheetError),
+
+    #[error("the temporary directory's path contains invalid UTF-8")]
+    TempDir,
+
+    #[error("failed to write output file: {0}")]
+    WriteOutput(#[source] io::Error),
+
+    #[error("failed to open rendered HTML: {0}")]
+    Open(#[source] io::Error),
+}
@@ -0,0 +1,86 @@
+use std::io;
+
+use camino::Utf8PathBuf;
+use chrono::Utc;
+use thiserror::Error;
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::info;
+
+use crate::state::Config;
+
+/// Start a new post (or page) called `title` in the content path, and print the path of its file.
+///
+/// Posts are named after today's date and the title, and start out as drafts. Nothing that's
+/// already there is ever overwritten.
+pub async fn new(
+    config: Config,
+    title: &str,
+    bundle: bool,
+    page: bool,
+) -> Result<(), ScaffoldError> {
+    use ScaffoldError::*;
+
+    let slug = slugify(title);
+    if slug.is_empty() {
+        return Err(EmptySlug(title.to_owned()));
+    }
+
+    let title = toml::Value::String(title.to_owned());
+    let (path, frontmatter) = if page {
+        let path = config.content_path.join(format!("{slug}.md"));
+        (path, format!("title = {title}\n"))
+    } else {
+        let name = format!("{}-{slug}", Utc::now().date_naive());
+        let path = if bundle {
+            config.content_path.join(&name).join("index.md")
+        } else {
+            config.content_path.join(format!("{name}.md"))
+        };
+        (path, format!("title = {title}\ndraft = true\ntags = []\n"))
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|error| Write(path.clone(), error))?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .await
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::AlreadyExists => AlreadyExists(path.clone()),
+            _ => Write(path.clone(), error),
+        })?;
+    file.write_all(format!("---\n{frontmatter}---\n\n").as_bytes())
+        .await
+        .map_err(|error| Write(path.clone(), error))?;
+
+    info!(%path, "created new file");
+    println!("{path}");
+    Ok(())
+}
+
+/// The title in lowercase, with anything that isn't a letter or a number replaced with dashes.
+fn slugify(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[derive(Error, Debug)]
+pub enum ScaffoldError {
+    #[error("\"{0}\" has nothing in it to name a file after")]
+    EmptySlug(String),
+
+    #[error("{0} already exists")]
+    AlreadyExists(Utf8PathBuf),
+
+    #[error("failed to write {0}: {1}")]
+    Write(Utf8PathBuf, #[source] io::Error),
+}
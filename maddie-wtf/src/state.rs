@@ -1,72 +1,338 @@
 use std::{
-    collections::HashMap, fs::Metadata, io, path::StripPrefixError, sync::Arc, time::Duration,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    convert::Infallible,
+    fs::Metadata,
+    future::Future,
+    io, iter,
+    ops::Deref,
+    panic,
+    path::StripPrefixError,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, SystemTime},
 };
 
-use axum::extract::FromRef;
+use arc_swap::ArcSwap;
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use axum_extra::extract::CookieJar;
 use camino::{Utf8Path, Utf8PathBuf};
-use chrono::naive::NaiveDate;
+use chrono::{naive::NaiveDate, DateTime, Days, Utc};
 use comrak::{
-    adapters::HeadingAdapter, markdown_to_html_with_plugins, plugins::syntect::SyntectAdapter,
+    adapters::HeadingAdapter,
+    plugins::syntect::{SyntectAdapter, SyntectAdapterBuilder},
     ComrakOptions, ComrakPlugins,
 };
 use either::Either;
 use ignore::Walk;
 use lazy_static::lazy_static;
 use maud::{html, Markup, PreEscaped};
-use notify::{RecommendedWatcher, RecursiveMode};
-use notify_debouncer_mini::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use syntect::{
     highlighting::ThemeSet as SyntectThemeSet,
     html::{css_for_theme_with_class_style, ClassStyle},
+    parsing::SyntaxSet,
     Error as SyntectError, LoadingError as SyntectLoadingError,
 };
 use thiserror::Error;
 use tokio::{
-    fs, runtime,
-    sync::{RwLock, RwLockReadGuard},
+    fs,
+    sync::{broadcast, Notify, RwLock},
     task::JoinHandle,
+    time,
 };
 use tower_livereload::Reloader;
-use tracing::{debug, error, info, instrument, span, warn, Level};
-use url::Url;
+use tracing::{debug, error, info, instrument, warn};
+use url::{form_urlencoded, Url};
+use www::{config::Environment, observability::LogFilter};
 
+#[cfg(feature = "demo")]
+use crate::demo::DemoFiles;
 use crate::{
+    config::{
+        self, AdminConfig, CacheControlConfig, CompressionConfig, ConfigFile, EmbargoConfig,
+        EmbedsConfig, EventsConfig, FeedsConfig, FooterConfig, GitConfig, LimitsConfig, Link,
+        LinkcheckConfig, LintConfig, MarkdownConfig, NamedThemeConfig, ReadConfigError, SiteConfig,
+        StaleDraftsConfig, TagConfig, ThemeConfig, WarmupConfig, WatcherConfig,
+    },
+    demo,
+    embeds::Embeds,
+    flags::{FeatureFlags, Flags, FLAGS_COOKIE, FLAGS_HEADER},
+    git::{self, ContentRepo, Forge, GitError},
+    metric,
     state::{
+        assets::{fingerprint, Assets},
+        bodies::{Bodies, Body},
+        embargo::{EmbargoedPost, Sealed, Vault},
+        events::{ContentEvent, Webhook},
+        index::{Index, Indexes},
+        manifest::{Manifest, ManifestDiff, Manifests},
+        markdown::Pipeline,
         names::TagName,
-        render::{NodesRef, PageRef, PostRef},
+        render::{HistoryRef, NodesRef, PageRef, PostRef, Revision},
+        sanitize::Sanitizer,
+        shortcodes::InvalidShortcode,
+        watcher::{ContentWatcher, ReloadTrigger, WatchError, WatcherHealth},
     },
-    Args,
+    warmup::Warmup,
+    ConfigArgs,
 };
 
+mod ansi;
+pub mod assets;
+pub mod bodies;
+pub mod embargo;
+mod emoji;
+pub mod events;
+#[cfg(feature = "responsive-images")]
+mod images;
+pub mod index;
+pub mod manifest;
+mod markdown;
 pub mod names;
 pub mod render;
+mod sanitize;
+mod shortcodes;
+pub mod visibility;
+pub mod watcher;
 
 lazy_static! {
-    static ref SYNTECT_ADAPTER: SyntectAdapter = SyntectAdapter::new(None);
+    static ref SYNTECT_ADAPTER: SyntectAdapter = SyntectAdapterBuilder::new()
+        .css()
+        .syntax_set(
+            SYNTAX_SET
+                .get()
+                .cloned()
+                .unwrap_or_else(SyntaxSet::load_defaults_newlines),
+        )
+        .build();
     static ref COMRAK_PLUGINS: ComrakPlugins<'static> = {
         let mut plugins = ComrakPlugins::default();
         plugins.render.codefence_syntax_highlighter = Some(&*SYNTECT_ADAPTER);
         plugins
     };
     static ref COMRAK_OPTIONS: ComrakOptions = {
+        let config = markdown_config();
         let mut options = ComrakOptions::default();
         options.render.unsafe_ = true;
+        options.extension.footnotes = config.footnotes;
+        options.extension.table = config.tables;
+        options.extension.strikethrough = config.strikethrough;
+        options.extension.tasklist = config.tasklist;
+        options.extension.superscript = config.superscript;
+        options.extension.autolink = config.autolink;
         options
     };
+    /// The passes every piece of markdown goes through, in order. Headings are tagged for the
+    /// table of contents separately, by a comrak plugin, since that only applies to some of it.
+    static ref MARKDOWN_PIPELINE: Pipeline = {
+        let config = markdown_config();
+        let mut pipeline = Pipeline::default();
+        // Figures have to be found before light and dark images are paired up into a picture.
+        if config.figures.enabled {
+            pipeline = pipeline.with(markdown::Figures(&config.figures));
+        }
+        pipeline = pipeline
+            .with(markdown::ImageVariants)
+            .with(markdown::MediaEmbeds)
+            .with(markdown::Diagrams(&config.diagrams))
+            // Diagrams have to be taken out before the rest of the code blocks are rendered.
+            .with(markdown::CodeBlocks {
+                line_numbers: config.line_numbers,
+                highlighter: &SYNTECT_ADAPTER,
+            })
+            .with(markdown::InlineCode {
+                highlighter: &SYNTECT_ADAPTER,
+            });
+        if config.emoji {
+            pipeline = pipeline.with(markdown::EmojiShortcodes);
+        }
+        if config.external_links.enabled {
+            pipeline = pipeline.with(markdown::ExternalLinks {
+                config: &config.external_links,
+                base_url: base_url(),
+            });
+        }
+        if config.sanitize {
+            pipeline = pipeline.with_sanitizer(Sanitizer::new());
+        }
+        pipeline
+    };
+}
+
+/// The markdown extensions turned on in the config, which have to be set before anything is
+/// rendered.
+static MARKDOWN_CONFIG: OnceLock<MarkdownConfig> = OnceLock::new();
+
+fn markdown_config() -> &'static MarkdownConfig {
+    MARKDOWN_CONFIG.get_or_init(MarkdownConfig::default)
+}
+
+/// The languages code blocks can be highlighted in, if any were loaded on top of the ones built
+/// into syntect.
+static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+
+/// The syntaxes built into syntect, along with every `.sublime-syntax` file in the given directory.
+fn load_syntaxes(syntaxes_path: &Utf8Path) -> Result<SyntaxSet, SyntectLoadingError> {
+    let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+    builder.add_from_folder(syntaxes_path, true)?;
+    Ok(builder.build())
+}
+
+/// The base URL of the site, which links are compared against to tell whether they're external.
+static BASE_URL: OnceLock<Url> = OnceLock::new();
+
+fn base_url() -> &'static Url {
+    BASE_URL.get_or_init(|| SiteConfig::default().base_url)
 }
 
+/// Smaller copies of images, made once they're turned on in the config.
+#[cfg(feature = "responsive-images")]
+static IMAGES: OnceLock<images::Images> = OnceLock::new();
+
 fn markdown_to_html(md_input: &str) -> String {
-    markdown_to_html_with_plugins(md_input, &COMRAK_OPTIONS, &COMRAK_PLUGINS)
+    markdown_to_html_in(md_input, &markdown::Context::default())
+}
+
+fn markdown_to_html_in(md_input: &str, context: &markdown::Context) -> String {
+    MARKDOWN_PIPELINE.render(md_input, &COMRAK_OPTIONS, &COMRAK_PLUGINS, context)
 }
 
-fn markdown_to_html_toc_tagged(md_input: &str) -> String {
+fn markdown_to_html_toc_tagged(md_input: &str, context: &markdown::Context) -> String {
+    let tagger = TocTagger::default();
     let mut plugins = COMRAK_PLUGINS.clone();
-    plugins.render.heading_adapter = Some(&TocTagger);
-    markdown_to_html_with_plugins(md_input, &COMRAK_OPTIONS, &plugins)
+    plugins.render.heading_adapter = Some(&tagger);
+    MARKDOWN_PIPELINE.render(md_input, &COMRAK_OPTIONS, &plugins, context)
 }
 
-struct TocTagger;
+/// Run `render` on the blocking thread pool, since rendering markdown (with its code highlighted,
+/// its images resized and its diagrams drawn) can take long enough to hold up everything else
+/// waiting on an async worker.
+pub async fn render_blocking<T, F>(render: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(render)
+        .await
+        .unwrap_or_else(|error| panic::resume_unwind(error.into_panic()))
+}
+
+/// Finish off the HTML rendered from a post's or page's markdown: in a bundle, relative links are
+/// made to lead to its files, and images are given smaller copies to choose from and loaded lazily
+/// if those are on. `leading` is whether the HTML is at the top of the page it's shown on, where
+/// its first image shouldn't wait.
+fn finish_html(html: String, bundle: Option<&str>, leading: bool) -> String {
+    let html = match bundle {
+        Some(base) => markdown::rebase_links(&html, base),
+        None => html,
+    };
+
+    #[cfg(feature = "responsive-images")]
+    let html = match IMAGES.get() {
+        Some(images) => images.rewrite(&html),
+        None => html,
+    };
+
+    if markdown_config().lazy_images {
+        markdown::lazy_images(&html, leading)
+    } else {
+        html
+    }
+}
+
+/// The offset of the first `---` in `raw` that's on a line of its own, which is what separates
+/// frontmatter from markdown. A `---` anywhere else is part of the markdown, like the row under the
+/// header of a table.
+pub fn find_delimiter(raw: &str) -> Option<usize> {
+    let mut line_start = 0;
+    for line in raw.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some(line_start);
+        }
+        line_start += line.len();
+    }
+    None
+}
+
+fn split_at_delimiter(raw: &str) -> Option<(&str, &str)> {
+    find_delimiter(raw).map(|offset| (&raw[..offset], &raw[offset + 3..]))
+}
+
+/// The day of the newest of `commits` (which are newest first), if it was after `date`, for
+/// whatever was posted on `date` to be taken to have been updated then.
+fn commit_after(commits: &[NaiveDate], date: NaiveDate) -> Option<NaiveDate> {
+    commits.first().copied().filter(|updated| *updated > date)
+}
+
+/// The key a node is loaded under, from the path of its file relative to the content root: the
+/// path without its extension, or the path of the directory for a bundled post.
+pub fn node_key(relative_path: &Utf8Path) -> Utf8PathBuf {
+    match bundle_dir(relative_path) {
+        Some(dir) => dir.to_owned(),
+        None => relative_path.with_extension(""),
+    }
+}
+
+/// The directory a post is bundled in, if `relative_path` is a bundle's `index.md`.
+///
+/// A bundle is a directory named like a post's file would be, starting with a date, holding the
+/// post's markdown in `index.md` alongside the images and other files it uses. Those are served at
+/// `/posts/<post>/<file>`.
+pub fn bundle_dir(relative_path: &Utf8Path) -> Option<&Utf8Path> {
+    if relative_path.file_name() != Some("index.md") {
+        return None;
+    }
+
+    let dir = relative_path.parent()?;
+    let name = dir.file_name()?;
+    NaiveDate::parse_and_remainder(name, "%Y-%m-%d")
+        .is_ok()
+        .then_some(dir)
+}
+
+/// The ID of the anchor placed where the summary of a post or thread entry ends. Entries after the
+/// first get their own, since a whole thread is shown on one page.
+pub fn continue_reading_id(index: usize) -> String {
+    if index == 0 {
+        "continue-reading".to_owned()
+    } else {
+        format!("continue-reading-{index}")
+    }
+}
+
+/// Gives every heading in a document an ID to link to, made from its text.
+///
+/// A new one is needed for each document, since it keeps track of the IDs it's already given out
+/// so that headings with the same text don't end up with the same ID.
+#[derive(Default)]
+struct TocTagger {
+    used: Mutex<HashSet<String>>,
+}
+
+impl TocTagger {
+    /// Claim `slug` for a heading, or the first of `slug-1`, `slug-2`, etc. that hasn't already
+    /// been claimed by an earlier one.
+    fn claim(&self, slug: String) -> String {
+        let mut used = self.used.lock().expect("used slugs mutex is not poisoned");
+        let slug = if used.contains(&slug) {
+            (1..)
+                .map(|n| format!("{slug}-{n}"))
+                .find(|candidate| !used.contains(candidate))
+                .expect("some suffix is unused")
+        } else {
+            slug
+        };
+        used.insert(slug.clone());
+        slug
+    }
+}
 
 impl HeadingAdapter for TocTagger {
     fn enter(
@@ -88,6 +354,7 @@ impl HeadingAdapter for TocTagger {
                 }
             })
             .collect::<String>();
+        let slug = self.claim(slug);
 
         write!(
             output,
@@ -110,241 +377,761 @@ impl HeadingAdapter for TocTagger {
 #[derive(Clone, Debug)]
 pub struct Config {
     pub drafts: bool,
+    pub strict: bool,
     pub content_path: Utf8PathBuf,
     pub static_path: Utf8PathBuf,
     pub themes_path: Utf8PathBuf,
+    pub syntaxes_path: Option<Utf8PathBuf>,
+    pub site: SiteConfig,
+    pub feeds: FeedsConfig,
+    pub theme: ThemeConfig,
+    pub nav: Vec<Link>,
+    pub footer: FooterConfig,
+    pub redirects: HashMap<String, String>,
+    pub admin_token: Option<String>,
+    pub git: GitConfig,
+    pub lints: LintConfig,
+    pub linkcheck: LinkcheckConfig,
+    pub limits: LimitsConfig,
+    pub warmup: WarmupConfig,
+    pub stale_drafts: StaleDraftsConfig,
+    pub watcher: WatcherConfig,
+    pub compression: CompressionConfig,
+    pub cache_control: CacheControlConfig,
+    pub embeds: EmbedsConfig,
+    pub embargo: EmbargoConfig,
+    pub events: EventsConfig,
+    pub flags: BTreeMap<String, bool>,
+    pub markdown: MarkdownConfig,
+    pub tags: BTreeMap<String, TagConfig>,
+    /// The example content extracted in demo mode, which is deleted once every copy of it is
+    /// dropped.
+    #[cfg(feature = "demo")]
+    pub demo_files: Option<Arc<DemoFiles>>,
 }
 
-impl From<Args> for Config {
-    fn from(args: Args) -> Self {
-        let Args {
+impl TryFrom<ConfigArgs> for Config {
+    type Error = LoadConfigError;
+
+    fn try_from(args: ConfigArgs) -> Result<Self, Self::Error> {
+        use LoadConfigError::*;
+
+        let ConfigArgs {
+            config,
+            demo,
             drafts,
+            strict,
+            environment,
+            site_root,
             content_path,
+            content_git_url,
             static_path,
             themes_path,
-            ..
+            syntaxes_path,
+            admin_token,
         } = args;
-        Self {
-            drafts,
-            content_path: content_path
-                .canonicalize_utf8()
-                .expect("should be able to canonicalize content path"),
-            static_path: static_path
-                .canonicalize_utf8()
-                .expect("should be able to canonicalize static path"),
-            themes_path: themes_path
-                .canonicalize_utf8()
-                .expect("should be able to canonicalize themes path"),
-        }
+
+        let file = match config {
+            Some(path) => ConfigFile::read(&path)?,
+            None => ConfigFile::default(),
+        };
+
+        // Content that fails to load is only tolerated by default outside of production, where
+        // it's more likely to be a work in progress.
+        let strict = strict
+            .or(file.strict)
+            .unwrap_or(matches!(environment, Some(Environment::Production)));
+
+        // In demo mode, the embedded example content is served instead of anything on disk, with
+        // themes that are built into syntect. The files are deleted again once every copy of them
+        // has been dropped.
+        #[cfg(feature = "demo")]
+        let demo_files = demo
+            .then(demo::extract)
+            .transpose()
+            .map_err(ExtractDemoFiles)?
+            .map(Arc::new);
+        #[cfg(feature = "demo")]
+        let demo_root = demo_files.as_ref().map(|files| files.root());
+        #[cfg(not(feature = "demo"))]
+        let demo_root: Option<&Utf8Path> = match demo {
+            true => return Err(DemoNotIncluded),
+            false => None,
+        };
+
+        // Anything passed on the command line or through the environment overrides the config
+        // file, and any path that isn't given at all falls back to a subdirectory of the site
+        // root, if there is one.
+        let site_root = site_root.or(file.site_root);
+        let resolve = |path: Option<Utf8PathBuf>, file_path: Option<Utf8PathBuf>, name, subdir| {
+            path.or(file_path)
+                .or_else(|| site_root.as_ref().map(|root| root.join(subdir)))
+                .ok_or(MissingPath(name))
+        };
+
+        // Every path has to exist, and is made absolute so that it doesn't depend on where the
+        // server is run from.
+        let canonicalize = |path: Utf8PathBuf, name| {
+            path.canonicalize_utf8()
+                .map_err(|err| ResolvePath(name, path, err))
+        };
+
+        // Content cloned from git is read from wherever it's checked out, whatever the content path
+        // would otherwise be. It isn't checked out until a command needs it (see
+        // `check_out_content`), so that path might not exist yet.
+        let mut git = file.git;
+        git.url = content_git_url.or(git.url);
+
+        let (content_path, static_path, themes_path, syntaxes_path, theme) = match demo_root {
+            Some(root) => (
+                root.join("content"),
+                root.join("static"),
+                root.join("themes"),
+                None,
+                ThemeConfig {
+                    light: demo::LIGHT_THEME.to_owned(),
+                    dark: demo::DARK_THEME.to_owned(),
+                    ..Default::default()
+                },
+            ),
+            None => {
+                let content_path = match &git.url {
+                    Some(_) => match &git.subdirectory {
+                        Some(subdirectory) => git.checkout_path().join(subdirectory),
+                        None => git.checkout_path(),
+                    },
+                    None => canonicalize(
+                        resolve(content_path, file.content_path, "content_path", "content")?,
+                        "content_path",
+                    )?,
+                };
+                let static_path = resolve(static_path, file.static_path, "static_path", "static")?;
+                let themes_path = resolve(themes_path, file.themes_path, "themes_path", "themes")?;
+                let syntaxes_path = syntaxes_path.or(file.syntaxes_path).or_else(|| {
+                    site_root
+                        .as_ref()
+                        .map(|root| root.join("syntaxes"))
+                        .filter(|path| path.is_dir())
+                });
+                (
+                    content_path,
+                    static_path,
+                    themes_path,
+                    syntaxes_path,
+                    file.theme,
+                )
+            }
+        };
+
+        Ok(Self {
+            drafts: drafts.unwrap_or(file.drafts),
+            strict,
+            content_path,
+            static_path: canonicalize(static_path, "static_path")?,
+            themes_path: canonicalize(themes_path, "themes_path")?,
+            syntaxes_path: syntaxes_path
+                .map(|path| canonicalize(path, "syntaxes_path"))
+                .transpose()?,
+            site: file.site,
+            feeds: file.feeds,
+            theme,
+            nav: file.nav.unwrap_or_else(config::default_nav),
+            footer: file.footer,
+            redirects: file.redirects.into_iter().collect(),
+            admin_token: admin_token.or(file.admin.token),
+            git,
+            lints: file.lints,
+            linkcheck: file.linkcheck,
+            limits: file.limits,
+            warmup: file.warmup,
+            stale_drafts: file.stale_drafts,
+            watcher: file.watcher,
+            compression: file.compression,
+            cache_control: file.cache_control,
+            embeds: file.embeds,
+            embargo: file.embargo,
+            events: file.events,
+            flags: file.flags,
+            markdown: file.markdown,
+            tags: file.tags,
+            #[cfg(feature = "demo")]
+            demo_files,
+        })
     }
 }
 
+#[derive(Error, Debug)]
+pub enum LoadConfigError {
+    #[error(transparent)]
+    ReadConfig(#[from] ReadConfigError),
+
+    #[error(
+        "no {0} was given on the command line, in the environment or in the config file, and \
+         there's no site root to find it in"
+    )]
+    MissingPath(&'static str),
+
+    #[error("the {0} {1} could not be resolved: {2}")]
+    ResolvePath(&'static str, Utf8PathBuf, #[source] io::Error),
+
+    #[cfg(feature = "demo")]
+    #[error("failed to extract demo files: {0}")]
+    ExtractDemoFiles(#[source] io::Error),
+
+    #[cfg(not(feature = "demo"))]
+    #[error("demo mode was asked for, but this build doesn't include the demo content")]
+    DemoNotIncluded,
+}
+
 impl Config {
-    pub async fn load_state(self, reloader: Reloader) -> Result<State, LoadStateError> {
-        use LoadStateError::*;
+    /// Clone the content repository, or bring the clone from last time up to date, if the content
+    /// comes from git. This is left to the commands that read the content, since it can mean going
+    /// over the network.
+    pub fn check_out_content(&self) -> Result<(), GitError> {
+        #[cfg(feature = "demo")]
+        if self.demo_files.is_some() {
+            return Ok(());
+        }
 
-        #[cfg(not(debug_assertions))]
-        let _ = reloader;
+        match &self.git.url {
+            Some(url) => git::checkout(url, self.git.branch.as_deref(), &self.git.checkout_path()),
+            None => Ok(()),
+        }
+    }
 
-        let theme_set = SyntectThemeSet::load_from_folder(self.themes_path)?;
-        let theme = Theme::try_load(theme_set, "OneHalfLight", "OneHalfDark")?;
+    /// The effective configuration, in the form of a config file that would produce it (apart from
+    /// the admin token, the embargo preview and webhook secrets, and any credentials in the clone
+    /// URL, which are redacted).
+    pub fn to_file(&self) -> ConfigFile {
+        ConfigFile {
+            site_root: None,
+            content_path: Some(self.content_path.clone()),
+            static_path: Some(self.static_path.clone()),
+            themes_path: Some(self.themes_path.clone()),
+            syntaxes_path: self.syntaxes_path.clone(),
+            drafts: self.drafts,
+            strict: Some(self.strict),
+            site: self.site.clone(),
+            feeds: self.feeds.clone(),
+            theme: self.theme.clone(),
+            nav: Some(self.nav.clone()),
+            footer: self.footer.clone(),
+            redirects: self.redirects.clone().into_iter().collect(),
+            admin: AdminConfig {
+                token: self.admin_token.as_ref().map(|_| "<redacted>".to_owned()),
+            },
+            git: GitConfig {
+                webhook_secret: self
+                    .git
+                    .webhook_secret
+                    .as_ref()
+                    .map(|_| "<redacted>".to_owned()),
+                url: self.git.url.as_deref().map(git::redact_url),
+                ..self.git.clone()
+            },
+            lints: self.lints.clone(),
+            linkcheck: self.linkcheck.clone(),
+            limits: self.limits.clone(),
+            warmup: self.warmup.clone(),
+            stale_drafts: self.stale_drafts.clone(),
+            watcher: self.watcher.clone(),
+            compression: self.compression.clone(),
+            cache_control: self.cache_control.clone(),
+            embeds: self.embeds.clone(),
+            embargo: EmbargoConfig {
+                preview_secret: self
+                    .embargo
+                    .preview_secret
+                    .as_ref()
+                    .map(|_| "<redacted>".to_owned()),
+            },
+            events: EventsConfig {
+                webhook: self.events.webhook.clone(),
+                secret: self.events.secret.as_ref().map(|_| "<redacted>".to_owned()),
+            },
+            flags: self.flags.clone(),
+            markdown: self.markdown.clone(),
+            tags: self.tags.clone(),
+        }
+    }
 
-        let content = Content::empty_in(self.content_path.clone());
+    /// Turn on the markdown extensions from the config. This has to happen before anything is
+    /// rendered, and only the first call has any effect.
+    pub fn configure_markdown(&self) {
+        // Both are set even if the first one already was, so that neither is left at its default.
+        let markdown_set = MARKDOWN_CONFIG.set(self.markdown.clone()).is_ok();
+        let base_url_set = BASE_URL.set(self.site.base_url.clone()).is_ok();
+        if !markdown_set || !base_url_set {
+            warn!("markdown extensions were already configured, so changes won't apply");
+        }
 
-        let walker = Walk::new(&self.content_path);
-        for result in walker {
-            match result {
-                Ok(entry) => {
-                    let Ok(path) = Utf8PathBuf::from_path_buf(entry.path().to_path_buf()) else {
-                        warn!(
-                            path = ?entry.path(),
-                            "skipping entry with path that contains invalid UTF-8"
-                        );
-                        continue;
-                    };
+        if self.markdown.images.enabled {
+            #[cfg(feature = "responsive-images")]
+            match images::Images::new(&self.markdown.images, &self.static_path, &self.content_path)
+            {
+                Ok(images) => {
+                    let _ = IMAGES.set(images);
+                }
+                Err(error) => {
+                    warn!(%error, "failed to create directory for resized images, not resizing");
+                }
+            }
 
-                    let Ok(metadata) = entry.metadata() else {
-                        warn!(%path, "skipping entry without valid metadata");
-                        continue;
-                    };
+            #[cfg(not(feature = "responsive-images"))]
+            warn!("resizing images is turned on, but this build doesn't include it");
+        }
 
-                    if let Err(error) = content.load(path, metadata).await {
-                        warn!(%error, "failed to load content");
-                    }
+        if let Some(syntaxes_path) = &self.syntaxes_path {
+            match load_syntaxes(syntaxes_path) {
+                Ok(syntax_set) => {
+                    info!(
+                        %syntaxes_path,
+                        syntaxes = %syntax_set.syntaxes().len(),
+                        "loaded syntax definitions",
+                    );
+                    let _ = SYNTAX_SET.set(syntax_set);
+                }
+                Err(error) => {
+                    warn!(%syntaxes_path, %error, "failed to load syntax definitions, skipping them");
                 }
-                Err(error) => error!(%error, "directory walker encountered error"),
             }
         }
+    }
 
-        let (event_tx, event_rx) = std::sync::mpsc::channel::<DebouncedEvent>();
+    /// Walk the content path and load everything found into a new set of content, without
+    /// watching for changes.
+    pub async fn load_content(&self) -> Content {
+        self.load_content_counting_failures().await.0
+    }
 
-        let runtime = runtime::Handle::current();
-        let content_1 = content.clone();
-        let content_path_1 = self.content_path.clone();
+    /// Load the content like [`Config::load_content`], along with the number of files that failed
+    /// to load.
+    pub async fn load_content_counting_failures(&self) -> (Content, usize) {
+        self.configure_markdown();
 
-        let loader_handle = runtime.spawn_blocking(move || {
-            let _guard = span!(Level::ERROR, "content_loader").entered();
-            let runtime = runtime::Handle::current();
-            while let Ok(event) = event_rx.recv() {
-                runtime.block_on(async {
-                    let Ok(path) = Utf8PathBuf::from_path_buf(event.path.clone()) else {
-                        warn!(
-                            path = ?event.path,
-                            "skipping event with path that contains invalid UTF-8"
-                        );
-                        return;
-                    };
+        let content = Content::empty_in(self.content_path.clone(), &self.limits)
+            .with_preview_secret(self.embargo.preview_secret.clone())
+            .with_git_dates(self.git.dates)
+            .with_forge(self.git.forge());
 
-                    let Ok(relative) = path.strip_prefix(&content_path_1) else {
-                        debug!(
-                            %path,
-                            "skipping entry for path that isn't relative to the content path"
-                        );
-                        return;
-                    };
+        let failures = content.load_all().await;
+        let _ = content.initial_failures.set(failures);
 
-                    if relative
-                        .components()
-                        .any(|component| component.as_str().starts_with('.'))
-                    {
-                        debug!(
-                            %path,
-                            "skipping entry for a path containing a hidden file or directory"
-                        );
-                        return;
-                    }
+        content.record_manifest();
+        (content, failures)
+    }
 
-                    if path
-                        .file_name()
-                        .is_some_and(|name| name == "4913" || name.ends_with('~'))
-                    {
-                        // nvim creates these when you write files. I think the ~ one is
-                        // intentional, but the 4913 thing seems to be a longstanding bug:
-                        //
-                        // https://github.com/neovim/neovim/issues/3460
-                        debug!(
-                            %path,
-                            "skipping entry that appears to be an editor temporary file"
-                        );
-                        return;
-                    }
+    pub fn settings(&self) -> Settings {
+        Settings {
+            show_drafts: self.drafts,
+            strict: self.strict,
+            max_draft_age: Days::new(self.stale_drafts.max_age_days.into()),
+            flags: FeatureFlags::new(self.flags.clone(), self.admin_token.clone()),
+        }
+    }
 
-                    if !fs::try_exists(&path).await.unwrap_or_default() {
-                        warn!(%path, "event probably represents a deleted file");
-                        // TODO: handle deletions
-                    } else {
-                        let Ok(metadata) = fs::metadata(&path).await else {
-                            warn!(
-                                %path,
-                                "skipping entry because metadata could not be accessed"
-                            );
-                            return;
-                        };
-
-                        match content_1.load(path, metadata).await {
-                            Ok(_) => {
-                                #[cfg(debug_assertions)]
-                                {
-                                    info!("sending reload");
-                                    reloader.reload();
-                                }
-                            }
-                            Err(error) => {
-                                warn!(%error, "failed to load content");
-                            }
-                        }
-                    }
-                });
+    pub async fn load_state(
+        self,
+        reloader: Reloader,
+        log_filter: LogFilter,
+    ) -> Result<State, LoadStateError> {
+        use LoadStateError::*;
+
+        #[cfg(not(debug_assertions))]
+        let _ = reloader;
+
+        let themes = Themes::load(&self.themes_path, &self.theme)?;
+
+        let webhook = Webhook::spawn(&self.events).map_err(CreateHttpClient)?;
+        let (content, failures) = self.load_content_counting_failures().await;
+        if self.strict && failures > 0 {
+            return Err(InvalidContent(failures));
+        }
+        let content = content.with_webhook(webhook);
+        let assets = Assets::load(&self.static_path).await;
+
+        let settings = self.settings();
+
+        if self.stale_drafts.warn {
+            let today = Utc::now().date_naive();
+            for draft in content.stale_drafts(today, settings.max_draft_age()) {
+                warn!(
+                    path = %draft.path,
+                    entry = ?draft.entry,
+                    date = %draft.date,
+                    age_days = %draft.age_days,
+                    "draft has been left unfinished",
+                );
             }
+        }
 
-            warn!("event sender hung up");
+        let site = Site::new(
+            self.site,
+            self.feeds,
+            self.nav,
+            self.footer,
+            &self.tags,
+            assets.clone(),
+        );
+        let renders = RenderCache::new(Duration::from_millis(self.limits.render_budget_ms));
+        let embeds = Embeds::new(&self.embeds).map_err(CreateHttpClient)?;
+
+        let warmup = self.warmup.enabled.then(|| Warmup {
+            renders: renders.clone(),
+            content: content.clone(),
+            themes: themes.clone(),
+            site: site.clone(),
+            settings: settings.clone(),
         });
 
-        let mut watcher = new_debouncer(
-            Duration::from_millis(25),
-            move |res: DebounceEventResult| {
-                let _guard = span!(Level::ERROR, "file_watcher").entered();
-                match res {
-                    Ok(events) => {
-                        info!(events = %events.len(), "received batch of debounced events");
-                        for event in events {
-                            if let Err(error) = event_tx.send(event) {
-                                error!(%error, "failed to send event to content loader");
-                            }
-                        }
-                    }
-                    Err(error) => error!(%error, "watcher error received"),
-                }
-            },
-        )
-        .map_err(CreateWatcher)?;
-
-        watcher
-            .watcher()
-            .watch(self.content_path.as_std_path(), RecursiveMode::Recursive)
-            .map_err(WatchPath)?;
+        if let Some(warmup) = &warmup {
+            warmup.spawn(Vec::new());
+        }
 
-        let settings = Settings {
-            show_drafts: self.drafts,
+        let embargo_handle = tokio::spawn(embargo::refresh_when_due(
+            content.clone(),
+            renders.clone(),
+            warmup.clone(),
+        ));
+
+        let redirects = Redirects::new(self.redirects);
+
+        let watcher_health = WatcherHealth::new();
+        let reloads = ReloadTrigger::default();
+        let watcher = ContentWatcher {
+            content: content.clone(),
+            content_path: self.content_path.clone(),
+            themes: themes.clone(),
+            themes_path: self.themes_path.clone(),
+            static_path: self.static_path.clone(),
+            assets: assets.clone(),
+            renders: renders.clone(),
+            redirects: redirects.clone(),
+            warmup,
+            strict: self.strict,
+            config: self.watcher.clone(),
+            #[cfg(debug_assertions)]
+            reloader,
+            health: watcher_health.clone(),
+            reloads: reloads.clone(),
         };
+        let running = watcher.start()?;
+        let watcher_handle = tokio::spawn(watcher.supervise(running));
 
         Ok(State {
             content,
-            theme,
+            themes,
             settings,
-            _watcher: Arc::new(watcher),
-            _loader_handle: Arc::new(loader_handle),
+            site,
+            redirects,
+            request_log: RequestLog::new(REQUEST_LOG_CAPACITY),
+            renders,
+            embeds,
+            log_filter,
+            watcher_health,
+            reloads,
+            content_repo: ContentRepo::new(self.content_path.clone()),
+            syntaxes_path: self.syntaxes_path,
+            _watcher_handle: Arc::new(watcher_handle),
+            _embargo_handle: Arc::new(embargo_handle),
         })
     }
 }
 
+impl State {
+    /// Load all of the content and themes again, along with the redirects and theme settings from
+    /// the config file at `config_path`, if there is one. Nothing else in the config file can
+    /// change without a restart, and changes to the markdown settings, base URL or syntaxes path
+    /// are warned about, since they'd otherwise be ignored silently.
+    pub async fn reload(&self, config_path: Option<&Utf8Path>) {
+        let file = config_path.map(ConfigFile::read).transpose();
+        let themes = match file {
+            Ok(Some(file)) => {
+                info!(redirects = %file.redirects.len(), "read config file again");
+                self.warn_about_restart_only_changes(&file);
+                self.redirects
+                    .reconfigure(file.redirects.into_iter().collect());
+                self.themes.reconfigure(file.theme).await
+            }
+            Ok(None) => self.themes.reload().await,
+            Err(error) => {
+                warn!(%error, "failed to read config file again, keeping the previous config");
+                self.themes.reload().await
+            }
+        };
+        match themes {
+            Ok(()) => info!("reloaded themes"),
+            Err(error) => warn!(%error, "failed to reload themes, keeping previous ones"),
+        }
+
+        // Reloading the content brings every page up to date, themes included.
+        self.reloads.reload();
+    }
+
+    /// Warn about any changes in the config file to settings that are only read at startup, which
+    /// are ignored until the server is restarted.
+    fn warn_about_restart_only_changes(&self, file: &ConfigFile) {
+        if file.markdown != *markdown_config() {
+            warn!("markdown settings have changed, but they only apply after a restart");
+        }
+
+        if file.site.base_url != *base_url() {
+            warn!(
+                base_url = %file.site.base_url,
+                "base URL has changed, but it only applies after a restart",
+            );
+        }
+
+        let syntaxes_path = file
+            .syntaxes_path
+            .as_ref()
+            .map(|path| path.canonicalize_utf8().unwrap_or_else(|_| path.clone()));
+        if syntaxes_path.is_some() && syntaxes_path != self.syntaxes_path {
+            warn!("syntaxes path has changed, but it only applies after a restart");
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum LoadStateError {
-    #[error("failed to load theme set: {0}")]
-    LoadThemeSet(#[from] SyntectLoadingError),
-
     #[error(transparent)]
     LoadThemeError(#[from] LoadThemeError),
 
-    #[error("failed to create notify watcher: {0}")]
-    CreateWatcher(#[source] notify::Error),
+    #[error(transparent)]
+    Watch(#[from] WatchError),
+
+    #[error("failed to create HTTP client: {0}")]
+    CreateHttpClient(#[source] reqwest::Error),
 
-    #[error("failed to watch new path: {0}")]
-    WatchPath(#[source] notify::Error),
+    #[error("{0} file(s) in the content path failed to load, which isn't allowed in strict mode")]
+    InvalidContent(usize),
 }
 
 #[derive(Clone, Debug)]
 pub struct State {
     pub content: Content,
-    pub theme: Theme,
+    pub themes: Themes,
     pub settings: Settings,
-    _watcher: Arc<Debouncer<RecommendedWatcher>>,
-    _loader_handle: Arc<JoinHandle<()>>,
+    pub site: Site,
+    pub redirects: Redirects,
+    pub request_log: RequestLog,
+    pub renders: RenderCache,
+    pub embeds: Embeds,
+    pub log_filter: LogFilter,
+    pub watcher_health: WatcherHealth,
+    pub reloads: ReloadTrigger,
+    pub content_repo: ContentRepo,
+    /// The syntaxes path the server started with, which can't change without a restart.
+    syntaxes_path: Option<Utf8PathBuf>,
+    _watcher_handle: Arc<JoinHandle<()>>,
+    _embargo_handle: Arc<JoinHandle<()>>,
 }
 
+/// All of the posts and pages, kept as an immutable snapshot that's swapped out whenever anything
+/// is loaded, or once for a whole batch of changes. Rendering holds on to the snapshot it started
+/// with, so it never waits on a reload, and a reload never waits on rendering.
 #[derive(Clone, Debug)]
 pub struct Content {
     root: Arc<Utf8PathBuf>,
-    nodes: Arc<RwLock<HashMap<Utf8PathBuf, Node>>>,
+    snapshot: Arc<ArcSwap<Snapshot>>,
+    /// The changes made so far in a batch, while one is open, which are swapped in as a single new
+    /// snapshot when it's finished.
+    batch: Arc<Mutex<Option<Staged>>>,
+    bodies: Bodies,
+    limits: Arc<LimitsConfig>,
+    /// The files that failed to load the last time they changed, keyed the same way as the nodes.
+    stale: Arc<Mutex<BTreeMap<Utf8PathBuf, StaleNode>>>,
+    manifests: Manifests,
+    vault: Vault,
+    /// The posts that are embargoed, keyed the same way as the nodes, which they aren't part of
+    /// until they're published.
+    embargoed: Arc<Mutex<BTreeMap<Utf8PathBuf, Sealed>>>,
+    embargoes_changed: Arc<Notify>,
+    webhook: Option<Webhook>,
+    /// When the content was last loaded or changed.
+    reloaded: Arc<Mutex<Option<DateTime<Utc>>>>,
+    /// How many files failed to load when the content was first loaded, once it has been.
+    initial_failures: Arc<OnceLock<usize>>,
+    /// Whether posts' dates fall back to when their files were committed to git.
+    git_dates: bool,
+    /// The days each file in the content was changed by a commit, newest first, as of the last
+    /// time git was asked.
+    commit_dates: Arc<ArcSwap<HashMap<Utf8PathBuf, Vec<NaiveDate>>>>,
+    /// Where the repository the content is kept in can be browsed, to link to posts' files and
+    /// the commits that changed them.
+    forge: Option<Forge>,
+}
+
+/// The posts and pages at some point in time, along with the indexes the list pages are rendered
+/// from.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    nodes: HashMap<Utf8PathBuf, Node>,
+    /// A fingerprint of the file each node was loaded from.
+    hashes: HashMap<Utf8PathBuf, String>,
+    indexes: Indexes,
+}
+
+impl Snapshot {
+    fn new(nodes: HashMap<Utf8PathBuf, Node>, hashes: HashMap<Utf8PathBuf, String>) -> Self {
+        let indexes = Indexes::build(&nodes);
+        Self {
+            nodes,
+            hashes,
+            indexes,
+        }
+    }
+
+    pub fn index(&self, show_drafts: bool) -> &Index {
+        self.indexes.get(show_drafts)
+    }
+}
+
+/// The nodes a batch of changes is being made to, copied once when it was opened and indexed once
+/// when it's finished, rather than for every file loaded.
+#[derive(Debug)]
+struct Staged {
+    /// How many batches are open, since loading everything can happen as part of a larger batch.
+    depth: usize,
+    nodes: HashMap<Utf8PathBuf, Node>,
+    hashes: HashMap<Utf8PathBuf, String>,
+}
+
+/// An open batch of changes to the content, which are swapped in once it (and any batch it's part
+/// of) is dropped.
+#[must_use]
+pub struct Batch<'a> {
+    content: &'a Content,
+}
+
+impl Drop for Batch<'_> {
+    fn drop(&mut self) {
+        let mut batch = self
+            .content
+            .batch
+            .lock()
+            .expect("content batch mutex is not poisoned");
+        let Some(staged) = batch.as_mut() else {
+            return;
+        };
+        staged.depth -= 1;
+        if staged.depth == 0 {
+            let Staged { nodes, hashes, .. } = batch.take().expect("batch is still open");
+            self.content
+                .snapshot
+                .store(Arc::new(Snapshot::new(nodes, hashes)));
+        }
+    }
 }
 
 impl Content {
-    /// Create a new empty set of content, but with the root path set to `root`.
-    pub fn empty_in(root: Utf8PathBuf) -> Self {
+    /// Create a new empty set of content, but with the root path set to `root`, and keeping to
+    /// `limits` on how many bodies are kept rendered and how large anything can be.
+    pub fn empty_in(root: Utf8PathBuf, limits: &LimitsConfig) -> Self {
         Self {
             root: Arc::new(root),
-            nodes: Arc::new(RwLock::new(HashMap::default())),
+            snapshot: Arc::new(ArcSwap::from_pointee(Snapshot::default())),
+            batch: Arc::new(Mutex::new(None)),
+            bodies: Bodies::new(limits.rendered_bodies, limits.max_rendered_bytes),
+            limits: Arc::new(limits.clone()),
+            stale: Arc::new(Mutex::new(BTreeMap::new())),
+            manifests: Manifests::new(MANIFEST_HISTORY),
+            vault: Vault::default(),
+            embargoed: Arc::new(Mutex::new(BTreeMap::new())),
+            embargoes_changed: Arc::new(Notify::new()),
+            webhook: None,
+            reloaded: Arc::new(Mutex::new(None)),
+            initial_failures: Arc::new(OnceLock::new()),
+            git_dates: false,
+            commit_dates: Arc::new(ArcSwap::from_pointee(HashMap::new())),
+            forge: None,
         }
     }
 
-    #[instrument(name = "load_content", level = "ERROR", skip_all)]
+    /// Take a post that doesn't say when it was updated to have been updated when its file was
+    /// last committed to git, and a thread entry that doesn't say when it was posted to have been
+    /// posted when it was first committed.
+    pub fn with_git_dates(self, git_dates: bool) -> Self {
+        Self { git_dates, ..self }
+    }
+
+    /// Link each post to where its file can be edited on `forge`, and to its history.
+    pub fn with_forge(self, forge: Option<Forge>) -> Self {
+        Self { forge, ..self }
+    }
+
+    /// Allow embargoed posts to be previewed with a token derived from `secret`, if one is given.
+    pub fn with_preview_secret(self, secret: Option<String>) -> Self {
+        Self {
+            vault: self.vault.with_preview_secret(secret),
+            ..self
+        }
+    }
+
+    /// Tell `webhook` about every change to the content from now on.
+    pub fn with_webhook(self, webhook: Option<Webhook>) -> Self {
+        Self { webhook, ..self }
+    }
+
     pub async fn load<P>(&self, path: P, metadata: Metadata) -> Result<(), LoadContentError>
     where
         P: AsRef<Utf8Path>,
     {
-        let path = path.as_ref();
+        self.load_replacing(path, metadata, None).await
+    }
 
-        let mut nodes_guard = self.nodes.write().await;
+    /// Load every file in the content path, returning how many of them failed to load. Each failure
+    /// is kept track of the same way as a file that fails to load after it changes.
+    pub async fn load_all(&self) -> usize {
+        self.refresh_commit_dates().await;
+        let _batch = self.batch();
+        let mut failures = 0;
+        let walker = Walk::new(&*self.root);
+        for result in walker {
+            match result {
+                Ok(entry) => {
+                    let Ok(path) = Utf8PathBuf::from_path_buf(entry.path().to_path_buf()) else {
+                        warn!(
+                            path = ?entry.path(),
+                            "skipping entry with path that contains invalid UTF-8"
+                        );
+                        continue;
+                    };
+
+                    let Ok(metadata) = entry.metadata() else {
+                        warn!(%path, "skipping entry without valid metadata");
+                        continue;
+                    };
+
+                    if let Err(error) = self.load(&path, metadata).await {
+                        warn!(%error, "failed to load content");
+                        if let Ok(relative) = path.strip_prefix(&*self.root) {
+                            self.mark_stale(node_key(relative), &error);
+                        }
+                        failures += 1;
+                    }
+                }
+                Err(error) => error!(%error, "directory walker encountered error"),
+            }
+        }
+
+        failures
+    }
+
+    /// Load a file like [`Content::load`], taking the node at `replaced` out of the content in the
+    /// same step, so that a file that's been renamed is never served under both names (or neither).
+    /// If the file fails to load, nothing is taken out.
+    #[instrument(name = "load_content", level = "ERROR", skip_all)]
+    pub async fn load_replacing<P>(
+        &self,
+        path: P,
+        metadata: Metadata,
+        replaced: Option<&Utf8Path>,
+    ) -> Result<(), LoadContentError>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let path = path.as_ref();
 
         // All the nodes will be keyed by their paths relative to the content root, without an
         // extension.
@@ -357,9 +1144,15 @@ impl Content {
             .to_owned();
 
         if metadata.is_file() {
-            let file_name = relative_path
-                .file_stem()
-                .ok_or(LoadContentError::NoFileName)?;
+            if metadata.len() > self.limits.max_file_bytes {
+                return Err(LoadContentError::TooLarge {
+                    size: metadata.len(),
+                    limit: self.limits.max_file_bytes,
+                });
+            }
+
+            let key = node_key(&relative_path);
+            let file_name = key.file_name().ok_or(LoadContentError::NoFileName)?;
             let file_ext = relative_path
                 .extension()
                 .ok_or(LoadContentError::NoExtension)?;
@@ -367,9 +1160,20 @@ impl Content {
             if file_ext == "md" {
                 if let Ok((date, _)) = NaiveDate::parse_and_remainder(file_name, "%Y-%m-%d") {
                     debug!(%relative_path, "loading post from file");
-                    match self.load_post(&relative_path, date).await {
-                        Ok(post) => {
-                            nodes_guard.insert(relative_path.with_extension(""), Node::Post(post));
+                    match self
+                        .load_post(&relative_path, date, metadata.modified().ok())
+                        .await
+                    {
+                        Ok(LoadedPost::Public(post, hash)) => {
+                            self.unseal(&key);
+                            self.insert(key, Node::Post(post), hash, replaced);
+                            Ok(())
+                        }
+                        Ok(LoadedPost::Embargoed(sealed)) => {
+                            self.seal(key, sealed);
+                            if let Some(replaced) = replaced {
+                                self.remove_node(replaced);
+                            }
                             Ok(())
                         }
                         Err(error) => Err(error.into()),
@@ -377,8 +1181,8 @@ impl Content {
                 } else {
                     debug!(%relative_path, "loading page from file");
                     match self.load_page(&relative_path).await {
-                        Ok(page) => {
-                            nodes_guard.insert(relative_path.with_extension(""), Node::Page(page));
+                        Ok((page, hash)) => {
+                            self.insert(key, Node::Page(Arc::new(page)), hash, replaced);
                             Ok(())
                         }
                         Err(error) => Err(error.into()),
@@ -397,28 +1201,521 @@ impl Content {
         }
     }
 
+    /// Open a batch of changes to the content, so that everything loaded or taken out until it's
+    /// dropped is swapped in as a single new snapshot, with the indexes rebuilt once.
+    ///
+    /// Until then, readers keep seeing the snapshot from before the batch.
+    pub fn batch(&self) -> Batch<'_> {
+        let mut batch = self
+            .batch
+            .lock()
+            .expect("content batch mutex is not poisoned");
+        match batch.as_mut() {
+            Some(staged) => staged.depth += 1,
+            None => {
+                let snapshot = self.snapshot.load();
+                *batch = Some(Staged {
+                    depth: 1,
+                    nodes: snapshot.nodes.clone(),
+                    hashes: snapshot.hashes.clone(),
+                });
+            }
+        }
+        Batch { content: self }
+    }
+
+    /// Change the nodes, as part of the open batch if there is one, or by swapping in a new
+    /// snapshot with the indexes rebuilt to match if not.
+    fn change<F>(&self, change: F)
+    where
+        F: Fn(&mut HashMap<Utf8PathBuf, Node>, &mut HashMap<Utf8PathBuf, String>),
+    {
+        let mut batch = self
+            .batch
+            .lock()
+            .expect("content batch mutex is not poisoned");
+        match batch.as_mut() {
+            Some(staged) => change(&mut staged.nodes, &mut staged.hashes),
+            None => {
+                self.snapshot.rcu(|snapshot| {
+                    let mut nodes = snapshot.nodes.clone();
+                    let mut hashes = snapshot.hashes.clone();
+                    change(&mut nodes, &mut hashes);
+                    Snapshot::new(nodes, hashes)
+                });
+            }
+        }
+    }
+
+    /// The keys of every node, including any changes made in the open batch.
+    fn node_keys(&self) -> Vec<Utf8PathBuf> {
+        match self
+            .batch
+            .lock()
+            .expect("content batch mutex is not poisoned")
+            .as_ref()
+        {
+            Some(staged) => staged.nodes.keys().cloned().collect(),
+            None => self.snapshot.load().nodes.keys().cloned().collect(),
+        }
+    }
+
+    /// Whether there's a node at `key`, including any changes made in the open batch.
+    fn has_node(&self, key: &Utf8Path) -> bool {
+        match self
+            .batch
+            .lock()
+            .expect("content batch mutex is not poisoned")
+            .as_ref()
+        {
+            Some(staged) => staged.nodes.contains_key(key),
+            None => self.snapshot.load().nodes.contains_key(key),
+        }
+    }
+
+    /// Add a node, replacing any that was already at its path (and taking out the one at
+    /// `replaced`, if any).
+    fn insert(&self, path: Utf8PathBuf, node: Node, hash: String, replaced: Option<&Utf8Path>) {
+        self.change(|nodes, hashes| {
+            if let Some(replaced) = replaced.filter(|replaced| *replaced != path) {
+                nodes.remove(replaced);
+                hashes.remove(replaced);
+            }
+            nodes.insert(path.clone(), node.clone());
+            hashes.insert(path.clone(), hash.clone());
+        });
+
+        let mut stale = self
+            .stale
+            .lock()
+            .expect("stale nodes mutex is not poisoned");
+        if stale.remove(&path).is_some() {
+            info!(%path, "previously stale content has loaded");
+            metrics::gauge!(*metric::STALE_CONTENT).set(stale.len() as f64);
+        }
+    }
+
+    /// Take everything loaded from a file or directory that's been deleted out of the content,
+    /// returning the keys of the nodes that were there.
+    pub fn remove(&self, path: &Utf8Path) -> Vec<Utf8PathBuf> {
+        let Ok(relative) = path.strip_prefix(&*self.root) else {
+            return Vec::new();
+        };
+
+        // A deleted directory can't be told apart from a file without an extension, so both take
+        // everything under them with them. Other files (like a bundled post's images) never have
+        // nodes of their own.
+        let key = node_key(relative);
+        let keys = match relative.extension() {
+            Some("md") => vec![key],
+            Some(_) => return Vec::new(),
+            None => self
+                .node_keys()
+                .into_iter()
+                .chain(
+                    self.stale
+                        .lock()
+                        .expect("stale nodes mutex is not poisoned")
+                        .keys()
+                        .cloned(),
+                )
+                .filter(|node| node.starts_with(&key))
+                .collect(),
+        };
+
+        keys.into_iter()
+            .filter(|key| self.remove_node(key))
+            .collect()
+    }
+
+    /// Whether there's a node at `key`, even if it's embargoed or only a stale one.
+    pub fn contains(&self, key: &Utf8Path) -> bool {
+        self.has_node(key)
+            || self
+                .stale
+                .lock()
+                .expect("stale nodes mutex is not poisoned")
+                .contains_key(key)
+            || self
+                .embargoed
+                .lock()
+                .expect("embargoed posts mutex is not poisoned")
+                .contains_key(key)
+    }
+
+    /// Take out every node whose file (or bundle) isn't in the content path anymore, returning
+    /// their keys.
+    pub fn remove_missing(&self) -> Vec<Utf8PathBuf> {
+        let mut keys = self.node_keys();
+        keys.extend(
+            self.stale
+                .lock()
+                .expect("stale nodes mutex is not poisoned")
+                .keys()
+                .cloned(),
+        );
+        keys.extend(
+            self.embargoed
+                .lock()
+                .expect("embargoed posts mutex is not poisoned")
+                .keys()
+                .cloned(),
+        );
+
+        keys.into_iter()
+            .filter(|key| {
+                let path = self.root.join(key);
+                !path.with_extension("md").is_file() && !path.join("index.md").is_file()
+            })
+            .filter(|key| self.remove_node(key))
+            .collect()
+    }
+
+    /// Take the node at `key` out of the content, along with any embargo or stale version of it,
+    /// returning whether there was anything there.
+    fn remove_node(&self, key: &Utf8Path) -> bool {
+        let mut removed = false;
+        if self.has_node(key) {
+            self.change(|nodes, hashes| {
+                nodes.remove(key);
+                hashes.remove(key);
+            });
+            removed = true;
+        }
+
+        let mut stale = self
+            .stale
+            .lock()
+            .expect("stale nodes mutex is not poisoned");
+        if stale.remove(key).is_some() {
+            metrics::gauge!(*metric::STALE_CONTENT).set(stale.len() as f64);
+            removed = true;
+        }
+        drop(stale);
+
+        if self
+            .embargoed
+            .lock()
+            .expect("embargoed posts mutex is not poisoned")
+            .remove(key)
+            .is_some()
+        {
+            self.embargoes_changed.notify_one();
+            removed = true;
+        }
+
+        removed
+    }
+
+    /// Take the post at `path` out of the content, if it's there, until its embargo ends.
+    fn seal(&self, path: Utf8PathBuf, sealed: Sealed) {
+        if self.has_node(&path) {
+            self.change(|nodes, hashes| {
+                nodes.remove(&path);
+                hashes.remove(&path);
+            });
+        }
+
+        self.embargoed
+            .lock()
+            .expect("embargoed posts mutex is not poisoned")
+            .insert(path, sealed);
+        self.embargoes_changed.notify_one();
+    }
+
+    /// Forget about the embargo on the post at `path`, if there was one, since it's been loaded
+    /// again without one.
+    fn unseal(&self, path: &Utf8Path) {
+        let removed = self
+            .embargoed
+            .lock()
+            .expect("embargoed posts mutex is not poisoned")
+            .remove(path);
+        if removed.is_some() {
+            info!(%path, "embargo was lifted");
+            self.embargoes_changed.notify_one();
+        }
+    }
+
+    /// When the next embargo ends, if any posts are embargoed.
+    pub fn next_embargo(&self) -> Option<DateTime<Utc>> {
+        self.embargoed
+            .lock()
+            .expect("embargoed posts mutex is not poisoned")
+            .values()
+            .map(|sealed| sealed.until)
+            .min()
+    }
+
+    /// Wait until a post is sealed or unsealed, other than by its embargo ending.
+    pub async fn embargoes_changed(&self) {
+        self.embargoes_changed.notified().await;
+    }
+
+    /// Publish every embargoed post whose embargo has ended by `now`, returning how many there
+    /// were.
+    pub async fn release_embargoes(&self, now: DateTime<Utc>) -> usize {
+        let due = {
+            let mut embargoed = self
+                .embargoed
+                .lock()
+                .expect("embargoed posts mutex is not poisoned");
+            let due = embargoed
+                .iter()
+                .filter(|(_, sealed)| sealed.until <= now)
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>();
+            due.into_iter()
+                .filter_map(|path| embargoed.remove_entry(&path))
+                .collect::<Vec<_>>()
+        };
+
+        let mut released = 0;
+        for (path, sealed) in due {
+            let raw_content = self.vault.open(&sealed);
+            let (raw_content, parsed) = Self::parse_post_blocking(
+                &sealed.relative_path,
+                sealed.date,
+                sealed.modified,
+                self.commit_dates(&sealed.relative_path),
+                raw_content,
+            )
+            .await;
+            match parsed {
+                Ok((post, _)) => {
+                    info!(%path, until = %sealed.until, "embargo ended, publishing post");
+                    self.insert(
+                        path,
+                        Node::Post(Arc::new(post)),
+                        fingerprint(raw_content.as_bytes()),
+                        None,
+                    );
+                    released += 1;
+                }
+                Err(error) => error!(%path, %error, "failed to publish post after its embargo"),
+            }
+        }
+        released
+    }
+
+    /// The embargoed post at `path`, opened for a preview, if `token` is its preview token.
+    pub async fn preview_embargoed(&self, path: &str, token: &str) -> Option<PostRef> {
+        if !self.vault.is_preview_token(path, token) {
+            return None;
+        }
+
+        let sealed = self
+            .embargoed
+            .lock()
+            .expect("embargoed posts mutex is not poisoned")
+            .get(Utf8Path::new(path))
+            .cloned()?;
+        let raw_content = self.vault.open(&sealed);
+        let (_, parsed) = Self::parse_post_blocking(
+            &sealed.relative_path,
+            sealed.date,
+            sealed.modified,
+            self.commit_dates(&sealed.relative_path),
+            raw_content,
+        )
+        .await;
+        let (post, _) = parsed.ok()?;
+
+        Some(PostRef {
+            edit: self.edit_link(&post),
+            history: None,
+            post: Arc::new(post),
+            path: Utf8PathBuf::from(path),
+            show_drafts: true,
+            bodies: self.bodies.clone(),
+            stale: None,
+        })
+    }
+
+    /// Every embargoed post, soonest to be published first.
+    pub fn embargoed(&self) -> Vec<EmbargoedPost> {
+        let mut embargoed = self
+            .embargoed
+            .lock()
+            .expect("embargoed posts mutex is not poisoned")
+            .iter()
+            .map(|(path, sealed)| EmbargoedPost {
+                path: path.clone(),
+                until: sealed.until,
+                preview: self
+                    .vault
+                    .preview_token(path.as_str())
+                    .map(|token| format!("/posts/{path}?preview={token}")),
+            })
+            .collect::<Vec<_>>();
+        embargoed.sort_by(|a, b| (a.until, &a.path).cmp(&(b.until, &b.path)));
+        embargoed
+    }
+
+    /// Record that the file at `path` (relative to the content root, without an extension) failed
+    /// to load, so whatever was last loaded from it (if anything) is still being served.
+    ///
+    /// If it was already stale, it's counted as stale since the first failure.
+    pub fn mark_stale(&self, path: Utf8PathBuf, error: &LoadContentError) {
+        let previous = self.has_node(&path);
+        let mut stale = self
+            .stale
+            .lock()
+            .expect("stale nodes mutex is not poisoned");
+        let since = stale.get(&path).map_or_else(Utc::now, |node| node.since);
+        stale.insert(
+            path.clone(),
+            StaleNode {
+                path,
+                error: error.to_string(),
+                since,
+                previous,
+            },
+        );
+        metrics::gauge!(*metric::STALE_CONTENT).set(stale.len() as f64);
+    }
+
+    /// Every file that failed to load the last time it was loaded, ordered by path, whether or not
+    /// there's an older version of it that's still being served.
+    pub fn errors(&self) -> Vec<StaleNode> {
+        self.stale
+            .lock()
+            .expect("stale nodes mutex is not poisoned")
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    fn stale_node(&self, path: &Utf8Path) -> Option<StaleNode> {
+        self.stale
+            .lock()
+            .expect("stale nodes mutex is not poisoned")
+            .get(path)
+            .cloned()
+    }
+
+    /// Ask git when every file in the content was committed, if posts' dates fall back to that,
+    /// so that it only has to be asked once for everything that's about to be loaded.
+    pub async fn refresh_commit_dates(&self) {
+        if !self.git_dates {
+            return;
+        }
+
+        match git::commit_dates(&self.root).await {
+            Ok(dates) => self.commit_dates.store(Arc::new(dates)),
+            Err(error) => warn!(%error, "failed to find when content was committed"),
+        }
+    }
+
+    /// The days the file at `relative_path` was changed by a commit, newest first, which is nothing
+    /// if posts' dates don't fall back to them.
+    fn commit_dates(&self, relative_path: &Utf8Path) -> Vec<NaiveDate> {
+        self.commit_dates
+            .load()
+            .get(relative_path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     async fn load_post(
         &self,
         relative_path: &Utf8Path,
         date: NaiveDate,
-    ) -> Result<Post, LoadPostError> {
-        use LoadPostError::*;
-
+        modified: Option<SystemTime>,
+    ) -> Result<LoadedPost, LoadPostError> {
         let raw_content = fs::read_to_string(self.root.join(relative_path))
             .await
-            .map_err(ReadContent)?;
+            .map_err(LoadPostError::ReadContent)?;
+
+        let commits = self.commit_dates(relative_path);
+        let (raw_content, parsed) =
+            Self::parse_post_blocking(relative_path, date, modified, commits, raw_content).await;
+        let (post, embargo_until) = parsed?;
+
+        if let Post::Thread { entries, .. } = &post {
+            if entries.len() > self.limits.max_thread_entries {
+                return Err(LoadPostError::TooManyEntries {
+                    entries: entries.len(),
+                    limit: self.limits.max_thread_entries,
+                });
+            }
+        }
+
+        match embargo_until {
+            Some(until) if until > Utc::now() => {
+                info!(%relative_path, %until, "sealed embargoed post");
+                let sealed = self.vault.seal(
+                    relative_path.to_owned(),
+                    date,
+                    modified,
+                    until,
+                    &raw_content,
+                );
+                Ok(LoadedPost::Embargoed(sealed))
+            }
+            _ => Ok(LoadedPost::Public(
+                Arc::new(post),
+                fingerprint(raw_content.as_bytes()),
+            )),
+        }
+    }
+
+    /// Parse a post like [`Content::parse_post`], on the blocking thread pool, since its summaries
+    /// are rendered along with it (resizing their images and drawing their diagrams, among other
+    /// things). The contents of the file are handed back along with it.
+    async fn parse_post_blocking(
+        relative_path: &Utf8Path,
+        date: NaiveDate,
+        modified: Option<SystemTime>,
+        commits: Vec<NaiveDate>,
+        raw_content: String,
+    ) -> (String, Result<(Post, Option<DateTime<Utc>>), LoadPostError>) {
+        let relative_path = relative_path.to_owned();
+        render_blocking(move || {
+            let parsed = Self::parse_post(&relative_path, date, modified, &commits, &raw_content);
+            (raw_content, parsed)
+        })
+        .await
+    }
+
+    /// Parse the contents of a post's file, returning the post along with the time it's embargoed
+    /// until, if it is.
+    ///
+    /// `commits` are the days the file was changed by a commit, newest first, which any dates the
+    /// post doesn't give fall back to.
+    fn parse_post(
+        relative_path: &Utf8Path,
+        date: NaiveDate,
+        modified: Option<SystemTime>,
+        commits: &[NaiveDate],
+        raw_content: &str,
+    ) -> Result<(Post, Option<DateTime<Utc>>), LoadPostError> {
+        use LoadPostError::*;
+
+        let key = node_key(relative_path);
+        // Relative links in a bundled post lead to the files next to it.
+        let bundle = bundle_dir(relative_path).map(|dir| format!("/posts/{dir}/"));
+        // Summaries are shown in lists of posts, where none of them are reliably at the top.
+        let finish = |html: String| finish_html(html, bundle.as_deref(), false);
+        // Shortcodes are expanded before anything else, so that their line numbers are the file's.
+        let raw_content = shortcodes::expand(raw_content)?;
 
         let (first_raw_fm, mut rest) = raw_content
             .strip_prefix("---")
-            .ok_or(MissingFrontmatter)?
-            .split_once("---")
-            .ok_or(MalformedFrontmatter)?;
+            .ok_or(MissingFrontmatter)
+            .and_then(|raw| split_at_delimiter(raw).ok_or(MalformedFrontmatter))?;
 
         let first_frontmatter = toml::from_str::<PostFrontmatter>(first_raw_fm.trim())?;
+        let embargo_until = first_frontmatter.embargo_until;
+        let code_language = first_frontmatter.code_language;
+        let context = markdown::Context {
+            code_language: code_language.as_deref(),
+        };
         let mut metadata: Either<
             SinglePostMetadata,
             (ThreadMetadata, Vec<ThreadEntryMetadata>, Vec<&str>),
         > = Either::Left(SinglePostMetadata {
+            source: relative_path.to_owned(),
             md_title: first_frontmatter.md_title,
             draft: first_frontmatter.draft,
             tags: first_frontmatter.tags,
@@ -426,19 +1723,45 @@ impl Content {
             updated: first_frontmatter.updated,
             lobsters: first_frontmatter.lobsters,
             hacker_news: first_frontmatter.hacker_news,
+            robots: first_frontmatter.robots,
+            see_also: first_frontmatter.see_also,
         });
 
-        while let Some((last_content, (this_raw_frontmatter, new_rest))) = rest
-            .split_once("---")
+        while let Some((last_content, (this_raw_frontmatter, new_rest))) = split_at_delimiter(rest)
             .and_then(|(last_content, fm_and_rest)| {
-                fm_and_rest
-                    .split_once("---")
-                    .map(|split_fm_rest| (last_content, split_fm_rest))
+                split_at_delimiter(fm_and_rest).map(|split_fm_rest| (last_content, split_fm_rest))
             })
         {
             rest = new_rest;
 
-            let this_metadata = toml::from_str::<ThreadEntryMetadata>(this_raw_frontmatter.trim())?;
+            let this_frontmatter =
+                toml::from_str::<ThreadEntryFrontmatter>(this_raw_frontmatter.trim())?;
+            let previous_date = match &metadata {
+                Either::Left(single) => single.date,
+                Either::Right((_, entries, _)) => {
+                    entries.last().expect("thread has at least one entry").date
+                }
+            };
+            // Entries are added to the end of a thread, so the first commit since the one before
+            // it was made is the one that added it.
+            let this_date = this_frontmatter
+                .date
+                .or_else(|| {
+                    commits
+                        .iter()
+                        .rev()
+                        .find(|day| **day >= previous_date)
+                        .copied()
+                })
+                .ok_or(MissingEntryDate)?;
+            let this_metadata = ThreadEntryMetadata {
+                md_title: this_frontmatter.md_title,
+                draft: this_frontmatter.draft,
+                date: this_date,
+                updated: this_frontmatter.updated,
+                lobsters: this_frontmatter.lobsters,
+                hacker_news: this_frontmatter.hacker_news,
+            };
 
             match metadata {
                 Either::Left(single) => {
@@ -457,47 +1780,69 @@ impl Content {
         }
 
         match metadata {
-            Either::Left(metadata) => {
+            Either::Left(mut metadata) => {
                 let rest = rest.trim();
+                metadata.updated = metadata
+                    .updated
+                    .or_else(|| commit_after(commits, metadata.date));
 
-                let html_summary = Self::build_html_summary(rest);
-                let html_content = markdown_to_html_toc_tagged(rest);
-                let html_toc = Self::build_toc_list(&html_content);
+                let (html_summary, cut) = Self::build_html_summary(rest, &context);
+                let html_summary = finish(html_summary);
+                let body = Body::new(key, modified, 0, Self::mark_summary_end(rest, cut, 0))
+                    .in_bundle(bundle.clone())
+                    .with_code_language(code_language.clone());
 
                 let post = Post::Single {
                     metadata,
                     html_summary,
-                    html_toc,
-                    html_content,
+                    body,
                 };
 
                 info!(%relative_path, "loaded single post");
-                Ok(post)
+                Ok((post, embargo_until))
             }
-            Either::Right((thread_meta, entry_metas, mut entry_raw_content)) => {
+            Either::Right((thread_meta, mut entry_metas, mut entry_raw_content)) => {
                 entry_raw_content.push(rest.trim());
 
-                let html_summary = Self::build_html_summary(
+                // Only the newest entry is likely to have changed since it was posted, so that's
+                // the one a later commit is taken to have updated.
+                let last = entry_metas
+                    .last_mut()
+                    .expect("thread has at least one entry");
+                if last.updated.is_none() {
+                    last.updated = commit_after(commits, last.date);
+                }
+
+                let (html_summary, _) = Self::build_html_summary(
                     entry_raw_content
                         .first()
                         .expect("threaded post has at least one entry"),
+                    &context,
                 );
+                let html_summary = finish(html_summary);
 
                 let entries = entry_metas
                     .into_iter()
                     .zip(entry_raw_content.into_iter())
-                    .map(|(metadata, raw_content)| {
+                    .enumerate()
+                    .map(|(index, (metadata, raw_content))| {
                         let raw_content = raw_content.trim();
 
-                        let html_summary = Self::build_html_summary(raw_content);
-                        let html_content = markdown_to_html_toc_tagged(raw_content);
-                        let html_toc = Self::build_toc_list(&html_content);
+                        let (html_summary, cut) = Self::build_html_summary(raw_content, &context);
+                        let html_summary = finish(html_summary);
+                        let body = Body::new(
+                            key.clone(),
+                            modified,
+                            index,
+                            Self::mark_summary_end(raw_content, cut, index),
+                        )
+                        .in_bundle(bundle.clone())
+                        .with_code_language(code_language.clone());
 
                         ThreadEntry {
                             metadata,
                             html_summary,
-                            html_toc,
-                            html_content,
+                            body,
                         }
                     })
                     .collect::<Vec<_>>();
@@ -510,33 +1855,53 @@ impl Content {
                 };
 
                 info!(entries = %entries_len, %relative_path, "loaded threaded post");
-                Ok(post)
+                Ok((post, embargo_until))
             }
         }
     }
 
-    fn build_html_summary(html_content: &str) -> String {
+    /// Render the summary of some content, returning it along with the offset in the content where
+    /// the summary ended.
+    fn build_html_summary(html_content: &str, context: &markdown::Context) -> (String, usize) {
         let mut raw_summary_paras = Vec::new();
+        let mut offset = 0;
+        let mut cut = html_content.len();
 
         for (i, par) in html_content.split("\n\n").enumerate() {
+            let start = offset;
+            offset = (offset + par.len() + 2).min(html_content.len());
+
             if par.starts_with('#') && i == 0 {
                 // This is a heading, but it's the first one, so just skip it
                 continue;
             } else if par.starts_with('#') || par == "<!-- cut -->" {
                 // We've hit the next heading or a manual summary cut, so the summary
                 // should stop
+                cut = start;
                 break;
             } else {
                 raw_summary_paras.push(par);
             }
 
             if raw_summary_paras.len() == 2 {
+                cut = offset;
                 break;
             }
         }
 
         let raw_summary = raw_summary_paras.join("\n\n");
-        markdown_to_html(&raw_summary)
+        (markdown_to_html_in(&raw_summary, context), cut)
+    }
+
+    /// Place an anchor in some content at the point where its summary ended, for "Read more" links
+    /// to jump to.
+    fn mark_summary_end(raw_content: &str, cut: usize, index: usize) -> String {
+        format!(
+            "{}\n\n<div id=\"{}\"></div>\n\n{}",
+            raw_content[..cut].trim_end(),
+            continue_reading_id(index),
+            &raw_content[cut..],
+        )
     }
 
     fn build_toc_list(html_content: &str) -> Option<String> {
@@ -642,93 +2007,312 @@ impl Content {
         any_entries.then_some(toc)
     }
 
-    async fn load_page(&self, relative_path: &Utf8Path) -> Result<Page, LoadPageError> {
+    async fn load_page(&self, relative_path: &Utf8Path) -> Result<(Page, String), LoadPageError> {
         use LoadPageError::*;
 
         let raw_content = fs::read_to_string(self.root.join(relative_path))
             .await
             .map_err(ReadContent)?;
+        let hash = fingerprint(raw_content.as_bytes());
+        let max_rendered_bytes = self.limits.max_rendered_bytes;
+        // Rendering a page resizes its images, among other things, which shouldn't hold up an
+        // async worker.
+        let page =
+            render_blocking(move || Self::parse_page(&raw_content, max_rendered_bytes)).await?;
+
+        info!(%relative_path, "loaded page");
+        Ok((page, hash))
+    }
+
+    /// Parse the contents of a page's file, rendering it to HTML no larger than
+    /// `max_rendered_bytes`.
+    fn parse_page(raw_content: &str, max_rendered_bytes: usize) -> Result<Page, LoadPageError> {
+        use LoadPageError::*;
+
+        let raw_content = shortcodes::expand(raw_content)?;
 
         let (frontmatter, raw_content) = raw_content
             .strip_prefix("---")
-            .ok_or(MissingFrontmatter)?
-            .split_once("---")
-            .ok_or(MalformedFrontmatter)?;
+            .ok_or(MissingFrontmatter)
+            .and_then(|raw| split_at_delimiter(raw).ok_or(MalformedFrontmatter))?;
 
         let metadata = toml::from_str::<PageMetadata>(frontmatter.trim())?;
-        let html_content = markdown_to_html(raw_content);
+        let context = markdown::Context {
+            code_language: metadata.code_language.as_deref(),
+        };
+        let (html_toc, html_content) = if metadata.toc {
+            let html = markdown_to_html_toc_tagged(raw_content, &context);
+            let html_content = finish_html(html, None, true);
+            (Self::build_toc_list(&html_content), html_content)
+        } else {
+            let html = markdown_to_html_in(raw_content, &context);
+            (None, finish_html(html, None, true))
+        };
 
-        let page = Page {
+        if html_content.len() > max_rendered_bytes {
+            return Err(RenderedTooLarge {
+                size: html_content.len(),
+                limit: max_rendered_bytes,
+            });
+        }
+
+        Ok(Page {
             metadata,
+            html_toc,
             html_content,
-        };
-
-        info!(%relative_path, "loaded page");
-        Ok(page)
+        })
     }
 
-    pub async fn post<P>(&self, path: P, show_drafts: bool) -> Option<PostRef<'_>>
+    pub fn post<P>(&self, path: P, show_drafts: bool) -> Option<PostRef>
     where
         P: AsRef<Utf8Path>,
     {
-        let nodes_guard = self.nodes.read().await;
-        let post_guard = RwLockReadGuard::try_map(nodes_guard, |nodes| {
-            nodes.get(path.as_ref()).and_then(|node| {
-                if let Node::Post(post) = node {
-                    if show_drafts || !post.is_entirely_draft() {
-                        Some(post)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            })
-        });
+        match self.snapshot.load().nodes.get(path.as_ref()) {
+            Some(Node::Post(post)) if visibility::post_is_visible(post, show_drafts) => {
+                Some(PostRef {
+                    edit: self.edit_link(post),
+                    history: self
+                        .forge
+                        .as_ref()
+                        .map(|_| format!("/posts/{}/history", path.as_ref())),
+                    post: Arc::clone(post),
+                    path: path.as_ref().to_owned(),
+                    show_drafts,
+                    bodies: self.bodies.clone(),
+                    // Readers who can't see drafts aren't told about content that failed to load.
+                    stale: self.stale_node(path.as_ref()).filter(|_| show_drafts),
+                })
+            }
+            _ => None,
+        }
+    }
 
-        if let Ok(post_guard) = post_guard {
-            Some(PostRef {
-                guard: post_guard,
-                path: path.as_ref().to_owned(),
-                show_drafts,
+    /// Where the file `post` was loaded from can be edited, if anywhere.
+    fn edit_link(&self, post: &Post) -> Option<String> {
+        Some(self.forge.as_ref()?.edit_link(post.source()))
+    }
+
+    /// Every commit that changed the file the post at `path` was loaded from, newest first, or
+    /// `None` if there's no such post, or its history can't be read from git.
+    pub async fn history(&self, path: &str, show_drafts: bool) -> Option<HistoryRef> {
+        let post = self.post(path, show_drafts)?;
+        let commits = match git::history(&self.root, post.source()).await {
+            Ok(commits) => commits,
+            Err(error) => {
+                warn!(%path, %error, "failed to read post's history");
+                return None;
+            }
+        };
+
+        let revisions = commits
+            .into_iter()
+            .map(|commit| Revision {
+                link: self
+                    .forge
+                    .as_ref()
+                    .map(|forge| forge.commit_link(&commit.hash)),
+                commit,
             })
-        } else {
-            None
+            .collect();
+        Some(HistoryRef { post, revisions })
+    }
+
+    /// The path of a file bundled with the post at `post`, if the post is a bundle and has a file
+    /// called `name` that can be served. The post's own markdown is never served, since it could
+    /// have drafts in it.
+    pub fn bundled_file(&self, post: &str, name: &str) -> Option<Utf8PathBuf> {
+        let servable = !name.is_empty()
+            && !name.starts_with('.')
+            && !name.contains(['/', '\\'])
+            && Utf8Path::new(name).extension() != Some("md");
+        if !servable {
+            return None;
         }
+
+        let dir = self.root.join(post);
+        if !dir.join("index.md").is_file() {
+            return None;
+        }
+
+        let file = dir.join(name);
+        file.is_file().then_some(file)
     }
 
-    pub async fn page<P>(&self, path: P) -> Option<PageRef<'_>>
+    pub fn page<P>(&self, path: P) -> Option<PageRef>
     where
         P: AsRef<Utf8Path>,
     {
-        let nodes_guard = self.nodes.read().await;
-        let page_guard = RwLockReadGuard::try_map(nodes_guard, |nodes| {
-            nodes.get(path.as_ref()).and_then(|node| {
-                if let Node::Page(page) = node {
-                    Some(page)
-                } else {
-                    None
+        match self.snapshot.load().nodes.get(path.as_ref()) {
+            Some(Node::Page(page)) => Some(PageRef {
+                page: Arc::clone(page),
+                path: path.as_ref().to_owned(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn nodes(&self, show_drafts: bool) -> NodesRef {
+        NodesRef {
+            snapshot: self.snapshot.load_full(),
+            show_drafts,
+        }
+    }
+
+    /// Every draft post, or draft entry in a thread, whose date is more than `max_age` before
+    /// `today`, oldest first.
+    pub fn stale_drafts(&self, today: NaiveDate, max_age: Days) -> Vec<StaleDraft> {
+        let Some(cutoff) = today.checked_sub_days(max_age) else {
+            return Vec::new();
+        };
+
+        let snapshot = self.snapshot.load();
+        let mut stale = snapshot
+            .nodes
+            .iter()
+            .filter_map(|(path, node)| Some((path, node.as_post()?)))
+            .flat_map(|(path, post)| {
+                let drafts = match post {
+                    Post::Single { metadata, .. } => metadata
+                        .draft
+                        .then_some((None, metadata.md_title.as_str(), metadata.date))
+                        .into_iter()
+                        .collect::<Vec<_>>(),
+                    Post::Thread {
+                        metadata, entries, ..
+                    } => entries
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, entry)| entry.metadata.draft)
+                        .map(|(index, entry)| {
+                            let title = entry
+                                .metadata
+                                .md_title
+                                .as_deref()
+                                .unwrap_or(&metadata.md_title);
+                            (Some(index), title, entry.metadata.date)
+                        })
+                        .collect(),
+                };
+
+                drafts
+                    .into_iter()
+                    .filter(|(_, _, date)| *date < cutoff)
+                    .map(|(entry, title, date)| StaleDraft {
+                        path: path.clone(),
+                        entry,
+                        title: title.to_owned(),
+                        date,
+                        age_days: (today - date).num_days(),
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        stale.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.path.cmp(&b.path)));
+        stale
+    }
+
+    /// Every post and page that's been loaded, drafts included, with posts in the order they were
+    /// posted and pages after them.
+    pub fn inventory(&self) -> Vec<InventoryItem> {
+        let snapshot = self.snapshot.load();
+        let mut items = snapshot
+            .nodes
+            .iter()
+            .map(|(path, node)| match node {
+                Node::Post(post) => {
+                    let (kind, draft_entries) = match post.as_ref() {
+                        Post::Single { .. } => (InventoryKind::Post, 0),
+                        Post::Thread { entries, .. } => (
+                            InventoryKind::Thread,
+                            entries.iter().filter(|entry| entry.metadata.draft).count(),
+                        ),
+                    };
+                    InventoryItem {
+                        path: path.clone(),
+                        kind,
+                        title: Some(post.md_title().to_owned()),
+                        date: Some(post.date_posted()),
+                        updated: Some(post.date_updated(true)),
+                        draft: !visibility::post_is_visible(post, false),
+                        draft_entries,
+                        tags: post.tags().map(ToString::to_string).collect(),
+                    }
                 }
+                Node::Page(page) => InventoryItem {
+                    path: path.clone(),
+                    kind: InventoryKind::Page,
+                    title: page.metadata.title.clone(),
+                    date: None,
+                    updated: None,
+                    draft: false,
+                    draft_entries: 0,
+                    tags: Vec::new(),
+                },
             })
+            .collect::<Vec<_>>();
+
+        // Pages have no date, so they sort after every post.
+        items.sort_by(|a, b| {
+            a.date
+                .is_none()
+                .cmp(&b.date.is_none())
+                .then_with(|| a.date.cmp(&b.date))
+                .then_with(|| a.path.cmp(&b.path))
         });
+        items
+    }
 
-        if let Ok(page_guard) = page_guard {
-            Some(PageRef { guard: page_guard })
-        } else {
-            None
+    /// Remember the state the content is in now, so that it can be compared against later.
+    ///
+    /// This is done once everything has been loaded at startup and after each change, rather than
+    /// for every file loaded, so that only states the site was actually served in are kept.
+    ///
+    /// Whatever changed since the last state recorded is also sent to the webhook, if there is one.
+    pub fn record_manifest(&self) {
+        let snapshot = self.snapshot.load_full();
+        let previous = self.manifests.record(Arc::clone(&snapshot));
+        *self
+            .reloaded
+            .lock()
+            .expect("reload time mutex is not poisoned") = Some(Utc::now());
+
+        if let (Some(webhook), Some(previous)) = (&self.webhook, previous) {
+            webhook.publish(ContentEvent::between(&previous, &snapshot, Utc::now()));
         }
     }
 
-    pub async fn nodes(&self, show_drafts: bool) -> NodesRef<'_> {
-        let nodes_guard = self.nodes.read().await;
-        NodesRef {
-            guard: nodes_guard,
-            show_drafts,
-        }
+    /// How many files failed to load when the content was first loaded, or `None` if it hasn't
+    /// finished loading yet.
+    pub fn initial_failures(&self) -> Option<usize> {
+        self.initial_failures.get().copied()
+    }
+
+    /// How many posts and pages are loaded, drafts included.
+    pub fn len(&self) -> usize {
+        self.snapshot.load().nodes.len()
+    }
+
+    /// When the content was last loaded or changed, if it ever has been.
+    pub fn last_reload(&self) -> Option<DateTime<Utc>> {
+        *self
+            .reloaded
+            .lock()
+            .expect("reload time mutex is not poisoned")
+    }
+
+    pub fn manifest(&self, show_drafts: bool) -> Manifest {
+        Manifest::build(&self.snapshot.load(), show_drafts)
+    }
+
+    /// The URLs that have changed since the content was in the state with the manifest `since`, or
+    /// `None` if that state is too old to be remembered (or never existed).
+    pub fn diff_since(&self, since: &str, show_drafts: bool) -> Option<ManifestDiff> {
+        let previous = self.manifests.find(since, show_drafts)?;
+        Some(self.manifest(show_drafts).diff(&previous))
     }
 
-    pub async fn tag_exists(&self, tag: &TagName) -> bool {
-        self.nodes.read().await.iter().any(|(_, node)| {
+    pub fn tag_exists(&self, tag: &TagName) -> bool {
+        self.snapshot.load().nodes.values().any(|node| {
             if let Node::Post(post) = node {
                 post.has_tag(tag)
             } else {
@@ -738,6 +2322,64 @@ impl Content {
     }
 }
 
+/// A post or page, as listed by the `list` subcommand and the development content page.
+#[derive(Clone, Debug, Serialize)]
+pub struct InventoryItem {
+    pub path: Utf8PathBuf,
+    pub kind: InventoryKind,
+    pub title: Option<String>,
+    /// When a post was posted, or when the first entry in a thread was.
+    pub date: Option<NaiveDate>,
+    pub updated: Option<NaiveDate>,
+    /// Whether the whole post is a draft, which for a thread means its first entry is.
+    pub draft: bool,
+    /// How many of the entries in a thread are drafts.
+    pub draft_entries: usize,
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InventoryKind {
+    Post,
+    Thread,
+    Page,
+}
+
+impl InventoryKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            InventoryKind::Post => "post",
+            InventoryKind::Thread => "thread",
+            InventoryKind::Page => "page",
+        }
+    }
+}
+
+/// A draft that has gone unfinished for longer than it should have, as listed in the admin
+/// interface.
+#[derive(Clone, Debug, Serialize)]
+pub struct StaleDraft {
+    pub path: Utf8PathBuf,
+    /// The index of the draft entry, if it's part of a thread.
+    pub entry: Option<usize>,
+    pub title: String,
+    pub date: NaiveDate,
+    pub age_days: i64,
+}
+
+/// A file that failed to load, either at startup or after it changed, so the version loaded before
+/// that is still being served, if there is one.
+#[derive(Clone, Debug, Serialize)]
+pub struct StaleNode {
+    pub path: Utf8PathBuf,
+    pub error: String,
+    /// When the file first failed to load, since when it may have failed again.
+    pub since: DateTime<Utc>,
+    /// Whether there's an older version of the file that's still being served.
+    pub previous: bool,
+}
+
 impl FromRef<State> for Content {
     fn from_ref(input: &State) -> Self {
         input.content.clone()
@@ -755,6 +2397,9 @@ pub enum LoadContentError {
     #[error("path doesn't contain a file extension")]
     NoExtension,
 
+    #[error("file is {size} bytes, more than the limit of {limit}")]
+    TooLarge { size: u64, limit: u64 },
+
     #[error(transparent)]
     LoadPost(#[from] LoadPostError),
 
@@ -763,10 +2408,18 @@ pub enum LoadContentError {
 }
 
 #[derive(Clone, Debug)]
-#[expect(clippy::large_enum_variant)]
 pub enum Node {
-    Post(Post),
-    Page(Page),
+    Post(Arc<Post>),
+    Page(Arc<Page>),
+}
+
+impl Node {
+    pub fn as_post(&self) -> Option<&Post> {
+        match self {
+            Node::Post(post) => Some(post),
+            Node::Page(_) => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -775,8 +2428,7 @@ pub enum Post {
     Single {
         metadata: SinglePostMetadata,
         html_summary: String,
-        html_toc: Option<String>,
-        html_content: String,
+        body: Body,
     },
     Thread {
         metadata: ThreadMetadata,
@@ -825,56 +2477,24 @@ impl Post {
         match self {
             Post::Single { metadata, .. } => metadata.updated.unwrap_or(metadata.date),
             Post::Thread { entries, .. } => {
-                // If we're not including draft entries, then return the most recent date found in
-                // either the `date` or `updated` field of any entry between the first entry and
-                // the last non-draft entry.
+                // Only the entries that are shown count towards when the post was last updated.
                 //
-                // If all entries are drafts (or the first entry is a draft, which is treated as
-                // equivalent), consider all entries when determining the maximum date - the caller
-                // should only be displaying that date if they're showing drafts, because otherwise
-                // they wouldn't be looking at a post that's entirely full of drafts in the first
-                // place.
-                if include_draft_entries {
-                    entries
-                        .iter()
-                        .map(|e| e.metadata.date)
-                        .chain(entries.iter().filter_map(|e| e.metadata.updated))
-                        .max()
-                        .expect("a threaded post cannot have zero entries")
-                } else {
-                    entries
-                        .iter()
-                        .fold(
-                            (
-                                false,
-                                entries
-                                    .first()
-                                    .expect("a threaded post cannot have zero entries")
-                                    .metadata
-                                    .date,
-                            ),
-                            |(found_draft, acc), next| {
-                                let found_draft = found_draft || next.metadata.draft;
-
-                                if !found_draft {
-                                    // If we still haven't found a draft anywhere, that means *this*
-                                    // one isn't a draft, so include its date in the calculation.
-                                    (
-                                        found_draft,
-                                        acc.max(
-                                            next.metadata
-                                                .updated
-                                                .map(|up| up.max(next.metadata.date))
-                                                .unwrap_or(next.metadata.date),
-                                        ),
-                                    )
-                                } else {
-                                    (found_draft, acc)
-                                }
-                            },
-                        )
-                        .1
-                }
+                // If none of them are (because the first entry is a draft), consider all entries
+                // when determining the maximum date - the caller should only be displaying that
+                // date if they're showing drafts, because otherwise they wouldn't be looking at a
+                // post that's entirely full of drafts in the first place.
+                let visible = visibility::visible_entries(entries, include_draft_entries);
+                let entries = if visible.is_empty() { entries } else { visible };
+
+                entries
+                    .iter()
+                    .map(|e| {
+                        e.metadata
+                            .updated
+                            .map_or(e.metadata.date, |up| up.max(e.metadata.date))
+                    })
+                    .max()
+                    .expect("a threaded post cannot have zero entries")
             }
         }
     }
@@ -893,10 +2513,27 @@ impl Post {
         }
     }
 
-    pub fn is_entirely_draft(&self) -> bool {
+    /// The value of the `robots` meta tag for this post, if one was given in the frontmatter.
+    pub fn robots(&self) -> Option<&str> {
+        match self {
+            Post::Single { metadata, .. } => metadata.robots.as_deref(),
+            Post::Thread { metadata, .. } => metadata.robots.as_deref(),
+        }
+    }
+
+    /// The file the post was loaded from, relative to the content root.
+    pub fn source(&self) -> &Utf8Path {
+        match self {
+            Post::Single { metadata, .. } => &metadata.source,
+            Post::Thread { metadata, .. } => &metadata.source,
+        }
+    }
+
+    /// Links to related posts or pages elsewhere, given in the frontmatter.
+    pub fn see_also(&self) -> &[String] {
         match self {
-            Post::Single { metadata, .. } => metadata.draft,
-            Post::Thread { entries, .. } => entries.iter().all(|entry| entry.metadata.draft),
+            Post::Single { metadata, .. } => &metadata.see_also,
+            Post::Thread { metadata, .. } => &metadata.see_also,
         }
     }
 
@@ -929,8 +2566,7 @@ impl Post {
 pub struct ThreadEntry {
     metadata: ThreadEntryMetadata,
     html_summary: String,
-    html_toc: Option<String>,
-    html_content: String,
+    body: Body,
 }
 
 impl ThreadEntry {
@@ -959,6 +2595,15 @@ pub enum LoadPostError {
 
     #[error("failed to parse post frontmatter: {0}")]
     ParseFrontmatter(#[from] toml::de::Error),
+
+    #[error("thread entry has no date, and there's no commit to take one from")]
+    MissingEntryDate,
+
+    #[error("thread has {entries} entries, more than the limit of {limit}")]
+    TooManyEntries { entries: usize, limit: usize },
+
+    #[error(transparent)]
+    InvalidShortcode(#[from] InvalidShortcode),
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -973,10 +2618,28 @@ pub struct PostFrontmatter {
     updated: Option<NaiveDate>,
     lobsters: Option<Url>,
     hacker_news: Option<Url>,
+    robots: Option<String>,
+    #[serde(default)]
+    see_also: Vec<String>,
+    /// When the post can be published, as an RFC 3339 string, e.g. `"2024-06-01T09:00:00Z"`. Until
+    /// then, it's kept encrypted and can only be seen with a preview token.
+    embargo_until: Option<DateTime<Utc>>,
+    /// The language inline code is highlighted in when it isn't marked with one, like
+    /// `` `x`{rust} ``.
+    code_language: Option<String>,
+}
+
+/// A post as it's loaded from its file: either ready to serve, or sealed away until its embargo
+/// ends.
+enum LoadedPost {
+    Public(Arc<Post>, String),
+    Embargoed(Sealed),
 }
 
 #[derive(Clone, Debug)]
 pub struct SinglePostMetadata {
+    /// The file the post was loaded from, relative to the content root.
+    pub source: Utf8PathBuf,
     pub md_title: String,
     pub draft: bool,
     pub tags: Vec<TagName>,
@@ -984,11 +2647,14 @@ pub struct SinglePostMetadata {
     pub updated: Option<NaiveDate>,
     pub lobsters: Option<Url>,
     pub hacker_news: Option<Url>,
+    pub robots: Option<String>,
+    pub see_also: Vec<String>,
 }
 
 impl SinglePostMetadata {
     fn split_for_thread(self) -> (ThreadMetadata, ThreadEntryMetadata) {
         let SinglePostMetadata {
+            source,
             md_title,
             draft,
             tags,
@@ -996,9 +2662,17 @@ impl SinglePostMetadata {
             updated,
             lobsters,
             hacker_news,
+            robots,
+            see_also,
         } = self;
         (
-            ThreadMetadata { md_title, tags },
+            ThreadMetadata {
+                source,
+                md_title,
+                tags,
+                robots,
+                see_also,
+            },
             ThreadEntryMetadata {
                 md_title: None,
                 draft,
@@ -1013,16 +2687,32 @@ impl SinglePostMetadata {
 
 #[derive(Clone, Debug)]
 pub struct ThreadMetadata {
+    /// The file the post was loaded from, relative to the content root.
+    pub source: Utf8PathBuf,
     pub md_title: String,
     pub tags: Vec<TagName>,
+    pub robots: Option<String>,
+    pub see_also: Vec<String>,
 }
 
+/// The frontmatter of each entry in a thread after the first.
 #[derive(Clone, Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
-pub struct ThreadEntryMetadata {
+struct ThreadEntryFrontmatter {
     #[serde(rename = "title")]
-    pub md_title: Option<String>,
+    md_title: Option<String>,
     #[serde(default)]
+    draft: bool,
+    /// When the entry was posted, which can only be left out if it can be found from git.
+    date: Option<NaiveDate>,
+    updated: Option<NaiveDate>,
+    lobsters: Option<Url>,
+    hacker_news: Option<Url>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ThreadEntryMetadata {
+    pub md_title: Option<String>,
     pub draft: bool,
     pub date: NaiveDate,
     pub updated: Option<NaiveDate>,
@@ -1033,6 +2723,7 @@ pub struct ThreadEntryMetadata {
 #[derive(Clone, Debug)]
 pub struct Page {
     pub metadata: PageMetadata,
+    pub html_toc: Option<String>,
     pub html_content: String,
 }
 
@@ -1053,6 +2744,14 @@ impl Page {
 #[serde(deny_unknown_fields)]
 pub struct PageMetadata {
     pub title: Option<String>,
+
+    /// Whether to give the page's headings anchors and list them in a table of contents, like a
+    /// post's.
+    #[serde(default)]
+    pub toc: bool,
+
+    /// The language inline code is highlighted in when it isn't marked with one, as for a post.
+    pub code_language: Option<String>,
 }
 
 #[derive(Error, Debug)]
@@ -1068,80 +2767,340 @@ pub enum LoadPageError {
 
     #[error("failed to parse page frontmatter: {0}")]
     ParseFrontmatter(#[from] toml::de::Error),
+
+    #[error("page renders to {size} bytes of HTML, more than the limit of {limit}")]
+    RenderedTooLarge { size: usize, limit: usize },
+
+    #[error(transparent)]
+    InvalidShortcode(#[from] InvalidShortcode),
 }
 
+/// The name of the cookie that stores the reader's choice of colour scheme, if they've made one.
+pub const COLOR_SCHEME_COOKIE: &str = "color-scheme";
+
+/// The name of the cookie that stores the reader's choice of named theme, if they've made one. The
+/// same name is used for the query parameter that can pick a theme for a single request.
+pub const THEME_COOKIE: &str = "theme";
+
+/// The name of the theme made up of the syntect themes at the top level of the theme config, using
+/// the colours from the stylesheet.
+pub const DEFAULT_THEME: &str = "default";
+
+/// The CSS for every theme, kept behind a lock so that it can be rebuilt when the themes directory
+/// changes.
 #[derive(Clone, Debug)]
-pub struct Theme {
-    theme_header: Arc<Markup>,
+pub struct Themes {
+    headers: Arc<RwLock<Arc<BTreeMap<String, Markup>>>>,
+    themes_path: Arc<Utf8PathBuf>,
+    /// The config the CSS was last built from, which it's built from again whenever it's reloaded.
+    config: Arc<RwLock<ThemeConfig>>,
 }
 
-impl Theme {
-    pub fn try_load(
-        theme_set: SyntectThemeSet,
-        light: &'static str,
-        dark: &'static str,
-    ) -> Result<Self, LoadThemeError> {
+impl Themes {
+    pub fn load(themes_path: &Utf8Path, config: &ThemeConfig) -> Result<Self, LoadThemeError> {
+        Ok(Self {
+            headers: Arc::new(RwLock::new(Arc::new(Self::build_headers(
+                themes_path,
+                config,
+            )?))),
+            themes_path: Arc::new(themes_path.to_owned()),
+            config: Arc::new(RwLock::new(config.clone())),
+        })
+    }
+
+    /// The theme with the given name (or the default one, if there's no such theme), optionally
+    /// with a colour scheme that overrides the reader's OS preference.
+    pub async fn get(&self, name: Option<&str>, color_scheme: Option<ColorScheme>) -> Theme {
+        let headers = self.headers.read().await.clone();
+        let name = name
+            .filter(|name| headers.contains_key(*name))
+            .unwrap_or(DEFAULT_THEME)
+            .to_owned();
+
+        Theme {
+            headers,
+            name,
+            color_scheme,
+            flags: Flags::default(),
+        }
+    }
+
+    /// Rebuild the CSS for every theme from the contents of the themes directory. If this fails,
+    /// the previous CSS is kept.
+    pub async fn reload(&self) -> Result<(), LoadThemeError> {
+        let config = self.config.read().await;
+        let headers = Self::build_headers(&self.themes_path, &config)?;
+        *self.headers.write().await = Arc::new(headers);
+        Ok(())
+    }
+
+    /// Rebuild the CSS for every theme like [`Themes::reload`], but from a new config, which is
+    /// kept for reloading from only if the CSS could be built.
+    pub async fn reconfigure(&self, config: ThemeConfig) -> Result<(), LoadThemeError> {
+        let mut current = self.config.write().await;
+        let headers = Self::build_headers(&self.themes_path, &config)?;
+        *self.headers.write().await = Arc::new(headers);
+        *current = config;
+        Ok(())
+    }
+
+    fn build_headers(
+        themes_path: &Utf8Path,
+        config: &ThemeConfig,
+    ) -> Result<BTreeMap<String, Markup>, LoadThemeError> {
+        // The themes built into syntect are always available, alongside any in the themes path.
+        let mut theme_set = SyntectThemeSet::load_defaults();
+        theme_set.add_from_folder(themes_path)?;
+
+        let default = NamedThemeConfig {
+            light: config.light.clone(),
+            dark: config.dark.clone(),
+            light_colors: Default::default(),
+            dark_colors: Default::default(),
+        };
+
+        iter::once((DEFAULT_THEME, &default))
+            .chain(
+                config
+                    .named
+                    .iter()
+                    .map(|(name, named)| (name.as_str(), named)),
+            )
+            .map(|(name, named)| {
+                Self::build_header(&theme_set, named).map(|header| (name.to_owned(), header))
+            })
+            .collect()
+    }
+
+    fn build_header(
+        theme_set: &SyntectThemeSet,
+        config: &NamedThemeConfig,
+    ) -> Result<Markup, LoadThemeError> {
         use LoadThemeError::*;
 
         let light_css = css_for_theme_with_class_style(
             theme_set
                 .themes
-                .get(light)
-                .ok_or_else(|| MissingTheme(light))?,
+                .get(&config.light)
+                .ok_or_else(|| MissingTheme(config.light.clone()))?,
             ClassStyle::Spaced,
         )
         .map_err(GenerateThemeCss)?;
-        let light_block = format!(":root {{ {light_css} }}");
+        let light_colors = config.light_colors.to_css();
+        let light_block = format!(":root {{ {light_colors} {light_css} }}");
 
         let dark_css = css_for_theme_with_class_style(
             theme_set
                 .themes
-                .get(dark)
-                .ok_or_else(|| MissingTheme(dark))?,
+                .get(&config.dark)
+                .ok_or_else(|| MissingTheme(config.dark.clone()))?,
             ClassStyle::Spaced,
         )
         .map_err(GenerateThemeCss)?;
-        let dark_block = format!("@media(prefers-color-scheme: dark) {{ :root{{ {dark_css} }} }}");
-
-        Ok(Self {
-            theme_header: Arc::new(html! {
-                (PreEscaped(light_block))
-                (PreEscaped(dark_block))
-            }),
+        let dark_colors = config.dark_colors.to_css();
+
+        // The OS preference applies unless the reader has explicitly picked the light scheme, and
+        // an explicit choice of the dark scheme applies regardless of the OS preference.
+        let dark_block = format!(
+            "@media(prefers-color-scheme: dark) {{ :root:not([data-color-scheme=\"light\"]) {{ \
+             {dark_colors} {dark_css} }} }} :root[data-color-scheme=\"dark\"] {{ {dark_colors} \
+             {dark_css} }}"
+        );
+
+        Ok(html! {
+            (PreEscaped(light_block))
+            (PreEscaped(dark_block))
         })
     }
 }
 
 #[derive(Error, Debug)]
 pub enum LoadThemeError {
+    #[error("failed to load theme set: {0}")]
+    LoadThemeSet(#[from] SyntectLoadingError),
+
     #[error("failed to generate CSS for theme: {0}")]
     GenerateThemeCss(#[source] SyntectError),
 
     #[error("theme set does not contain a theme with name: {0}")]
-    MissingTheme(&'static str),
+    MissingTheme(String),
+}
+
+/// The CSS for every theme as it was when the request came in, along with the theme and colour
+/// scheme the reader has picked (if any) and the feature flags that are on for them.
+///
+/// This is extracted per-request rather than with `State`, so that the reader's choices are picked
+/// up from their cookies and the query string.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    headers: Arc<BTreeMap<String, Markup>>,
+    name: String,
+    color_scheme: Option<ColorScheme>,
+    flags: Flags,
 }
 
 impl Theme {
     pub fn theme_header(&self) -> &Markup {
-        &self.theme_header
+        self.headers
+            .get(&self.name)
+            .expect("selected theme is always one that was loaded")
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The names of all the themes that can be picked, starting with the default one.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        iter::once(DEFAULT_THEME).chain(
+            self.headers
+                .keys()
+                .map(String::as_str)
+                .filter(|name| *name != DEFAULT_THEME),
+        )
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.headers.contains_key(name)
+    }
+
+    pub fn color_scheme(&self) -> Option<ColorScheme> {
+        self.color_scheme
+    }
+
+    pub fn with_flags(self, flags: Flags) -> Self {
+        Self { flags, ..self }
+    }
+
+    pub fn flags(&self) -> &Flags {
+        &self.flags
     }
 }
 
-impl FromRef<State> for Theme {
-    fn from_ref(input: &State) -> Self {
-        input.theme.clone()
+#[async_trait]
+impl FromRequestParts<State> for Theme {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let jar = CookieJar::from_headers(&parts.headers);
+
+        let color_scheme = jar
+            .get(COLOR_SCHEME_COOKIE)
+            .and_then(|cookie| cookie.value().parse().ok());
+
+        // A theme given in the query string takes precedence over the one stored in the cookie,
+        // and anything that isn't the name of a loaded theme is ignored.
+        let from_query = parts.uri.query().and_then(|query| {
+            form_urlencoded::parse(query.as_bytes())
+                .find(|(key, _)| key == THEME_COOKIE)
+                .map(|(_, value)| value.into_owned())
+        });
+        let from_cookie = jar
+            .get(THEME_COOKIE)
+            .map(|cookie| cookie.value().to_owned());
+
+        let headers = state.themes.headers.read().await.clone();
+        let name = from_query
+            .into_iter()
+            .chain(from_cookie)
+            .find(|name| headers.contains_key(name))
+            .unwrap_or_else(|| DEFAULT_THEME.to_owned());
+
+        // Overrides to the feature flags can come from a header as well as the cookie, and are
+        // ignored unless they're signed.
+        let overrides = parts
+            .headers
+            .get(FLAGS_HEADER)
+            .and_then(|val| val.to_str().ok())
+            .or_else(|| jar.get(FLAGS_COOKIE).map(|cookie| cookie.value()));
+        let flags = state.settings.flags().resolve(overrides);
+
+        Ok(Self {
+            headers,
+            name,
+            color_scheme,
+            flags,
+        })
+    }
+}
+
+/// A colour scheme that the reader can pick to override the one chosen by their OS preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+}
+
+impl FromStr for ColorScheme {
+    type Err = UnknownColorScheme;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(ColorScheme::Light),
+            "dark" => Ok(ColorScheme::Dark),
+            other => Err(UnknownColorScheme(other.to_owned())),
+        }
     }
 }
 
+#[derive(Error, Debug)]
+#[error("unknown colour scheme: {0}")]
+pub struct UnknownColorScheme(String);
+
 #[derive(Clone, Debug)]
 pub struct Settings {
     show_drafts: bool,
+    strict: bool,
+    max_draft_age: Days,
+    flags: FeatureFlags,
 }
 
 impl Settings {
     pub fn show_drafts(&self) -> bool {
         self.show_drafts
     }
+
+    /// Whether content that fails to load counts as a failure, rather than something to work
+    /// around by serving what was there before.
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// How long after its date a draft counts as stale.
+    pub fn max_draft_age(&self) -> Days {
+        self.max_draft_age
+    }
+
+    pub fn flags(&self) -> &FeatureFlags {
+        &self.flags
+    }
+}
+
+impl FromRef<State> for ReloadTrigger {
+    fn from_ref(input: &State) -> Self {
+        input.reloads.clone()
+    }
+}
+
+impl FromRef<State> for ContentRepo {
+    fn from_ref(input: &State) -> Self {
+        input.content_repo.clone()
+    }
+}
+
+impl FromRef<State> for WatcherHealth {
+    fn from_ref(input: &State) -> Self {
+        input.watcher_health.clone()
+    }
 }
 
 impl FromRef<State> for Settings {
@@ -1149,3 +3108,378 @@ impl FromRef<State> for Settings {
         input.settings.clone()
     }
 }
+
+#[derive(Clone, Debug)]
+pub struct Site {
+    config: Arc<SiteConfig>,
+    feeds: Arc<FeedsConfig>,
+    nav: Arc<Vec<Link>>,
+    footer: Arc<Footer>,
+    /// The CSS that gives each tag with its own accent that accent, wherever the tag is marked
+    /// with a `data-tag` attribute.
+    tag_accents: Arc<str>,
+    assets: Assets,
+}
+
+impl Site {
+    pub fn new(
+        config: SiteConfig,
+        feeds: FeedsConfig,
+        nav: Vec<Link>,
+        footer: FooterConfig,
+        tags: &BTreeMap<String, TagConfig>,
+        assets: Assets,
+    ) -> Self {
+        let footer = Footer {
+            links: footer.links,
+            html_text: footer.text.as_deref().map(markdown_to_html),
+            repository: footer.repository,
+        };
+
+        let tag_accents = tags
+            .iter()
+            .filter_map(|(name, tag)| {
+                let accent = tag.accent.as_ref()?;
+                // Only valid tag names are safe to put in a selector, and no post could be tagged
+                // with anything else anyway.
+                match TagName::try_from(name.as_str()) {
+                    Ok(name) => Some(format!("[data-tag=\"{name}\"] {{ --accent: {accent}; }}")),
+                    Err(error) => {
+                        warn!(%error, "ignoring accent for invalid tag name");
+                        None
+                    }
+                }
+            })
+            .collect::<String>();
+
+        Self {
+            config: Arc::new(config),
+            feeds: Arc::new(feeds),
+            nav: Arc::new(nav),
+            footer: Arc::new(footer),
+            tag_accents: tag_accents.into(),
+            assets,
+        }
+    }
+
+    pub fn feeds(&self) -> &FeedsConfig {
+        &self.feeds
+    }
+
+    pub fn nav(&self) -> &[Link] {
+        &self.nav
+    }
+
+    pub fn footer(&self) -> &Footer {
+        &self.footer
+    }
+
+    pub fn tag_accents(&self) -> &str {
+        &self.tag_accents
+    }
+
+    pub fn assets(&self) -> &Assets {
+        &self.assets
+    }
+}
+
+#[derive(Debug)]
+pub struct Footer {
+    pub links: Vec<Link>,
+    pub html_text: Option<String>,
+    pub repository: Option<Url>,
+}
+
+impl Deref for Site {
+    type Target = SiteConfig;
+
+    fn deref(&self) -> &Self::Target {
+        &self.config
+    }
+}
+
+impl FromRef<State> for Site {
+    fn from_ref(input: &State) -> Self {
+        input.site.clone()
+    }
+}
+
+impl FromRef<State> for LogFilter {
+    fn from_ref(input: &State) -> Self {
+        input.log_filter.clone()
+    }
+}
+
+/// Permanent redirects from old paths to new ones, configured in the config file, along with any
+/// from the old paths of posts and pages that have been renamed since the server started.
+#[derive(Clone, Debug)]
+pub struct Redirects {
+    configured: Arc<ArcSwap<HashMap<String, String>>>,
+    renamed: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl Redirects {
+    pub fn new(configured: HashMap<String, String>) -> Self {
+        Self {
+            configured: Arc::new(ArcSwap::from_pointee(configured)),
+            renamed: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<String> {
+        if let Some(target) = self.configured.load().get(path) {
+            return Some(target.clone());
+        }
+
+        let renamed = self
+            .renamed
+            .lock()
+            .expect("renamed redirects mutex is not poisoned");
+        if let Some(target) = renamed.get(path) {
+            return Some(target.clone());
+        }
+
+        // Everything under a renamed post, like its entries and bundled files, moves along with it.
+        let rest = path.strip_prefix("/posts/")?;
+        let (post, rest) = rest.split_once('/')?;
+        let target = renamed.get(&format!("/posts/{post}"))?;
+        Some(format!("{target}/{rest}"))
+    }
+
+    /// Replace the redirects from the config file, like after it's been read again. Those from
+    /// renames are kept.
+    pub fn reconfigure(&self, configured: HashMap<String, String>) {
+        self.configured.store(Arc::new(configured));
+    }
+
+    /// Redirect the URL of the node at `from` to the one it's been renamed to, `to`.
+    pub fn rename(&self, from: &Utf8Path, to: &Utf8Path) {
+        let (Some(from), Some(to)) = (node_url(from), node_url(to)) else {
+            return;
+        };
+
+        let mut renamed = self
+            .renamed
+            .lock()
+            .expect("renamed redirects mutex is not poisoned");
+        // Anything that already led to the old URL now leads to the new one, and the new URL
+        // mustn't lead anywhere, in case something's been renamed back.
+        for target in renamed.values_mut() {
+            if *target == from {
+                target.clone_from(&to);
+            }
+        }
+        renamed.remove(&to);
+        renamed.retain(|source, target| source != target);
+
+        info!(%from, %to, "redirecting renamed content");
+        renamed.insert(from, to);
+    }
+
+    /// Stop redirecting away from the URL of the node at `key`, since something's been loaded
+    /// there.
+    pub fn forget(&self, key: &Utf8Path) {
+        if let Some(url) = node_url(key) {
+            self.renamed
+                .lock()
+                .expect("renamed redirects mutex is not poisoned")
+                .remove(&url);
+        }
+    }
+}
+
+/// The URL the node at `key` is served at, if it's a post or a top-level page.
+fn node_url(key: &Utf8Path) -> Option<String> {
+    let name = key.file_name()?;
+    if NaiveDate::parse_and_remainder(name, "%Y-%m-%d").is_ok() {
+        Some(format!("/posts/{key}"))
+    } else if key.parent() == Some(Utf8Path::new("")) && name != "_index" {
+        Some(format!("/{name}"))
+    } else {
+        None
+    }
+}
+
+impl FromRef<State> for Redirects {
+    fn from_ref(input: &State) -> Self {
+        input.redirects.clone()
+    }
+}
+
+/// How many of the most recent requests are kept around to replay to new request log subscribers.
+const REQUEST_LOG_CAPACITY: usize = 100;
+
+/// How many past snapshots of the content are kept to compare the current one against. One is
+/// recorded at startup and after each batch of changes, so this is how many reloads back a manifest
+/// can be compared from.
+const MANIFEST_HISTORY: usize = 256;
+
+/// A summary of a single handled request, as streamed to the admin request log.
+#[derive(Clone, Debug, Serialize)]
+pub struct RequestSummary {
+    pub time: DateTime<Utc>,
+    pub route: String,
+    pub status: u16,
+    pub duration_ms: f64,
+    pub referer: Option<String>,
+}
+
+/// A ring buffer of the most recently handled requests, plus a channel that each new one is
+/// broadcast on as it's recorded.
+#[derive(Clone, Debug)]
+pub struct RequestLog {
+    recent: Arc<Mutex<VecDeque<RequestSummary>>>,
+    sender: broadcast::Sender<RequestSummary>,
+    capacity: usize,
+}
+
+impl RequestLog {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            recent: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            sender,
+            capacity,
+        }
+    }
+
+    pub fn record(&self, summary: RequestSummary) {
+        {
+            let mut recent = self
+                .recent
+                .lock()
+                .expect("request log mutex is not poisoned");
+            if recent.len() == self.capacity {
+                recent.pop_front();
+            }
+            recent.push_back(summary.clone());
+        }
+
+        // There's nobody to send to if no one is subscribed, which is fine.
+        let _ = self.sender.send(summary);
+    }
+
+    /// Returns the currently buffered requests, oldest first, along with a receiver for the ones
+    /// recorded after them.
+    pub fn subscribe(&self) -> (Vec<RequestSummary>, broadcast::Receiver<RequestSummary>) {
+        let recent = self
+            .recent
+            .lock()
+            .expect("request log mutex is not poisoned");
+        (recent.iter().cloned().collect(), self.sender.subscribe())
+    }
+}
+
+impl FromRef<State> for RequestLog {
+    fn from_ref(input: &State) -> Self {
+        input.request_log.clone()
+    }
+}
+
+/// The last copy of each page that finished rendering, along with the generation of content and
+/// themes it was rendered from.
+///
+/// Copies from the current generation are served as they are. Older ones are only served in place
+/// of a fresh render when that takes longer than the render budget.
+///
+/// Pages are keyed by whatever distinguishes one rendering of them from another, e.g. the path
+/// along with the theme.
+#[derive(Clone, Debug)]
+pub struct RenderCache {
+    budget: Duration,
+    generation: Arc<AtomicU64>,
+    rendered: Arc<Mutex<HashMap<String, (u64, Markup)>>>,
+}
+
+impl RenderCache {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            generation: Arc::new(AtomicU64::new(0)),
+            rendered: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Mark every copy rendered so far as out of date, e.g. because the content has changed.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Serve a page from the cache if it's up to date, and otherwise render it in its own task,
+    /// waiting for it for no longer than the render budget. If it isn't done by then, the last copy
+    /// rendered under the same key is returned instead, while the render carries on in the
+    /// background so that the copy is up to date next time.
+    ///
+    /// Rendering returns `None` if there's no such page.
+    pub async fn render<F>(&self, key: String, render: F) -> Result<Markup, RenderError>
+    where
+        F: Future<Output = Option<Markup>> + Send + 'static,
+    {
+        let generation = self.generation.load(Ordering::SeqCst);
+
+        if let Some((_, markup)) = self
+            .rendered
+            .lock()
+            .expect("render cache mutex is not poisoned")
+            .get(&key)
+            .filter(|(rendered_in, _)| *rendered_in == generation)
+        {
+            debug!(%key, "serving cached render");
+            return Ok(markup.clone());
+        }
+
+        let rendered = Arc::clone(&self.rendered);
+        let key_1 = key.clone();
+        let handle = tokio::spawn(async move {
+            let markup = render.await?;
+            rendered
+                .lock()
+                .expect("render cache mutex is not poisoned")
+                .insert(key_1, (generation, markup.clone()));
+            Some(markup)
+        });
+
+        match time::timeout(self.budget, handle).await {
+            Ok(Ok(Some(markup))) => Ok(markup),
+            Ok(Ok(None)) => Err(RenderError::NotFound),
+            Ok(Err(error)) => {
+                error!(%error, %key, "render task failed");
+                Err(RenderError::Failed)
+            }
+            Err(_) => {
+                let stale = self
+                    .rendered
+                    .lock()
+                    .expect("render cache mutex is not poisoned")
+                    .get(&key)
+                    .map(|(_, markup)| markup.clone());
+
+                if stale.is_some() {
+                    warn!(%key, budget = ?self.budget, "render exceeded budget, serving stale copy");
+                } else {
+                    warn!(%key, budget = ?self.budget, "render exceeded budget with no copy to serve");
+                }
+
+                stale.ok_or(RenderError::OverBudget)
+            }
+        }
+    }
+}
+
+impl FromRef<State> for RenderCache {
+    fn from_ref(input: &State) -> Self {
+        input.renders.clone()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum RenderError {
+    #[error("there's no such page to render")]
+    NotFound,
+
+    #[error("render took longer than the budget and there's no earlier copy to serve")]
+    OverBudget,
+
+    #[error("render task failed")]
+    Failed,
+}
@@ -0,0 +1,223 @@
+use std::fmt::Write;
+
+use maud::html;
+
+/// The basic terminal colours, in the order of their SGR codes. Each is styled by a class (and a
+/// CSS variable) with its name, so that it can fit in with the light and dark themes.
+const COLORS: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright-black",
+    "bright-red",
+    "bright-green",
+    "bright-yellow",
+    "bright-blue",
+    "bright-magenta",
+    "bright-cyan",
+    "bright-white",
+];
+
+/// The ways the escape character tends to be written out when terminal output is pasted into
+/// markdown, which only count as escapes when they're followed by a `[`.
+const WRITTEN_ESCAPES: [&str; 4] = ["\\x1b", "\\033", "\\e", "\\u001b"];
+
+/// Turn terminal output into HTML, with the text styled by SGR escape sequences (like `\x1b[32m`)
+/// wrapped in spans that style it the same way. Any other escape sequences, like ones that move
+/// the cursor or set the window title, are dropped.
+pub fn to_html(output: &str) -> String {
+    let mut html = String::with_capacity(output.len());
+    let mut style = Style::default();
+    let mut rest = output;
+
+    while let Some((start, len)) = next_escape(rest) {
+        push_text(&mut html, &style, &rest[..start]);
+        let sequence = &rest[start + len..];
+
+        rest = if let Some(csi) = sequence.strip_prefix('[') {
+            let end = csi
+                .find(|c: char| ('@'..='~').contains(&c))
+                .unwrap_or(csi.len());
+            if csi[end..].starts_with('m') {
+                style.apply(&csi[..end]);
+            }
+            csi.get(end + 1..).unwrap_or_default()
+        } else if let Some(osc) = sequence.strip_prefix(']') {
+            // These run until a bell, or an escape followed by a backslash.
+            match osc.find(['\x07', '\x1b']) {
+                Some(end) if osc[end..].starts_with("\x1b\\") => &osc[end + 2..],
+                Some(end) => &osc[end + 1..],
+                None => "",
+            }
+        } else {
+            // Anything else is a single character, apart from choosing a character set, which
+            // has one more after it to say which.
+            let skip = if sequence.starts_with(['(', ')']) {
+                2
+            } else {
+                1
+            };
+            sequence
+                .char_indices()
+                .nth(skip)
+                .map_or("", |(start, _)| &sequence[start..])
+        };
+    }
+
+    push_text(&mut html, &style, rest);
+    html
+}
+
+/// The start and length of the next escape character in some terminal output, if there is one.
+fn next_escape(output: &str) -> Option<(usize, usize)> {
+    let written = WRITTEN_ESCAPES.iter().filter_map(|escape| {
+        output
+            .match_indices(escape)
+            .find(|(start, _)| output[start + escape.len()..].starts_with('['))
+            .map(|(start, _)| (start, escape.len()))
+    });
+
+    output
+        .find('\x1b')
+        .map(|start| (start, 1))
+        .into_iter()
+        .chain(written)
+        .min()
+}
+
+fn push_text(html: &mut String, style: &Style, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if *style == Style::default() {
+        html.push_str(&html! { (text) }.into_string());
+        return;
+    }
+
+    let mut classes = [
+        (style.bold, "ansi-bold"),
+        (style.dim, "ansi-dim"),
+        (style.italic, "ansi-italic"),
+        (style.underline, "ansi-underline"),
+        (style.strikethrough, "ansi-strikethrough"),
+    ]
+    .into_iter()
+    .filter(|(on, _)| *on)
+    .map(|(_, class)| class.to_owned())
+    .collect::<Vec<_>>();
+    let mut css = String::new();
+
+    for (color, property, prefix) in [
+        (style.foreground, "color", "ansi-"),
+        (style.background, "background-color", "ansi-bg-"),
+    ] {
+        match color {
+            Some(Color::Basic(index)) => classes.push(format!("{prefix}{}", COLORS[index])),
+            Some(Color::Rgb(r, g, b)) => {
+                let _ = write!(css, "{property}: #{r:02x}{g:02x}{b:02x};");
+            }
+            None => {}
+        }
+    }
+
+    let classes = Some(classes.join(" ")).filter(|classes| !classes.is_empty());
+    let css = Some(css).filter(|css| !css.is_empty());
+    let span = html! {
+        span class=[classes] style=[css] { (text) }
+    };
+    html.push_str(&span.into_string());
+}
+
+/// How the text at some point in terminal output is styled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+    foreground: Option<Color>,
+    background: Option<Color>,
+}
+
+impl Style {
+    /// Apply the parameters of an SGR sequence, like `1;32` (bold and green).
+    fn apply(&mut self, parameters: &str) {
+        let mut codes = parameters
+            .split(';')
+            .map(|code| code.parse::<u16>().unwrap_or(0));
+
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                29 => self.strikethrough = false,
+                30..=37 => self.foreground = Some(Color::Basic(usize::from(code - 30))),
+                38 => self.foreground = Color::extended(&mut codes),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(Color::Basic(usize::from(code - 40))),
+                48 => self.background = Color::extended(&mut codes),
+                49 => self.background = None,
+                90..=97 => self.foreground = Some(Color::Basic(usize::from(code - 90 + 8))),
+                100..=107 => self.background = Some(Color::Basic(usize::from(code - 100 + 8))),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    /// One of the 16 colours in [`COLORS`], which depend on the theme.
+    Basic(usize),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// The colour chosen by the codes after a `38` or `48`: either `5` and an index into the 256
+    /// colour palette, or `2` and the red, green and blue parts.
+    fn extended(codes: &mut impl Iterator<Item = u16>) -> Option<Self> {
+        let mut part = || {
+            codes
+                .next()
+                .map(|part| u8::try_from(part).unwrap_or(u8::MAX))
+        };
+        match part()? {
+            5 => Some(Self::indexed(part()?)),
+            2 => Some(Self::Rgb(part()?, part()?, part()?)),
+            _ => None,
+        }
+    }
+
+    /// A colour in the 256 colour palette: the basic colours, then a 6×6×6 cube of colours, then
+    /// 24 shades of grey.
+    fn indexed(index: u8) -> Self {
+        match index {
+            0..=15 => Self::Basic(usize::from(index)),
+            16..=231 => {
+                let index = index - 16;
+                let level = |level: u8| if level == 0 { 0 } else { 55 + level * 40 };
+                Self::Rgb(level(index / 36), level(index / 6 % 6), level(index % 6))
+            }
+            232..=255 => {
+                let grey = 8 + (index - 232) * 10;
+                Self::Rgb(grey, grey, grey)
+            }
+        }
+    }
+}
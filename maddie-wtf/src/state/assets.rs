@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+// The stylesheet is only compiled at runtime in development.
+#[cfg(not(debug_assertions))]
+use grass as _;
+use ignore::Walk;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::fs;
+use tracing::{debug, error, warn};
+
+/// The stylesheet, compiled from the source at build time.
+#[cfg(not(debug_assertions))]
+const STYLESHEET: &str = include_str!(concat!(env!("OUT_DIR"), "/style.css"));
+
+/// In development, the stylesheet is compiled from the source on every request, so that changes to
+/// it show up without a rebuild.
+#[cfg(debug_assertions)]
+pub const STYLESHEET_SOURCE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/scss");
+
+/// The name the stylesheet is served under, both at the root and under `/assets`.
+pub const STYLESHEET_NAME: &str = "style.css";
+
+/// How many hex characters of a SHA-256 digest end up in a fingerprint.
+const HASH_LEN: usize = 16;
+
+/// Fingerprints of the files in the static path (and of the stylesheet, in release builds), used to
+/// build `/assets/<hash>/<file>` URLs that can be cached forever, since any change to a file also
+/// changes its URL.
+#[derive(Clone, Debug)]
+pub struct Assets {
+    static_path: Arc<Utf8PathBuf>,
+    hashes: Arc<RwLock<HashMap<Utf8PathBuf, String>>>,
+    /// Fingerprinted along with the files it refers to, so it's replaced whenever one of them is.
+    #[cfg(not(debug_assertions))]
+    stylesheet: Arc<RwLock<Stylesheet>>,
+    inline_stylesheet: Option<Arc<str>>,
+}
+
+#[cfg(not(debug_assertions))]
+#[derive(Debug)]
+struct Stylesheet {
+    css: String,
+    hash: String,
+}
+
+impl Assets {
+    /// Walk the static path and fingerprint every file found in it.
+    pub async fn load(static_path: &Utf8Path) -> Self {
+        let mut hashes = HashMap::new();
+
+        for result in Walk::new(static_path) {
+            let entry = match result {
+                Ok(entry) => entry,
+                Err(error) => {
+                    error!(%error, "directory walker encountered error");
+                    continue;
+                }
+            };
+
+            let Ok(path) = Utf8PathBuf::from_path_buf(entry.path().to_path_buf()) else {
+                warn!(path = ?entry.path(), "skipping entry with path that contains invalid UTF-8");
+                continue;
+            };
+
+            if !path.is_file() {
+                continue;
+            }
+
+            let Ok(relative) = path.strip_prefix(static_path) else {
+                continue;
+            };
+
+            match fs::read(&path).await {
+                Ok(bytes) => {
+                    hashes.insert(relative.to_owned(), fingerprint(&bytes));
+                }
+                Err(error) => warn!(%path, %error, "failed to read static file"),
+            }
+        }
+
+        debug!(files = %hashes.len(), "fingerprinted static files");
+
+        let assets = Self {
+            static_path: Arc::new(static_path.to_owned()),
+            hashes: Arc::new(RwLock::new(hashes)),
+            #[cfg(not(debug_assertions))]
+            stylesheet: Arc::new(RwLock::new(Stylesheet {
+                css: String::new(),
+                hash: String::new(),
+            })),
+            inline_stylesheet: None,
+        };
+
+        // The stylesheet refers to fonts in the static path, so it can only be fingerprinted once
+        // they have been.
+        #[cfg(not(debug_assertions))]
+        assets.refresh_stylesheet();
+
+        assets
+    }
+
+    /// Recompute the fingerprint of a single file in the static path after it's changed, or forget
+    /// it if it's been deleted.
+    pub async fn refresh(&self, path: &Utf8Path) {
+        let Ok(relative) = path.strip_prefix(&*self.static_path) else {
+            return;
+        };
+
+        let hash = match fs::read(path).await {
+            Ok(bytes) => Some(fingerprint(&bytes)),
+            Err(error) => {
+                debug!(%path, %error, "static file can no longer be read");
+                None
+            }
+        };
+
+        let mut hashes = self.hashes.write().expect("asset hashes lock was poisoned");
+        match hash {
+            Some(hash) => hashes.insert(relative.to_owned(), hash),
+            None => hashes.remove(relative),
+        };
+        drop(hashes);
+
+        #[cfg(not(debug_assertions))]
+        self.refresh_stylesheet();
+    }
+
+    /// Point the stylesheet at the current fingerprints of the files it refers to, and fingerprint
+    /// the result.
+    #[cfg(not(debug_assertions))]
+    fn refresh_stylesheet(&self) {
+        let css = self.rewrite_urls(STYLESHEET);
+        let hash = fingerprint(css.as_bytes());
+        *self
+            .stylesheet
+            .write()
+            .expect("stylesheet lock was poisoned") = Stylesheet { css, hash };
+    }
+
+    /// Embed the given stylesheet in every page rather than linking to it, for pages that will be
+    /// viewed without the server.
+    pub fn with_inline_stylesheet(self, css: String) -> Self {
+        Self {
+            inline_stylesheet: Some(css.into()),
+            ..self
+        }
+    }
+
+    pub fn inline_stylesheet(&self) -> Option<&str> {
+        self.inline_stylesheet.as_deref()
+    }
+
+    pub fn static_path(&self) -> &Utf8Path {
+        &self.static_path
+    }
+
+    /// The current fingerprint of a file, given by its path relative to the static path.
+    pub fn hash(&self, file: &str) -> Option<String> {
+        #[cfg(not(debug_assertions))]
+        if file == STYLESHEET_NAME {
+            return Some(
+                self.stylesheet
+                    .read()
+                    .expect("stylesheet lock was poisoned")
+                    .hash
+                    .clone(),
+            );
+        }
+
+        self.hashes
+            .read()
+            .expect("asset hashes lock was poisoned")
+            .get(Utf8Path::new(file))
+            .cloned()
+    }
+
+    /// Every file in the static path that's been fingerprinted, relative to it.
+    pub fn files(&self) -> Vec<Utf8PathBuf> {
+        self.hashes
+            .read()
+            .expect("asset hashes lock was poisoned")
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// The URL to link to a file in the static path with. Files that haven't been fingerprinted are
+    /// linked to under `/static` instead, which isn't cached.
+    pub fn url(&self, file: &str) -> String {
+        match self.hash(file) {
+            Some(hash) => format!("/assets/{hash}/{file}"),
+            None => format!("/static/{file}"),
+        }
+    }
+
+    /// The URL to link to the stylesheet with. In development it changes on every request, so it
+    /// isn't fingerprinted.
+    pub fn stylesheet_url(&self) -> String {
+        if cfg!(debug_assertions) {
+            format!("/{STYLESHEET_NAME}")
+        } else {
+            self.url(STYLESHEET_NAME)
+        }
+    }
+
+    /// The stylesheet, with any URLs of static files in it replaced by fingerprinted ones.
+    #[cfg(not(debug_assertions))]
+    pub async fn stylesheet(&self) -> Result<String, CompileStylesheetError> {
+        Ok(self
+            .stylesheet
+            .read()
+            .expect("stylesheet lock was poisoned")
+            .css
+            .clone())
+    }
+
+    /// The stylesheet, with any URLs of static files in it replaced by fingerprinted ones.
+    #[cfg(debug_assertions)]
+    pub async fn stylesheet(&self) -> Result<String, CompileStylesheetError> {
+        let css = tokio::task::spawn_blocking(|| {
+            grass::from_path(
+                format!("{STYLESHEET_SOURCE_DIR}/style.scss"),
+                &grass::Options::default(),
+            )
+        })
+        .await
+        .map_err(|_| CompileStylesheetError::Panicked)?
+        .map_err(|error| CompileStylesheetError::Compile(error.to_string()))?;
+
+        Ok(self.rewrite_urls(&css))
+    }
+
+    /// Replace every `url(/static/<file>)` in some CSS with the fingerprinted URL of that file.
+    fn rewrite_urls(&self, css: &str) -> String {
+        const PREFIX: &str = "/static/";
+
+        let mut output = String::with_capacity(css.len());
+        let mut rest = css;
+
+        while let Some(start) = rest.find("url(") {
+            let (before, after) = rest.split_at(start + "url(".len());
+            output.push_str(before);
+
+            let quote = if after.starts_with(['\'', '"']) {
+                &after[..1]
+            } else {
+                ""
+            };
+            let inner = &after[quote.len()..];
+
+            let end = inner.find([')', '\'', '"']).unwrap_or(inner.len());
+            match inner[..end].strip_prefix(PREFIX) {
+                Some(file) => {
+                    output.push_str(quote);
+                    output.push_str(&self.url(file));
+                    rest = &inner[end..];
+                }
+                None => rest = after,
+            }
+        }
+
+        output.push_str(rest);
+        output
+    }
+}
+
+/// Errors that can occur while compiling the stylesheet, which only happens in development.
+#[derive(Error, Debug)]
+pub enum CompileStylesheetError {
+    #[cfg(debug_assertions)]
+    #[error("failed to compile stylesheet: {0}")]
+    Compile(String),
+
+    #[cfg(debug_assertions)]
+    #[error("stylesheet compilation panicked")]
+    Panicked,
+}
+
+/// A short hash of some content, which changes whenever the content does.
+pub fn fingerprint(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>()[..HASH_LEN]
+        .to_owned()
+}
@@ -0,0 +1,152 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use camino::Utf8PathBuf;
+use lru::LruCache;
+use maud::html;
+use tracing::{debug, warn};
+
+use crate::state::{finish_html, markdown, markdown_to_html_toc_tagged, Content};
+
+/// The full content of a post or thread entry, which is kept as markdown until someone reads it.
+#[derive(Clone, Debug)]
+pub struct Body {
+    key: BodyKey,
+    markdown: Arc<str>,
+    /// The path the files bundled with the post are served under, if it's a bundle.
+    bundle: Option<Arc<str>>,
+    /// The language inline code is highlighted in when it isn't marked with one.
+    code_language: Option<Arc<str>>,
+}
+
+/// Identifies a body across reloads: if the file hasn't been modified, it renders the same.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct BodyKey {
+    path: Utf8PathBuf,
+    modified: Option<SystemTime>,
+    index: usize,
+}
+
+impl Body {
+    pub fn new(
+        path: Utf8PathBuf,
+        modified: Option<SystemTime>,
+        index: usize,
+        markdown: String,
+    ) -> Self {
+        Self {
+            key: BodyKey {
+                path,
+                modified,
+                index,
+            },
+            markdown: markdown.into(),
+            bundle: None,
+            code_language: None,
+        }
+    }
+
+    /// Resolve relative links in the body against `bundle`, if it's the path of a bundle.
+    pub fn in_bundle(self, bundle: Option<String>) -> Self {
+        Self {
+            bundle: bundle.map(Arc::from),
+            ..self
+        }
+    }
+
+    /// Highlight inline code in `code_language` when it isn't marked with a language.
+    pub fn with_code_language(self, code_language: Option<String>) -> Self {
+        Self {
+            code_language: code_language.map(Arc::from),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderedBody {
+    pub html_toc: Option<String>,
+    pub html_content: String,
+}
+
+/// The most recently read bodies, rendered to HTML, so that a large archive doesn't have to be
+/// rendered up front or kept in memory as HTML all at once.
+#[derive(Clone, Debug)]
+pub struct Bodies {
+    rendered: Arc<Mutex<LruCache<BodyKey, Arc<RenderedBody>>>>,
+    max_bytes: usize,
+}
+
+impl Bodies {
+    pub fn new(capacity: NonZeroUsize, max_bytes: usize) -> Self {
+        Self {
+            rendered: Arc::new(Mutex::new(LruCache::new(capacity))),
+            max_bytes,
+        }
+    }
+
+    /// The rendered HTML of `body`, rendering it if it isn't one of the most recently read. That
+    /// can take a while, so pages with bodies on them are rendered with [`render_blocking`].
+    ///
+    /// [`render_blocking`]: crate::state::render_blocking
+    pub fn render(&self, body: &Body) -> Arc<RenderedBody> {
+        if let Some(rendered) = self
+            .rendered
+            .lock()
+            .expect("rendered bodies mutex is not poisoned")
+            .get(&body.key)
+        {
+            return Arc::clone(rendered);
+        }
+
+        debug!(path = %body.key.path, index = %body.key.index, "rendering body");
+
+        // The lock isn't held while rendering, so the same body might occasionally be rendered
+        // twice at once, which is harmless.
+        let html = markdown_to_html_toc_tagged(
+            &body.markdown,
+            &markdown::Context {
+                code_language: body.code_language.as_deref(),
+            },
+        );
+        // Only the first entry of a thread is at the top of the page.
+        let html_content = finish_html(html, body.bundle.as_deref(), body.key.index == 0);
+        let rendered = if html_content.len() > self.max_bytes {
+            warn!(
+                path = %body.key.path,
+                index = %body.key.index,
+                size = %html_content.len(),
+                limit = %self.max_bytes,
+                "body rendered to more HTML than the limit, showing a note instead",
+            );
+            Arc::new(RenderedBody {
+                html_toc: None,
+                html_content: html! {
+                    aside {
+                        p {
+                            "This is too long to show here: it came to " (html_content.len())
+                            " bytes, more than the limit of " (self.max_bytes) "."
+                        }
+                    }
+                }
+                .into_string(),
+            })
+        } else {
+            let html_toc = Content::build_toc_list(&html_content);
+            Arc::new(RenderedBody {
+                html_toc,
+                html_content,
+            })
+        };
+
+        self.rendered
+            .lock()
+            .expect("rendered bodies mutex is not poisoned")
+            .put(body.key.clone(), Arc::clone(&rendered));
+
+        rendered
+    }
+}
@@ -0,0 +1,171 @@
+use std::{fmt, sync::Arc, time::SystemTime};
+
+use camino::Utf8PathBuf;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Nonce,
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::Serialize;
+use tokio::time;
+use tracing::info;
+
+use crate::{
+    crypto::{constant_time_eq, hmac_sha256_hex},
+    state::{Content, RenderCache},
+    warmup::Warmup,
+};
+
+/// Keeps embargoed posts encrypted until they're due to be published, with a key that only ever
+/// exists in memory. An embargoed post isn't part of the content at all until then, so nothing
+/// (not even showing drafts) can serve it early by mistake.
+#[derive(Clone)]
+pub struct Vault {
+    cipher: ChaCha20Poly1305,
+    preview_secret: Option<Arc<str>>,
+}
+
+impl fmt::Debug for Vault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Vault").finish_non_exhaustive()
+    }
+}
+
+impl Default for Vault {
+    fn default() -> Self {
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        Self {
+            cipher: ChaCha20Poly1305::new(&key),
+            preview_secret: None,
+        }
+    }
+}
+
+impl Vault {
+    pub fn with_preview_secret(self, preview_secret: Option<String>) -> Self {
+        Self {
+            preview_secret: preview_secret.map(Arc::from),
+            ..self
+        }
+    }
+
+    pub fn seal(
+        &self,
+        relative_path: Utf8PathBuf,
+        date: NaiveDate,
+        modified: Option<SystemTime>,
+        until: DateTime<Utc>,
+        raw_content: &str,
+    ) -> Sealed {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, raw_content.as_bytes())
+            .expect("encrypting in memory does not fail");
+
+        Sealed {
+            relative_path,
+            date,
+            modified,
+            until,
+            nonce,
+            ciphertext,
+        }
+    }
+
+    pub fn open(&self, sealed: &Sealed) -> String {
+        let raw_content = self
+            .cipher
+            .decrypt(&sealed.nonce, sealed.ciphertext.as_slice())
+            .expect("sealed content was encrypted with this key");
+        String::from_utf8(raw_content).expect("sealed content was valid UTF-8")
+    }
+
+    /// The token that lets an embargoed post at `path` be previewed, if previews are enabled by
+    /// setting a secret. It's an HMAC of the path, keyed with the secret.
+    pub fn preview_token(&self, path: &str) -> Option<String> {
+        let secret = self.preview_secret.as_deref()?;
+        Some(hmac_sha256_hex(secret.as_bytes(), path.as_bytes()))
+    }
+
+    /// Whether `token` is the preview token for the embargoed post at `path`.
+    pub fn is_preview_token(&self, path: &str, token: &str) -> bool {
+        self.preview_token(path)
+            .is_some_and(|expected| constant_time_eq(expected.as_bytes(), token.as_bytes()))
+    }
+}
+
+/// The encrypted source of an embargoed post, along with what's needed to load it once it's
+/// opened.
+#[derive(Clone, Debug)]
+pub struct Sealed {
+    /// The path of the file, relative to the content root and with its extension.
+    pub relative_path: Utf8PathBuf,
+    pub date: NaiveDate,
+    pub modified: Option<SystemTime>,
+    pub until: DateTime<Utc>,
+    nonce: Nonce,
+    ciphertext: Vec<u8>,
+}
+
+/// An embargoed post, as listed in the admin interface.
+#[derive(Clone, Debug, Serialize)]
+pub struct EmbargoedPost {
+    pub path: Utf8PathBuf,
+    pub until: DateTime<Utc>,
+    /// Where the post can be previewed before the embargo ends, if previews are enabled.
+    pub preview: Option<String>,
+}
+
+/// Publish each embargoed post as soon as its embargo ends, and render everything afresh whenever
+/// the date changes (in UTC), for as long as the server runs.
+///
+/// The content doesn't change at midnight, but anything rendered from the date does, so without
+/// the second part cached pages could show yesterday's until something else is edited.
+pub async fn refresh_when_due(content: Content, renders: RenderCache, warmup: Option<Warmup>) {
+    let mut today = Utc::now().date_naive();
+
+    loop {
+        let now = Utc::now();
+
+        let released = content.release_embargoes(now).await;
+        if released > 0 {
+            info!(%released, "published posts whose embargoes ended");
+            content.record_manifest();
+        }
+
+        let rolled_over = now.date_naive() != today;
+        if rolled_over {
+            today = now.date_naive();
+            info!(%today, "date has changed, rendering pages afresh");
+        }
+
+        if released > 0 || rolled_over {
+            renders.invalidate();
+            if let Some(warmup) = &warmup {
+                warmup.spawn(Vec::new());
+            }
+        }
+
+        // Wake up at midnight or when the next embargo ends, whichever is first, or sooner if
+        // another post is sealed in the meantime.
+        let midnight = today
+            .succ_opt()
+            .map(|tomorrow| tomorrow.and_time(NaiveTime::MIN).and_utc());
+        let wake = match (content.next_embargo(), midnight) {
+            (Some(until), Some(midnight)) => Some(until.min(midnight)),
+            (until, midnight) => until.or(midnight),
+        };
+
+        match wake {
+            Some(wake) => {
+                let wait = (wake - Utc::now()).to_std().unwrap_or_default();
+                tokio::select! {
+                    _ = time::sleep(wait) => {}
+                    _ = content.embargoes_changed() => {}
+                }
+            }
+            None => content.embargoes_changed().await,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+/// The shortcodes that can be written as `:name:` in place of an emoji, using the same names as
+/// GitHub and Slack for the ones that are likely to come up.
+const SHORTCODES: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("angry", "😠"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("beers", "🍻"),
+    ("blush", "😊"),
+    ("boom", "💥"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("calendar", "📆"),
+    ("cat", "🐱"),
+    ("check", "✔️"),
+    ("clap", "👏"),
+    ("coffee", "☕"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cry", "😢"),
+    ("dog", "🐶"),
+    ("eyes", "👀"),
+    ("facepalm", "🤦"),
+    ("fire", "🔥"),
+    ("grimacing", "😬"),
+    ("grin", "😁"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("hourglass", "⌛"),
+    ("hugs", "🤗"),
+    ("info", "ℹ️"),
+    ("innocent", "😇"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("memo", "📝"),
+    ("muscle", "💪"),
+    ("neutral_face", "😐"),
+    ("ok_hand", "👌"),
+    ("open_mouth", "😮"),
+    ("package", "📦"),
+    ("party", "🥳"),
+    ("pensive", "😔"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝️"),
+    ("pray", "🙏"),
+    ("question", "❓"),
+    ("rage", "😡"),
+    ("raised_hands", "🙌"),
+    ("rainbow", "🌈"),
+    ("rainbow_flag", "🏳️‍🌈"),
+    ("recycle", "♻️"),
+    ("relaxed", "☺️"),
+    ("relieved", "😌"),
+    ("rocket", "🚀"),
+    ("rofl", "🤣"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("shrug", "🤷"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("smirk", "😏"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("sparkling_heart", "💖"),
+    ("star", "⭐"),
+    ("star_struck", "🤩"),
+    ("stuck_out_tongue", "😛"),
+    ("sunglasses", "😎"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("tired_face", "😫"),
+    ("transgender_flag", "🏳️‍⚧️"),
+    ("trophy", "🏆"),
+    ("unamused", "😒"),
+    ("upside_down_face", "🙃"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("weary", "😩"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("yum", "😋"),
+    ("zap", "⚡"),
+    ("zzz", "💤"),
+];
+
+/// The emoji written as `:name:`, if there is one.
+fn lookup(name: &str) -> Option<&'static str> {
+    SHORTCODES
+        .iter()
+        .find(|(shortcode, _)| *shortcode == name)
+        .map(|(_, emoji)| *emoji)
+}
+
+/// Replace every `:shortcode:` in `text` with its emoji, leaving anything between colons that
+/// isn't a known shortcode (like the time in "at 10:30:00") as it is.
+pub fn replace_shortcodes(text: &str) -> Option<String> {
+    if !text.contains(':') {
+        return None;
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut replaced = false;
+
+    while let Some(start) = rest.find(':') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let emoji = after
+            .find(':')
+            .and_then(|end| lookup(&after[..end]).map(|emoji| (emoji, end)));
+
+        match emoji {
+            Some((emoji, end)) => {
+                output.push_str(emoji);
+                rest = &after[end + 1..];
+                replaced = true;
+            }
+            None => {
+                // The closing colon might open the next shortcode, so only this one is consumed.
+                output.push(':');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+
+    replaced.then_some(output)
+}
@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use camino::Utf8PathBuf;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    build_info,
+    config::EventsConfig,
+    state::{visibility, Node, Snapshot},
+};
+
+/// How long to wait for the webhook to respond before giving up on an event.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Something that happened to a post or page, as sent to the webhook.
+#[derive(Clone, Debug, Serialize)]
+pub struct ContentEvent {
+    /// The node's path relative to the content root, without an extension, which is the same key
+    /// it's served and listed under.
+    pub key: Utf8PathBuf,
+    pub kind: NodeKind,
+    pub change: Change,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Post,
+    Page,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Added,
+    Updated,
+    Removed,
+}
+
+impl ContentEvent {
+    /// Everything that's changed between two snapshots of the content, as readers see it: drafts
+    /// don't exist as far as this is concerned, so a post that's turned into a draft counts as
+    /// removed, and one that stops being a draft counts as added.
+    pub fn between(previous: &Snapshot, current: &Snapshot, timestamp: DateTime<Utc>) -> Vec<Self> {
+        let mut keys = previous
+            .nodes
+            .keys()
+            .chain(current.nodes.keys())
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter_map(|key| {
+                let (kind, change) = match (published(previous, key), published(current, key)) {
+                    (None, Some((kind, _))) => (kind, Change::Added),
+                    (Some((kind, _)), None) => (kind, Change::Removed),
+                    (Some((_, before)), Some((kind, after))) if before != after => {
+                        (kind, Change::Updated)
+                    }
+                    _ => return None,
+                };
+
+                Some(Self {
+                    key: key.clone(),
+                    kind,
+                    change,
+                    timestamp,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The kind of node at `key` and the hash of its file, if it's there and not a draft.
+fn published<'a>(
+    snapshot: &'a Snapshot,
+    key: &Utf8PathBuf,
+) -> Option<(NodeKind, Option<&'a String>)> {
+    match snapshot.nodes.get(key)? {
+        Node::Post(post) if visibility::post_is_visible(post, false) => {
+            Some((NodeKind::Post, snapshot.hashes.get(key)))
+        }
+        Node::Post(_) => None,
+        Node::Page(_) => Some((NodeKind::Page, snapshot.hashes.get(key))),
+    }
+}
+
+/// Sends each change to the content to a webhook, one request per event and in the order they
+/// happened, so that automations elsewhere can react without polling the manifest.
+///
+/// Delivery happens in the background and isn't retried, so an event the webhook misses (because
+/// it was down, say) is only logged.
+#[derive(Clone, Debug)]
+pub struct Webhook {
+    events: mpsc::UnboundedSender<ContentEvent>,
+}
+
+impl Webhook {
+    /// Start delivering events to the webhook in `config`, if one is configured.
+    pub fn spawn(config: &EventsConfig) -> Result<Option<Self>, reqwest::Error> {
+        let Some(url) = config.webhook.clone() else {
+            return Ok(None);
+        };
+
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .user_agent(format!("maddie-wtf/{}", build_info::PKG_VERSION))
+            .build()?;
+
+        let (events, rx) = mpsc::unbounded_channel();
+        tokio::spawn(deliver(client, url, config.secret.clone(), rx));
+
+        Ok(Some(Self { events }))
+    }
+
+    pub fn publish(&self, events: Vec<ContentEvent>) {
+        for event in events {
+            if self.events.send(event).is_err() {
+                warn!("webhook delivery has stopped, dropping content event");
+                return;
+            }
+        }
+    }
+}
+
+async fn deliver(
+    client: reqwest::Client,
+    url: Url,
+    secret: Option<String>,
+    mut events: mpsc::UnboundedReceiver<ContentEvent>,
+) {
+    while let Some(event) = events.recv().await {
+        let mut request = client.post(url.clone()).json(&event);
+        if let Some(secret) = &secret {
+            request = request.bearer_auth(secret);
+        }
+
+        match request.send().await.and_then(|res| res.error_for_status()) {
+            Ok(_) => debug!(key = %event.key, change = ?event.change, "delivered content event"),
+            Err(error) => {
+                warn!(
+                    key = %event.key,
+                    change = ?event.change,
+                    %error,
+                    "failed to deliver content event",
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use std::{
+    cell::OnceCell,
+    collections::{BTreeSet, HashMap},
+    fmt::Write as _,
+    fs, io,
+    io::Cursor,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageReader};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::{
+    config::{ImageEncoding, ImagesConfig},
+    state::assets::fingerprint,
+};
+
+/// How hard the AV1 encoder tries, from 1 (slowest) to 10 (fastest).
+#[cfg(feature = "avif-images")]
+const AVIF_SPEED: u8 = 6;
+
+/// The quality of AVIF copies, from 1 to 100.
+#[cfg(feature = "avif-images")]
+const AVIF_QUALITY: u8 = 70;
+
+/// Makes smaller copies of the images that posts and pages show, and offers them to browsers in
+/// `srcset` attributes so that each can pick the smallest that will look right. Copies in other
+/// formats are offered as the sources of a `<picture>`, for browsers that support them.
+///
+/// Originals and copies alike are stored in the cache path under a hash of the original, and served
+/// from there under `/images`. An image is only resized the first time it's rendered after it
+/// changes, even across restarts, and the same image used in several posts (like a screenshot
+/// that's copied into more than one bundle) is only stored and downloaded once.
+pub struct Images {
+    widths: BTreeSet<u32>,
+    sizes: String,
+    formats: Vec<ImageEncoding>,
+    cache_path: Utf8PathBuf,
+    static_path: Utf8PathBuf,
+    content_path: Utf8PathBuf,
+    known: Mutex<HashMap<Utf8PathBuf, Known>>,
+}
+
+/// What's been found out about an image, as of the time it was last modified.
+struct Known {
+    modified: Option<SystemTime>,
+    processed: Option<Arc<Processed>>,
+}
+
+/// The URL an image is stored under, along with its size and copies, if it's in a format that can
+/// be resized.
+struct Processed {
+    url: String,
+    size: Option<(u32, u32)>,
+    /// The width and URL of each smaller copy in the original's format.
+    copies: Vec<(u32, String)>,
+    /// Copies in other formats, at full size as well as smaller ones.
+    sources: Vec<Source>,
+}
+
+struct Source {
+    mime: &'static str,
+    copies: Vec<(u32, String)>,
+}
+
+#[derive(Error, Debug)]
+pub enum ProcessImageError {
+    #[error("failed to read image: {0}")]
+    Read(#[source] io::Error),
+
+    #[error("failed to decode image: {0}")]
+    Decode(#[source] image::ImageError),
+
+    #[error("failed to store image: {0}")]
+    Store(#[source] io::Error),
+
+    #[error("failed to encode resized copy: {0}")]
+    Encode(#[source] image::ImageError),
+}
+
+impl Images {
+    pub fn new(
+        config: &ImagesConfig,
+        static_path: &Utf8Path,
+        content_path: &Utf8Path,
+    ) -> io::Result<Self> {
+        let cache_path = config.cache_path();
+        fs::create_dir_all(&cache_path)?;
+
+        let formats = config
+            .formats
+            .iter()
+            .copied()
+            .filter(|format| {
+                let supported = *format != ImageEncoding::Avif || cfg!(feature = "avif-images");
+                if !supported {
+                    warn!(
+                        "AVIF copies of images are turned on, but this build doesn't include them"
+                    );
+                }
+                supported
+            })
+            .collect();
+
+        Ok(Self {
+            widths: config.widths.iter().copied().filter(|w| *w > 0).collect(),
+            sizes: config.sizes.clone(),
+            formats,
+            cache_path,
+            static_path: static_path.to_owned(),
+            content_path: content_path.to_owned(),
+            known: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Point every `<img>` in some HTML whose source can be found on disk at its stored copy, and
+    /// give it its intrinsic width and height, along with a `srcset` of smaller copies if it's
+    /// wider than any of the configured widths. Images that already have a `srcset` or a width are
+    /// left alone.
+    ///
+    /// Images with copies in other formats are wrapped in a `<picture>` to offer them, unless
+    /// they're already in one (like an image with a dark variant), which can't be nested.
+    pub fn rewrite(&self, html: &str) -> String {
+        let mut output = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(start) = rest.find("<img ") {
+            let Some(len) = rest[start..].find('>') else {
+                break;
+            };
+            let tag = &rest[start..=start + len];
+
+            output.push_str(&rest[..start]);
+            let in_picture = output.rfind("<picture") > output.rfind("</picture>");
+            match self.rewrite_tag(tag, in_picture) {
+                Some(rewritten) => output.push_str(&rewritten),
+                None => output.push_str(tag),
+            }
+            rest = &rest[start + len + 1..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    /// An `<img>` tag pointed at its stored copy, with sizes added and in a `<picture>` if there
+    /// are other formats to offer, if it could be stored.
+    fn rewrite_tag(&self, tag: &str, in_picture: bool) -> Option<String> {
+        if tag.contains(" srcset=\"") || tag.contains(" width=\"") {
+            return None;
+        }
+
+        let src = attribute(tag, "src")?;
+        let processed = self.processed(&self.resolve(src)?)?;
+
+        let tag = tag.replacen(
+            &format!(" src=\"{src}\""),
+            &format!(" src=\"{}\"", processed.url),
+            1,
+        );
+        let tag = tag.strip_suffix('>').unwrap_or(&tag).trim_end();
+        let (tag, self_closing) = match tag.strip_suffix('/') {
+            Some(tag) => (tag.trim_end(), true),
+            None => (tag, false),
+        };
+        let sizes = self.sizes.replace('"', "&quot;");
+
+        let mut rewritten = String::new();
+        let in_picture = in_picture || processed.sources.is_empty();
+        if !in_picture {
+            rewritten.push_str("<picture>");
+            for source in &processed.sources {
+                let _ = write!(
+                    rewritten,
+                    "<source type=\"{}\" srcset=\"{}\" sizes=\"{sizes}\">",
+                    source.mime,
+                    srcset(&source.copies),
+                );
+            }
+        }
+
+        rewritten.push_str(tag);
+        if let Some((width, height)) = processed.size {
+            let _ = write!(rewritten, " width=\"{width}\" height=\"{height}\"");
+        }
+        if let (Some((width, _)), false) = (processed.size, processed.copies.is_empty()) {
+            let mut copies = processed.copies.clone();
+            copies.push((width, processed.url.clone()));
+            let _ = write!(
+                rewritten,
+                " srcset=\"{}\" sizes=\"{sizes}\"",
+                srcset(&copies)
+            );
+        }
+        rewritten.push_str(if self_closing { " />" } else { ">" });
+
+        if !in_picture {
+            rewritten.push_str("</picture>");
+        }
+
+        Some(rewritten)
+    }
+
+    /// The file on disk that an image's URL is served from, if it's in the static path or in a
+    /// post's bundle.
+    fn resolve(&self, src: &str) -> Option<Utf8PathBuf> {
+        let path = src.split(['?', '#']).next()?;
+
+        let (root, relative) = if let Some(file) = path.strip_prefix("/static/") {
+            (&self.static_path, Utf8Path::new(file))
+        } else if let Some(file) = path
+            .strip_prefix("/assets/")
+            .and_then(|rest| rest.split_once('/'))
+            .map(|(_hash, file)| file)
+        {
+            (&self.static_path, Utf8Path::new(file))
+        } else if let Some(file) = path.strip_prefix("/posts/") {
+            let file = Utf8Path::new(file);
+            let in_bundle = file
+                .parent()
+                .is_some_and(|dir| self.content_path.join(dir).join("index.md").is_file());
+            if !in_bundle {
+                return None;
+            }
+            (&self.content_path, file)
+        } else {
+            return None;
+        };
+
+        let plain = relative
+            .components()
+            .all(|component| matches!(component, Utf8Component::Normal(_)));
+        plain.then(|| root.join(relative))
+    }
+
+    /// Where the image at `path` is stored, along with its size and smaller copies, storing it and
+    /// making the copies if that hasn't happened since it last changed. Nothing is returned for
+    /// images that can't be read.
+    fn processed(&self, path: &Utf8Path) -> Option<Arc<Processed>> {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        if let Some(known) = self
+            .known
+            .lock()
+            .expect("known images mutex is not poisoned")
+            .get(path)
+        {
+            if known.modified == modified {
+                return known.processed.clone();
+            }
+        }
+
+        // As with rendered bodies, the lock isn't held while processing, so the same image might
+        // occasionally be processed twice at once, which is harmless.
+        let processed = match self.process(path) {
+            Ok(processed) => Some(Arc::new(processed)),
+            Err(error) => {
+                warn!(%path, %error, "failed to process image");
+                None
+            }
+        };
+
+        self.known
+            .lock()
+            .expect("known images mutex is not poisoned")
+            .insert(
+                path.to_owned(),
+                Known {
+                    modified,
+                    processed: processed.clone(),
+                },
+            );
+        processed
+    }
+
+    fn process(&self, path: &Utf8Path) -> Result<Processed, ProcessImageError> {
+        use ProcessImageError::*;
+
+        let bytes = fs::read(path).map_err(Read)?;
+        let hash = fingerprint(&bytes);
+
+        // Only formats that can be written back out are resized, which leaves out SVGs (which
+        // don't need it) and GIFs (which would lose their animation).
+        let format = ImageFormat::from_path(path).ok().filter(|format| {
+            matches!(
+                format,
+                ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP
+            )
+        });
+        let size = match format {
+            Some(format) => Some(
+                ImageReader::with_format(Cursor::new(&bytes), format)
+                    .into_dimensions()
+                    .map_err(Decode)?,
+            ),
+            None => None,
+        };
+
+        let name = match path.extension() {
+            Some(extension) => format!("{hash}.{}", extension.to_ascii_lowercase()),
+            None => hash.clone(),
+        };
+        if !self.cache_path.join(&name).is_file() {
+            self.store(&name, |partial| fs::write(partial, &bytes).map_err(Store))?;
+            debug!(%path, %name, "stored image");
+        }
+        let url = format!("/images/{name}");
+
+        let (Some(format), Some((width, height))) = (format, size) else {
+            return Ok(Processed {
+                url,
+                size: None,
+                copies: Vec::new(),
+                sources: Vec::new(),
+            });
+        };
+
+        let decoded = OnceCell::new();
+        let mut copies = Vec::new();
+        let mut sources = Vec::new();
+
+        // Copies in the original's format only need to be smaller, since the original itself is
+        // already stored, but copies in other formats are needed at full size too.
+        let encodings = self
+            .formats
+            .iter()
+            .copied()
+            .filter(|encoding| is_worth_converting(format, *encoding))
+            .map(Some);
+        for encoding in [None].into_iter().chain(encodings) {
+            let extension = match encoding {
+                Some(encoding) => extension(encoding),
+                None => format.extensions_str()[0],
+            };
+            let widths = self
+                .widths
+                .iter()
+                .copied()
+                .filter(|target| *target < width)
+                .chain(encoding.map(|_| width));
+
+            let mut urls = Vec::new();
+            for target in widths {
+                let name = if target == width {
+                    format!("{hash}.{extension}")
+                } else {
+                    format!("{hash}-{target}.{extension}")
+                };
+
+                if !self.cache_path.join(&name).is_file() {
+                    let image = match decoded.get() {
+                        Some(image) => image,
+                        None => {
+                            let image = ImageReader::with_format(Cursor::new(&bytes), format)
+                                .decode()
+                                .map_err(Decode)?;
+                            decoded.get_or_init(|| image)
+                        }
+                    };
+
+                    let target_height = u64::from(height) * u64::from(target) / u64::from(width);
+                    let target_height = u32::try_from(target_height).unwrap_or(height).max(1);
+
+                    let resized;
+                    let image = if target < width {
+                        resized = image.resize_exact(target, target_height, FilterType::Lanczos3);
+                        &resized
+                    } else {
+                        image
+                    };
+                    self.store(&name, |partial| {
+                        encode(image, format, encoding, partial).map_err(Encode)
+                    })?;
+
+                    debug!(%path, width = %target, %extension, "made copy of image");
+                }
+
+                urls.push((target, format!("/images/{name}")));
+            }
+
+            match encoding {
+                Some(encoding) => sources.push(Source {
+                    mime: mime(encoding),
+                    copies: urls,
+                }),
+                None => copies = urls,
+            }
+        }
+
+        Ok(Processed {
+            url,
+            size: Some((width, height)),
+            copies,
+            sources,
+        })
+    }
+
+    /// Store a file in the cache path under `name`, writing it under another name first so that a
+    /// half-written file is never served.
+    fn store(
+        &self,
+        name: &str,
+        write: impl FnOnce(&Utf8Path) -> Result<(), ProcessImageError>,
+    ) -> Result<(), ProcessImageError> {
+        let partial = self.cache_path.join(format!(".{name}.partial"));
+        write(&partial)?;
+        fs::rename(&partial, self.cache_path.join(name)).map_err(ProcessImageError::Store)
+    }
+}
+
+/// The value of an attribute in an HTML tag, as written.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+    let len = tag[start..].find('"')?;
+    Some(&tag[start..start + len])
+}
+
+/// A `srcset` offering each of `copies` at its width.
+fn srcset(copies: &[(u32, String)]) -> String {
+    copies
+        .iter()
+        .map(|(width, url)| format!("{url} {width}w"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether converting an image in `format` to `encoding` is likely to make it any smaller.
+fn is_worth_converting(format: ImageFormat, encoding: ImageEncoding) -> bool {
+    match encoding {
+        ImageEncoding::Avif => true,
+        // WebP copies are lossless, which only beats PNG.
+        ImageEncoding::Webp => format == ImageFormat::Png,
+    }
+}
+
+fn extension(encoding: ImageEncoding) -> &'static str {
+    match encoding {
+        ImageEncoding::Avif => "avif",
+        ImageEncoding::Webp => "webp",
+    }
+}
+
+fn mime(encoding: ImageEncoding) -> &'static str {
+    match encoding {
+        ImageEncoding::Avif => "image/avif",
+        ImageEncoding::Webp => "image/webp",
+    }
+}
+
+/// Write an image to `path`, in `encoding` if there is one or in its original `format` otherwise.
+fn encode(
+    image: &DynamicImage,
+    format: ImageFormat,
+    encoding: Option<ImageEncoding>,
+    path: &Utf8Path,
+) -> image::ImageResult<()> {
+    let Some(encoding) = encoding else {
+        return image.save_with_format(path, format);
+    };
+
+    // Neither encoder handles every colour type, but they both handle 8-bit RGB(A).
+    let image = if image.color().has_alpha() {
+        DynamicImage::from(image.to_rgba8())
+    } else {
+        DynamicImage::from(image.to_rgb8())
+    };
+
+    match encoding {
+        ImageEncoding::Webp => image.save_with_format(path, ImageFormat::WebP),
+        ImageEncoding::Avif => encode_avif(&image, path),
+    }
+}
+
+#[cfg(feature = "avif-images")]
+fn encode_avif(image: &DynamicImage, path: &Utf8Path) -> image::ImageResult<()> {
+    use image::codecs::avif::AvifEncoder;
+
+    let file = io::BufWriter::new(fs::File::create(path)?);
+    image.write_with_encoder(AvifEncoder::new_with_speed_quality(
+        file,
+        AVIF_SPEED,
+        AVIF_QUALITY,
+    ))
+}
+
+/// AVIF copies are never asked for without the `avif-images` feature, since they're filtered out
+/// of the formats when the config is loaded, so this only exists to keep the match exhaustive.
+#[cfg(not(feature = "avif-images"))]
+fn encode_avif(image: &DynamicImage, path: &Utf8Path) -> image::ImageResult<()> {
+    image.save_with_format(path, ImageFormat::Avif)
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use camino::Utf8PathBuf;
+use chrono::NaiveDate;
+
+use crate::state::{names::TagName, visibility, Node, Post};
+
+/// The posts and entries the list pages show, sorted and filtered once whenever the content
+/// changes rather than on every request. There's one index for readers who can see drafts, and one
+/// for readers who can't.
+#[derive(Debug, Default)]
+pub struct Indexes {
+    with_drafts: Index,
+    without_drafts: Index,
+}
+
+impl Indexes {
+    pub fn build(nodes: &HashMap<Utf8PathBuf, Node>) -> Self {
+        Self {
+            with_drafts: Index::build(nodes, true),
+            without_drafts: Index::build(nodes, false),
+        }
+    }
+
+    pub fn get(&self, show_drafts: bool) -> &Index {
+        if show_drafts {
+            &self.with_drafts
+        } else {
+            &self.without_drafts
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Index {
+    /// Every visible post, oldest first by the date it was originally posted.
+    pub posts: Vec<IndexedPost>,
+
+    /// Every visible entry, with single posts counting as one entry, oldest first by the date it
+    /// was last updated.
+    pub chrono: Vec<IndexedEntry>,
+
+    /// The visible posts with each tag, in the same order as `posts`.
+    pub tags: BTreeMap<TagName, Vec<IndexedPost>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexedPost {
+    pub path: Utf8PathBuf,
+    pub post: Arc<Post>,
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexedEntry {
+    pub path: Utf8PathBuf,
+    pub post: Arc<Post>,
+    /// The index of the entry within its thread, which is always 0 for single posts.
+    pub index: usize,
+    /// Whether to link to the entry on its own, rather than to the whole post.
+    pub display_as_entry: bool,
+}
+
+impl IndexedEntry {
+    pub fn date_updated(&self) -> NaiveDate {
+        match &*self.post {
+            Post::Single { metadata, .. } => metadata.updated.unwrap_or(metadata.date),
+            Post::Thread { entries, .. } => {
+                let metadata = &entries[self.index].metadata;
+                metadata.updated.unwrap_or(metadata.date)
+            }
+        }
+    }
+}
+
+impl Index {
+    fn build(nodes: &HashMap<Utf8PathBuf, Node>, show_drafts: bool) -> Self {
+        let mut posts = Vec::new();
+        let mut chrono = Vec::new();
+
+        for (path, node) in nodes {
+            let Node::Post(post) = node else {
+                continue;
+            };
+
+            if !visibility::post_is_visible(post, show_drafts) {
+                continue;
+            }
+
+            posts.push(IndexedPost {
+                path: path.clone(),
+                post: Arc::clone(post),
+            });
+
+            match &**post {
+                Post::Single { .. } => chrono.push(IndexedEntry {
+                    path: path.clone(),
+                    post: Arc::clone(post),
+                    index: 0,
+                    display_as_entry: false,
+                }),
+                Post::Thread { entries, .. } => {
+                    let display_as_entry = visibility::entries_are_linkable(entries, show_drafts);
+                    let visible = visibility::visible_entries(entries, show_drafts);
+                    chrono.extend((0..visible.len()).map(|index| IndexedEntry {
+                        path: path.clone(),
+                        post: Arc::clone(post),
+                        index,
+                        display_as_entry,
+                    }));
+                }
+            }
+        }
+
+        // Ties are broken by path, so that the order doesn't depend on the order of the map.
+        posts.sort_by(|a, b| (a.post.date_posted(), &a.path).cmp(&(b.post.date_posted(), &b.path)));
+        chrono.sort_by(|a, b| {
+            (a.date_updated(), &a.path, a.index).cmp(&(b.date_updated(), &b.path, b.index))
+        });
+
+        let mut tags = BTreeMap::<TagName, Vec<IndexedPost>>::new();
+        for post in &posts {
+            for tag in post.post.tags() {
+                tags.entry(tag.clone()).or_default().push(post.clone());
+            }
+        }
+
+        Self {
+            posts,
+            chrono,
+            tags,
+        }
+    }
+}
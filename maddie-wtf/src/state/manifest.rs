@@ -0,0 +1,150 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::{assets::fingerprint, Node, Snapshot};
+
+/// Every URL a post, entry or page is served at, along with a fingerprint of the file it comes
+/// from.
+///
+/// The URLs served from the same file all share its fingerprint, so a change to one entry in a
+/// thread shows up as a change to every entry in it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Manifest {
+    /// A fingerprint of the whole manifest, to pass back later as the one to compare against.
+    pub hash: String,
+    pub urls: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub fn build(snapshot: &Snapshot, show_drafts: bool) -> Self {
+        let file_hash = |path| {
+            snapshot
+                .hashes
+                .get(path)
+                .cloned()
+                .expect("every node has the hash of its file")
+        };
+
+        let index = snapshot.index(show_drafts);
+        let mut urls = BTreeMap::new();
+
+        for post in &index.posts {
+            urls.insert(format!("/posts/{}", post.path), file_hash(&post.path));
+        }
+
+        for entry in index.chrono.iter().filter(|entry| entry.display_as_entry) {
+            urls.insert(
+                format!("/posts/{}/entry/{}", entry.path, entry.index),
+                file_hash(&entry.path),
+            );
+        }
+
+        for (path, node) in &snapshot.nodes {
+            if let Node::Page(_) = node {
+                // The `_index` page is the root of the site.
+                let url = if path == "_index" {
+                    "/".to_owned()
+                } else {
+                    format!("/{path}")
+                };
+                urls.insert(url, file_hash(path));
+            }
+        }
+
+        let hash = fingerprint(
+            urls.iter()
+                .map(|(url, hash)| format!("{url} {hash}\n"))
+                .collect::<String>()
+                .as_bytes(),
+        );
+
+        Self { hash, urls }
+    }
+
+    /// The URLs that have been added, changed or removed since `since`.
+    pub fn diff(&self, since: &Manifest) -> ManifestDiff {
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+
+        for (url, hash) in &self.urls {
+            match since.urls.get(url) {
+                None => added.push(url.clone()),
+                Some(previous) if previous != hash => changed.push(url.clone()),
+                Some(_) => {}
+            }
+        }
+
+        let removed = since
+            .urls
+            .keys()
+            .filter(|url| !self.urls.contains_key(*url))
+            .cloned()
+            .collect();
+
+        ManifestDiff {
+            since: since.hash.clone(),
+            current: self.hash.clone(),
+            added,
+            changed,
+            removed,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestDiff {
+    pub since: String,
+    pub current: String,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// The snapshots the content has recently been in, newest last, so that the manifest of any of
+/// them can be compared against the current one.
+#[derive(Clone, Debug)]
+pub struct Manifests {
+    snapshots: Arc<Mutex<VecDeque<Arc<Snapshot>>>>,
+    capacity: usize,
+}
+
+impl Manifests {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            snapshots: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Remember `snapshot` as the newest state, returning the one that was newest before it.
+    pub fn record(&self, snapshot: Arc<Snapshot>) -> Option<Arc<Snapshot>> {
+        let mut snapshots = self
+            .snapshots
+            .lock()
+            .expect("manifest history mutex is not poisoned");
+        let previous = snapshots.back().cloned();
+        if snapshots.len() == self.capacity {
+            snapshots.pop_front();
+        }
+        snapshots.push_back(snapshot);
+        previous
+    }
+
+    /// The manifest with the given hash, if the content has been in that state recently enough
+    /// for it to still be remembered.
+    pub fn find(&self, hash: &str, show_drafts: bool) -> Option<Manifest> {
+        let snapshots = self
+            .snapshots
+            .lock()
+            .expect("manifest history mutex is not poisoned");
+        snapshots
+            .iter()
+            .rev()
+            .map(|snapshot| Manifest::build(snapshot, show_drafts))
+            .find(|manifest| manifest.hash == hash)
+    }
+}
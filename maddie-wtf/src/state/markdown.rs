@@ -0,0 +1,1162 @@
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, Write},
+    num::NonZeroUsize,
+    ops::RangeInclusive,
+    process::{Command, ExitStatus, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::Instant,
+};
+
+use comrak::{
+    adapters::SyntaxHighlighterAdapter,
+    arena_tree::Node,
+    format_html_with_plugins,
+    nodes::{Ast, AstNode, NodeHtmlBlock, NodeValue},
+    parse_document,
+    plugins::syntect::SyntectAdapter,
+    Arena, ComrakOptions, ComrakPlugins,
+};
+use lazy_static::lazy_static;
+use lru::LruCache;
+use maud::{html, Markup, PreEscaped};
+use thiserror::Error;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::{
+    config::{DiagramRendering, DiagramsConfig, ExternalLinksConfig, FiguresConfig},
+    embeds, metric,
+    state::{ansi, assets::fingerprint, emoji, sanitize::Sanitizer},
+};
+
+/// How many rendered diagrams are kept, so that old versions of diagrams that have since been
+/// edited don't build up forever.
+const RENDERED_DIAGRAMS: usize = 256;
+
+lazy_static! {
+    /// The diagrams rendered on the server most recently, keyed by a fingerprint of their language
+    /// and source, so that each is only rendered once however many times its body is. Failures
+    /// are kept too, so that a broken diagram isn't retried until it's changed.
+    static ref DIAGRAMS: Mutex<LruCache<String, Option<Arc<str>>>> =
+        Mutex::new(LruCache::new(
+            NonZeroUsize::new(RENDERED_DIAGRAMS).expect("256 is not zero")
+        ));
+}
+
+/// A step in rendering markdown, which rewrites the parsed document in place before it's turned
+/// into HTML.
+pub trait Pass: Send + Sync {
+    /// What the pass is called in metrics.
+    fn name(&self) -> &'static str;
+
+    fn run<'a>(&self, arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, context: &Context);
+}
+
+/// What a piece of markdown is being rendered as part of, for the passes that depend on it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Context<'c> {
+    /// The language to highlight inline code in when it isn't marked with one.
+    pub code_language: Option<&'c str>,
+}
+
+/// The passes markdown goes through between being parsed and being turned into HTML, run in the
+/// order they were added, with the time each one takes recorded. If it has a sanitizer, the HTML
+/// that was written into the markdown is cleaned at the end.
+#[derive(Default)]
+pub struct Pipeline {
+    passes: Vec<Box<dyn Pass>>,
+    sanitizer: Option<Sanitizer>,
+}
+
+impl Pipeline {
+    pub fn with(mut self, pass: impl Pass + 'static) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    pub fn with_sanitizer(self, sanitizer: Sanitizer) -> Self {
+        Self {
+            sanitizer: Some(sanitizer),
+            ..self
+        }
+    }
+
+    /// Render markdown to HTML, applying the site's own extensions to the syntax along the way.
+    pub fn render(
+        &self,
+        md_input: &str,
+        options: &ComrakOptions,
+        plugins: &ComrakPlugins,
+        context: &Context,
+    ) -> String {
+        let md_input = match &self.sanitizer {
+            Some(sanitizer) => sanitizer.scrub(md_input),
+            None => Cow::Borrowed(md_input),
+        };
+        let arena = Arena::new();
+        let root = parse_document(&arena, &md_input, options);
+        let written = self
+            .sanitizer
+            .as_ref()
+            .map(|sanitizer| (sanitizer, sanitizer.written(root)));
+
+        for pass in &self.passes {
+            let start = Instant::now();
+            pass.run(&arena, root, context);
+            metrics::histogram!(*metric::MARKDOWN_PASS_DURATION, "pass" => pass.name())
+                .record(start.elapsed().as_secs_f64());
+        }
+
+        let added = written
+            .as_ref()
+            .map(|(sanitizer, written)| sanitizer.set_aside(root, written));
+
+        let mut html = Vec::new();
+        format_html_with_plugins(root, options, &mut html, plugins)
+            .expect("writing HTML to a Vec should not fail");
+        let html = String::from_utf8(html).expect("comrak should produce valid UTF-8");
+
+        match written.zip(added) {
+            Some(((sanitizer, _), added)) => {
+                let start = Instant::now();
+                let html = sanitizer.clean(&html, &added);
+                metrics::histogram!(*metric::MARKDOWN_PASS_DURATION, "pass" => "sanitize")
+                    .record(start.elapsed().as_secs_f64());
+                html
+            }
+            None => html,
+        }
+    }
+}
+
+/// Turn images written as `![alt](light.png#light)(dark.png#dark)` into a `<picture>` that shows
+/// the dark variant when the reader prefers a dark colour scheme, and the light one otherwise.
+///
+/// Commonmark parses the dark half as plain text following the image, so that's where it's looked
+/// for.
+pub struct ImageVariants;
+
+impl Pass for ImageVariants {
+    fn name(&self) -> &'static str {
+        "image_variants"
+    }
+
+    fn run<'a>(&self, arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, _: &Context) {
+        pair_image_variants(arena, root);
+    }
+}
+
+/// Render code blocks with the extras asked for in their info string, after the language and a
+/// comma: line numbers with `linenos` (or on every block, if `line_numbers` is set, unless it has
+/// `nolinenos`), and highlighted lines with `hl=` and a list of lines and ranges, like
+/// ```` ```rust,linenos,hl=3-5,9 ````.
+///
+/// Diffs get a class on each added, removed and hunk header line, and a diff of code in another
+/// language can be fenced as (say) `diff+rust` to highlight the code as well. Terminal output fenced
+/// as `ansi` is styled by the escape sequences in it, rather than showing them.
+///
+/// The extras are taken out of every info string either way, so that they're never mistaken for
+/// part of the language.
+pub struct CodeBlocks {
+    pub line_numbers: bool,
+    pub highlighter: &'static SyntectAdapter,
+}
+
+impl Pass for CodeBlocks {
+    fn name(&self) -> &'static str {
+        "code_blocks"
+    }
+
+    fn run<'a>(&self, _: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, _: &Context) {
+        render_code_blocks(root, self.line_numbers, self.highlighter);
+    }
+}
+
+/// Turn an image with a title that's in a paragraph of its own into a figure, captioned with the
+/// title.
+pub struct Figures(pub &'static FiguresConfig);
+
+impl Pass for Figures {
+    fn name(&self) -> &'static str {
+        "figures"
+    }
+
+    fn run<'a>(&self, arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, _: &Context) {
+        wrap_figures(arena, root, self.0);
+    }
+}
+
+/// Turn a paragraph containing nothing but a link to media on a site that can be embedded from
+/// into a preview card.
+pub struct MediaEmbeds;
+
+impl Pass for MediaEmbeds {
+    fn name(&self) -> &'static str {
+        "media_embeds"
+    }
+
+    fn run<'a>(&self, arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, _: &Context) {
+        embed_media_links(arena, root);
+    }
+}
+
+/// Turn code blocks fenced as a diagram language into the diagram they describe.
+pub struct Diagrams(pub &'static DiagramsConfig);
+
+impl Pass for Diagrams {
+    fn name(&self) -> &'static str {
+        "diagrams"
+    }
+
+    fn run<'a>(&self, _: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, _: &Context) {
+        render_diagrams(root, self.0);
+    }
+}
+
+/// Highlight inline code marked with a language straight after it, like `` `let x = 5`{rust} ``, or
+/// in the default language for what's being rendered, if it has one.
+pub struct InlineCode {
+    pub highlighter: &'static SyntectAdapter,
+}
+
+impl Pass for InlineCode {
+    fn name(&self) -> &'static str {
+        "inline_code"
+    }
+
+    fn run<'a>(&self, _: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, context: &Context) {
+        highlight_inline_code(root, self.highlighter, context.code_language);
+    }
+}
+
+/// Replace shortcodes like `:tada:` with the emoji they stand for.
+pub struct EmojiShortcodes;
+
+impl Pass for EmojiShortcodes {
+    fn name(&self) -> &'static str {
+        "emoji_shortcodes"
+    }
+
+    fn run<'a>(&self, _: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, _: &Context) {
+        replace_emoji_shortcodes(root);
+    }
+}
+
+/// Give links to other sites the configured attributes.
+pub struct ExternalLinks {
+    pub config: &'static ExternalLinksConfig,
+    pub base_url: &'static Url,
+}
+
+impl Pass for ExternalLinks {
+    fn name(&self) -> &'static str {
+        "external_links"
+    }
+
+    fn run<'a>(&self, arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, _: &Context) {
+        mark_external_links(arena, root, self.config, self.base_url);
+    }
+}
+
+/// Replace the URL in every `href`, `src` and `srcset` attribute in some rendered HTML with
+/// whatever `rewrite` returns for it, leaving the ones it returns `None` for as they are. Each of
+/// the candidates in a `srcset` is rewritten separately, keeping its width or density.
+///
+/// Comrak always quotes attributes with double quotes, and escapes any that appear in text, so
+/// looking for the attributes by name is enough to find them.
+pub fn rewrite_links(html: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+    const ATTRIBUTES: [&str; 3] = ["href=\"", "src=\"", "srcset=\""];
+
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some((start, attribute)) = ATTRIBUTES
+        .iter()
+        .filter_map(|attribute| rest.find(attribute).map(|start| (start, attribute)))
+        .min()
+    {
+        let value_start = start + attribute.len();
+        let Some(value_len) = rest[value_start..].find('"') else {
+            break;
+        };
+        let value = &rest[value_start..value_start + value_len];
+
+        output.push_str(&rest[..value_start]);
+        if *attribute == "srcset=\"" {
+            let candidates = value
+                .split(',')
+                .map(|candidate| {
+                    let candidate = candidate.trim();
+                    let (url, descriptor) = candidate.split_once(' ').unwrap_or((candidate, ""));
+                    let url = rewrite(url).unwrap_or_else(|| url.to_owned());
+                    match descriptor.trim() {
+                        "" => url,
+                        descriptor => format!("{url} {descriptor}"),
+                    }
+                })
+                .collect::<Vec<_>>();
+            output.push_str(&candidates.join(", "));
+        } else {
+            output.push_str(rewrite(value).as_deref().unwrap_or(value));
+        }
+        rest = &rest[value_start + value_len..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Give every image in some rendered HTML `loading="lazy"` and `decoding="async"`, except for the
+/// loading of the first one if `eager_first` is set. Images that already say how to load are left
+/// alone.
+pub fn lazy_images(html: &str, eager_first: bool) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut first = true;
+
+    while let Some(start) = rest.find("<img ") {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = &rest[start..start + len];
+
+        output.push_str(&rest[..start + "<img".len()]);
+        let lazy = !(first && eager_first);
+        if lazy && !tag.contains(" loading=\"") {
+            output.push_str(" loading=\"lazy\"");
+        }
+        if !tag.contains(" decoding=\"") {
+            output.push_str(" decoding=\"async\"");
+        }
+        rest = &rest[start + "<img".len()..];
+        first = false;
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Make every relative link and image in a bundled post's HTML relative to `base` instead, the
+/// path its bundle's files are served under, since the post itself isn't served from a directory
+/// that a relative path would resolve against.
+pub fn rebase_links(html: &str, base: &str) -> String {
+    rewrite_links(html, |value| {
+        let relative = !value.starts_with(['/', '#', '?']) && Url::parse(value).is_err();
+        relative.then(|| format!("{base}{}", value.trim_start_matches("./")))
+    })
+}
+
+/// Turn images written as `![alt](light.png#light)(dark.png#dark)` into a `<picture>` that shows
+/// the dark variant when the reader prefers a dark colour scheme, and the light one otherwise.
+///
+/// Commonmark parses the dark half as plain text following the image, so that's where it's looked
+/// for.
+fn pair_image_variants<'a>(arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>) {
+    let light_images = root
+        .descendants()
+        .filter(|node| {
+            matches!(
+                &node.data.borrow().value,
+                NodeValue::Image(link) if link.url.ends_with("#light")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for image in light_images {
+        let Some(next) = image.next_sibling() else {
+            continue;
+        };
+
+        let (dark_url, rest) = {
+            let NodeValue::Text(text) = &next.data.borrow().value else {
+                continue;
+            };
+            let Some((url, rest)) = split_dark_variant(text) else {
+                continue;
+            };
+            (url.to_owned(), rest.to_owned())
+        };
+
+        let (light_url, title, start) = {
+            let ast = image.data.borrow();
+            let NodeValue::Image(link) = &ast.value else {
+                unreachable!("only image nodes were collected");
+            };
+            let light_url = link.url.trim_end_matches("#light").to_owned();
+            let title = Some(link.title.clone()).filter(|title| !title.is_empty());
+            (light_url, title, ast.sourcepos.start)
+        };
+
+        let picture = picture(&light_url, &dark_url, &alt_text(image), title.as_deref());
+
+        let replacement = arena.alloc(Node::new(RefCell::new(Ast::new(
+            NodeValue::HtmlInline(picture.into_string()),
+            start,
+        ))));
+        image.insert_before(replacement);
+        image.detach();
+
+        if rest.is_empty() {
+            next.detach();
+        } else {
+            next.data.borrow_mut().value = NodeValue::Text(rest);
+        }
+    }
+}
+
+/// The URL in a dark variant written as `(dark.png#dark)` at the start of `text`, along with
+/// whatever follows it.
+fn split_dark_variant(text: &str) -> Option<(&str, &str)> {
+    let (url, rest) = text.strip_prefix('(')?.split_once(')')?;
+    let url = url.trim().strip_suffix("#dark")?;
+    Some((url, rest))
+}
+
+/// An image's alt text, which commonmark parses as its children.
+fn alt_text<'a>(image: &'a AstNode<'a>) -> String {
+    image
+        .descendants()
+        .filter_map(|node| match &node.data.borrow().value {
+            NodeValue::Text(text) => Some(text.clone()),
+            NodeValue::Code(code) => Some(code.literal.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn picture(light_url: &str, dark_url: &str, alt: &str, title: Option<&str>) -> Markup {
+    html! {
+        picture {
+            source srcset=(dark_url) media="(prefers-color-scheme: dark)";
+            img src=(light_url) alt=(alt) title=[title];
+        }
+    }
+}
+
+/// Turn an image with a title, like `![alt](image.png "Caption")`, into a `<figure>` captioned
+/// with the title when it's in a paragraph of its own. Images with light and dark variants are
+/// turned into figures too, so this has to run before they're paired up.
+///
+/// Images in the middle of text, or without a title, are left alone.
+fn wrap_figures<'a>(arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>, config: &FiguresConfig) {
+    let paragraphs = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))
+        .collect::<Vec<_>>();
+
+    for paragraph in paragraphs {
+        let Some(image) = paragraph.first_child() else {
+            continue;
+        };
+
+        let (url, caption) = {
+            let NodeValue::Image(link) = &image.data.borrow().value else {
+                continue;
+            };
+            if link.title.is_empty() {
+                continue;
+            }
+            (link.url.clone(), link.title.clone())
+        };
+
+        let dark_url = match image.next_sibling() {
+            None => None,
+            Some(next) if next.next_sibling().is_none() && url.ends_with("#light") => {
+                let NodeValue::Text(text) = &next.data.borrow().value else {
+                    continue;
+                };
+                match split_dark_variant(text) {
+                    Some((dark_url, rest)) if rest.trim().is_empty() => Some(dark_url.to_owned()),
+                    _ => continue,
+                }
+            }
+            Some(_) => continue,
+        };
+
+        let alt = alt_text(image);
+        let class = Some(&config.class).filter(|class| !class.is_empty());
+        let figure = html! {
+            figure class=[class] {
+                @if let Some(dark_url) = &dark_url {
+                    (picture(url.trim_end_matches("#light"), dark_url, &alt, None))
+                } @else {
+                    img src=(url) alt=(alt);
+                }
+                figcaption { (caption) }
+            }
+        };
+
+        let start = paragraph.data.borrow().sourcepos.start;
+        let replacement = arena.alloc(Node::new(RefCell::new(Ast::new(
+            NodeValue::HtmlBlock(NodeHtmlBlock {
+                block_type: 6,
+                literal: figure.into_string(),
+            }),
+            start,
+        ))));
+        paragraph.insert_before(replacement);
+        paragraph.detach();
+    }
+}
+
+/// Turn a paragraph containing nothing but a link to media on a site that can be embedded from,
+/// like `<https://www.youtube.com/watch?v=...>`, into a preview card.
+///
+/// The card is served by the site itself, so readers' browsers don't talk to the provider until
+/// they choose to play the media.
+fn embed_media_links<'a>(arena: &'a Arena<AstNode<'a>>, root: &'a AstNode<'a>) {
+    let paragraphs = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))
+        .collect::<Vec<_>>();
+
+    for paragraph in paragraphs {
+        let Some(link) = paragraph
+            .first_child()
+            .filter(|link| link.next_sibling().is_none())
+        else {
+            continue;
+        };
+
+        let url = {
+            let NodeValue::Link(link) = &link.data.borrow().value else {
+                continue;
+            };
+            let Ok(url) = Url::parse(&link.url) else {
+                continue;
+            };
+            url
+        };
+
+        let Some(card) = embeds::card_frame(&url) else {
+            continue;
+        };
+
+        let start = paragraph.data.borrow().sourcepos.start;
+        let replacement = arena.alloc(Node::new(RefCell::new(Ast::new(
+            NodeValue::HtmlBlock(NodeHtmlBlock {
+                // Type 6 is the kind of HTML block that starts with a block-level tag.
+                block_type: 6,
+                literal: card.into_string(),
+            }),
+            start,
+        ))));
+        paragraph.insert_before(replacement);
+        paragraph.detach();
+    }
+}
+
+fn highlight_inline_code<'a>(
+    root: &'a AstNode<'a>,
+    highlighter: &SyntectAdapter,
+    default_language: Option<&str>,
+) {
+    let spans = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Code(_)))
+        .collect::<Vec<_>>();
+
+    for span in spans {
+        let marked = span.next_sibling().and_then(take_language_mark);
+        let Some(language) = marked.as_deref().or(default_language) else {
+            continue;
+        };
+
+        let mut ast = span.data.borrow_mut();
+        let NodeValue::Code(code) = &ast.value else {
+            unreachable!("only inline code was collected");
+        };
+
+        let mut highlighted = Vec::new();
+        if let Err(error) =
+            highlighter.write_highlighted(&mut highlighted, Some(language), &code.literal)
+        {
+            warn!(%language, %error, "failed to highlight inline code, leaving it plain");
+            continue;
+        }
+
+        let html = html! {
+            code class={ "language-" (language) " syntax-highlighting" } {
+                (PreEscaped(String::from_utf8_lossy(&highlighted)))
+            }
+        };
+        ast.value = NodeValue::HtmlInline(html.into_string());
+    }
+}
+
+/// Take a language mark like `{rust}` off the start of the text after some inline code, returning
+/// the language.
+fn take_language_mark<'a>(node: &'a AstNode<'a>) -> Option<String> {
+    let mut ast = node.data.borrow_mut();
+    let NodeValue::Text(text) = &mut ast.value else {
+        return None;
+    };
+
+    let (language, rest) = text.strip_prefix('{')?.split_once('}')?;
+    let is_language = |c: char| c.is_ascii_alphanumeric() || "+-_#.".contains(c);
+    if language.is_empty() || !language.chars().all(is_language) {
+        return None;
+    }
+
+    let language = language.to_owned();
+    *text = rest.to_owned();
+    Some(language)
+}
+
+/// Replace shortcodes like `:tada:` with the emoji they stand for. Only plain text is touched, so
+/// code spans, code blocks and URLs keep their colons.
+fn replace_emoji_shortcodes<'a>(root: &'a AstNode<'a>) {
+    for node in root.descendants() {
+        let mut ast = node.data.borrow_mut();
+        let NodeValue::Text(text) = &ast.value else {
+            continue;
+        };
+        if let Some(replaced) = emoji::replace_shortcodes(text) {
+            ast.value = NodeValue::Text(replaced);
+        }
+    }
+}
+
+/// Give links to other sites `rel="noopener noreferrer"`, along with the configured class and
+/// target.
+///
+/// Comrak has no way to add attributes to a link, so each one is swapped for its opening and
+/// closing tags as raw HTML, with the link text left between them.
+fn mark_external_links<'a>(
+    arena: &'a Arena<AstNode<'a>>,
+    root: &'a AstNode<'a>,
+    config: &ExternalLinksConfig,
+    base_url: &Url,
+) {
+    let links = root
+        .descendants()
+        .filter(|node| {
+            let NodeValue::Link(link) = &node.data.borrow().value else {
+                return false;
+            };
+            Url::parse(&link.url).is_ok_and(|url| {
+                matches!(url.scheme(), "http" | "https") && url.host() != base_url.host()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for link in links {
+        let (open, start, end) = {
+            let ast = link.data.borrow();
+            let NodeValue::Link(target) = &ast.value else {
+                unreachable!("only links were collected");
+            };
+            let title = Some(&target.title).filter(|title| !title.is_empty());
+            let class = Some(&config.class).filter(|class| !class.is_empty());
+            let anchor = html! {
+                a
+                    href=(target.url)
+                    title=[title]
+                    class=[class]
+                    rel="noopener noreferrer"
+                    target=[config.new_tab.then_some("_blank")] {}
+            };
+            let open = anchor
+                .into_string()
+                .strip_suffix("</a>")
+                .expect("an empty anchor ends with its closing tag")
+                .to_owned();
+            (open, ast.sourcepos.start, ast.sourcepos.end)
+        };
+
+        let inline = |html: &str, position| {
+            arena.alloc(Node::new(RefCell::new(Ast::new(
+                NodeValue::HtmlInline(html.to_owned()),
+                position,
+            ))))
+        };
+
+        link.insert_before(inline(&open, start));
+        while let Some(child) = link.first_child() {
+            link.insert_before(child);
+        }
+        link.insert_before(inline("</a>", end));
+        link.detach();
+    }
+}
+
+/// Turn code blocks fenced as a diagram language into the diagram they describe, either rendered to
+/// SVG here or marked up for a script to render in the browser.
+fn render_diagrams<'a>(root: &'a AstNode<'a>, config: &DiagramsConfig) {
+    if config.render == DiagramRendering::Off {
+        return;
+    }
+
+    let code_blocks = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::CodeBlock(_)))
+        .collect::<Vec<_>>();
+
+    for code_block in code_blocks {
+        let diagram = {
+            let ast = code_block.data.borrow();
+            let NodeValue::CodeBlock(block) = &ast.value else {
+                unreachable!("only code blocks were collected");
+            };
+            let language = block.info.split_whitespace().next().unwrap_or_default();
+
+            match config.render {
+                DiagramRendering::Off => None,
+                DiagramRendering::Server => config
+                    .commands
+                    .get(language)
+                    .and_then(|command| render_diagram(language, command, &block.literal))
+                    .map(|svg| {
+                        html! {
+                            figure class="diagram" {
+                                (PreEscaped(svg))
+                            }
+                        }
+                    }),
+                DiagramRendering::Client => config.scripts.get(language).map(|script| {
+                    html! {
+                        pre class=(format!("diagram {language}")) {
+                            (block.literal)
+                        }
+                        script type="module" src=(script) {}
+                    }
+                }),
+            }
+        };
+
+        if let Some(diagram) = diagram {
+            code_block.data.borrow_mut().value = NodeValue::HtmlBlock(NodeHtmlBlock {
+                block_type: 6,
+                literal: diagram.into_string(),
+            });
+        }
+    }
+}
+
+/// Take the extras out of each code block's info string, and render those that asked for any.
+///
+/// Line numbers are kept in a separate `<pre>` rather than at the start of each line, so that
+/// selecting the code never selects them too.
+fn render_code_blocks<'a>(root: &'a AstNode<'a>, line_numbers: bool, highlighter: &SyntectAdapter) {
+    let code_blocks = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::CodeBlock(_)))
+        .collect::<Vec<_>>();
+
+    for code_block in code_blocks {
+        let rendered = {
+            let mut ast = code_block.data.borrow_mut();
+            let NodeValue::CodeBlock(block) = &mut ast.value else {
+                unreachable!("only code blocks were collected");
+            };
+
+            let (info, extras) = CodeBlockExtras::parse(&block.info, line_numbers);
+            block.info = info;
+
+            let language = block.info.split_whitespace().next();
+            let diff = language.and_then(Diff::from_language);
+            let plain = !extras.numbered && extras.highlighted.is_empty() && diff.is_none();
+            if !block.fenced || (plain && language != Some("ansi")) {
+                continue;
+            }
+
+            let code = match highlight(highlighter, language, &block.literal, &extras, diff) {
+                Ok(code) => code,
+                Err(error) => {
+                    warn!(%error, "failed to highlight code block, leaving it as it is");
+                    continue;
+                }
+            };
+
+            if extras.numbered {
+                let lines = block.literal.lines().count().max(1);
+                html! {
+                    div class="line-numbers" {
+                        pre class="gutter" aria-hidden="true" {
+                            @for line in 1..=lines {
+                                @if extras.is_highlighted(line) {
+                                    span class="line-highlight" { (line) "\n" }
+                                } @else {
+                                    (line) "\n"
+                                }
+                            }
+                        }
+                        (PreEscaped(code))
+                    }
+                }
+            } else {
+                PreEscaped(code)
+            }
+        };
+
+        code_block.data.borrow_mut().value = NodeValue::HtmlBlock(NodeHtmlBlock {
+            block_type: 6,
+            literal: rendered.into_string(),
+        });
+    }
+}
+
+/// What a code block asked for in its info string, besides its language.
+struct CodeBlockExtras {
+    numbered: bool,
+    highlighted: Vec<RangeInclusive<usize>>,
+}
+
+impl CodeBlockExtras {
+    /// The extras in an info string, along with the info string without them. Lines to highlight
+    /// carry on after `hl=` for as long as each part is a line number or a range of them.
+    fn parse(info: &str, numbered: bool) -> (String, Self) {
+        let mut extras = Self {
+            numbered,
+            highlighted: Vec::new(),
+        };
+
+        let (first, rest) = info.split_once(char::is_whitespace).unwrap_or((info, ""));
+        let mut language = Vec::new();
+        let mut in_highlights = false;
+        for part in first.split(',') {
+            let lines = part.strip_prefix("hl=").or(in_highlights.then_some(part));
+            if let Some(lines) = lines.and_then(parse_line_range) {
+                extras.highlighted.push(lines);
+                in_highlights = true;
+                continue;
+            }
+
+            in_highlights = false;
+            match part {
+                "linenos" => extras.numbered = true,
+                "nolinenos" => extras.numbered = false,
+                part => language.push(part),
+            }
+        }
+
+        let language = language.join(",");
+        let info = match rest {
+            "" => language,
+            rest => format!("{language} {rest}"),
+        };
+        (info, extras)
+    }
+
+    fn is_highlighted(&self, line: usize) -> bool {
+        self.highlighted.iter().any(|lines| lines.contains(&line))
+    }
+}
+
+/// A line number like `9`, or a range of them like `3-5`.
+fn parse_line_range(lines: &str) -> Option<RangeInclusive<usize>> {
+    match lines.split_once('-') {
+        Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+        None => lines.parse().ok().map(|line| line..=line),
+    }
+}
+
+/// A code block that's a diff, and the language of the code being changed, if it's given.
+#[derive(Clone, Copy)]
+struct Diff<'a> {
+    code_language: Option<&'a str>,
+}
+
+impl<'a> Diff<'a> {
+    fn from_language(language: &'a str) -> Option<Self> {
+        match language.strip_prefix("diff")? {
+            "" => Some(Self {
+                code_language: None,
+            }),
+            rest => Some(Self {
+                code_language: Some(rest.strip_prefix('+')?),
+            }),
+        }
+    }
+}
+
+/// What a line of a diff does, going by how it starts.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DiffLine {
+    Added,
+    Removed,
+    /// A hunk header, or one of the file headers before the first hunk.
+    Header,
+    Context,
+}
+
+impl DiffLine {
+    fn classify(lines: &[&str]) -> Vec<Self> {
+        let mut in_hunks = false;
+        lines
+            .iter()
+            .map(|line| {
+                if line.starts_with("@@") {
+                    in_hunks = true;
+                    Self::Header
+                } else if !in_hunks && (line.starts_with("+++ ") || line.starts_with("--- ")) {
+                    Self::Header
+                } else if line.starts_with('+') {
+                    Self::Added
+                } else if line.starts_with('-') {
+                    Self::Removed
+                } else {
+                    Self::Context
+                }
+            })
+            .collect()
+    }
+
+    fn class(self) -> Option<&'static str> {
+        match self {
+            Self::Added => Some("line-added"),
+            Self::Removed => Some("line-removed"),
+            Self::Header => Some("line-hunk"),
+            Self::Context => None,
+        }
+    }
+}
+
+/// A line of a diff without the character that says whether it was added or removed.
+fn without_marker(line: &str) -> &str {
+    line.char_indices()
+        .nth(1)
+        .map_or("", |(start, _)| &line[start..])
+}
+
+/// Highlighted code, wrapped in the same `<pre>` and `<code>` as comrak would wrap it in, with each
+/// of the lines to highlight in a `.line-highlight` span.
+///
+/// A diff of code in another language has the markers at the start of its lines taken off before
+/// it's highlighted (as a whole, since the highlighter needs to see what comes before each line),
+/// and put back on afterwards. Its headers aren't code, so they're left as they are.
+fn highlight(
+    highlighter: &SyntectAdapter,
+    language: Option<&str>,
+    code: &str,
+    extras: &CodeBlockExtras,
+    diff: Option<Diff<'_>>,
+) -> io::Result<String> {
+    let source_lines = code.lines().collect::<Vec<_>>();
+    let diff_lines = diff.map(|_| DiffLine::classify(&source_lines));
+    let code_language = diff.and_then(|diff| diff.code_language);
+
+    let write_highlighted = |language: Option<&str>, code: &str| -> io::Result<String> {
+        let mut highlighted = Vec::new();
+        highlighter.write_highlighted(&mut highlighted, language, code)?;
+        Ok(String::from_utf8_lossy(&highlighted).into_owned())
+    };
+
+    let mut output = Vec::new();
+    highlighter.write_pre_tag(&mut output, HashMap::new())?;
+    let code_attributes = language
+        .map(|language| HashMap::from([("class".to_owned(), format!("language-{language}"))]))
+        .unwrap_or_default();
+    highlighter.write_code_tag(&mut output, code_attributes)?;
+
+    let lines = match (code_language, &diff_lines) {
+        // The old and new versions of the code are highlighted separately, so that (say) a comment
+        // opened on a removed line doesn't carry on through the added lines after it. Each line is
+        // taken from whichever version it belongs to, with the other version's lines left blank.
+        (Some(code_language), Some(diff_lines)) => {
+            let version = |skip: DiffLine| {
+                let mut stripped = String::with_capacity(code.len());
+                for (line, kind) in source_lines.iter().zip(diff_lines) {
+                    if *kind != skip && *kind != DiffLine::Header {
+                        stripped.push_str(without_marker(line));
+                    }
+                    stripped.push('\n');
+                }
+                write_highlighted(Some(code_language), &stripped)
+                    .map(|highlighted| split_highlighted_lines(&highlighted))
+            };
+            let old = version(DiffLine::Added)?;
+            let new = version(DiffLine::Removed)?;
+
+            diff_lines
+                .iter()
+                .zip(&source_lines)
+                .zip(old.into_iter().zip(new))
+                .map(|((kind, source), (old, new))| {
+                    let marker = &source[..source.len() - without_marker(source).len()];
+                    match kind {
+                        DiffLine::Header => format!("{}\n", html! { (source) }.0),
+                        DiffLine::Removed => format!("{}{old}", html! { (marker) }.0),
+                        DiffLine::Added | DiffLine::Context => {
+                            format!("{}{new}", html! { (marker) }.0)
+                        }
+                    }
+                })
+                .collect()
+        }
+        _ => {
+            let highlighted = match language {
+                Some("ansi") => ansi::to_html(code),
+                _ => write_highlighted(language, code)?,
+            };
+            if extras.highlighted.is_empty() && diff.is_none() {
+                output.write_all(highlighted.as_bytes())?;
+                output.write_all(b"</code></pre>")?;
+                return Ok(String::from_utf8_lossy(&output).into_owned());
+            }
+            split_highlighted_lines(&highlighted)
+        }
+    };
+
+    for (index, line) in lines.into_iter().enumerate() {
+        let class = diff_lines
+            .as_ref()
+            .and_then(|kinds| kinds.get(index).copied())
+            .and_then(DiffLine::class);
+        let line = match class {
+            Some(class) => format!("<span class=\"{class}\">{line}</span>"),
+            None => line,
+        };
+
+        if extras.is_highlighted(index + 1) {
+            write!(output, "<span class=\"line-highlight\">{line}</span>")?;
+        } else {
+            output.write_all(line.as_bytes())?;
+        }
+    }
+
+    output.write_all(b"</code></pre>")?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Split highlighted code into lines (each keeping its newline) that can be wrapped on their own.
+///
+/// The highlighter's spans can cross lines, like one around a multi-line comment, so any that are
+/// still open at the end of a line are closed there and opened again at the start of the next.
+fn split_highlighted_lines(html: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    let mut line = String::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if rest.starts_with("<span") {
+            let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+            open.push(&rest[..end]);
+            line.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else if let Some(after) = rest.strip_prefix("</span>") {
+            open.pop();
+            line.push_str("</span>");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('\n') {
+            line.extend(open.iter().map(|_| "</span>"));
+            line.push('\n');
+            lines.push(std::mem::take(&mut line));
+            line.extend(open.iter().copied());
+            rest = after;
+        } else {
+            let end = rest
+                .find(['<', '\n'])
+                .filter(|end| *end > 0)
+                .unwrap_or(rest.len());
+            line.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+
+    // Whatever comes after the last newline is only the ends of spans, which the last line needs.
+    match lines.last_mut() {
+        Some(last) if !open.is_empty() || !line.is_empty() => {
+            let newline = last.pop();
+            last.push_str(&line);
+            last.extend(newline);
+        }
+        None if !line.is_empty() => lines.push(line),
+        _ => {}
+    }
+
+    lines
+}
+
+/// The SVG for a diagram, rendered with `command` unless it's been rendered recently.
+///
+/// This waits for the command to finish, which is why markdown is rendered with
+/// [`render_blocking`](crate::state::render_blocking) wherever it could hold up an async worker.
+fn render_diagram(language: &str, command: &[String], source: &str) -> Option<Arc<str>> {
+    let key = fingerprint(format!("{language}\0{source}").as_bytes());
+
+    if let Some(svg) = DIAGRAMS
+        .lock()
+        .expect("rendered diagrams mutex is not poisoned")
+        .get(&key)
+    {
+        return svg.clone();
+    }
+
+    let svg = match run_diagram_command(command, source) {
+        Ok(svg) => {
+            info!(%language, "rendered diagram");
+            Some(Arc::from(svg))
+        }
+        Err(error) => {
+            warn!(%language, %error, "failed to render diagram, leaving it as code");
+            None
+        }
+    };
+
+    DIAGRAMS
+        .lock()
+        .expect("rendered diagrams mutex is not poisoned")
+        .put(key, svg.clone());
+    svg
+}
+
+fn run_diagram_command(command: &[String], source: &str) -> Result<String, RenderDiagramError> {
+    use RenderDiagramError::*;
+
+    let (program, args) = command.split_first().ok_or(EmptyCommand)?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(Run)?;
+
+    // The source is written from another thread, so that a command that starts printing before
+    // it's read everything can't leave both sides waiting on each other.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let source = source.to_owned();
+    let writer = thread::spawn(move || stdin.write_all(source.as_bytes()));
+
+    let output = child.wait_with_output().map_err(Run)?;
+    let written = writer
+        .join()
+        .expect("writing to a diagram command does not panic");
+
+    // A command that fails early won't have read all of its input, so its exit status says more
+    // about what went wrong than the broken pipe does.
+    if !output.status.success() {
+        return Err(Failed {
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+    written.map_err(Run)?;
+
+    // Anything before the `<svg>` itself, like an XML declaration or a doctype, isn't allowed
+    // inline in HTML.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let start = stdout.find("<svg").ok_or(NotSvg)?;
+    Ok(stdout[start..].trim_end().to_owned())
+}
+
+#[derive(Error, Debug)]
+enum RenderDiagramError {
+    #[error("diagram command is empty")]
+    EmptyCommand,
+
+    #[error("failed to run diagram command: {0}")]
+    Run(#[source] io::Error),
+
+    #[error("diagram command exited with {status}: {stderr}")]
+    Failed { status: ExitStatus, stderr: String },
+
+    #[error("diagram command didn't print an SVG")]
+    NotSvg,
+}
@@ -1,65 +1,80 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{
+    ops::{Deref, Range},
+    sync::Arc,
+};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use chrono::NaiveDate;
 use maud::{html, Markup, PreEscaped, Render};
-use tokio::sync::RwLockReadGuard;
+use url::Url;
 
 use crate::{
+    git::Commit,
     state::{
-        markdown_to_html, names::TagName, Node, Page, Post, SinglePostMetadata, ThreadEntry,
+        bodies::Bodies,
+        continue_reading_id,
+        index::{IndexedEntry, IndexedPost},
+        markdown, markdown_to_html,
+        names::TagName,
+        visibility, Node, Page, Post, SinglePostMetadata, Snapshot, StaleNode, ThreadEntry,
         ThreadEntryMetadata, ThreadMetadata,
     },
     templates::partials,
 };
 
-pub struct PostRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, Post>,
+pub struct PostRef {
+    pub(super) post: Arc<Post>,
     pub(super) path: Utf8PathBuf,
     pub(super) show_drafts: bool,
+    pub(super) bodies: Bodies,
+    pub(super) stale: Option<StaleNode>,
+    /// Where the post's file can be edited, if anywhere.
+    pub(super) edit: Option<String>,
+    /// Where the commits that changed the post's file are listed, if anywhere.
+    pub(super) history: Option<String>,
 }
 
-impl<'a> PostRef<'a> {
-    pub fn into_entry(self, index: usize, show_drafts: bool) -> Option<EntryRef<'a>> {
-        if let Post::Thread { ref entries, .. } = *self {
-            if index < entries.len() {
-                // Ok, we know the entry exists. There are a few more checks to make, though.
-                if !show_drafts && entries[index].metadata.draft {
-                    // Simple: if the entry is a draft and we're not showing drafts, don't show it.
-                    None
-                } else if !show_drafts && entries.iter().filter(|e| !e.metadata.draft).count() == 1
-                {
-                    // If there's only one non-draft entry (regardless of whether there are more!),
-                    // we shouldn't allow this entry to be displayed as an entry (because it will
-                    // confuse readers, and it will leak that there might be more entries coming in
-                    // the future). Just pretend it doesn't exist.
-                    None
-                } else {
-                    Some(EntryRef {
-                        guard: self.guard,
-                        post_path: self.path,
-                        index,
-                    })
-                }
-            } else {
-                None
+impl PostRef {
+    /// The path this post is served at, regardless of the one it was requested with.
+    pub fn canonical_path(&self) -> String {
+        format!("/posts/{}", self.path)
+    }
+
+    pub fn into_entry(self, index: usize, show_drafts: bool) -> Option<EntryRef> {
+        match *self {
+            Post::Thread { ref entries, .. }
+                if visibility::entry_is_visible(entries, index, show_drafts) =>
+            {
+                Some(EntryRef {
+                    post: self.post,
+                    post_path: self.path,
+                    index,
+                    bodies: self.bodies,
+                    stale: self.stale,
+                    edit: self.edit,
+                    history: self.history,
+                })
             }
-        } else {
-            None
+            _ => None,
         }
     }
 }
 
-impl Render for PostRef<'_> {
+impl Render for PostRef {
     fn render(&self) -> Markup {
-        match self.guard.deref() {
+        match self.post.deref() {
             post @ Post::Single {
                 metadata: _,
                 html_summary: _,
-                html_toc,
-                html_content,
+                body,
             } => html! {
+                @let body = self.bodies.render(body);
+
                 main {
+                    @if let Some(stale) = &self.stale {
+                        (partials::stale_notice(stale))
+                    }
+
                     article {
                         (partials::page_title(PreEscaped(post.html_title()), None))
 
@@ -71,20 +86,28 @@ impl Render for PostRef<'_> {
 
                         hr;
 
-                        @if let Some(toc) = html_toc {
+                        @if let Some(ref toc) = body.html_toc {
                             (partials::table_of_contents(PreEscaped(toc.clone())))
 
                             hr;
                         }
 
-                        (PreEscaped(&html_content))
+                        (PreEscaped(&body.html_content))
 
                         @if post.lobsters().is_some()
-                            || post.hacker_news().is_some() {
+                            || post.hacker_news().is_some()
+                            || self.edit.is_some() {
                             hr;
                         }
 
-                        (partials::post_endmatter(post.lobsters(), post.hacker_news()))
+                        (partials::post_endmatter(
+                            post.lobsters(),
+                            post.hacker_news(),
+                            self.edit.as_deref(),
+                            self.history.as_deref(),
+                        ))
+
+                        (partials::see_also(post.see_also()))
                     }
                 }
             },
@@ -93,18 +116,14 @@ impl Render for PostRef<'_> {
                 html_summary: _,
                 entries,
             } => {
-                let mut filtered_entries = vec![];
-                let mut found_draft = false;
-
-                for entry in entries {
-                    found_draft |= entry.metadata.draft;
-                    if self.show_drafts || !found_draft {
-                        filtered_entries.push(entry);
-                    }
-                }
+                let filtered_entries = visibility::visible_entries(entries, self.show_drafts);
 
                 html! {
                     main {
+                        @if let Some(stale) = &self.stale {
+                            (partials::stale_notice(stale))
+                        }
+
                         @let multiple_entries = filtered_entries.len() > 1;
                         @let title_id = if multiple_entries {
                             Some("entry-0")
@@ -152,7 +171,9 @@ impl Render for PostRef<'_> {
                                 (partials::entry_aside(i, &self.path, has_next, has_prev))
                             }
 
-                            @if let Some(toc) = entry.html_toc.as_ref() {
+                            @let body = self.bodies.render(&entry.body);
+
+                            @if let Some(toc) = body.html_toc.as_ref() {
                                 hr;
 
                                 (partials::table_of_contents(PreEscaped(toc.clone())))
@@ -160,16 +181,20 @@ impl Render for PostRef<'_> {
 
                             hr;
 
-                            (PreEscaped(&entry.html_content))
+                            (PreEscaped(&body.html_content))
 
                             @if i == 0 {
-                                @if post.lobsters().is_some() || post.hacker_news().is_some() {
+                                @if post.lobsters().is_some()
+                                    || post.hacker_news().is_some()
+                                    || self.edit.is_some() {
                                     hr;
                                 }
 
                                 (partials::post_endmatter(
                                     post.lobsters(),
                                     post.hacker_news(),
+                                    self.edit.as_deref(),
+                                    self.history.as_deref(),
                                 ))
                             } @else {
                                 @if entry.metadata.lobsters.is_some()
@@ -180,9 +205,85 @@ impl Render for PostRef<'_> {
                                 (partials::post_endmatter(
                                     entry.metadata.lobsters.as_ref(),
                                     entry.metadata.hacker_news.as_ref(),
+                                    None,
+                                    None,
                                 ))
                             }
                         }
+
+                        (partials::see_also(post.see_also()))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The commits that changed the file a post was loaded from.
+pub struct HistoryRef {
+    pub(super) post: PostRef,
+    pub(super) revisions: Vec<Revision>,
+}
+
+pub struct Revision {
+    pub(super) commit: Commit,
+    /// Where the commit can be seen, if anywhere.
+    pub(super) link: Option<String>,
+}
+
+impl Revision {
+    fn short_hash(&self) -> &str {
+        let hash = &self.commit.hash;
+        &hash[..hash.len().min(8)]
+    }
+}
+
+impl HistoryRef {
+    pub fn canonical_path(&self) -> String {
+        format!("{}/history", self.post.canonical_path())
+    }
+
+    pub fn post(&self) -> &PostRef {
+        &self.post
+    }
+}
+
+impl Render for HistoryRef {
+    fn render(&self) -> Markup {
+        html! {
+            main {
+                (partials::page_title(
+                    html! { "History of " (PreEscaped(self.post.html_title())) },
+                    None,
+                ))
+
+                p {
+                    "Every change made to "
+                    a href=(self.post.canonical_path()) { "this post" }
+                    " since it was first written, newest first."
+                }
+
+                @if self.revisions.is_empty() {
+                    p { "This post hasn't been committed yet." }
+                } @else {
+                    ul class="history" {
+                        @for revision in &self.revisions {
+                            li {
+                                time datetime=(revision.commit.date.format("%Y-%m-%d")) {
+                                    (revision.commit.date.format("%d %B %Y"))
+                                }
+                                ": "
+                                (revision.commit.subject)
+                                " ("
+                                @let hash = revision.short_hash();
+                                @if let Some(link) = &revision.link {
+                                    a href=(link) { code { (hash) } }
+                                } @else {
+                                    code { (hash) }
+                                }
+                                ")"
+                            }
+                        }
                     }
                 }
             }
@@ -190,21 +291,30 @@ impl Render for PostRef<'_> {
     }
 }
 
-impl Deref for PostRef<'_> {
+impl Deref for PostRef {
     type Target = Post;
 
     fn deref(&self) -> &Self::Target {
-        self.guard.deref()
+        self.post.deref()
     }
 }
 
-pub struct EntryRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, Post>,
+pub struct EntryRef {
+    pub(super) post: Arc<Post>,
     pub(super) post_path: Utf8PathBuf,
     pub(super) index: usize,
+    pub(super) bodies: Bodies,
+    pub(super) stale: Option<StaleNode>,
+    pub(super) edit: Option<String>,
+    pub(super) history: Option<String>,
 }
 
-impl EntryRef<'_> {
+impl EntryRef {
+    /// The path this entry is served at on its own, regardless of the one it was requested with.
+    pub fn canonical_path(&self) -> String {
+        format!("/posts/{}/entry/{}", self.post_path, self.index)
+    }
+
     pub fn md_title(&self) -> &str {
         self.metadata
             .md_title
@@ -221,18 +331,26 @@ impl EntryRef<'_> {
             .unwrap_or(html)
     }
 
+    pub fn date_posted(&self) -> NaiveDate {
+        self.metadata.date
+    }
+
     pub fn thread_metadata(&self) -> &ThreadMetadata {
-        let Post::Thread { metadata, .. } = self.guard.deref() else {
+        let Post::Thread { metadata, .. } = self.post.deref() else {
             unreachable!()
         };
         metadata
     }
 }
 
-impl Render for EntryRef<'_> {
+impl Render for EntryRef {
     fn render(&self) -> Markup {
         html! {
             main {
+                @if let Some(stale) = &self.stale {
+                    (partials::stale_notice(stale))
+                }
+
                 article {
                     (partials::page_title(PreEscaped(self.html_title()), None))
 
@@ -254,21 +372,27 @@ impl Render for EntryRef<'_> {
 
                     hr;
 
-                    @if let Some(ref toc) = self.html_toc {
+                    @let body = self.bodies.render(&self.body);
+
+                    @if let Some(ref toc) = body.html_toc {
                         (partials::table_of_contents(PreEscaped(toc.clone())))
 
                         hr;
                     }
 
-                    (PreEscaped(&self.html_content))
+                    (PreEscaped(&body.html_content))
 
                     hr;
 
                     (partials::post_endmatter(
                         self.metadata.lobsters.as_ref(),
                         self.metadata.hacker_news.as_ref(),
+                        self.edit.as_deref(),
+                        self.history.as_deref(),
                     ))
 
+                    (partials::see_also(&self.thread_metadata().see_also))
+
                     aside {
                         em {
                             "You're reading a single entry in a longer post. The entire post is available "
@@ -284,134 +408,280 @@ impl Render for EntryRef<'_> {
     }
 }
 
-impl Deref for EntryRef<'_> {
+impl Deref for EntryRef {
     type Target = ThreadEntry;
 
     fn deref(&self) -> &Self::Target {
-        let Post::Thread { entries, .. } = self.guard.deref() else {
+        let Post::Thread { entries, .. } = self.post.deref() else {
             unreachable!()
         };
         &entries[self.index]
     }
 }
 
-pub struct PageRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, Page>,
+pub struct PageRef {
+    pub(super) page: Arc<Page>,
+    pub(super) path: Utf8PathBuf,
+}
+
+impl PageRef {
+    /// The path this page is served at. The `_index` page is the root of the site.
+    pub fn canonical_path(&self) -> String {
+        if self.path == "_index" {
+            "/".to_owned()
+        } else {
+            format!("/{}", self.path)
+        }
+    }
 }
 
-impl Render for PageRef<'_> {
+impl Render for PageRef {
     fn render(&self) -> Markup {
-        let page = self.guard.deref();
+        let page = self.page.deref();
 
         html! {
             @if let Some(title) = page.html_title() {
                 (partials::page_title(PreEscaped(title), None))
             }
+
+            @if let Some(ref toc) = page.html_toc {
+                (partials::table_of_contents(PreEscaped(toc.clone())))
+
+                hr;
+            }
+
             (PreEscaped(&page.html_content))
         }
     }
 }
 
-impl Deref for PageRef<'_> {
+impl Deref for PageRef {
     type Target = Page;
 
     fn deref(&self) -> &Self::Target {
-        self.guard.deref()
+        self.page.deref()
     }
 }
 
-pub struct NodesRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, HashMap<Utf8PathBuf, Node>>,
+pub struct NodesRef {
+    pub(super) snapshot: Arc<Snapshot>,
     pub(super) show_drafts: bool,
 }
 
-impl<'a> NodesRef<'a> {
-    pub fn into_posts(self) -> PostsRef<'a> {
+impl NodesRef {
+    /// All posts that are visible, keyed by their paths.
+    pub fn posts(&self) -> impl Iterator<Item = (&Utf8Path, &Post)> {
+        self.snapshot
+            .nodes
+            .iter()
+            .filter_map(|(path, node)| match node {
+                Node::Post(post) if visibility::post_is_visible(post, self.show_drafts) => {
+                    Some((path.as_path(), &**post))
+                }
+                _ => None,
+            })
+    }
+
+    /// All pages, keyed by their paths.
+    pub fn pages(&self) -> impl Iterator<Item = (&Utf8Path, &Page)> {
+        self.snapshot
+            .nodes
+            .iter()
+            .filter_map(|(path, node)| match node {
+                Node::Page(page) => Some((path.as_path(), &**page)),
+                _ => None,
+            })
+    }
+
+    pub fn into_posts(self) -> PostsRef {
         PostsRef {
-            guard: self.guard,
+            snapshot: self.snapshot,
             show_drafts: self.show_drafts,
         }
     }
 
-    pub fn into_recent_pubs(self) -> RecentPubsRef<'a> {
+    pub fn into_recent_pubs(self) -> RecentPubsRef {
         RecentPubsRef {
-            guard: self.guard,
+            snapshot: self.snapshot,
             show_drafts: self.show_drafts,
         }
     }
 
-    pub fn into_chrono(self) -> ChronoRef<'a> {
+    pub fn into_chrono(self) -> ChronoRef {
         ChronoRef {
-            guard: self.guard,
+            snapshot: self.snapshot,
             show_drafts: self.show_drafts,
         }
     }
 
-    pub fn into_rss_feed(self) -> RssFeedRef<'a> {
-        RssFeedRef {
-            guard: self.guard,
+    /// One of the RSS feeds, with `per_feed` entries in each, or `None` if there's no archive with
+    /// that number.
+    pub fn into_rss_feed(
+        self,
+        base_url: &str,
+        per_feed: usize,
+        page: FeedPage,
+    ) -> Option<RssFeedRef> {
+        let per_feed = per_feed.max(1);
+        let entries = self.snapshot.index(self.show_drafts).chrono.len();
+        let archives = archive_count(entries, per_feed);
+
+        if let FeedPage::Archive(number) = page {
+            if number == 0 || number > archives {
+                return None;
+            }
+        }
+
+        Some(RssFeedRef {
+            snapshot: self.snapshot,
             show_drafts: self.show_drafts,
+            base_url: base_url.to_owned(),
+            per_feed,
+            page,
+            archives,
+        })
+    }
+
+    /// The RSS items a single post will have in the feed, drafts included, or `None` if there's no
+    /// such post.
+    pub fn into_rss_preview(self, path: &str, base_url: &str) -> Option<RssPreviewRef> {
+        let path = Utf8PathBuf::from(path);
+        let exists = self
+            .snapshot
+            .index(true)
+            .posts
+            .iter()
+            .any(|post| post.path == path);
+
+        exists.then(|| RssPreviewRef {
+            snapshot: self.snapshot,
+            path,
+            base_url: base_url.to_owned(),
+        })
+    }
+
+    /// The sitemap, or one of several if there are more than `per_sitemap` URLs to list. `None`
+    /// asks for `/sitemap.xml` itself, which is the index of the others when there are several.
+    pub fn into_sitemap(
+        self,
+        base_url: &str,
+        per_sitemap: usize,
+        number: Option<usize>,
+    ) -> Option<SitemapRef> {
+        let per_sitemap = per_sitemap.max(1);
+        let urls = self.urls(true);
+        let sitemaps = urls.len().div_ceil(per_sitemap);
+
+        if let Some(number) = number {
+            if sitemaps < 2 || number == 0 || number > sitemaps {
+                return None;
+            }
         }
+
+        Some(SitemapRef {
+            urls,
+            base_url: base_url.to_owned(),
+            per_sitemap,
+            number,
+        })
+    }
+
+    /// Every HTML page on the site, along with when it last changed where that's known. For the
+    /// sitemap, posts that search engines are asked not to index are left out.
+    fn urls(&self, for_sitemap: bool) -> Vec<SitemapUrl> {
+        let index = self.snapshot.index(self.show_drafts);
+        let listed = |post: &Post| !for_sitemap || visibility::post_is_indexable(post);
+        let newest = index.chrono.last().map(IndexedEntry::date_updated);
+        let url = |path: String, last_modified| SitemapUrl {
+            path,
+            last_modified,
+        };
+
+        let mut urls = vec![
+            url("/".to_owned(), newest),
+            url("/posts".to_owned(), newest),
+            url("/chrono".to_owned(), newest),
+            url("/tags".to_owned(), newest),
+        ];
+
+        urls.extend(
+            index
+                .posts
+                .iter()
+                .rev()
+                .filter(|IndexedPost { post, .. }| listed(post))
+                .map(|IndexedPost { path, post }| {
+                    url(
+                        format!("/posts/{path}"),
+                        Some(post.date_updated(self.show_drafts)),
+                    )
+                }),
+        );
+
+        urls.extend(
+            index
+                .chrono
+                .iter()
+                .rev()
+                .filter(|entry| entry.display_as_entry && listed(&entry.post))
+                .map(|entry| {
+                    url(
+                        format!("/posts/{}/entry/{}", entry.path, entry.index),
+                        Some(entry.date_updated()),
+                    )
+                }),
+        );
+
+        urls.extend(index.tags.iter().map(|(tag, posts)| {
+            let last_modified = posts
+                .iter()
+                .map(|IndexedPost { post, .. }| post.date_updated(self.show_drafts))
+                .max();
+            url(format!("/tagged/{tag}"), last_modified)
+        }));
+
+        // Pages are only served from the top level, and the index page is already listed as `/`.
+        let mut pages = self
+            .pages()
+            .map(|(path, _)| path)
+            .filter(|path| path.as_str() != "_index" && path.parent() == Some(Utf8Path::new("")))
+            .map(|path| url(format!("/{path}"), None))
+            .collect::<Vec<_>>();
+        pages.sort_by(|a, b| a.path.cmp(&b.path));
+        urls.extend(pages);
+
+        urls
+    }
+
+    /// The path of every HTML page on the site, including any left out of the sitemap.
+    pub fn into_paths(self) -> Vec<String> {
+        self.urls(false).into_iter().map(|url| url.path).collect()
     }
 
-    pub fn into_tags(self) -> TagsRef<'a> {
+    pub fn into_tags(self) -> TagsRef {
         TagsRef {
-            guard: self.guard,
+            snapshot: self.snapshot,
             show_drafts: self.show_drafts,
         }
     }
 
-    pub fn into_tagged(self, tag: TagName) -> TaggedRef<'a> {
+    pub fn into_tagged(self, tag: TagName) -> TaggedRef {
         TaggedRef {
-            guard: self.guard,
+            snapshot: self.snapshot,
             tag,
             show_drafts: self.show_drafts,
         }
     }
 }
 
-pub struct PostsRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, HashMap<Utf8PathBuf, Node>>,
+pub struct PostsRef {
+    pub(super) snapshot: Arc<Snapshot>,
     pub(super) show_drafts: bool,
 }
 
-impl Render for PostsRef<'_> {
+impl Render for PostsRef {
     fn render(&self) -> Markup {
-        let nodes = self.guard.deref();
-        let mut posts = nodes
-            .iter()
-            .filter_map(|(path, node)| {
-                if let Node::Post(post) = node {
-                    Some((path.as_path(), post))
-                } else {
-                    None
-                }
-            })
-            .filter(|(_, post)| {
-                if !self.show_drafts {
-                    // If we're not showing drafts, then filter out the following things:
-                    //
-                    // - Posts that are only a single entry that's a draft
-                    // - Posts that are a thread where we can't display any of the entries (i.e. the
-                    //   first entry is a draft, which implies the following are also drafts)
-                    let is_draft = match post {
-                        Post::Single { metadata, .. } => metadata.draft,
-                        Post::Thread { entries, .. } => {
-                            entries
-                                .first()
-                                .expect("a post cannot have no entries")
-                                .metadata
-                                .draft
-                        }
-                    };
-
-                    !is_draft
-                } else {
-                    true
-                }
-            })
-            .collect::<Vec<_>>();
-        posts.sort_by_key(|(_, post)| post.date_posted());
+        let posts = &self.snapshot.index(self.show_drafts).posts;
 
         html! {
             main {
@@ -425,7 +695,7 @@ impl Render for PostsRef<'_> {
                     "."
                 }
 
-                @for (path, post) in posts.iter().rev() {
+                @for IndexedPost { path, post } in posts.iter().rev() {
                     hr;
 
                     section {
@@ -441,7 +711,7 @@ impl Render for PostsRef<'_> {
                         ))
                         (PreEscaped(post.summary()))
                         p {
-                            a href=(format!("/posts/{}", path)) {
+                            a href=(format!("/posts/{}#{}", path, continue_reading_id(0))) {
                                 "Read more"
                             }
                         }
@@ -452,85 +722,20 @@ impl Render for PostsRef<'_> {
     }
 }
 
-pub struct RecentPubsRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, HashMap<Utf8PathBuf, Node>>,
+pub struct RecentPubsRef {
+    pub(super) snapshot: Arc<Snapshot>,
     pub(super) show_drafts: bool,
 }
 
-impl Render for RecentPubsRef<'_> {
+impl Render for RecentPubsRef {
     fn render(&self) -> Markup {
-        let nodes = self.guard.deref();
-        let mut entries = nodes
-            .iter()
-            .flat_map(|(path, node)| {
-                let mut to_render = vec![];
-                match node {
-                    Node::Post(Post::Single {
-                        metadata,
-                        html_summary,
-                        ..
-                    }) => {
-                        if self.show_drafts || !metadata.draft {
-                            to_render.push(ChronoEntry::Single {
-                                path,
-                                metadata,
-                                html_summary: html_summary.as_str(),
-                            });
-                        }
-                    }
-                    Node::Post(Post::Thread {
-                        metadata, entries, ..
-                    }) => {
-                        let mut entries_to_render = vec![];
-
-                        let mut found_draft = false;
-                        for (i, entry) in entries.iter().enumerate() {
-                            found_draft |= entry.metadata.draft;
-
-                            if self.show_drafts || !found_draft {
-                                entries_to_render.push(ChronoEntry::ThreadEntry {
-                                    post_path: path,
-                                    index: i,
-                                    display_as_entry: true,
-                                    thread_meta: metadata,
-                                    entry_meta: &entry.metadata,
-                                    html_summary: entry.html_summary.as_str(),
-                                });
-                            }
-                        }
-
-                        if found_draft && entries_to_render.len() == 1 {
-                            // Special case! There was more than one entry, but only the first one
-                            // was not a draft. That means that if we display this first entry *as*
-                            // an entry, we'll confuse readers (and tip them off that another entry
-                            // might be coming). We shouldn't link to the entry page, we should
-                            // just link to the main post.
-
-                            let ChronoEntry::ThreadEntry {
-                                ref mut display_as_entry,
-                                ..
-                            } = entries_to_render[0]
-                            else {
-                                unreachable!();
-                            };
-
-                            *display_as_entry = false;
-                        }
-
-                        to_render.extend(entries_to_render);
-                    }
-                    _ => {}
-                }
-                to_render
-            })
-            .collect::<Vec<ChronoEntry>>();
-        entries.sort_by_key(|chrono_entry| chrono_entry.date_updated());
+        let entries = &self.snapshot.index(self.show_drafts).chrono;
 
         html! {
             h1 { "Recent Publications" }
 
             ul {
-                @for entry in entries.iter().rev().take(5) {
+                @for entry in entries.iter().rev().take(5).map(ChronoEntry::from) {
                     li {
                         a href=(entry.path()) {
                             (PreEscaped(entry.html_title()))
@@ -543,8 +748,8 @@ impl Render for RecentPubsRef<'_> {
     }
 }
 
-pub struct ChronoRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, HashMap<Utf8PathBuf, Node>>,
+pub struct ChronoRef {
+    pub(super) snapshot: Arc<Snapshot>,
     pub(super) show_drafts: bool,
 }
 
@@ -564,6 +769,35 @@ enum ChronoEntry<'a> {
     },
 }
 
+impl<'a> From<&'a IndexedEntry> for ChronoEntry<'a> {
+    fn from(entry: &'a IndexedEntry) -> Self {
+        match &*entry.post {
+            Post::Single {
+                metadata,
+                html_summary,
+                ..
+            } => ChronoEntry::Single {
+                path: &entry.path,
+                metadata,
+                html_summary,
+            },
+            Post::Thread {
+                metadata, entries, ..
+            } => {
+                let thread_entry = &entries[entry.index];
+                ChronoEntry::ThreadEntry {
+                    post_path: &entry.path,
+                    index: entry.index,
+                    display_as_entry: entry.display_as_entry,
+                    thread_meta: metadata,
+                    entry_meta: &thread_entry.metadata,
+                    html_summary: &thread_entry.html_summary,
+                }
+            }
+        }
+    }
+}
+
 impl ChronoEntry<'_> {
     fn date_posted(&self) -> NaiveDate {
         match self {
@@ -624,6 +858,13 @@ impl ChronoEntry<'_> {
         }
     }
 
+    fn continue_reading_id(&self) -> String {
+        match self {
+            ChronoEntry::Single { .. } => continue_reading_id(0),
+            ChronoEntry::ThreadEntry { index, .. } => continue_reading_id(*index),
+        }
+    }
+
     fn rss_guid(&self) -> String {
         // RSS GUIDs are a little weird. We're going to pretend that any posts that are a single
         // entry are the first entry in a thread, because (1) the post might become a thread entry
@@ -656,74 +897,9 @@ impl ChronoEntry<'_> {
     }
 }
 
-impl Render for ChronoRef<'_> {
+impl Render for ChronoRef {
     fn render(&self) -> Markup {
-        let nodes = self.guard.deref();
-        let mut entries = nodes
-            .iter()
-            .flat_map(|(path, node)| {
-                let mut to_render = vec![];
-                match node {
-                    Node::Post(Post::Single {
-                        metadata,
-                        html_summary,
-                        ..
-                    }) => {
-                        if self.show_drafts || !metadata.draft {
-                            to_render.push(ChronoEntry::Single {
-                                path,
-                                metadata,
-                                html_summary: html_summary.as_str(),
-                            });
-                        }
-                    }
-                    Node::Post(Post::Thread {
-                        metadata, entries, ..
-                    }) => {
-                        let mut entries_to_render = vec![];
-
-                        let mut found_draft = false;
-                        for (i, entry) in entries.iter().enumerate() {
-                            found_draft |= entry.metadata.draft;
-
-                            if self.show_drafts || !found_draft {
-                                entries_to_render.push(ChronoEntry::ThreadEntry {
-                                    post_path: path,
-                                    index: i,
-                                    display_as_entry: true,
-                                    thread_meta: metadata,
-                                    entry_meta: &entry.metadata,
-                                    html_summary: entry.html_summary.as_str(),
-                                });
-                            }
-                        }
-
-                        if found_draft && entries_to_render.len() == 1 {
-                            // Special case! There was more than one entry, but only the first one
-                            // was not a draft. That means that if we display this first entry *as*
-                            // an entry, we'll confuse readers (and tip them off that another entry
-                            // might be coming). We shouldn't link to the entry page, we should
-                            // just link to the main post.
-
-                            let ChronoEntry::ThreadEntry {
-                                ref mut display_as_entry,
-                                ..
-                            } = entries_to_render[0]
-                            else {
-                                unreachable!();
-                            };
-
-                            *display_as_entry = false;
-                        }
-
-                        to_render.extend(entries_to_render);
-                    }
-                    _ => {}
-                }
-                to_render
-            })
-            .collect::<Vec<ChronoEntry>>();
-        entries.sort_by_key(|chrono_entry| chrono_entry.date_updated());
+        let entries = &self.snapshot.index(self.show_drafts).chrono;
 
         html! {
             main {
@@ -737,7 +913,7 @@ impl Render for ChronoRef<'_> {
                     "."
                 }
 
-                @for entry in entries.iter().rev() {
+                @for entry in entries.iter().rev().map(ChronoEntry::from) {
                     hr;
 
                     section {
@@ -753,7 +929,7 @@ impl Render for ChronoRef<'_> {
                         ))
                         (PreEscaped(entry.summary()))
                         p {
-                            a href=(entry.path()) {
+                            a href=(format!("{}#{}", entry.path(), entry.continue_reading_id())) {
                                 "Read more"
                             }
                         }
@@ -764,155 +940,222 @@ impl Render for ChronoRef<'_> {
     }
 }
 
-pub struct RssFeedRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, HashMap<Utf8PathBuf, Node>>,
+/// Which of the RSS feeds to render: the one readers subscribe to, with the most recent entries, or
+/// one of the archives of older entries, numbered from 1 for the oldest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeedPage {
+    Current,
+    Archive(usize),
+}
+
+impl FeedPage {
+    pub fn path(self) -> String {
+        match self {
+            FeedPage::Current => "/rss.xml".to_owned(),
+            FeedPage::Archive(number) => format!("/rss/archive/{number}.xml"),
+        }
+    }
+}
+
+pub struct RssFeedRef {
+    pub(super) snapshot: Arc<Snapshot>,
     pub(super) show_drafts: bool,
+    pub(super) base_url: String,
+    pub(super) per_feed: usize,
+    pub(super) page: FeedPage,
+    pub(super) archives: usize,
 }
 
-impl Render for RssFeedRef<'_> {
-    fn render(&self) -> Markup {
-        let nodes = self.guard.deref();
-        let mut entries = nodes
-            .iter()
-            .flat_map(|(path, node)| {
-                let mut to_render = vec![];
-                match node {
-                    Node::Post(Post::Single {
-                        metadata,
-                        html_summary,
-                        ..
-                    }) => {
-                        if self.show_drafts || !metadata.draft {
-                            to_render.push(ChronoEntry::Single {
-                                path,
-                                metadata,
-                                html_summary: html_summary.as_str(),
-                            });
-                        }
-                    }
-                    Node::Post(Post::Thread {
-                        metadata, entries, ..
-                    }) => {
-                        let mut entries_to_render = vec![];
-
-                        let mut found_draft = false;
-                        for (i, entry) in entries.iter().enumerate() {
-                            found_draft |= entry.metadata.draft;
-
-                            if self.show_drafts || !found_draft {
-                                entries_to_render.push(ChronoEntry::ThreadEntry {
-                                    post_path: path,
-                                    index: i,
-                                    display_as_entry: true,
-                                    thread_meta: metadata,
-                                    entry_meta: &entry.metadata,
-                                    html_summary: entry.html_summary.as_str(),
-                                });
-                            }
-                        }
+impl RssFeedRef {
+    pub fn page(&self) -> FeedPage {
+        self.page
+    }
 
-                        if found_draft && entries_to_render.len() == 1 {
-                            // Special case! There was more than one entry, but only the first one
-                            // was not a draft. That means that if we display this first entry *as*
-                            // an entry, we'll confuse readers (and tip them off that another entry
-                            // might be coming). We shouldn't link to the entry page, we should
-                            // just link to the main post.
-
-                            let ChronoEntry::ThreadEntry {
-                                ref mut display_as_entry,
-                                ..
-                            } = entries_to_render[0]
-                            else {
-                                unreachable!();
-                            };
-
-                            *display_as_entry = false;
-                        }
+    /// The archive with the entries just before these ones, if there is one.
+    pub fn prev_archive(&self) -> Option<FeedPage> {
+        match self.page {
+            FeedPage::Current => (self.archives > 0).then_some(FeedPage::Archive(self.archives)),
+            FeedPage::Archive(number) => (number > 1).then(|| FeedPage::Archive(number - 1)),
+        }
+    }
 
-                        to_render.extend(entries_to_render);
-                    }
-                    _ => {}
-                }
-                to_render
-            })
-            .collect::<Vec<ChronoEntry>>();
-        entries.sort_by_key(|chrono_entry| chrono_entry.date_updated());
+    /// The archive with the entries just after these ones, if these are in an archive that isn't
+    /// the newest.
+    pub fn next_archive(&self) -> Option<FeedPage> {
+        match self.page {
+            FeedPage::Current => None,
+            FeedPage::Archive(number) => {
+                (number < self.archives).then(|| FeedPage::Archive(number + 1))
+            }
+        }
+    }
+}
+
+impl Render for RssFeedRef {
+    fn render(&self) -> Markup {
+        let entries = &self.snapshot.index(self.show_drafts).chrono;
+        let entries = &entries[feed_range(self.page, self.per_feed, entries.len())];
 
         html! {
-            @for entry in entries.iter().rev() {
-                item {
-                    title {
-                        (PreEscaped(entry.md_title()))
-                    }
-                    pubDate {
-                        (entry.date_posted().format("%a, %d %b %Y 00:00:00 +0000"))
-                    }
-                    link {
-                        (format!("https://maddie.wtf{}", entry.path()))
-                    }
-                    guid isPermaLink="false" {
-                        (entry.rss_guid())
-                    }
-                    description {
-                        (entry.summary().replace('\n', " "))
-                    }
-                }
+            @for entry in entries.iter().rev().map(ChronoEntry::from) {
+                (rss_item(&entry, &self.base_url))
             }
         }
     }
 }
 
-pub struct TagsRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, HashMap<Utf8PathBuf, Node>>,
-    pub(super) show_drafts: bool,
+/// How many archives there are of a feed of `entries` entries, with `per_feed` in each.
+///
+/// Archives are only ever full, and each one is made as soon as the entries in it aren't all in the
+/// current feed any more, so an archive stays the same once it's been made. That means the newest
+/// archive can repeat some of the entries that are still in the current feed (unless the number of
+/// entries is a multiple of `per_feed`), but they have the same GUIDs, so readers only show them
+/// once.
+fn archive_count(entries: usize, per_feed: usize) -> usize {
+    entries.saturating_sub(1) / per_feed
 }
 
-impl Render for TagsRef<'_> {
+/// The range of entries, oldest first, that are in one page of a feed of `entries` entries. The
+/// current feed has the newest `per_feed` of them, and archive `n` has the `n`th `per_feed`
+/// counting from the oldest.
+fn feed_range(page: FeedPage, per_feed: usize, entries: usize) -> Range<usize> {
+    match page {
+        FeedPage::Current => entries.saturating_sub(per_feed)..entries,
+        FeedPage::Archive(number) => (number - 1) * per_feed..number * per_feed,
+    }
+}
+
+/// The RSS items for every entry in a single post, drafts included, exactly as they'd appear in the
+/// feed once published.
+pub struct RssPreviewRef {
+    pub(super) snapshot: Arc<Snapshot>,
+    pub(super) path: Utf8PathBuf,
+    pub(super) base_url: String,
+}
+
+impl RssPreviewRef {
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+}
+
+impl Render for RssPreviewRef {
     fn render(&self) -> Markup {
-        let nodes = self.guard.deref();
+        let entries = &self.snapshot.index(true).chrono;
 
-        let mut tags = HashMap::<TagName, Vec<_>>::new();
+        html! {
+            @for entry in entries.iter().rev().filter(|entry| entry.path == self.path) {
+                (rss_item(&ChronoEntry::from(entry), &self.base_url))
+            }
+        }
+    }
+}
 
-        for (path, post) in nodes
-            .iter()
-            .filter_map(|(path, node)| {
-                if let Node::Post(post) = node {
-                    Some((path.as_path(), post))
-                } else {
-                    None
-                }
-            })
-            .filter(|(_, post)| {
-                if !self.show_drafts {
-                    // If we're not showing drafts, then filter out the following things:
-                    //
-                    // - Posts that are only a single entry that's a draft
-                    // - Posts that are a thread where we can't display any of the entries (i.e. the
-                    //   first entry is a draft, which implies the following are also drafts)
-                    let is_draft = match post {
-                        Post::Single { metadata, .. } => metadata.draft,
-                        Post::Thread { entries, .. } => {
-                            entries
-                                .first()
-                                .expect("a post cannot have no entries")
-                                .metadata
-                                .draft
+fn rss_item(entry: &ChronoEntry<'_>, base_url: &str) -> Markup {
+    let link = format!("{}{}", base_url, entry.path());
+    // Feed readers show the summary away from the site, so links and images in it have to be
+    // absolute to keep working.
+    let summary = match Url::parse(&link) {
+        Ok(link) => absolute_links(entry.summary(), base_url, &link),
+        Err(_) => entry.summary().to_owned(),
+    };
+
+    html! {
+        item {
+            // Titles are plain text in RSS, so any markup in them has to be escaped to keep the
+            // feed valid XML.
+            title {
+                (entry.md_title())
+            }
+            pubDate {
+                (entry.date_posted().format("%a, %d %b %Y 00:00:00 +0000"))
+            }
+            link {
+                (link)
+            }
+            guid isPermaLink="false" {
+                (entry.rss_guid())
+            }
+            description {
+                (summary.replace('\n', " "))
+            }
+        }
+    }
+}
+
+/// Make every relative link, image and source in some rendered HTML absolute. Paths starting with
+/// `/` are relative to the site, so they go after the base URL (which might have a path of its
+/// own), while anything else is resolved against `page`, the URL of the page it's from.
+fn absolute_links(html: &str, base_url: &str, page: &Url) -> String {
+    markdown::rewrite_links(html, |value| {
+        if Url::parse(value).is_ok() {
+            return None;
+        }
+        match value.strip_prefix('/') {
+            Some(path) if !path.starts_with('/') => {
+                Some(format!("{}/{path}", base_url.trim_end_matches('/')))
+            }
+            _ => page.join(value).ok().map(String::from),
+        }
+    })
+}
+
+struct SitemapUrl {
+    path: String,
+    last_modified: Option<NaiveDate>,
+}
+
+pub struct SitemapRef {
+    urls: Vec<SitemapUrl>,
+    base_url: String,
+    per_sitemap: usize,
+    number: Option<usize>,
+}
+
+impl Render for SitemapRef {
+    fn render(&self) -> Markup {
+        let xmlns = "http://www.sitemaps.org/schemas/sitemap/0.9";
+        let sitemaps = self.urls.chunks(self.per_sitemap).collect::<Vec<_>>();
+
+        let urls = match self.number {
+            None if sitemaps.len() > 1 => {
+                return html! {
+                    sitemapindex xmlns=(xmlns) {
+                        @for number in 1..=sitemaps.len() {
+                            sitemap {
+                                loc { (self.base_url) "/sitemaps/" (number) ".xml" }
+                            }
                         }
-                    };
+                    }
+                };
+            }
+            None => sitemaps.first().copied().unwrap_or_default(),
+            Some(number) => sitemaps[number - 1],
+        };
 
-                    !is_draft
-                } else {
-                    true
+        html! {
+            urlset xmlns=(xmlns) {
+                @for url in urls {
+                    url {
+                        loc { (self.base_url) (url.path) }
+                        @if let Some(last_modified) = url.last_modified {
+                            lastmod { (last_modified.format("%Y-%m-%d")) }
+                        }
+                    }
                 }
-            })
-        {
-            for tag in post.tags() {
-                tags.entry(tag.clone()).or_default().push((path, post));
             }
         }
+    }
+}
 
-        let mut tags_list = tags.iter().collect::<Vec<_>>();
-        tags_list.sort_by_key(|(name, _)| *name);
+pub struct TagsRef {
+    pub(super) snapshot: Arc<Snapshot>,
+    pub(super) show_drafts: bool,
+}
+
+impl Render for TagsRef {
+    fn render(&self) -> Markup {
+        let tags = &self.snapshot.index(self.show_drafts).tags;
 
         html! {
             main {
@@ -926,10 +1169,10 @@ impl Render for TagsRef<'_> {
                 hr;
 
                 ul {
-                    @for (tag, posts) in tags_list {
+                    @for (tag, posts) in tags {
                         @let posts_len = posts.len();
                         li {
-                            a href=(format!("/tagged/{}", tag)) {
+                            a href=(format!("/tagged/{}", tag)) data-tag=(tag) {
                                 code { (tag) }
                             }
                             " ("
@@ -948,53 +1191,30 @@ impl Render for TagsRef<'_> {
     }
 }
 
-pub struct TaggedRef<'a> {
-    pub(super) guard: RwLockReadGuard<'a, HashMap<Utf8PathBuf, Node>>,
+pub struct TaggedRef {
+    pub(super) snapshot: Arc<Snapshot>,
     pub(crate) tag: TagName,
     pub(super) show_drafts: bool,
 }
 
-impl Render for TaggedRef<'_> {
-    fn render(&self) -> Markup {
-        let nodes = self.guard.deref();
-        let mut posts = nodes
-            .iter()
-            .filter_map(|(path, node)| {
-                if let Node::Post(post) = node {
-                    Some((path.as_path(), post))
-                } else {
-                    None
-                }
-            })
-            .filter(|(_, post)| {
-                if !self.show_drafts {
-                    // If we're not showing drafts, then filter out the following things:
-                    //
-                    // - Posts that are only a single entry that's a draft
-                    // - Posts that are a thread where we can't display any of the entries (i.e. the
-                    //   first entry is a draft, which implies the following are also drafts)
-                    let is_draft = match post {
-                        Post::Single { metadata, .. } => metadata.draft,
-                        Post::Thread { entries, .. } => {
-                            entries
-                                .first()
-                                .expect("a post cannot have no entries")
-                                .metadata
-                                .draft
-                        }
-                    };
+impl TaggedRef {
+    pub fn canonical_path(&self) -> String {
+        format!("/tagged/{}", self.tag)
+    }
+}
 
-                    !is_draft
-                } else {
-                    true
-                }
-            })
-            .filter(|(_, post)| post.has_tag(&self.tag))
-            .collect::<Vec<_>>();
-        posts.sort_by_key(|(_, post)| post.date_posted());
+impl Render for TaggedRef {
+    fn render(&self) -> Markup {
+        let posts = self
+            .snapshot
+            .index(self.show_drafts)
+            .tags
+            .get(&self.tag)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
 
         html! {
-            main {
+            main data-tag=(self.tag) {
                 (partials::page_title(html! {
                     "Posts Tagged " code { (self.tag) }
                 }, None))
@@ -1007,7 +1227,7 @@ impl Render for TaggedRef<'_> {
                     in its frontmatter."
                 }
 
-                @for (path, post) in posts.iter().rev() {
+                @for IndexedPost { path, post } in posts.iter().rev() {
                     hr;
 
                     section {
@@ -1023,7 +1243,7 @@ impl Render for TaggedRef<'_> {
                         ))
                         (PreEscaped(post.summary()))
                         p {
-                            a href=(format!("/posts/{}", path)) {
+                            a href=(format!("/posts/{}#{}", path, continue_reading_id(0))) {
                                 "Read more"
                             }
                         }
@@ -1033,3 +1253,39 @@ impl Render for TaggedRef<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_are_full_and_cover_every_entry_older_than_the_current_feed() {
+        for (entries, archives) in [(0, 0), (1, 0), (100, 0), (101, 1), (200, 1), (250, 2)] {
+            assert_eq!(archive_count(entries, 100), archives, "{entries} entries");
+        }
+
+        assert_eq!(feed_range(FeedPage::Current, 100, 250), 150..250);
+        assert_eq!(feed_range(FeedPage::Archive(1), 100, 250), 0..100);
+        assert_eq!(feed_range(FeedPage::Archive(2), 100, 250), 100..200);
+    }
+
+    #[test]
+    fn the_newest_archive_only_overlaps_the_current_feed_when_it_isnt_a_whole_number_of_feeds() {
+        let overlap = |entries| {
+            let current = feed_range(FeedPage::Current, 100, entries);
+            let newest = feed_range(FeedPage::Archive(archive_count(entries, 100)), 100, entries);
+            newest.end.saturating_sub(current.start)
+        };
+
+        assert_eq!(overlap(250), 50);
+        assert_eq!(overlap(200), 0);
+        assert_eq!(overlap(300), 0);
+    }
+
+    #[test]
+    fn archives_stay_the_same_as_entries_are_added() {
+        for entries in 101..=350 {
+            assert_eq!(feed_range(FeedPage::Archive(1), 100, entries), 0..100);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::{borrow::Cow, collections::HashSet};
+
+use ammonia::Builder;
+use comrak::nodes::{AstNode, NodeValue};
+
+/// What stands in for each piece of HTML added by the site itself while the rest is cleaned, with
+/// its index between them. They're from a private use area, and taken out of markdown before it's
+/// parsed, so the only ones left in a document are the site's own.
+const PLACEHOLDER_START: char = '\u{e000}';
+const PLACEHOLDER_END: char = '\u{e001}';
+
+/// Cleans the HTML written into markdown of anything that could run a script.
+///
+/// Only the HTML that was in the markdown is cleaned, not the HTML that passes add (like
+/// highlighted code or rendered diagrams), which is swapped out for placeholders while the rest of
+/// the document is cleaned, and swapped back in afterwards.
+pub struct Sanitizer {
+    builder: Builder<'static>,
+}
+
+impl Sanitizer {
+    pub fn new() -> Self {
+        let mut builder = Builder::default();
+        builder
+            .add_generic_attributes(["class", "id"])
+            // What the embed shortcodes expand into, along with task list checkboxes.
+            .add_tags([
+                "figure",
+                "figcaption",
+                "iframe",
+                "picture",
+                "source",
+                "input",
+            ])
+            .add_tag_attributes("iframe", ["src", "title", "loading"])
+            .add_tag_attributes("source", ["srcset", "sizes", "type", "media"])
+            .add_tag_attributes("img", ["srcset", "sizes", "loading", "decoding"])
+            .add_tag_attributes("input", ["type", "checked", "disabled"])
+            .attribute_filter(|element, attribute, value| match (element, attribute) {
+                // Frames can only show the site's own preview cards.
+                ("iframe", "src") => value
+                    .starts_with("/embeds/")
+                    .then_some(Cow::Borrowed(value)),
+                ("input", "type") => (value == "checkbox").then_some(Cow::Borrowed(value)),
+                _ => Some(Cow::Borrowed(value)),
+            })
+            // Headings are marked for the table of contents with comments.
+            .strip_comments(false)
+            .link_rel(None);
+
+        Self { builder }
+    }
+
+    /// Take the characters placeholders are made of out of markdown that's about to be parsed, so
+    /// nothing written into it can stand in for the site's own HTML.
+    pub fn scrub<'a>(&self, md_input: &'a str) -> Cow<'a, str> {
+        if md_input.contains([PLACEHOLDER_START, PLACEHOLDER_END]) {
+            Cow::Owned(md_input.replace([PLACEHOLDER_START, PLACEHOLDER_END], ""))
+        } else {
+            Cow::Borrowed(md_input)
+        }
+    }
+
+    /// Every piece of HTML in a freshly parsed document, which is what gets cleaned.
+    pub fn written<'a>(&self, root: &'a AstNode<'a>) -> HashSet<*const AstNode<'a>> {
+        root.descendants()
+            .filter(|node| is_html(node))
+            .map(|node| node as *const AstNode<'a>)
+            .collect()
+    }
+
+    /// Swap every piece of HTML in a document that wasn't written into it for a placeholder,
+    /// returning the pieces in the order of their placeholders' indices.
+    pub fn set_aside<'a>(
+        &self,
+        root: &'a AstNode<'a>,
+        written: &HashSet<*const AstNode<'a>>,
+    ) -> Vec<String> {
+        let mut added = Vec::new();
+
+        for node in root.descendants() {
+            if written.contains(&(node as *const AstNode<'a>)) {
+                continue;
+            }
+
+            let mut ast = node.data.borrow_mut();
+            let literal = match &mut ast.value {
+                NodeValue::HtmlBlock(block) => &mut block.literal,
+                NodeValue::HtmlInline(literal) => literal,
+                _ => continue,
+            };
+            let placeholder = format!("{PLACEHOLDER_START}{}{PLACEHOLDER_END}", added.len());
+            added.push(std::mem::replace(literal, placeholder));
+        }
+
+        added
+    }
+
+    /// Clean a rendered document, then put back the HTML that was set aside.
+    ///
+    /// Placeholders are only put back where text goes, never inside a tag or a comment, where a
+    /// piece of HTML could break out of the attribute or comment it was put into.
+    pub fn clean(&self, html: &str, added: &[String]) -> String {
+        let cleaned = self.builder.clean(html).to_string();
+        if added.is_empty() {
+            return cleaned;
+        }
+
+        let mut restored = String::with_capacity(cleaned.len());
+        let mut position = Position::Text;
+        let mut rest = cleaned.as_str();
+        while let Some(c) = rest.chars().next() {
+            if position == Position::Text && c == PLACEHOLDER_START {
+                let after = &rest[c.len_utf8()..];
+                let piece = after
+                    .split_once(PLACEHOLDER_END)
+                    .and_then(|(index, after)| {
+                        let piece = added.get(index.parse::<usize>().ok()?)?;
+                        Some((piece, after))
+                    });
+                if let Some((piece, after)) = piece {
+                    restored.push_str(piece);
+                    rest = after;
+                    continue;
+                }
+            }
+
+            position = match (position, c) {
+                (Position::Text, '<') if rest.starts_with("<!--") => Position::Comment,
+                (Position::Text, '<') => Position::Tag,
+                (Position::Tag, '"') => Position::Quoted,
+                (Position::Tag, '>') => Position::Text,
+                (Position::Quoted, '"') => Position::Tag,
+                (Position::Comment, '>') if restored.ends_with("--") => Position::Text,
+                (position, _) => position,
+            };
+            restored.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        restored
+    }
+}
+
+/// Where in cleaned HTML a character is. Attributes are always quoted with `"` once they've been
+/// through ammonia.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Position {
+    Text,
+    Tag,
+    Quoted,
+    Comment,
+}
+
+fn is_html(node: &AstNode<'_>) -> bool {
+    matches!(
+        node.data.borrow().value,
+        NodeValue::HtmlBlock(_) | NodeValue::HtmlInline(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_in_markdown_are_scrubbed() {
+        let sanitizer = Sanitizer::new();
+        assert_eq!(
+            sanitizer.scrub("<span title=\"\u{e000}0\u{e001}\">hi</span>"),
+            "<span title=\"0\">hi</span>"
+        );
+    }
+
+    #[test]
+    fn placeholders_in_attributes_stay_inert() {
+        let sanitizer = Sanitizer::new();
+        let added = [r#"<a href="x" onmouseover="alert(1)">"#.to_string()];
+        let html = sanitizer.clean(
+            "<p><span title=\"\u{e000}0\u{e001}\">hi</span> \u{e000}0\u{e001}</p>",
+            &added,
+        );
+
+        assert_eq!(html.matches("onmouseover").count(), 1);
+        assert!(html.starts_with("<p><span title=\"\u{e000}0\u{e001}\">hi</span> <a "));
+    }
+}
@@ -0,0 +1,342 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use maud::{html, Markup};
+use thiserror::Error;
+use tracing::warn;
+use url::Url;
+
+use crate::embeds;
+
+/// A shortcode that can be written on a line of its own as `{{ name key="value" }}`, along with
+/// what it expands into.
+struct Shortcode {
+    name: &'static str,
+    expand: fn(&mut Arguments) -> Result<Markup, ShortcodeError>,
+}
+
+/// Every shortcode there is. None of them load anything from another site until the reader asks.
+const SHORTCODES: &[Shortcode] = &[
+    Shortcode {
+        name: "youtube",
+        expand: youtube,
+    },
+    Shortcode {
+        name: "vimeo",
+        expand: vimeo,
+    },
+    Shortcode {
+        name: "gist",
+        expand: gist,
+    },
+    Shortcode {
+        name: "toot",
+        expand: toot,
+    },
+];
+
+/// `{{ youtube id="..." }}`: the same preview card as a link to the video on its own line.
+fn youtube(arguments: &mut Arguments) -> Result<Markup, ShortcodeError> {
+    let id = arguments.require_matching("id", |c| c.is_ascii_alphanumeric() || "-_".contains(c))?;
+    let url = Url::parse_with_params("https://www.youtube.com/watch", [("v", id)])
+        .expect("YouTube URL is valid");
+    Ok(embeds::card_frame(&url).expect("YouTube is an embed provider"))
+}
+
+/// `{{ vimeo id="..." }}`: the same preview card as a link to the video on its own line.
+fn vimeo(arguments: &mut Arguments) -> Result<Markup, ShortcodeError> {
+    let id = arguments.require_matching("id", |c| c.is_ascii_digit())?;
+    let url = Url::parse(&format!("https://vimeo.com/{id}")).expect("Vimeo URL is valid");
+    Ok(embeds::card_frame(&url).expect("Vimeo is an embed provider"))
+}
+
+/// `{{ gist user="..." id="..." file="..." }}`: a link to a gist (or one file in it), since
+/// GitHub's own embed is a script.
+fn gist(arguments: &mut Arguments) -> Result<Markup, ShortcodeError> {
+    let user = arguments.require_matching("user", |c| c.is_ascii_alphanumeric() || c == '-')?;
+    let id = arguments.require_matching("id", |c| c.is_ascii_hexdigit())?;
+    let file = arguments.take("file");
+
+    let mut url =
+        Url::parse(&format!("https://gist.github.com/{user}/{id}")).expect("gist URL is valid");
+    // GitHub's anchors for files turn anything that isn't alphanumeric into a dash.
+    if let Some(file) = &file {
+        let anchor = file
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect::<String>();
+        url.set_fragment(Some(&format!("file-{anchor}")));
+    }
+
+    Ok(html! {
+        figure class="embed-link" {
+            a href=(url) {
+                @if let Some(file) = &file {
+                    code { (file) }
+                } @else {
+                    "A gist"
+                }
+                " by " (user) " on GitHub"
+            }
+        }
+    })
+}
+
+/// `{{ toot url="..." text="..." }}`: a link to a post on the fediverse, quoting it if it's given
+/// the text, since embedding it would load the post's whole instance.
+fn toot(arguments: &mut Arguments) -> Result<Markup, ShortcodeError> {
+    let raw_url = arguments.require("url")?;
+    let url = Url::parse(&raw_url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+        .ok_or(ShortcodeError::InvalidArgument {
+            name: "url",
+            value: raw_url,
+        })?;
+    let text = arguments.take("text");
+
+    let host = url.host_str().unwrap_or_default();
+    let author = url
+        .path_segments()
+        .and_then(|mut segments| segments.find(|segment| segment.starts_with('@')))
+        .map(|user| format!("{user}@{host}"));
+
+    Ok(html! {
+        figure class="embed-link" {
+            @if let Some(text) = &text {
+                blockquote cite=(url) { p { (text) } }
+            }
+            figcaption {
+                a href=(url) {
+                    @if let Some(author) = &author {
+                        "A post by " (author)
+                    } @else {
+                        "A post on " (host)
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Expand every shortcode in the file of a post or page into HTML, leaving its frontmatter and
+/// anything in code blocks alone.
+///
+/// Shortcodes have to be on a line of their own, and are expanded into a block of HTML with a
+/// blank line after it, so that the markdown after it isn't taken as part of the block. Anything
+/// that looks like a shortcode but isn't one of them (like a post about templating might have) is
+/// left as it is.
+pub fn expand(markdown: &str) -> Result<Cow<'_, str>, InvalidShortcode> {
+    if !markdown.contains("{{") {
+        return Ok(Cow::Borrowed(markdown));
+    }
+
+    let mut expanded = String::with_capacity(markdown.len());
+    let mut fence: Option<&str> = None;
+    // Frontmatter is between a pair of `---` lines, the same as when it's parsed, and a thread has
+    // a pair for each of its entries.
+    let mut delimiters = 0;
+
+    for (index, line) in markdown.split_inclusive('\n').enumerate() {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        let trimmed = line.trim();
+
+        if line.trim_end() == "---" {
+            delimiters += 1;
+        }
+        if delimiters % 2 == 1 {
+            expanded.push_str(line);
+            continue;
+        }
+
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| indent < 4 && trimmed.starts_with(marker));
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            _ => {}
+        }
+
+        let parsed = match fence {
+            None if indent < 4 => parse(trimmed),
+            _ => None,
+        };
+        let Some(parsed) = parsed else {
+            expanded.push_str(line);
+            continue;
+        };
+
+        let (name, arguments) = parsed;
+        let Some(shortcode) = SHORTCODES.iter().find(|shortcode| shortcode.name == name) else {
+            warn!(line = %(index + 1), %name, "leaving unknown shortcode as it is");
+            expanded.push_str(line);
+            continue;
+        };
+
+        let html = arguments
+            .and_then(|mut arguments| {
+                let html = (shortcode.expand)(&mut arguments)?;
+                arguments.finish()?;
+                Ok(html)
+            })
+            .map_err(|reason| InvalidShortcode {
+                line: index + 1,
+                reason,
+            })?;
+
+        expanded.push_str(&html.into_string());
+        expanded.push_str("\n\n");
+    }
+
+    Ok(Cow::Owned(expanded))
+}
+
+/// The name and arguments of the shortcode on a line, if there's one there.
+fn parse(line: &str) -> Option<(&str, Result<Arguments, ShortcodeError>)> {
+    let inner = line.strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    let (name, rest) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c))
+    {
+        return None;
+    }
+
+    Some((name, Arguments::parse(rest)))
+}
+
+/// The arguments given to a shortcode, as `key="value"` (or `key=value`, if the value has no
+/// spaces in it).
+struct Arguments {
+    values: BTreeMap<String, String>,
+}
+
+impl Arguments {
+    fn parse(mut rest: &str) -> Result<Self, ShortcodeError> {
+        use ShortcodeError::*;
+
+        let mut values = BTreeMap::new();
+        rest = rest.trim_start();
+
+        while !rest.is_empty() {
+            let malformed =
+                || MalformedArgument(rest.split_whitespace().next().unwrap_or(rest).to_owned());
+            let (key, after) = rest.split_once('=').ok_or_else(malformed)?;
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(malformed());
+            }
+
+            let (value, after) = match after.strip_prefix('"') {
+                Some(quoted) => {
+                    let mut value = String::new();
+                    let mut chars = quoted.char_indices();
+                    loop {
+                        match chars.next() {
+                            Some((_, '\\')) => value.extend(chars.next().map(|(_, c)| c)),
+                            Some((end, '"')) => break (value, &quoted[end + 1..]),
+                            Some((_, c)) => value.push(c),
+                            None => return Err(UnclosedQuote(key.to_owned())),
+                        }
+                    }
+                }
+                None => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (after[..end].to_owned(), &after[end..])
+                }
+            };
+
+            values.insert(key.to_owned(), value);
+            rest = after.trim_start();
+        }
+
+        Ok(Self { values })
+    }
+
+    fn take(&mut self, name: &str) -> Option<String> {
+        self.values.remove(name)
+    }
+
+    fn require(&mut self, name: &'static str) -> Result<String, ShortcodeError> {
+        self.take(name).ok_or(ShortcodeError::MissingArgument(name))
+    }
+
+    /// An argument that has to be given, and can only be made up of certain characters, since it's
+    /// put into a URL.
+    fn require_matching(
+        &mut self,
+        name: &'static str,
+        allowed: impl Fn(char) -> bool,
+    ) -> Result<String, ShortcodeError> {
+        let value = self.require(name)?;
+        if value.is_empty() || !value.chars().all(allowed) {
+            return Err(ShortcodeError::InvalidArgument { name, value });
+        }
+        Ok(value)
+    }
+
+    /// Check that every argument was used by the shortcode.
+    fn finish(self) -> Result<(), ShortcodeError> {
+        match self.values.into_keys().next() {
+            Some(name) => Err(ShortcodeError::UnexpectedArgument(name)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("invalid shortcode on line {line}: {reason}")]
+pub struct InvalidShortcode {
+    line: usize,
+    reason: ShortcodeError,
+}
+
+#[derive(Error, Debug)]
+pub enum ShortcodeError {
+    #[error("expected an argument like key=\"value\", found \"{0}\"")]
+    MalformedArgument(String),
+
+    #[error("the value of \"{0}\" is missing its closing quote")]
+    UnclosedQuote(String),
+
+    #[error("it needs an argument called \"{0}\"")]
+    MissingArgument(&'static str),
+
+    #[error("it doesn't take an argument called \"{0}\"")]
+    UnexpectedArgument(String),
+
+    #[error("\"{value}\" isn't a valid {name}")]
+    InvalidArgument { name: &'static str, value: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_shortcodes_are_left_alone() {
+        let markdown = "---\ntitle = \"Templates\"\n---\n\n{{ page.title }}\n\n{{ nothing }}\n";
+        assert_eq!(expand(markdown).unwrap(), markdown);
+    }
+
+    #[test]
+    fn frontmatter_is_not_expanded() {
+        let markdown = "---\ntitle = \"\"\"\n{{ gist }}\n\"\"\"\n---\n\nBody\n";
+        assert_eq!(expand(markdown).unwrap(), markdown);
+    }
+
+    #[test]
+    fn shortcodes_in_thread_entries_are_expanded() {
+        let markdown = "---\ntitle = \"Thread\"\n---\n\nFirst\n\n---\n[[x]]\n---\n\n{{ gist \
+                        user=\"a\" id=\"b1\" }}\n";
+        let expanded = expand(markdown).unwrap();
+        assert!(expanded.contains("gist.github.com/a/b1"));
+        assert!(expanded.starts_with("---\ntitle = \"Thread\"\n---\n\nFirst\n\n---\n[[x]]\n---\n"));
+    }
+}
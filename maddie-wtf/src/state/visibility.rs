@@ -0,0 +1,188 @@
+use crate::state::{Post, ThreadEntry};
+
+/// Whether a post is shown at all, whether that's on its own page or in any of the lists. A thread
+/// whose first entry is a draft counts as a draft as a whole, even if later entries aren't: none of
+/// its entries come before the first draft, so there would be nothing of it to show.
+pub fn post_is_visible(post: &Post, show_drafts: bool) -> bool {
+    let is_draft = match post {
+        Post::Single { metadata, .. } => metadata.draft,
+        Post::Thread { entries, .. } => {
+            entries
+                .first()
+                .expect("a post cannot have no entries")
+                .metadata
+                .draft
+        }
+    };
+
+    show_drafts || !is_draft
+}
+
+/// Whether a post can be listed in the documents search engines find pages through, like the
+/// sitemap. A post whose robots directives include `noindex` (or `none`, which implies it) is still
+/// served and listed on the site, but left out of those.
+pub fn post_is_indexable(post: &Post) -> bool {
+    !post.robots().is_some_and(|robots| {
+        robots.split(',').map(str::trim).any(|directive| {
+            directive.eq_ignore_ascii_case("noindex") || directive.eq_ignore_ascii_case("none")
+        })
+    })
+}
+
+/// The entries of a thread that are shown: all of them when showing drafts, and otherwise every
+/// entry before the first draft, since entries are published in order.
+pub fn visible_entries(entries: &[ThreadEntry], show_drafts: bool) -> &[ThreadEntry] {
+    if show_drafts {
+        return entries;
+    }
+
+    let visible = entries
+        .iter()
+        .position(|entry| entry.metadata.draft)
+        .unwrap_or(entries.len());
+    &entries[..visible]
+}
+
+/// Whether the visible entries of a thread can be linked to (and viewed) on their own.
+///
+/// If only the first entry of several is visible, showing it *as* an entry would confuse readers
+/// (and tip them off that another entry might be coming), so it has to stand in for the whole post
+/// instead.
+pub fn entries_are_linkable(entries: &[ThreadEntry], show_drafts: bool) -> bool {
+    let visible = visible_entries(entries, show_drafts).len();
+    visible != 1 || visible == entries.len()
+}
+
+/// Whether the entry at `index` in a thread can be viewed on its own.
+pub fn entry_is_visible(entries: &[ThreadEntry], index: usize, show_drafts: bool) -> bool {
+    index < visible_entries(entries, show_drafts).len()
+        && entries_are_linkable(entries, show_drafts)
+}
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8PathBuf;
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::state::{bodies::Body, SinglePostMetadata, ThreadEntryMetadata, ThreadMetadata};
+
+    fn entry(index: usize, draft: bool) -> ThreadEntry {
+        ThreadEntry {
+            metadata: ThreadEntryMetadata {
+                md_title: None,
+                draft,
+                date: NaiveDate::from_ymd_opt(2024, 1, 1 + index as u32).unwrap(),
+                updated: None,
+                lobsters: None,
+                hacker_news: None,
+            },
+            html_summary: String::new(),
+            body: Body::new("thread.md".into(), None, index, String::new()),
+        }
+    }
+
+    fn entries(drafts: &[bool]) -> Vec<ThreadEntry> {
+        drafts
+            .iter()
+            .enumerate()
+            .map(|(index, &draft)| entry(index, draft))
+            .collect()
+    }
+
+    fn single(draft: bool, robots: Option<&str>) -> Post {
+        Post::Single {
+            metadata: SinglePostMetadata {
+                source: Utf8PathBuf::from("post.md"),
+                md_title: "Post".to_owned(),
+                draft,
+                tags: Vec::new(),
+                date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                updated: None,
+                lobsters: None,
+                hacker_news: None,
+                robots: robots.map(str::to_owned),
+                see_also: Vec::new(),
+            },
+            html_summary: String::new(),
+            body: Body::new("post.md".into(), None, 0, String::new()),
+        }
+    }
+
+    fn thread(drafts: &[bool]) -> Post {
+        Post::Thread {
+            metadata: ThreadMetadata {
+                source: Utf8PathBuf::from("thread.md"),
+                md_title: "Thread".to_owned(),
+                tags: Vec::new(),
+                robots: None,
+                see_also: Vec::new(),
+            },
+            html_summary: String::new(),
+            entries: entries(drafts),
+        }
+    }
+
+    #[test]
+    fn single_posts_are_hidden_only_when_drafts() {
+        assert!(post_is_visible(&single(false, None), false));
+        assert!(!post_is_visible(&single(true, None), false));
+        assert!(post_is_visible(&single(true, None), true));
+    }
+
+    #[test]
+    fn threads_are_drafts_when_their_first_entry_is() {
+        assert!(post_is_visible(&thread(&[false, true]), false));
+        assert!(!post_is_visible(&thread(&[true, true]), false));
+        assert!(post_is_visible(&thread(&[true, true]), true));
+    }
+
+    #[test]
+    fn threads_starting_with_a_draft_are_hidden_even_with_published_entries_after_it() {
+        let post = thread(&[true, false]);
+        assert!(!post_is_visible(&post, false));
+        assert!(post_is_visible(&post, true));
+    }
+
+    #[test]
+    fn entries_are_visible_up_to_the_first_draft() {
+        let entries = entries(&[false, false, true, false]);
+        assert_eq!(visible_entries(&entries, false).len(), 2);
+        assert_eq!(visible_entries(&entries, true).len(), 4);
+        assert!(visible_entries(&self::entries(&[true, false]), false).is_empty());
+    }
+
+    #[test]
+    fn a_lone_visible_entry_of_several_is_not_linkable() {
+        assert!(!entries_are_linkable(&entries(&[false, true]), false));
+        assert!(entries_are_linkable(&entries(&[false, true]), true));
+        assert!(entries_are_linkable(&entries(&[false]), false));
+        assert!(entries_are_linkable(&entries(&[false, false, true]), false));
+    }
+
+    #[test]
+    fn entries_are_visible_only_before_the_first_draft_and_when_linkable() {
+        let three = entries(&[false, false, true]);
+        assert!(entry_is_visible(&three, 0, false));
+        assert!(entry_is_visible(&three, 1, false));
+        assert!(!entry_is_visible(&three, 2, false));
+        assert!(entry_is_visible(&three, 2, true));
+        assert!(!entry_is_visible(&three, 3, true));
+
+        let lone = entries(&[false, true]);
+        assert!(!entry_is_visible(&lone, 0, false));
+        assert!(entry_is_visible(&lone, 0, true));
+    }
+
+    #[test]
+    fn noindex_posts_are_not_indexable() {
+        assert!(post_is_indexable(&single(false, None)));
+        assert!(post_is_indexable(&single(false, Some("nofollow"))));
+        assert!(!post_is_indexable(&single(
+            false,
+            Some("noindex, nofollow")
+        )));
+        assert!(!post_is_indexable(&single(false, Some("NoIndex"))));
+        assert!(!post_is_indexable(&single(false, Some("none"))));
+    }
+}
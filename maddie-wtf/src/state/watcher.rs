@@ -0,0 +1,584 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::NaiveDate;
+use either::Either;
+use notify::{PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use notify_debouncer_mini::{new_debouncer_opt, DebounceEventResult, DebouncedEvent, Debouncer};
+use serde::Serialize;
+use thiserror::Error;
+use tokio::{
+    fs, runtime,
+    sync::Notify,
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+#[cfg(debug_assertions)]
+use tower_livereload::Reloader;
+use tracing::{debug, error, info, span, warn, Level};
+
+use crate::{
+    config::WatcherConfig,
+    metric,
+    state::{assets::Assets, bundle_dir, node_key, Content, Redirects, RenderCache, Themes},
+    warmup::Warmup,
+};
+
+/// How often the content loader checks in while it's waiting for events, and how often the
+/// supervisor checks on it.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long the content loader can go without checking in before it's assumed to be stuck.
+const STALLED_AFTER: Duration = Duration::from_secs(120);
+
+/// How many errors in a row from the file watcher it takes to give up on it and start a new one.
+const MAX_CONSECUTIVE_ERRORS: usize = 5;
+
+/// Watches the content, themes and static files for changes, and loads them into the running site.
+#[derive(Clone)]
+pub struct ContentWatcher {
+    pub content: Content,
+    pub content_path: Utf8PathBuf,
+    pub themes: Themes,
+    pub themes_path: Utf8PathBuf,
+    pub static_path: Utf8PathBuf,
+    pub assets: Assets,
+    pub renders: RenderCache,
+    pub redirects: Redirects,
+    pub warmup: Option<Warmup>,
+    pub strict: bool,
+    pub config: WatcherConfig,
+    #[cfg(debug_assertions)]
+    pub reloader: Reloader,
+    pub health: WatcherHealth,
+    pub reloads: ReloadTrigger,
+}
+
+/// A file watcher and the content loader it sends its events to, which both stop when this is
+/// dropped.
+pub struct Running {
+    _debouncer: Either<Debouncer<RecommendedWatcher>, Debouncer<PollWatcher>>,
+    loader: JoinHandle<()>,
+}
+
+impl ContentWatcher {
+    /// Start watching for changes, with a new thread to load them on.
+    pub fn start(&self) -> Result<Running, WatchError> {
+        use WatchError::*;
+
+        let (event_tx, event_rx) = mpsc::channel::<Vec<DebouncedEvent>>();
+
+        let runtime = runtime::Handle::current();
+        let watcher = self.clone();
+        let loader = runtime.spawn_blocking(move || {
+            let _guard = span!(Level::ERROR, "content_loader").entered();
+            let runtime = runtime::Handle::current();
+            loop {
+                watcher.health.beat();
+                match event_rx.recv_timeout(HEARTBEAT_INTERVAL) {
+                    Ok(events) => runtime.block_on(watcher.handle(events)),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+
+            warn!("event sender hung up");
+        });
+
+        let health = self.health.clone();
+        let handler = move |res: DebounceEventResult| {
+            let _guard = span!(Level::ERROR, "file_watcher").entered();
+            match res {
+                Ok(events) => {
+                    info!(events = %events.len(), "received batch of debounced events");
+                    match event_tx.send(events) {
+                        Ok(()) => health.succeeded(),
+                        Err(error) => {
+                            error!(%error, "failed to send events to content loader");
+                            health.failed();
+                        }
+                    }
+                }
+                Err(error) => {
+                    error!(%error, "watcher error received");
+                    health.failed();
+                }
+            }
+        };
+
+        let config = notify_debouncer_mini::Config::default()
+            .with_timeout(Duration::from_millis(self.config.debounce_ms));
+        let debouncer = if self.config.poll {
+            let poll_interval = Duration::from_millis(self.config.poll_interval_ms);
+            let config = config
+                .with_notify_config(notify::Config::default().with_poll_interval(poll_interval));
+            let mut debouncer =
+                new_debouncer_opt::<_, PollWatcher>(config, handler).map_err(CreateWatcher)?;
+            self.watch_paths(debouncer.watcher())?;
+            info!(?poll_interval, "polling for changes");
+            Either::Right(debouncer)
+        } else {
+            let mut debouncer = new_debouncer_opt::<_, RecommendedWatcher>(config, handler)
+                .map_err(CreateWatcher)?;
+            self.watch_paths(debouncer.watcher())?;
+            Either::Left(debouncer)
+        };
+
+        self.health.started();
+        Ok(Running {
+            _debouncer: debouncer,
+            loader,
+        })
+    }
+
+    fn watch_paths(&self, watcher: &mut dyn Watcher) -> Result<(), WatchError> {
+        use WatchError::*;
+
+        watcher
+            .watch(self.content_path.as_std_path(), RecursiveMode::Recursive)
+            .map_err(WatchPath)?;
+
+        watcher
+            .watch(self.themes_path.as_std_path(), RecursiveMode::Recursive)
+            .map_err(WatchPath)?;
+
+        // Static files are served under their fingerprints, which have to change along with them.
+        watcher
+            .watch(self.static_path.as_std_path(), RecursiveMode::Recursive)
+            .map_err(WatchPath)?;
+
+        // Changes to the stylesheet source only matter for live reloading.
+        #[cfg(debug_assertions)]
+        {
+            // The stylesheet source won't be around if the binary has been moved away from it,
+            // which shouldn't stop it from starting up.
+            if let Err(error) = watcher.watch(
+                Utf8Path::new(super::assets::STYLESHEET_SOURCE_DIR).as_std_path(),
+                RecursiveMode::Recursive,
+            ) {
+                warn!(%error, "failed to watch stylesheet source");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keep watching for as long as the server runs, checking every so often that the watcher and
+    /// the loader are both still working, and starting them again if they aren't.
+    ///
+    /// Whatever changed while they were down is picked up by loading all of the content again once
+    /// they're back.
+    pub async fn supervise(self, running: Running) {
+        let mut running = Some(running);
+        let mut interval = time::interval(HEARTBEAT_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                () = self.reloads.0.notified() => {
+                    info!("reloading all content");
+                    self.rescan().await;
+                    continue;
+                }
+            }
+
+            let failure = match &running {
+                Some(running) => self.health.failure(running),
+                None => Some("the watcher couldn't be started"),
+            };
+            let Some(failure) = failure else {
+                continue;
+            };
+
+            error!(%failure, "content watcher stopped working, restarting it");
+            self.health.set_healthy(false);
+            // The old watcher has to stop before the new one starts, so they don't both load
+            // the same changes.
+            running = None;
+
+            match self.start() {
+                Ok(restarted) => {
+                    running = Some(restarted);
+                    let restarts = self.health.restarted();
+                    metrics::counter!(*metric::WATCHER_RESTARTS).increment(1);
+                    info!(%restarts, "restarted content watcher");
+                    self.rescan().await;
+                }
+                Err(error) => error!(%error, "failed to restart content watcher"),
+            }
+        }
+    }
+
+    /// Load everything in the content path again, and take out anything that's gone from it, in
+    /// case it changed while nothing was watching.
+    async fn rescan(&self) {
+        let batch = self.content.batch();
+        let failures = self.content.load_all().await;
+        if failures > 0 {
+            warn!(%failures, "some content failed to load while reloading all of it");
+        }
+        for key in self.content.remove_missing() {
+            info!(%key, "removed deleted content");
+        }
+        drop(batch);
+
+        self.content.record_manifest();
+        self.renders.invalidate();
+        if let Some(warmup) = &self.warmup {
+            warmup.spawn(Vec::new());
+        }
+        self.reload_page();
+    }
+
+    /// Handle a batch of events from the file watcher.
+    async fn handle(&self, events: Vec<DebouncedEvent>) {
+        // Content files are handled together once the whole batch has been looked at, since a
+        // rename shows up as one path that's gone and another that's appeared.
+        let mut changed = Vec::new();
+        let mut removed = Vec::new();
+
+        for event in events {
+            let Ok(path) = Utf8PathBuf::from_path_buf(event.path.clone()) else {
+                warn!(
+                    path = ?event.path,
+                    "skipping event with path that contains invalid UTF-8"
+                );
+                continue;
+            };
+
+            // Static files are served straight from disk, so there's nothing to load besides their
+            // new fingerprint, but the page still needs reloading to pick them up.
+            if path.starts_with(&self.static_path) || is_stylesheet_source(&path) {
+                debug!(%path, "static file or stylesheet changed");
+                self.assets.refresh(&path).await;
+                self.renders.invalidate();
+                if let Some(warmup) = &self.warmup {
+                    warmup.spawn(Vec::new());
+                }
+                self.reload_page();
+                continue;
+            }
+
+            if path.starts_with(&self.themes_path) {
+                self.reload_themes(&path).await;
+                continue;
+            }
+
+            let Ok(relative) = path.strip_prefix(&self.content_path) else {
+                debug!(
+                    %path,
+                    "skipping entry for path that isn't relative to the content path"
+                );
+                continue;
+            };
+
+            if relative
+                .components()
+                .any(|component| component.as_str().starts_with('.'))
+            {
+                debug!(
+                    %path,
+                    "skipping entry for a path containing a hidden file or directory"
+                );
+                continue;
+            }
+
+            if path
+                .file_name()
+                .is_some_and(|name| name == "4913" || name.ends_with('~'))
+            {
+                // nvim creates these when you write files. I think the ~ one is intentional, but
+                // the 4913 thing seems to be a longstanding bug:
+                //
+                // https://github.com/neovim/neovim/issues/3460
+                debug!(
+                    %path,
+                    "skipping entry that appears to be an editor temporary file"
+                );
+                continue;
+            }
+
+            if !fs::try_exists(&path).await.unwrap_or_default() {
+                debug!(%path, "event probably represents a deleted file");
+                removed.push(path);
+            } else if path.is_dir() {
+                // A bundled post that's been renamed shows up as its directory, not the files in
+                // it.
+                if bundle_dir(&relative.join("index.md")).is_some() {
+                    changed.push(path.join("index.md"));
+                }
+            } else {
+                changed.push(path);
+            }
+        }
+
+        if changed.is_empty() && removed.is_empty() {
+            return;
+        }
+
+        changed.sort();
+        changed.dedup();
+
+        let key = |path: &Utf8Path| node_key(path.strip_prefix(&self.content_path).unwrap_or(path));
+        let gone = removed
+            .iter()
+            .filter(|path| matches!(path.extension(), Some("md") | None))
+            .map(|path| key(path))
+            .filter(|key| self.content.contains(key))
+            .collect::<Vec<_>>();
+        let appeared = changed
+            .iter()
+            .map(|path| key(path))
+            .filter(|key| !self.content.contains(key))
+            .collect::<Vec<_>>();
+        let renames = pair_renames(&gone, &appeared);
+        let mut loaded = Vec::new();
+
+        self.content.refresh_commit_dates().await;
+        let batch = self.content.batch();
+
+        for path in &changed {
+            let key = key(path);
+            let replaced = renames.get(&key);
+
+            let Ok(metadata) = fs::metadata(&path).await else {
+                warn!(%path, "skipping entry because metadata could not be accessed");
+                continue;
+            };
+
+            match self
+                .content
+                .load_replacing(path, metadata, replaced.map(|old| old.as_path()))
+                .await
+            {
+                Ok(_) => {
+                    self.redirects.forget(&key);
+                    if let Some(old) = replaced {
+                        info!(from = %old, to = %key, "content was renamed");
+                        if self.config.redirect_renames {
+                            self.redirects.rename(old, &key);
+                        }
+                    }
+                    loaded.push(key.into_string());
+                }
+                Err(error) => {
+                    // Strict mode can't stop the site now it's running, but it can make sure the
+                    // failure is noticed, and readiness reports it too.
+                    if self.strict {
+                        error!(
+                            %error,
+                            "failed to load content in strict mode, serving previous version"
+                        );
+                    } else {
+                        warn!(%error, "failed to load content, serving previous version");
+                    }
+                    self.content.mark_stale(key, &error);
+                }
+            }
+        }
+
+        // Whatever was renamed has already been taken out along with its replacement being loaded,
+        // unless that failed, in which case the old version has nowhere left to come from.
+        for path in &removed {
+            for key in self.content.remove(path) {
+                info!(%key, "removed deleted content");
+            }
+        }
+        drop(batch);
+
+        self.content.record_manifest();
+        // Anything warmed before the cache is invalidated would be thrown away with it.
+        self.renders.invalidate();
+        if let Some(warmup) = &self.warmup {
+            warmup.spawn(loaded);
+        }
+        self.reload_page();
+    }
+
+    async fn reload_themes(&self, path: &Utf8Path) {
+        if path.extension() != Some("tmTheme") {
+            debug!(%path, "skipping entry in themes path that isn't a theme");
+            return;
+        }
+
+        match self.themes.reload().await {
+            Ok(_) => {
+                info!(%path, "reloaded themes");
+                self.renders.invalidate();
+                if let Some(warmup) = &self.warmup {
+                    warmup.spawn(Vec::new());
+                }
+                self.reload_page();
+            }
+            Err(error) => {
+                warn!(%error, "failed to reload themes, keeping previous ones");
+            }
+        }
+    }
+
+    /// Tell any open pages to reload themselves, when live reloading is on.
+    fn reload_page(&self) {
+        #[cfg(debug_assertions)]
+        {
+            info!("sending reload");
+            self.reloader.reload();
+        }
+    }
+}
+
+/// Asks for all of the content to be loaded again, outside of the file watcher, like after it's
+/// been pulled from git.
+#[derive(Clone, Debug, Default)]
+pub struct ReloadTrigger(Arc<Notify>);
+
+impl ReloadTrigger {
+    pub fn reload(&self) {
+        self.0.notify_one();
+    }
+}
+
+/// Whether the file watcher and content loader are working, shared between them and whatever
+/// reports on it.
+#[derive(Clone, Debug)]
+pub struct WatcherHealth(Arc<Health>);
+
+#[derive(Debug)]
+struct Health {
+    healthy: AtomicBool,
+    consecutive_errors: AtomicUsize,
+    restarts: AtomicUsize,
+    heartbeat: Mutex<Instant>,
+}
+
+/// How the file watcher is doing, as reported by readiness.
+#[derive(Clone, Debug, Serialize)]
+pub struct WatcherStatus {
+    pub healthy: bool,
+    pub restarts: usize,
+}
+
+impl WatcherHealth {
+    pub fn new() -> Self {
+        Self(Arc::new(Health {
+            healthy: AtomicBool::new(false),
+            consecutive_errors: AtomicUsize::new(0),
+            restarts: AtomicUsize::new(0),
+            heartbeat: Mutex::new(Instant::now()),
+        }))
+    }
+
+    pub fn status(&self) -> WatcherStatus {
+        WatcherStatus {
+            healthy: self.0.healthy.load(Ordering::Relaxed),
+            restarts: self.0.restarts.load(Ordering::Relaxed),
+        }
+    }
+
+    fn set_healthy(&self, healthy: bool) {
+        self.0.healthy.store(healthy, Ordering::Relaxed);
+        metrics::gauge!(*metric::WATCHER_HEALTHY).set(if healthy { 1.0 } else { 0.0 });
+    }
+
+    fn started(&self) {
+        self.0.consecutive_errors.store(0, Ordering::Relaxed);
+        self.beat();
+        self.set_healthy(true);
+    }
+
+    fn restarted(&self) -> usize {
+        self.0.restarts.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn beat(&self) {
+        *self
+            .0
+            .heartbeat
+            .lock()
+            .expect("heartbeat mutex is not poisoned") = Instant::now();
+    }
+
+    fn succeeded(&self) {
+        self.0.consecutive_errors.store(0, Ordering::Relaxed);
+    }
+
+    fn failed(&self) {
+        self.0.consecutive_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Why the watcher needs restarting, if it does.
+    fn failure(&self, running: &Running) -> Option<&'static str> {
+        let heartbeat = *self
+            .0
+            .heartbeat
+            .lock()
+            .expect("heartbeat mutex is not poisoned");
+        if running.loader.is_finished() {
+            Some("the content loader exited")
+        } else if heartbeat.elapsed() > STALLED_AFTER {
+            Some("the content loader stopped responding")
+        } else if self.0.consecutive_errors.load(Ordering::Relaxed) >= MAX_CONSECUTIVE_ERRORS {
+            Some("the file watcher kept failing")
+        } else {
+            None
+        }
+    }
+}
+
+/// Pair up the keys of nodes that have appeared in a batch of events with the ones that have gone,
+/// where it looks like they've been renamed: either they have the same name besides the date, or
+/// they're the only ones on either side. Each new key maps to the old one it replaces.
+fn pair_renames(
+    gone: &[Utf8PathBuf],
+    appeared: &[Utf8PathBuf],
+) -> HashMap<Utf8PathBuf, Utf8PathBuf> {
+    fn undated(key: &Utf8Path) -> &str {
+        let name = key.file_name().unwrap_or_default();
+        NaiveDate::parse_and_remainder(name, "%Y-%m-%d").map_or(name, |(_, rest)| rest)
+    }
+
+    let mut gone = gone.to_vec();
+    let mut renames = HashMap::new();
+    let mut unpaired = Vec::new();
+    for key in appeared {
+        match gone.iter().position(|old| undated(old) == undated(key)) {
+            Some(index) => {
+                renames.insert(key.clone(), gone.remove(index));
+            }
+            None => unpaired.push(key),
+        }
+    }
+
+    if let ([old], [new]) = (gone.as_slice(), unpaired.as_slice()) {
+        renames.insert((*new).clone(), old.clone());
+    }
+
+    renames
+}
+
+#[cfg(debug_assertions)]
+fn is_stylesheet_source(path: &Utf8Path) -> bool {
+    path.starts_with(super::assets::STYLESHEET_SOURCE_DIR)
+}
+
+#[cfg(not(debug_assertions))]
+fn is_stylesheet_source(_path: &Utf8Path) -> bool {
+    false
+}
+
+#[derive(Error, Debug)]
+pub enum WatchError {
+    #[error("failed to create notify watcher: {0}")]
+    CreateWatcher(#[source] notify::Error),
+
+    #[error("failed to watch new path: {0}")]
+    WatchPath(#[source] notify::Error),
+}
@@ -1,20 +1,30 @@
-use maud::{html, Markup, PreEscaped};
+use chrono::{DateTime, Utc};
+use maud::{html, Markup, PreEscaped, Render};
+use url::Url;
 
 use crate::{
+    embeds::{self, Preview, Provider},
     state::{
         render::{
-            ChronoRef, EntryRef, PageRef, PostRef, PostsRef, RecentPubsRef, RssFeedRef, TaggedRef,
-            TagsRef,
+            ChronoRef, EntryRef, FeedPage, HistoryRef, PageRef, PostRef, PostsRef, RecentPubsRef,
+            RssFeedRef, RssPreviewRef, SitemapRef, TaggedRef, TagsRef,
         },
-        Theme,
+        render_blocking, InventoryItem, Site, StaleNode, Theme,
     },
-    templates::wrappers,
+    templates::{partials::PageMeta, wrappers},
 };
 
-pub async fn index(index: PageRef<'_>, recent_posts: RecentPubsRef<'_>, theme: Theme) -> Markup {
+pub async fn index(
+    index: PageRef,
+    recent_posts: RecentPubsRef,
+    theme: Theme,
+    site: Site,
+) -> Markup {
+    let canonical = index.canonical_path();
     wrappers::base(
-        index.metadata.title.as_deref(),
+        PageMeta::new(index.metadata.title.as_deref()).with_canonical(&canonical),
         theme,
+        site,
         html! {
             main {
                 (index)
@@ -25,10 +35,12 @@ pub async fn index(index: PageRef<'_>, recent_posts: RecentPubsRef<'_>, theme: T
     .await
 }
 
-pub async fn page(page: PageRef<'_>, theme: Theme) -> Markup {
+pub async fn page(page: PageRef, theme: Theme, site: Site) -> Markup {
+    let canonical = page.canonical_path();
     wrappers::base(
-        page.metadata.title.as_deref(),
+        PageMeta::new(page.metadata.title.as_deref()).with_canonical(&canonical),
         theme,
+        site,
         html! {
             main {
                 (page)
@@ -38,34 +50,69 @@ pub async fn page(page: PageRef<'_>, theme: Theme) -> Markup {
     .await
 }
 
-pub async fn post(post: PostRef<'_>, theme: Theme) -> Markup {
+pub async fn post(post: PostRef, theme: Theme, site: Site) -> Markup {
+    let (post, body) = render_blocking(move || {
+        let body = post.render();
+        (post, body)
+    })
+    .await;
+    let canonical = post.canonical_path();
     wrappers::base(
-        Some(post.md_title()),
+        PageMeta::titled(post.md_title())
+            .with_robots(post.robots())
+            .with_canonical(&canonical)
+            .with_article(post.date_posted(), post.tags()),
         theme,
+        site,
+        body,
+    )
+    .await
+}
+
+pub async fn post_history(history: HistoryRef, theme: Theme, site: Site) -> Markup {
+    let title = format!("History of {}", history.post().md_title());
+    let canonical = history.canonical_path();
+    wrappers::base(
+        PageMeta::titled(&title)
+            .with_robots(history.post().robots())
+            .with_canonical(&canonical),
+        theme,
+        site,
         html! {
-            (post)
+            (history)
         },
     )
     .await
 }
 
-pub async fn entry(entry: EntryRef<'_>, theme: Theme) -> Markup {
+pub async fn entry(entry: EntryRef, theme: Theme, site: Site) -> Markup {
+    let (entry, body) = render_blocking(move || {
+        let body = entry.render();
+        (entry, body)
+    })
+    .await;
+    let canonical = entry.canonical_path();
     wrappers::base(
-        Some(entry.md_title()),
+        PageMeta::titled(entry.md_title())
+            .with_robots(entry.thread_metadata().robots.as_deref())
+            .with_canonical(&canonical)
+            .with_article(entry.date_posted(), entry.thread_metadata().tags.iter()),
         theme,
+        site,
         html! {
             main {
-                (entry)
+                (body)
             }
         },
     )
     .await
 }
 
-pub async fn posts(posts: PostsRef<'_>, theme: Theme) -> Markup {
+pub async fn posts(posts: PostsRef, theme: Theme, site: Site) -> Markup {
     wrappers::base(
-        Some("Posts"),
+        PageMeta::titled("Posts").with_canonical("/posts"),
         theme,
+        site,
         html! {
             (posts)
         },
@@ -73,10 +120,11 @@ pub async fn posts(posts: PostsRef<'_>, theme: Theme) -> Markup {
     .await
 }
 
-pub async fn chrono(chrono: ChronoRef<'_>, theme: Theme) -> Markup {
+pub async fn chrono(chrono: ChronoRef, theme: Theme, site: Site) -> Markup {
     wrappers::base(
-        Some("Chrono"),
+        PageMeta::titled("Chrono").with_canonical("/chrono"),
         theme,
+        site,
         html! {
             (chrono)
         },
@@ -84,10 +132,11 @@ pub async fn chrono(chrono: ChronoRef<'_>, theme: Theme) -> Markup {
     .await
 }
 
-pub async fn tags(tags: TagsRef<'_>, theme: Theme) -> Markup {
+pub async fn tags(tags: TagsRef, theme: Theme, site: Site) -> Markup {
     wrappers::base(
-        Some("Tags"),
+        PageMeta::titled("Tags").with_canonical("/tags"),
         theme,
+        site,
         html! {
             (tags)
         },
@@ -95,10 +144,13 @@ pub async fn tags(tags: TagsRef<'_>, theme: Theme) -> Markup {
     .await
 }
 
-pub async fn tagged(tagged: TaggedRef<'_>, theme: Theme) -> Markup {
+pub async fn tagged(tagged: TaggedRef, theme: Theme, site: Site) -> Markup {
+    let title = tagged.tag.to_string();
+    let canonical = tagged.canonical_path();
     wrappers::base(
-        Some(&tagged.tag.to_string()),
+        PageMeta::titled(&title).with_canonical(&canonical),
         theme,
+        site,
         html! {
             (tagged)
         },
@@ -106,19 +158,37 @@ pub async fn tagged(tagged: TaggedRef<'_>, theme: Theme) -> Markup {
     .await
 }
 
-pub async fn rss_feed(rss_feed: RssFeedRef<'_>) -> Markup {
-    // It's not HTML, it's XML, but we should be fine as long as we're careful.
+pub async fn rss_feed(rss_feed: RssFeedRef, site: &Site) -> Markup {
+    let href = |page: FeedPage| format!("{}{}", site.base_url(), page.path());
+
+    // It's not HTML, it's XML, but we should be fine as long as we're careful. Elements without
+    // content are written with a closing tag, since maud's void elements aren't valid XML.
     html! {
         (PreEscaped("<?xml version=\"1.0\" ?>"))
-        rss version="2.0" {
+        rss
+            version="2.0"
+            xmlns:atom="http://www.w3.org/2005/Atom"
+            xmlns:fh="http://purl.org/syndication/history/1.0" {
             channel {
-                title { "maddie, wtf?!" }
-                link { "https://maddie.wtf" }
-                description { "Madeleine Mortensen" }
+                title { (site.title) }
+                link { (site.base_url()) }
+                description { (site.description) }
                 image {
-                    title { "maddie, wtf?!" }
-                    link { "https://maddie.wtf" }
-                    url { "https://maddie.wtf/static/favicon.svg" }
+                    title { (site.title) }
+                    link { (site.base_url()) }
+                    url { (site.base_url()) (site.assets().url("favicon.svg")) }
+                }
+                atom:link rel="self" type="application/rss+xml" href=(href(rss_feed.page())) {}
+                // Older entries are paged into archives, as described in RFC 5005.
+                @if rss_feed.page() != FeedPage::Current {
+                    fh:archive {}
+                    atom:link rel="current" href=(href(FeedPage::Current)) {}
+                }
+                @if let Some(prev) = rss_feed.prev_archive() {
+                    atom:link rel="prev-archive" href=(href(prev)) {}
+                }
+                @if let Some(next) = rss_feed.next_archive() {
+                    atom:link rel="next-archive" href=(href(next)) {}
                 }
                 (rss_feed)
             }
@@ -126,10 +196,91 @@ pub async fn rss_feed(rss_feed: RssFeedRef<'_>) -> Markup {
     }
 }
 
-pub async fn not_found(theme: Theme) -> Markup {
+/// A feed with nothing in it but the items for a single post, for checking how it'll look in feed
+/// readers before it's published.
+pub async fn rss_preview(preview: RssPreviewRef, site: &Site) -> Markup {
+    html! {
+        (PreEscaped("<?xml version=\"1.0\" ?>"))
+        rss version="2.0" {
+            channel {
+                title { "Preview of " (preview.path()) " - " (site.title) }
+                link { (site.base_url()) }
+                description { (site.description) }
+                (preview)
+            }
+        }
+    }
+}
+
+pub async fn sitemap(sitemap: SitemapRef) -> Markup {
+    html! {
+        (PreEscaped("<?xml version=\"1.0\" encoding=\"UTF-8\" ?>"))
+        (sitemap)
+    }
+}
+
+pub async fn embed_card(
+    url: &Url,
+    provider: &Provider,
+    preview: Option<&Preview>,
+    theme: Theme,
+    site: Site,
+) -> Markup {
+    let title = preview.and_then(|preview| preview.title.as_deref());
+    let author = preview.and_then(|preview| preview.author_name.as_deref());
+    wrappers::embed(
+        PageMeta::new(title),
+        theme,
+        site,
+        html! {
+            div class="embed-card" {
+                @if let Some(preview) = preview.filter(|preview| preview.player_html.is_some()) {
+                    a class="embed-play" href=(embeds::player_path(url)) {
+                        @if preview.thumbnail.is_some() {
+                            img src=(embeds::thumbnail_path(url)) alt="";
+                        }
+                        span { "Play" }
+                    }
+                }
+
+                p class="embed-caption" {
+                    @if let Some(title) = title {
+                        strong { (title) }
+                        @if let Some(author) = author {
+                            " by " (author)
+                        }
+                        br;
+                    }
+                    a href=(url) target="_blank" rel="noopener noreferrer" {
+                        "Watch on " (provider.name)
+                    }
+                    @if preview.is_some() {
+                        " (playing it here also loads it from " (provider.name) ")"
+                    }
+                }
+            }
+        },
+    )
+    .await
+}
+
+pub async fn embed_player(provider: &str, player_html: &str, theme: Theme, site: Site) -> Markup {
+    wrappers::embed(
+        PageMeta::titled(provider),
+        theme,
+        site,
+        html! {
+            (PreEscaped(player_html))
+        },
+    )
+    .await
+}
+
+pub async fn not_found(theme: Theme, site: Site) -> Markup {
     wrappers::base(
-        Some("not found"),
+        PageMeta::titled("not found"),
         theme,
+        site,
         html! {
             main class="error" {
                 h1 class="title" {
@@ -145,10 +296,11 @@ pub async fn not_found(theme: Theme) -> Markup {
     .await
 }
 
-pub async fn internal_error(theme: Theme) -> Markup {
+pub async fn internal_error(theme: Theme, site: Site) -> Markup {
     wrappers::base(
-        Some("internal server error"),
+        PageMeta::titled("internal server error"),
         theme,
+        site,
         html! {
             main class="error" {
                 h1 class="title" {
@@ -163,3 +315,144 @@ pub async fn internal_error(theme: Theme) -> Markup {
     )
     .await
 }
+
+pub async fn timeout(theme: Theme, site: Site) -> Markup {
+    wrappers::base(
+        PageMeta::titled("timed out"),
+        theme,
+        site,
+        html! {
+            main class="error" {
+                h1 class="title" {
+                    "Timed Out"
+                }
+
+                p {
+                    "wtf is taking so long?! try again in a moment."
+                }
+            }
+        },
+    )
+    .await
+}
+
+/// Every file that's failing to load, for `/_dev/errors` in development.
+pub async fn dev_errors(
+    errors: Vec<StaleNode>,
+    last_reload: Option<DateTime<Utc>>,
+    theme: Theme,
+    site: Site,
+) -> Markup {
+    wrappers::base(
+        PageMeta::titled("Load errors"),
+        theme,
+        site,
+        html! {
+            main class="dev" {
+                h1 class="title" { "Load errors" }
+                (last_reloaded(last_reload))
+
+                @if errors.is_empty() {
+                    p { "Everything in the content path loaded." }
+                }
+                @for error in &errors {
+                    section {
+                        h2 { code { (error.path) } }
+                        p {
+                            "Failing since "
+                            (error.since.format("%d %B %Y %H:%M:%S UTC"))
+                            @if error.previous {
+                                ", so an older version is being served instead."
+                            } @else {
+                                ", and there's no older version to serve instead."
+                            }
+                        }
+                        pre { (error.error.trim_end()) }
+                    }
+                }
+
+                p { a href="/_dev/content" { "Everything that did load" } }
+            }
+        },
+    )
+    .await
+}
+
+/// Every post and page that's loaded, with the keys they're loaded under, for `/_dev/content` in
+/// development.
+pub async fn dev_content(
+    items: Vec<InventoryItem>,
+    errors: usize,
+    last_reload: Option<DateTime<Utc>>,
+    theme: Theme,
+    site: Site,
+) -> Markup {
+    wrappers::base(
+        PageMeta::titled("Loaded content"),
+        theme,
+        site,
+        html! {
+            main class="dev" {
+                h1 class="title" { "Loaded content" }
+                (last_reloaded(last_reload))
+
+                @if errors > 0 {
+                    p {
+                        a href="/_dev/errors" {
+                            (errors) " file(s) failed to load"
+                        }
+                        ", so they're missing here unless an older version of them loaded."
+                    }
+                }
+
+                table {
+                    thead {
+                        tr {
+                            th { "Key" }
+                            th { "Kind" }
+                            th { "Date" }
+                            th { "Title" }
+                            th { "Visibility" }
+                        }
+                    }
+                    tbody {
+                        @for item in &items {
+                            tr {
+                                td { code { (item.path) } }
+                                td { (item.kind.name()) }
+                                td {
+                                    @if let Some(date) = item.date {
+                                        (date)
+                                    }
+                                }
+                                td { (item.title.as_deref().unwrap_or("(untitled)")) }
+                                td {
+                                    @if item.draft {
+                                        "draft"
+                                    } @else if item.draft_entries > 0 {
+                                        (item.draft_entries) " draft entries"
+                                    } @else {
+                                        "published"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+    )
+    .await
+}
+
+fn last_reloaded(last_reload: Option<DateTime<Utc>>) -> Markup {
+    html! {
+        p {
+            @if let Some(last_reload) = last_reload {
+                "Last reloaded at " (last_reload.format("%d %B %Y %H:%M:%S UTC")) "."
+            } @else {
+                "Not loaded yet."
+            }
+        }
+    }
+}
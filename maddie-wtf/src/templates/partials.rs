@@ -1,67 +1,172 @@
-use std::{env, option_env};
+use std::env;
 
 use camino::Utf8Path;
 use chrono::NaiveDate;
-use maud::{html, Markup};
+use maud::{html, Markup, PreEscaped};
 use url::Url;
 
 use crate::{
+    audit::Issue,
     build_info,
-    state::{names::TagName, Theme},
+    state::{names::TagName, ColorScheme, Site, StaleNode, Theme},
 };
 
-pub async fn head(title: Option<&str>, theme: Theme) -> Markup {
+/// Metadata about the page being rendered that ends up in its `<head>`.
+#[derive(Clone, Debug, Default)]
+pub struct PageMeta<'a> {
+    pub title: Option<&'a str>,
+    pub robots: Option<&'a str>,
+    /// The path that this page should be found at, if it's one that search engines should index.
+    pub canonical: Option<&'a str>,
+    /// Present if the page is a post (or an entry in one), for the Open Graph article metadata.
+    pub article: Option<ArticleMeta<'a>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ArticleMeta<'a> {
+    pub published: NaiveDate,
+    pub tags: Vec<&'a TagName>,
+}
+
+impl<'a> PageMeta<'a> {
+    pub fn new(title: Option<&'a str>) -> Self {
+        Self {
+            title,
+            ..Default::default()
+        }
+    }
+
+    pub fn titled(title: &'a str) -> Self {
+        Self::new(Some(title))
+    }
+
+    pub fn with_robots(self, robots: Option<&'a str>) -> Self {
+        Self { robots, ..self }
+    }
+
+    pub fn with_canonical(self, canonical: &'a str) -> Self {
+        Self {
+            canonical: Some(canonical),
+            ..self
+        }
+    }
+
+    pub fn with_article(
+        self,
+        published: NaiveDate,
+        tags: impl Iterator<Item = &'a TagName>,
+    ) -> Self {
+        Self {
+            article: Some(ArticleMeta {
+                published,
+                tags: tags.collect(),
+            }),
+            ..self
+        }
+    }
+}
+
+/// The fonts used by the stylesheet, which are preloaded so that text doesn't jump around as they
+/// arrive.
+const FONTS: [&str; 5] = [
+    "iosevka-regular.woff2",
+    "IBMPlexSans-Italic.woff2",
+    "IBMPlexSans-Regular.woff2",
+    "IBMPlexSans-SemiBold.woff2",
+    "IBMPlexSans-SemiBoldItalic.woff2",
+];
+
+pub async fn head(meta: PageMeta<'_>, theme: &Theme, site: &Site) -> Markup {
     let theme_header = theme.theme_header();
+    let assets = site.assets();
+    let canonical = meta
+        .canonical
+        .map(|canonical| format!("{}{}", site.base_url(), canonical));
     html! {
         head {
             meta charset="utf-8";
             meta name="viewport" content="width=device-width,initial-scale=1,height=device-height";
 
-            link rel="icon" href="/static/favicon.svg" type="image/svg+xml";
+            @if let Some(robots) = meta.robots {
+                meta name="robots" content=(robots);
+            }
 
-            link rel="stylesheet" href="/style.css" type="text/css";
+            @if let Some(canonical) = &canonical {
+                link rel="canonical" href=(canonical);
+            }
 
-            link rel="preload" href="/static/iosevka-regular.woff2" as="font" type="font/woff2" crossorigin;
-            link rel="preload" href="/static/IBMPlexSans-Italic.woff2" as="font" type="font/woff2" crossorigin;
-            link rel="preload" href="/static/IBMPlexSans-Regular.woff2" as="font" type="font/woff2" crossorigin;
-            link rel="preload" href="/static/IBMPlexSans-SemiBold.woff2" as="font" type="font/woff2" crossorigin;
-            link rel="preload" href="/static/IBMPlexSans-SemiBoldItalic.woff2" as="font" type="font/woff2" crossorigin;
+            meta property="og:site_name" content=(site.title);
+            meta property="og:locale" content=(site.language.replace('-', "_"));
+            meta property="og:title" content=(meta.title.unwrap_or(&site.title));
+            @if let Some(canonical) = &canonical {
+                meta property="og:url" content=(canonical);
+            }
+            @if let Some(article) = &meta.article {
+                meta property="og:type" content="article";
+                meta property="article:published_time" content=(article.published);
+                @for tag in &article.tags {
+                    meta property="article:tag" content=(tag);
+                }
+            } @else {
+                meta property="og:type" content="website";
+            }
 
-            link rel="alternate" type="application/rss+xml" href="/rss.xml" title="maddie, wtf?!";
+            link rel="icon" href=(assets.url("favicon.svg")) type="image/svg+xml";
+
+            @if let Some(css) = assets.inline_stylesheet() {
+                style {
+                    (PreEscaped(css))
+                }
+            } @else {
+                link rel="stylesheet" href=(assets.stylesheet_url()) type="text/css";
+            }
+
+            @for font in FONTS {
+                link rel="preload" href=(assets.url(font)) as="font" type="font/woff2" crossorigin;
+            }
+
+            link rel="alternate" type="application/rss+xml" href="/rss.xml" title=(site.title);
 
             title {
-                (title.map_or("maddie, wtf?!".into(), |title| format!("{} | maddie, wtf?!", title)))
+                (meta.title.map_or(site.title.clone(), |title| format!("{} | {}", title, site.title)))
             }
             style {
                 (theme_header)
+                (PreEscaped(site.tag_accents()))
             }
         }
     }
 }
 
-pub async fn footer() -> Markup {
-    let raw_hash = build_info::GIT_COMMIT_HASH.or(option_env!("COMMIT_HASH"));
+pub async fn footer(site: &Site, theme: &Theme) -> Markup {
+    let footer = site.footer();
+    let raw_hash = build_info::commit_hash();
 
-    let (url, short_hash) = match raw_hash {
-        Some(raw) if raw.ends_with("-dirty") && raw.len() >= 7 => {
-            let url = format!(
-                "https://github.com/maddiemort/maddie-wtf/tree/{}",
-                &raw[0..7]
-            );
-            (url, Some(raw))
-        }
-        Some(raw) if raw.len() >= 7 => {
-            let url = format!("https://github.com/maddiemort/maddie-wtf/tree/{}", raw);
-            let short_hash = &raw[0..7];
-            (url, Some(short_hash))
-        }
-        _ => ("https://github.com/maddiemort/maddie-wtf".to_owned(), None),
+    let short_hash = match raw_hash {
+        Some(raw) if raw.ends_with("-dirty") && raw.len() >= 7 => Some(raw),
+        Some(raw) if raw.len() >= 7 => Some(&raw[0..7]),
+        _ => None,
     };
 
+    // Only GitHub repositories are known to have a `tree/<hash>` route, so for anything else just
+    // link to the repository itself.
+    let source_url = footer.repository.as_ref().map(|repository| {
+        let repository = repository.as_str().trim_end_matches('/');
+        match raw_hash {
+            Some(raw) if raw.len() >= 7 && repository.starts_with("https://github.com/") => {
+                let commit = raw.strip_suffix("-dirty").unwrap_or(raw);
+                format!("{}/tree/{}", repository, commit)
+            }
+            _ => repository.to_owned(),
+        }
+    });
+
     html! {
         footer class="sitefooter" {
             ul {
-                li { a href="/rss.xml" { "feed" } }
+                @for link in &footer.links {
+                    li { a href=(link.href) { (link.name) } }
+                }
                 li {
                     code {
                         (env!("CARGO_PKG_NAME"))
@@ -76,7 +181,49 @@ pub async fn footer() -> Markup {
                         code { (hash) }
                     }
                 }
-                li { a href=(url) { "source" } }
+                @if let Some(url) = source_url {
+                    li { a href=(url) { "source" } }
+                }
+                li {
+                    (theme_picker(theme))
+                }
+            }
+
+            @if let Some(html_text) = &footer.html_text {
+                div class="blurb" {
+                    (PreEscaped(html_text))
+                }
+            }
+        }
+    }
+}
+
+/// A form for overriding the OS colour scheme preference, and for picking between the named themes
+/// if there's more than one, which works without any JavaScript. The buttons for the current
+/// choices are disabled.
+pub fn theme_picker(theme: &Theme) -> Markup {
+    let current = theme.color_scheme();
+    let schemes = [
+        ("system", None),
+        ("light", Some(ColorScheme::Light)),
+        ("dark", Some(ColorScheme::Dark)),
+    ];
+
+    html! {
+        form class="themepicker" method="post" action="/theme" {
+            @for (name, scheme) in schemes {
+                button type="submit" name="scheme" value=(name) disabled[scheme == current] {
+                    (name)
+                }
+            }
+        }
+        @if theme.names().nth(1).is_some() {
+            form class="themepicker" method="post" action="/theme" {
+                @for name in theme.names() {
+                    button type="submit" name="theme" value=(name) disabled[name == theme.name()] {
+                        (name)
+                    }
+                }
             }
         }
     }
@@ -158,9 +305,15 @@ pub fn post_entry_frontmatter<'a>(
     )
 }
 
-pub fn post_endmatter(lobsters: Option<&Url>, hacker_news: Option<&Url>) -> Markup {
+/// Where a post has been discussed, where its file can be edited, and how it's changed.
+pub fn post_endmatter(
+    lobsters: Option<&Url>,
+    hacker_news: Option<&Url>,
+    edit: Option<&str>,
+    history: Option<&str>,
+) -> Markup {
     html! {
-        @if lobsters.is_some() || hacker_news.is_some() {
+        @if lobsters.is_some() || hacker_news.is_some() || edit.is_some() || history.is_some() {
             ul class="endmatter" {
                 @if let Some(lobsters) = lobsters {
                     li {
@@ -177,6 +330,92 @@ pub fn post_endmatter(lobsters: Option<&Url>, hacker_news: Option<&Url>) -> Mark
                         }
                     }
                 }
+
+                @if let Some(edit) = edit {
+                    li {
+                        a href=(edit) {
+                            "Edit this post"
+                        }
+                    }
+                }
+
+                @if let Some(history) = history {
+                    li {
+                        a href=(history) {
+                            "History"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A warning that what's shown is an older version of a post, because the file failed to load the
+/// last time it changed.
+pub fn stale_notice(stale: &StaleNode) -> Markup {
+    html! {
+        aside class="stale" {
+            em {
+                "This is an older version of this post, because its file has been failing to \
+                load since "
+                (stale.since.format("%d %B %Y %H:%M:%S UTC"))
+                ":"
+            }
+            code { (stale.error) }
+        }
+    }
+}
+
+/// The problems the accessibility audit found in a page, pinned to the bottom of it in development.
+pub fn audit_overlay(issues: &[Issue]) -> Markup {
+    html! {
+        aside class="audit" role="status" {
+            strong { "Accessibility audit" }
+            ul {
+                @for issue in issues {
+                    li { (issue.to_string()) }
+                }
+            }
+        }
+    }
+}
+
+/// The files that are failing to load, pinned to the bottom of every page in development.
+pub fn load_errors_banner(errors: &[StaleNode]) -> Markup {
+    html! {
+        aside class="load-errors" role="status" {
+            strong { a href="/_dev/errors" { "Content that failed to load" } }
+            ul {
+                @for error in errors {
+                    li {
+                        code { (error.path) }
+                        @if error.previous {
+                            " (serving an older version)"
+                        }
+                        pre { (error.error.trim_end()) }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A box of links to related posts or pages, given by hand in a post's frontmatter.
+pub fn see_also(links: &[String]) -> Markup {
+    html! {
+        @if !links.is_empty() {
+            aside class="seealso" {
+                h2 { "See also" }
+                ul {
+                    @for link in links {
+                        li {
+                            a href=(link) {
+                                (link)
+                            }
+                        }
+                    }
+                }
             }
         }
     }
@@ -210,7 +449,7 @@ fn tag_list<'a>(tags: impl Iterator<Item = &'a TagName>) -> Markup {
     html! {
         @for tag in tags {
             li {
-                a href=(format!("/tagged/{}", tag)) {
+                a href=(format!("/tagged/{}", tag)) data-tag=(tag) {
                     code { (tag) }
                 }
             }
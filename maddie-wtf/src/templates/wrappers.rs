@@ -1,12 +1,15 @@
 use maud::{html, Markup, DOCTYPE};
 
-use crate::{state::Theme, templates::partials};
+use crate::{
+    state::{Site, Theme},
+    templates::partials::{self, PageMeta},
+};
 
-pub async fn base(title: Option<&str>, theme: Theme, content: Markup) -> Markup {
+pub async fn base(meta: PageMeta<'_>, theme: Theme, site: Site, content: Markup) -> Markup {
     html! {
         (DOCTYPE)
-        html lang="en-GB" dir="ltr" {
-            (partials::head(title, theme).await)
+        html lang=(site.language) dir="ltr" data-color-scheme=[theme.color_scheme().map(|scheme| scheme.as_str())] data-flags=[flags_attr(&theme)] {
+            (partials::head(meta, &theme, &site).await)
             body {
                 script {
                     "let FF_FOUC_FIX;"
@@ -14,23 +17,43 @@ pub async fn base(title: Option<&str>, theme: Theme, content: Markup) -> Markup
 
                 header class="siteheader" role="banner" {
                     a href="/" class="sitetitle" {
-                        "Madeleine Mortensen"
+                        (site.author)
                     }
 
                     nav role="navigation" {
                         ul {
-                            li { a href="/projects" { "projects" } }
-                            li { a href="/posts" { "posts" } }
-                            li { a href="/chrono" { "chrono" } }
-                            li { a href="/tags" { "tags" } }
+                            @for link in site.nav() {
+                                li { a href=(link.href) { (link.name) } }
+                            }
                         }
                     }
                 }
 
                 (content)
 
-                (partials::footer().await)
+                (partials::footer(&site, &theme).await)
             }
         }
     }
 }
+
+/// A page shown in an iframe inside another one, which only needs the stylesheet, not the header or
+/// footer.
+pub async fn embed(meta: PageMeta<'_>, theme: Theme, site: Site, content: Markup) -> Markup {
+    html! {
+        (DOCTYPE)
+        html lang=(site.language) dir="ltr" data-color-scheme=[theme.color_scheme().map(|scheme| scheme.as_str())] data-flags=[flags_attr(&theme)] {
+            (partials::head(meta.with_robots(Some("noindex")), &theme, &site).await)
+            body class="embed" {
+                (content)
+            }
+        }
+    }
+}
+
+/// The feature flags that are on, for the stylesheet to select on with `[data-flags~="name"]`, or
+/// nothing if none are.
+fn flags_attr(theme: &Theme) -> Option<String> {
+    let flags = theme.flags().enabled().collect::<Vec<_>>();
+    (!flags.is_empty()).then(|| flags.join(" "))
+}
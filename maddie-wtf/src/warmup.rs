@@ -0,0 +1,115 @@
+use std::{future::Future, time::Instant};
+
+use maud::Markup;
+use tracing::{debug, info, span, Instrument, Level};
+
+use crate::{
+    handlers::{self, RSS_FEED_KEY, SITEMAP_KEY},
+    metric,
+    state::{render::FeedPage, Content, RenderCache, Settings, Site, Themes},
+};
+
+/// Everything needed to render the most visited pages into the render cache ahead of time, so
+/// that the first reader to visit one after the content changes doesn't have to wait for it.
+///
+/// Pages are rendered with the default theme, since that's what most readers will see.
+#[derive(Clone, Debug)]
+pub struct Warmup {
+    pub renders: RenderCache,
+    pub content: Content,
+    pub themes: Themes,
+    pub site: Site,
+    pub settings: Settings,
+}
+
+impl Warmup {
+    /// Render the index, the list pages, the RSS feed and the sitemap in the background, along with
+    /// the given posts if they've just changed.
+    pub fn spawn(&self, changed_posts: Vec<String>) {
+        let warmup = self.clone();
+        tokio::spawn(
+            async move { warmup.run(changed_posts).await }
+                .instrument(span!(Level::ERROR, "cache_warmup")),
+        );
+    }
+
+    async fn run(self, changed_posts: Vec<String>) {
+        let Self {
+            renders,
+            content,
+            themes,
+            site,
+            settings,
+        } = self;
+
+        let start = Instant::now();
+        let theme = themes
+            .get(None, None)
+            .await
+            .with_flags(settings.flags().resolve(None));
+
+        let index = handlers::render_index(
+            content.clone(),
+            theme.clone(),
+            site.clone(),
+            settings.clone(),
+        );
+        warm(&renders, handlers::render_key("/", &theme), index).await;
+
+        let posts = handlers::render_posts(
+            content.clone(),
+            theme.clone(),
+            site.clone(),
+            settings.clone(),
+        );
+        warm(&renders, handlers::render_key("/posts", &theme), posts).await;
+
+        let chrono = handlers::render_chrono(
+            content.clone(),
+            theme.clone(),
+            site.clone(),
+            settings.clone(),
+        );
+        warm(&renders, handlers::render_key("/chrono", &theme), chrono).await;
+
+        let feed = handlers::render_rss_feed(
+            content.clone(),
+            site.clone(),
+            settings.clone(),
+            FeedPage::Current,
+        );
+        warm(&renders, RSS_FEED_KEY.to_owned(), feed).await;
+
+        let sitemap =
+            handlers::render_sitemap(content.clone(), site.clone(), settings.clone(), None);
+        warm(&renders, SITEMAP_KEY.to_owned(), sitemap).await;
+
+        for post in changed_posts {
+            let key = handlers::render_key(&format!("/posts/{post}"), &theme);
+            let post = handlers::render_post(
+                content.clone(),
+                theme.clone(),
+                site.clone(),
+                settings.clone(),
+                post,
+            );
+            warm(&renders, key, post).await;
+        }
+
+        let elapsed = start.elapsed();
+        metrics::histogram!(*metric::CACHE_WARMUP_DURATION).record(elapsed.as_secs_f64());
+        info!(?elapsed, "warmed render cache");
+    }
+}
+
+async fn warm<F>(renders: &RenderCache, key: String, render: F)
+where
+    F: Future<Output = Option<Markup>> + Send + 'static,
+{
+    match renders.render(key.clone(), render).await {
+        Ok(_) => debug!(%key, "warmed page"),
+        // Pages that don't exist, like a changed file that turns out to be a page rather than a
+        // post, are skipped, and anything else has already been logged.
+        Err(error) => debug!(%key, %error, "failed to warm page"),
+    }
+}
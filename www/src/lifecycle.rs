@@ -1,3 +1,5 @@
+use std::future::Future;
+
 use cfg_if::cfg_if;
 use tokio::signal;
 use tracing::{info, instrument};
@@ -41,3 +43,26 @@ pub async fn graceful_shutdown() {
 
     info!("shutting down, see you soon!");
 }
+
+/// Call `reload` every time a SIGHUP is received, which is how a server is conventionally asked to
+/// pick up whatever's changed in its files, instead of being shut down by it. There's no SIGHUP
+/// outside of Unix, so this just waits forever there.
+pub async fn reload_on_hangup<F, Fut>(mut reload: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    cfg_if! {
+        if #[cfg(unix)] {
+            let mut hangups = signal::unix::signal(signal::unix::SignalKind::hangup())
+                .expect("should be able to install signal handler");
+            while hangups.recv().await.is_some() {
+                info!("hangup signal received, reloading");
+                reload().await;
+            }
+        } else {
+            let _ = &mut reload;
+            std::future::pending::<()>().await;
+        }
+    }
+}
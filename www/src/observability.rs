@@ -1,30 +1,73 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
 
 use metrics_exporter_prometheus::{BuildError, PrometheusBuilder};
+use thiserror::Error;
 use tracing_subscriber::{
+    filter::ParseError,
     fmt,
     layer::SubscriberExt as _,
+    reload,
     util::{SubscriberInitExt as _, TryInitError},
-    EnvFilter,
+    EnvFilter, Registry,
 };
 
 use crate::config::Environment;
 
-pub fn init_tracing(debug: bool) -> Result<(), TryInitError> {
-    let registry = tracing_subscriber::registry().with(
+pub fn init_tracing(debug: bool) -> Result<LogFilter, TryInitError> {
+    let (filter, handle) = reload::Layer::new(
         EnvFilter::try_from_default_env()
             .unwrap_or_else(|_| EnvFilter::new("otel::tracing=trace,info")),
     );
+    let registry = tracing_subscriber::registry().with(filter);
 
+    // Logs go to stderr so that they don't get mixed up with the output of subcommands.
     if debug {
         registry
-            .with(fmt::layer().with_timer(fmt::time::uptime()))
-            .try_init()
+            .with(
+                fmt::layer()
+                    .with_writer(io::stderr)
+                    .with_timer(fmt::time::uptime()),
+            )
+            .try_init()?;
     } else {
-        registry.with(fmt::layer()).try_init()
+        registry
+            .with(fmt::layer().with_writer(io::stderr))
+            .try_init()?;
+    }
+
+    Ok(LogFilter(handle))
+}
+
+/// Decides which logs are recorded, and can be changed while the program is running.
+#[derive(Clone, Debug)]
+pub struct LogFilter(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilter {
+    /// The directives currently in use, in the same form as `RUST_LOG`.
+    pub fn current(&self) -> Result<String, SetLogFilterError> {
+        Ok(self.0.with_current(ToString::to_string)?)
+    }
+
+    /// Replace the directives in use with `directives`, given in the same form as `RUST_LOG`, e.g.
+    /// `info,maddie_wtf::state=debug`.
+    pub fn set(&self, directives: &str) -> Result<(), SetLogFilterError> {
+        let filter = EnvFilter::try_new(directives)?;
+        Ok(self.0.reload(filter)?)
     }
 }
 
+#[derive(Error, Debug)]
+pub enum SetLogFilterError {
+    #[error("invalid log filter directives: {0}")]
+    Parse(#[from] ParseError),
+
+    #[error("failed to reload log filter: {0}")]
+    Reload(#[from] reload::Error),
+}
+
 pub fn init_metrics(port: u16, environment: Environment) -> Result<(), BuildError> {
     PrometheusBuilder::new()
         .with_http_listener(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), port))